@@ -0,0 +1,73 @@
+use crate::link::{LinkInfo, LinkStatus};
+use serde::{Deserialize, Serialize};
+
+/// Built-in user agents by name: a desktop browser, a mobile browser and
+/// Google's crawler
+pub const AGENTS: [(&str, &str); 3] = [
+    (
+        "desktop",
+        "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) \
+         Chrome/124.0.0.0 Safari/537.36",
+    ),
+    (
+        "mobile",
+        "Mozilla/5.0 (iPhone; CPU iPhone OS 17_4 like Mac OS X) AppleWebKit/605.1.15 \
+         (KHTML, like Gecko) Version/17.4 Mobile/15E148 Safari/604.1",
+    ),
+    (
+        "googlebot",
+        "Mozilla/5.0 (compatible; Googlebot/2.1; +http://www.google.com/bot.html)",
+    ),
+];
+
+/// `User-Agent` header of an agent: the built-in one with that name, or the
+/// name itself
+pub fn agent_header(agent: &str) -> &str {
+    AGENTS
+        .iter()
+        .find(|(name, _)| *name == agent)
+        .map_or(agent, |(_, header)| header)
+}
+
+/// What a URL answered one user agent
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AgentResult {
+    pub agent: String,
+    pub status: LinkStatus,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub redirected_to: Option<String>,
+}
+
+/// A URL answering user agents differently
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AgentDifference {
+    pub url: String,
+    pub results: Vec<AgentResult>,
+}
+
+/// URLs whose status or redirect differs between agents, given the probes
+/// of the same URLs, in the same order, for each agent
+pub fn agent_differences(probes: &[(String, Vec<LinkInfo>)]) -> Vec<AgentDifference> {
+    let Some((_, first)) = probes.first() else {
+        return Vec::new();
+    };
+    (0..first.len())
+        .filter_map(|index| {
+            let results: Vec<AgentResult> = probes
+                .iter()
+                .map(|(agent, links)| AgentResult {
+                    agent: agent.clone(),
+                    status: links[index].status.clone(),
+                    redirected_to: links[index].redirected_to.clone(),
+                })
+                .collect();
+            let same = results.windows(2).all(|pair| {
+                pair[0].status == pair[1].status && pair[0].redirected_to == pair[1].redirected_to
+            });
+            (!same).then(|| AgentDifference {
+                url: first[index].url.clone(),
+                results,
+            })
+        })
+        .collect()
+}
@@ -0,0 +1,60 @@
+use crate::link::LinkStatus;
+use serde::{Deserialize, Serialize};
+
+/// Media types of `<link rel="alternate">` feeds
+const FEED_TYPES: [&str; 4] = [
+    "application/rss+xml",
+    "application/atom+xml",
+    "application/rdf+xml",
+    "application/feed+json",
+];
+
+/// Kind of alternate representation a page announces
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AlternateKind {
+    /// AMP version, from `<link rel="amphtml">`
+    Amp,
+    /// Print version or print stylesheet, from `<link media="print">`
+    Print,
+    /// RSS, Atom or JSON feed, from `<link rel="alternate" type>`
+    Feed,
+}
+
+/// Kind of representation a `<link>` element announces, from its attributes.
+/// Language alternates (`hreflang`) are not representations.
+pub fn alternate_kind<'a>(attr: impl Fn(&str) -> Option<&'a str>) -> Option<AlternateKind> {
+    let rels: Vec<String> = attr("rel")?
+        .split_ascii_whitespace()
+        .map(str::to_ascii_lowercase)
+        .collect();
+    let has_rel = |rel: &str| rels.iter().any(|name| name == rel);
+    let print = attr("media").is_some_and(|media| {
+        media
+            .split(',')
+            .any(|query| query.trim().eq_ignore_ascii_case("print"))
+    });
+    let feed = attr("type")
+        .is_some_and(|kind| FEED_TYPES.contains(&kind.trim().to_ascii_lowercase().as_str()));
+    if has_rel("amphtml") {
+        Some(AlternateKind::Amp)
+    } else if print && (has_rel("alternate") || has_rel("stylesheet")) {
+        Some(AlternateKind::Print)
+    } else if feed && has_rel("alternate") {
+        Some(AlternateKind::Feed)
+    } else {
+        None
+    }
+}
+
+/// An alternate representation announced by a crawled page
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AlternateLink {
+    /// Page announcing the representation
+    pub page: String,
+    pub kind: AlternateKind,
+    pub url: String,
+    /// Status of the URL, when alternates are checked
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status: Option<LinkStatus>,
+}
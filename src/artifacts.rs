@@ -0,0 +1,214 @@
+use crate::check::{Finding, Severity};
+use crate::config::ArtifactConfig;
+use crate::link::{LinkInfo, USER_AGENT};
+use log::info;
+use regex::Regex;
+use reqwest::blocking::Client;
+use sha2::{Digest, Sha256};
+use std::error::Error;
+use std::io::{self, Read, Write};
+use std::time::Duration;
+use url::Url;
+
+/// Rule id of artifact checksum findings
+pub const ARTIFACT_RULE: &str = "artifact-checksum";
+/// Time allowed to download one artifact
+const DOWNLOAD_TIMEOUT: Duration = Duration::from_secs(600);
+
+/// Where the expected SHA-256 of an artifact is published
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ChecksumSource {
+    /// A file next to the artifact, named after it with a suffix, as in
+    /// `adjacent .sha256`
+    Adjacent(String),
+    /// A checksum file such as `SHA256SUMS`, absolute or relative to the artifact
+    File(String),
+}
+
+impl ChecksumSource {
+    /// Parse a `sha256_from` setting
+    pub fn parse(value: &str) -> Result<Self, String> {
+        let value = value.trim();
+        match value.strip_prefix("adjacent") {
+            Some(suffix) if suffix.starts_with(char::is_whitespace) => {
+                Ok(ChecksumSource::Adjacent(suffix.trim().to_string()))
+            }
+            Some("") => Err("adjacent needs a suffix, e.g. adjacent .sha256".to_string()),
+            _ if value.is_empty() => Err("sha256_from is empty".to_string()),
+            _ => Ok(ChecksumSource::File(value.to_string())),
+        }
+    }
+
+    /// URL of the checksum file for the artifact at `artifact`
+    pub fn location(&self, artifact: &str) -> Result<String, url::ParseError> {
+        match self {
+            ChecksumSource::Adjacent(suffix) => Ok(format!("{}{}", artifact, suffix)),
+            ChecksumSource::File(file) => Ok(Url::parse(artifact)?.join(file)?.to_string()),
+        }
+    }
+}
+
+/// A compiled `artifacts` entry
+#[derive(Debug, Clone)]
+pub struct ArtifactRule {
+    pattern: Regex,
+    source: ChecksumSource,
+}
+
+impl ArtifactRule {
+    pub fn from_config(config: &ArtifactConfig) -> Result<Self, Box<dyn Error>> {
+        Ok(ArtifactRule {
+            pattern: Regex::new(&config.url_regex)?,
+            source: ChecksumSource::parse(&config.sha256_from)?,
+        })
+    }
+}
+
+/// The SHA-256 a checksum file lists for `file_name`: the line naming it, in
+/// the `sha256sum` or BSD format, or the only checksum of the file
+pub fn expected_checksum(listing: &str, file_name: &str) -> Option<String> {
+    let is_sha256 = |hash: &str| hash.len() == 64 && hash.chars().all(|c| c.is_ascii_hexdigit());
+    let mut checksums = Vec::new();
+    for line in listing
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+    {
+        // BSD: `SHA256 (tool.tar.gz) = <hash>`
+        if let Some(rest) = line.strip_prefix("SHA256 (") {
+            if let Some((name, hash)) = rest.split_once(") = ") {
+                if is_sha256(hash.trim()) {
+                    checksums.push((Some(name.to_string()), hash.trim().to_lowercase()));
+                }
+            }
+            continue;
+        }
+        // sha256sum: `<hash>  tool.tar.gz`, `*` marking binary mode
+        let mut fields = line.split_whitespace();
+        let Some(hash) = fields.next().filter(|hash| is_sha256(hash)) else {
+            continue;
+        };
+        let name = fields.next().map(|name| {
+            let name = name.trim_start_matches('*');
+            name.strip_prefix("./").unwrap_or(name).to_string()
+        });
+        checksums.push((name, hash.to_lowercase()));
+    }
+    let named = checksums.iter().find(|(name, _)| {
+        name.as_deref()
+            .is_some_and(|name| name == file_name || name.ends_with(&format!("/{}", file_name)))
+    });
+    match (named, checksums.as_slice()) {
+        (Some((_, hash)), _) => Some(hash.clone()),
+        (None, [(_, hash)]) => Some(hash.clone()),
+        _ => None,
+    }
+}
+
+/// Hex SHA-256 of everything `reader` yields
+pub fn sha256_hex(mut reader: impl Read) -> io::Result<String> {
+    struct Hasher(Sha256);
+    impl Write for Hasher {
+        fn write(&mut self, bytes: &[u8]) -> io::Result<usize> {
+            self.0.update(bytes);
+            Ok(bytes.len())
+        }
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+    let mut hasher = Hasher(Sha256::new());
+    io::copy(&mut reader, &mut hasher)?;
+    Ok(format!("{:x}", hasher.0.finalize()))
+}
+
+/// Download the artifact at `url` and compare it with its published
+/// checksum, returning the problem if there is one
+fn verify(client: &Client, url: &str, source: &ChecksumSource) -> Option<(Severity, String)> {
+    let location = match source.location(url) {
+        Ok(location) => location,
+        Err(e) => return Some((Severity::Warning, format!("Invalid checksum URL: {}", e))),
+    };
+    let listing = client
+        .get(&location)
+        .send()
+        .and_then(|response| response.error_for_status())
+        .and_then(|response| response.text());
+    let listing = match listing {
+        Ok(listing) => listing,
+        Err(e) => {
+            return Some((
+                Severity::Warning,
+                format!("Could not read the checksum from {}: {}", location, e),
+            ))
+        }
+    };
+    let file_name = Url::parse(url)
+        .ok()
+        .and_then(|url| url.path_segments()?.next_back().map(String::from))
+        .unwrap_or_default();
+    let Some(expected) = expected_checksum(&listing, &file_name) else {
+        return Some((
+            Severity::Warning,
+            format!("{} lists no SHA-256 for {}", location, file_name),
+        ));
+    };
+    info!("Downloading {} to verify its checksum", url);
+    let actual = client
+        .get(url)
+        .send()
+        .and_then(|response| response.error_for_status())
+        .map_err(|e| e.to_string())
+        .and_then(|response| sha256_hex(response).map_err(|e| e.to_string()));
+    match actual {
+        Ok(actual) if actual == expected => None,
+        Ok(actual) => Some((
+            Severity::Error,
+            format!(
+                "Checksum mismatch: the download has SHA-256 {}, {} lists {}",
+                actual, location, expected
+            ),
+        )),
+        Err(e) => Some((
+            Severity::Warning,
+            format!("Could not download the artifact: {}", e),
+        )),
+    }
+}
+
+/// Findings for valid links matching an `artifacts` entry whose download
+/// does not match its published checksum, one per page linking to them.
+/// Each artifact is verified with the first entry it matches.
+pub fn artifact_findings(
+    configs: &[ArtifactConfig],
+    links: &[LinkInfo],
+) -> Result<Vec<Finding>, Box<dyn Error>> {
+    let rules = configs
+        .iter()
+        .map(ArtifactRule::from_config)
+        .collect::<Result<Vec<_>, _>>()?;
+    let client = Client::builder()
+        .timeout(DOWNLOAD_TIMEOUT)
+        .user_agent(USER_AGENT)
+        .build()?;
+    Ok(links
+        .iter()
+        .filter(|link| !link.is_broken() && !link.status.is_unchecked())
+        .filter_map(|link| {
+            let rule = rules.iter().find(|rule| rule.pattern.is_match(&link.url))?;
+            let (severity, message) = verify(&client, &link.url, &rule.source)?;
+            Some((link, severity, message))
+        })
+        .flat_map(|(link, severity, message)| {
+            link.sources.iter().map(move |page| Finding {
+                rule: ARTIFACT_RULE.to_string(),
+                severity,
+                page: page.clone(),
+                message: message.clone(),
+                target: Some(link.url.clone()),
+                owner: None,
+                other_pages: Vec::new(),
+            })
+        })
+        .collect())
+}
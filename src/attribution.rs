@@ -0,0 +1,155 @@
+use crate::check::{Check, Finding, Page, Severity};
+use crate::config::AttributionConfig;
+use crate::link::{status_label, LinkInfo};
+use regex::Regex;
+use scraper::Selector;
+use std::collections::BTreeSet;
+use url::Url;
+
+/// Rule id of attribution findings
+pub const ATTRIBUTION_RULE: &str = "attribution";
+
+/// Elements embedding third-party content, with the attribute holding its URL
+const EMBEDS: [(&str, &str); 7] = [
+    ("img", "src"),
+    ("iframe", "src"),
+    ("video", "src"),
+    ("audio", "src"),
+    ("source", "src"),
+    ("embed", "src"),
+    ("object", "data"),
+];
+
+/// A compiled `attribution` entry
+#[derive(Debug, Clone)]
+pub struct AttributionRule {
+    name: Option<String>,
+    embed: Regex,
+    link: Regex,
+}
+
+impl AttributionRule {
+    pub fn from_config(config: &AttributionConfig) -> Result<Self, regex::Error> {
+        Ok(AttributionRule {
+            name: config.name.clone(),
+            embed: Regex::new(&config.embed_regex)?,
+            link: Regex::new(&config.link_regex)?,
+        })
+    }
+
+    fn label(&self) -> &str {
+        self.name.as_deref().unwrap_or(self.link.as_str())
+    }
+}
+
+/// Requires pages embedding content matching an `attribution` entry, such
+/// as CC-licensed images, to link to its attribution
+pub struct AttributionCheck {
+    rules: Vec<AttributionRule>,
+}
+
+impl AttributionCheck {
+    /// Compile the `attribution` entries, rejecting invalid regexes
+    pub fn new(configs: &[AttributionConfig]) -> Result<Self, regex::Error> {
+        let rules = configs
+            .iter()
+            .map(AttributionRule::from_config)
+            .collect::<Result<_, _>>()?;
+        Ok(AttributionCheck { rules })
+    }
+}
+
+impl Check for AttributionCheck {
+    fn id(&self) -> &str {
+        ATTRIBUTION_RULE
+    }
+
+    fn check(&self, page: &Page) -> Vec<Finding> {
+        let base = Url::parse(page.url).ok();
+        let mut embedded = BTreeSet::new();
+        for (element, attribute) in EMBEDS {
+            let selector = Selector::parse(&format!("{}[{}]", element, attribute)).unwrap();
+            for value in page
+                .document
+                .select(&selector)
+                .filter_map(|element| element.value().attr(attribute))
+            {
+                let resolved = match &base {
+                    Some(base) => base.join(value.trim()).map(String::from),
+                    None => Url::parse(value.trim()).map(String::from),
+                };
+                if let Ok(url) = resolved {
+                    embedded.insert(url);
+                }
+            }
+        }
+
+        let links = Selector::parse("a[href]").unwrap();
+        let hrefs: Vec<String> = page
+            .document
+            .select(&links)
+            .filter_map(|element| element.value().attr("href"))
+            .filter_map(|href| match &base {
+                Some(base) => base.join(href.trim()).ok().map(String::from),
+                None => Some(href.trim().to_string()),
+            })
+            .collect();
+
+        self.rules
+            .iter()
+            .filter(|rule| !hrefs.iter().any(|href| rule.link.is_match(href)))
+            .filter_map(|rule| {
+                let first = embedded.iter().find(|url| rule.embed.is_match(url))?;
+                Some(Finding {
+                    rule: ATTRIBUTION_RULE.to_string(),
+                    severity: Severity::Error,
+                    page: page.url.to_string(),
+                    message: format!(
+                        "Page embeds {} without an attribution link ({})",
+                        first,
+                        rule.label()
+                    ),
+                    target: Some(first.clone()),
+                    owner: None,
+                    other_pages: Vec::new(),
+                })
+            })
+            .collect()
+    }
+}
+
+/// Findings for broken links matching the attribution link of an
+/// `attribution` entry, one per page linking to them. External attribution
+/// links are only known to resolve when external links are checked.
+pub fn attribution_findings<'a>(
+    configs: &[AttributionConfig],
+    links: impl IntoIterator<Item = &'a LinkInfo>,
+) -> Result<Vec<Finding>, regex::Error> {
+    let rules = configs
+        .iter()
+        .map(AttributionRule::from_config)
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(links
+        .into_iter()
+        .filter(|link| link.is_broken())
+        .filter_map(|link| {
+            let rule = rules.iter().find(|rule| rule.link.is_match(&link.url))?;
+            Some((link, rule))
+        })
+        .flat_map(|(link, rule)| {
+            link.sources.iter().map(move |page| Finding {
+                rule: ATTRIBUTION_RULE.to_string(),
+                severity: Severity::Error,
+                page: page.clone(),
+                message: format!(
+                    "Attribution link ({}) is broken: {}",
+                    rule.label(),
+                    status_label(&link.status)
+                ),
+                target: Some(link.url.clone()),
+                owner: None,
+                other_pages: Vec::new(),
+            })
+        })
+        .collect())
+}
@@ -0,0 +1,169 @@
+use crate::check::{Finding, Severity};
+use crate::config::BlocklistConfig;
+use crate::link::LinkInfo;
+use reqwest::blocking::Client;
+use serde_json::{json, Value};
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
+use std::fs;
+use url::Url;
+
+/// Environment variable holding the Google Safe Browsing API key
+pub const SAFE_BROWSING_API_KEY_ENV: &str = "SAFE_BROWSING_API_KEY";
+/// Rule id of blocklist findings
+pub const BLOCKLIST_RULE: &str = "blocklist";
+
+const SAFE_BROWSING_URL: &str = "https://safebrowsing.googleapis.com/v4/threatMatches:find";
+/// Most URLs a single Safe Browsing lookup accepts
+const SAFE_BROWSING_BATCH: usize = 500;
+
+/// Domains from a local blocklist file. Lines hold a domain, or an address
+/// and a domain as in hosts-file blocklists; `#` starts a comment.
+#[derive(Debug, Default, Clone)]
+pub struct Blocklist {
+    domains: HashSet<String>,
+}
+
+impl Blocklist {
+    pub fn load(path: &str) -> Result<Self, Box<dyn Error>> {
+        let content = fs::read_to_string(path)
+            .map_err(|e| format!("Cannot read blocklist {}: {}", path, e))?;
+        Ok(Blocklist::parse(&content))
+    }
+
+    pub fn parse(content: &str) -> Self {
+        let domains = content
+            .lines()
+            .map(|line| line.split('#').next().unwrap_or_default())
+            .filter_map(|line| line.split_whitespace().last())
+            .map(|domain| domain.trim_end_matches('.').to_ascii_lowercase())
+            .collect();
+        Blocklist { domains }
+    }
+
+    /// Whether the host of a URL, or one of its parent domains, is listed
+    pub fn contains(&self, url: &str) -> bool {
+        let Some(host) = Url::parse(url)
+            .ok()
+            .and_then(|url| url.host_str().map(str::to_ascii_lowercase))
+        else {
+            return false;
+        };
+        let mut domain = host.as_str();
+        loop {
+            if self.domains.contains(domain) {
+                return true;
+            }
+            match domain.split_once('.') {
+                Some((_, parent)) => domain = parent,
+                None => return false,
+            }
+        }
+    }
+}
+
+/// Client for the Google Safe Browsing v4 Lookup API
+pub struct SafeBrowsing {
+    client: Client,
+    key: String,
+}
+
+impl SafeBrowsing {
+    pub fn new(key: &str) -> Self {
+        SafeBrowsing {
+            client: Client::new(),
+            key: key.to_string(),
+        }
+    }
+
+    /// Threat type of each URL Safe Browsing has a match for
+    pub fn lookup(&self, urls: &[String]) -> Result<HashMap<String, String>, Box<dyn Error>> {
+        let mut threats = HashMap::new();
+        for batch in urls.chunks(SAFE_BROWSING_BATCH) {
+            let body = json!({
+                "client": {
+                    "clientId": env!("CARGO_PKG_NAME"),
+                    "clientVersion": env!("CARGO_PKG_VERSION"),
+                },
+                "threatInfo": {
+                    "threatTypes": [
+                        "MALWARE",
+                        "SOCIAL_ENGINEERING",
+                        "UNWANTED_SOFTWARE",
+                        "POTENTIALLY_HARMFUL_APPLICATION",
+                    ],
+                    "platformTypes": ["ANY_PLATFORM"],
+                    "threatEntryTypes": ["URL"],
+                    "threatEntries": batch
+                        .iter()
+                        .map(|url| json!({ "url": url }))
+                        .collect::<Vec<_>>(),
+                },
+            });
+            let response: Value = self
+                .client
+                .post(SAFE_BROWSING_URL)
+                .query(&[("key", &self.key)])
+                .json(&body)
+                .send()?
+                .error_for_status()?
+                .json()?;
+            for threat in response["matches"].as_array().into_iter().flatten() {
+                if let (Some(url), Some(kind)) = (
+                    threat["threat"]["url"].as_str(),
+                    threat["threatType"].as_str(),
+                ) {
+                    threats.insert(url.to_string(), kind.to_string());
+                }
+            }
+        }
+        Ok(threats)
+    }
+}
+
+/// Security findings for links whose domain is on the local blocklist or
+/// flagged by Safe Browsing, one per page linking to them. Only links for
+/// which `is_external` holds are looked up.
+pub fn blocklist_findings(
+    config: &BlocklistConfig,
+    links: &[LinkInfo],
+    is_external: impl Fn(&str) -> bool,
+) -> Result<Vec<Finding>, Box<dyn Error>> {
+    let links: Vec<&LinkInfo> = links.iter().filter(|link| is_external(&link.url)).collect();
+    let mut reasons: HashMap<String, String> = HashMap::new();
+
+    if let Some(path) = &config.file {
+        let blocklist = Blocklist::load(path)?;
+        for link in links.iter().filter(|link| blocklist.contains(&link.url)) {
+            reasons.insert(link.url.clone(), format!("domain is listed in {}", path));
+        }
+    }
+    if config.safe_browsing.unwrap_or(false) {
+        let key = std::env::var(SAFE_BROWSING_API_KEY_ENV).map_err(|_| {
+            format!(
+                "blocklist.safe_browsing requires {}",
+                SAFE_BROWSING_API_KEY_ENV
+            )
+        })?;
+        let urls: Vec<String> = links.iter().map(|link| link.url.clone()).collect();
+        for (url, threat) in SafeBrowsing::new(&key).lookup(&urls)? {
+            reasons.insert(url, format!("Safe Browsing reports {}", threat));
+        }
+    }
+
+    Ok(links
+        .iter()
+        .filter_map(|link| Some((link, reasons.get(&link.url)?)))
+        .flat_map(|(link, reason)| {
+            link.sources.iter().map(move |page| Finding {
+                rule: BLOCKLIST_RULE.to_string(),
+                severity: Severity::Error,
+                page: page.clone(),
+                message: format!("Links to a blocked site: {}", reason),
+                target: Some(link.url.clone()),
+                owner: None,
+                other_pages: Vec::new(),
+            })
+        })
+        .collect())
+}
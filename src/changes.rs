@@ -0,0 +1,74 @@
+use crate::output::ScanReport;
+use crate::store::StoredRun;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, HashSet};
+
+/// How a page differs from the previous run
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChangeKind {
+    Added,
+    Modified,
+}
+
+/// A page whose content changed since the previous run
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PageChange {
+    pub page: String,
+    pub change: ChangeKind,
+    /// Links on this page that are broken now but were not in the previous run
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub new_broken_links: Vec<String>,
+}
+
+/// SHA-256 of a page body, hex encoded
+pub fn content_hash(body: &str) -> String {
+    format!("{:x}", Sha256::digest(body.as_bytes()))
+}
+
+/// Compare the page hashes of a report against the previous run.
+///
+/// Newly broken links are attributed to the changed pages they were found on,
+/// which points at the deploy that introduced them.
+pub fn detect_changes(previous: &StoredRun, report: &ScanReport) -> Vec<PageChange> {
+    let previous_hashes: HashMap<&str, Option<&str>> = previous
+        .links
+        .iter()
+        .map(|link| (link.url.as_str(), link.content_hash.as_deref()))
+        .collect();
+    let previously_broken: HashSet<&str> = previous
+        .links
+        .iter()
+        .filter(|link| link.status.is_broken())
+        .map(|link| link.url.as_str())
+        .collect();
+    let newly_broken: Vec<_> = report
+        .links
+        .iter()
+        .filter(|link| link.is_broken() && !previously_broken.contains(link.url.as_str()))
+        .collect();
+
+    report
+        .links
+        .iter()
+        .filter_map(|link| {
+            let hash = link.content_hash.as_deref()?;
+            let change = match previous_hashes.get(link.url.as_str()) {
+                None => ChangeKind::Added,
+                Some(previous) if *previous != Some(hash) => ChangeKind::Modified,
+                Some(_) => return None,
+            };
+            let new_broken_links = newly_broken
+                .iter()
+                .filter(|broken| broken.sources.contains(&link.url))
+                .map(|broken| broken.url.clone())
+                .collect();
+            Some(PageChange {
+                page: link.url.clone(),
+                change,
+                new_broken_links,
+            })
+        })
+        .collect()
+}
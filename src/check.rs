@@ -0,0 +1,589 @@
+use crate::config::CheckConfig;
+use crate::references::ReferenceCheck;
+use crate::search::SearchIndexCheck;
+use crate::sourcelinks::SourceLinkCheck;
+use crate::videos::VideoCheck;
+use scraper::{Html, Selector};
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::Entry;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::fmt;
+use url::Url;
+
+/// Severity of a finding reported by a check
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Info,
+    Warning,
+    Error,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Severity::Info => write!(f, "info"),
+            Severity::Warning => write!(f, "warning"),
+            Severity::Error => write!(f, "error"),
+        }
+    }
+}
+
+/// A problem reported by a check on a single page
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Finding {
+    pub rule: String,
+    pub severity: Severity,
+    pub page: String,
+    pub message: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub target: Option<String>,
+    /// Team or person owning the page, from the `owners` config
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub owner: Option<String>,
+    /// Other pages with the same finding on the same target, see
+    /// [`aggregate_findings`]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub other_pages: Vec<String>,
+}
+
+/// Merge findings repeated across pages on the same absolute URL target, such
+/// as a blocked external site linked from hundreds of pages, into the first of
+/// them; the other pages are listed in `other_pages`. Findings without such a
+/// target, like missing anchors, are kept as they are. Order is preserved.
+pub fn aggregate_findings(findings: Vec<Finding>) -> Vec<Finding> {
+    type Key = (String, Severity, String, String, Option<String>);
+    let mut merged: Vec<Finding> = Vec::with_capacity(findings.len());
+    let mut first: HashMap<Key, usize> = HashMap::new();
+    for finding in findings {
+        let key = finding
+            .target
+            .as_ref()
+            .filter(|target| Url::parse(target).is_ok())
+            .map(|target| {
+                (
+                    finding.rule.clone(),
+                    finding.severity,
+                    target.clone(),
+                    finding.message.clone(),
+                    finding.owner.clone(),
+                )
+            });
+        let Some(key) = key else {
+            merged.push(finding);
+            continue;
+        };
+        match first.entry(key) {
+            Entry::Occupied(entry) => {
+                let kept = &mut merged[*entry.get()];
+                for page in std::iter::once(finding.page).chain(finding.other_pages) {
+                    if page != kept.page && !kept.other_pages.contains(&page) {
+                        kept.other_pages.push(page);
+                    }
+                }
+            }
+            Entry::Vacant(entry) => {
+                entry.insert(merged.len());
+                merged.push(finding);
+            }
+        }
+    }
+    merged
+}
+
+/// A fetched page handed to checks
+pub struct Page<'a> {
+    pub url: &'a str,
+    pub document: &'a Html,
+    pub links: &'a [String],
+}
+
+/// A lint-style rule run against every fetched page. Checks run on the
+/// checker threads of the crawl pipeline, hence `Send + Sync`.
+pub trait Check: Send + Sync {
+    /// Identifier used in the `checks:` config and in findings
+    fn id(&self) -> &str;
+
+    /// Whether the check runs when the config does not mention it
+    fn enabled_by_default(&self) -> bool {
+        true
+    }
+
+    /// Inspect a page and return any findings
+    fn check(&self, page: &Page) -> Vec<Finding>;
+}
+
+/// Registry of checks, filtered by the `checks:` config at run time
+#[derive(Default)]
+pub struct CheckRegistry {
+    checks: Vec<Box<dyn Check>>,
+}
+
+impl CheckRegistry {
+    /// Create an empty registry
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a registry with the built-in anchor, mixed-content, URL, SEO,
+    /// a11y, heading, source link, reference, video and search index checks
+    pub fn with_builtin() -> Self {
+        let mut registry = Self::new();
+        registry.register(Box::new(AnchorCheck));
+        registry.register(Box::new(MixedContentCheck));
+        registry.register(Box::new(UrlLintCheck::default()));
+        registry.register(Box::new(SeoCheck));
+        registry.register(Box::new(A11yCheck));
+        registry.register(Box::new(HeadingCheck));
+        registry.register(Box::new(SourceLinkCheck::new()));
+        registry.register(Box::new(ReferenceCheck::new()));
+        registry.register(Box::new(VideoCheck::new()));
+        registry.register(Box::new(SearchIndexCheck::new(0)));
+        registry
+    }
+
+    /// Register a check, replacing any existing check with the same id
+    pub fn register(&mut self, check: Box<dyn Check>) {
+        self.checks.retain(|existing| existing.id() != check.id());
+        self.checks.push(check);
+    }
+
+    /// Ids of all registered checks, in registration order
+    pub fn ids(&self) -> Vec<&str> {
+        self.checks.iter().map(|check| check.id()).collect()
+    }
+
+    /// Checks enabled by the given configuration
+    pub fn enabled<'a>(
+        &'a self,
+        config: Option<&'a BTreeMap<String, CheckConfig>>,
+    ) -> impl Iterator<Item = &'a dyn Check> + 'a {
+        self.checks
+            .iter()
+            .filter(move |check| {
+                config
+                    .and_then(|checks| checks.get(check.id()))
+                    .and_then(|rule| rule.enabled)
+                    .unwrap_or_else(|| check.enabled_by_default())
+            })
+            .map(|check| check.as_ref())
+    }
+
+    /// Whether any check is enabled by the given configuration, i.e. whether
+    /// fetched pages need to be parsed into a document
+    pub fn any_enabled(&self, config: Option<&BTreeMap<String, CheckConfig>>) -> bool {
+        self.enabled(config).next().is_some()
+    }
+
+    /// Run every enabled check whose include/exclude scope covers the page
+    pub fn run(&self, page: &Page, config: Option<&BTreeMap<String, CheckConfig>>) -> Vec<Finding> {
+        let path = Url::parse(page.url)
+            .map(|url| url.path().to_string())
+            .unwrap_or_default();
+
+        self.enabled(config)
+            .filter(|check| {
+                config
+                    .and_then(|checks| checks.get(check.id()))
+                    .is_none_or(|rule| rule.in_scope(&path))
+            })
+            .flat_map(|check| check.check(page))
+            .collect()
+    }
+}
+
+fn finding(check: &dyn Check, severity: Severity, page: &Page, message: String) -> Finding {
+    Finding {
+        rule: check.id().to_string(),
+        severity,
+        page: page.url.to_string(),
+        message,
+        target: None,
+        owner: None,
+        other_pages: Vec::new(),
+    }
+}
+
+/// Verifies that in-page `#fragment` links point at an existing id or anchor name
+pub struct AnchorCheck;
+
+impl Check for AnchorCheck {
+    fn id(&self) -> &str {
+        "anchors"
+    }
+
+    fn check(&self, page: &Page) -> Vec<Finding> {
+        let anchors = document_anchors(page.document);
+        let links = Selector::parse("a[href]").unwrap();
+        let mut reported = HashSet::new();
+
+        page.document
+            .select(&links)
+            .filter_map(|element| element.value().attr("href"))
+            .filter_map(|href| href.strip_prefix('#'))
+            .filter(|fragment| !fragment.is_empty() && *fragment != "top")
+            .filter(|fragment| !anchors.contains(*fragment))
+            .filter(|fragment| reported.insert(fragment.to_string()))
+            .map(|fragment| Finding {
+                target: Some(format!("#{}", fragment)),
+                owner: None,
+                ..finding(
+                    self,
+                    Severity::Error,
+                    page,
+                    format!("Anchor #{} does not exist on the page", fragment),
+                )
+            })
+            .collect()
+    }
+}
+
+/// Collect every `id` and `<a name>` defined in a document
+pub fn document_anchors(document: &Html) -> HashSet<String> {
+    let ids = Selector::parse("[id]").unwrap();
+    let names = Selector::parse("a[name]").unwrap();
+
+    document
+        .select(&ids)
+        .filter_map(|element| element.value().attr("id"))
+        .chain(
+            document
+                .select(&names)
+                .filter_map(|element| element.value().attr("name")),
+        )
+        .map(String::from)
+        .collect()
+}
+
+/// Flags `http://` subresources loaded from `https://` pages
+pub struct MixedContentCheck;
+
+impl Check for MixedContentCheck {
+    fn id(&self) -> &str {
+        "mixed-content"
+    }
+
+    fn check(&self, page: &Page) -> Vec<Finding> {
+        let page_url = match Url::parse(page.url) {
+            Ok(url) if url.scheme() == "https" => url,
+            _ => return Vec::new(),
+        };
+
+        // Active content is blocked by browsers, passive content only warns
+        let resources = [
+            ("script[src]", "src", Severity::Error),
+            ("iframe[src]", "src", Severity::Error),
+            ("link[rel=stylesheet][href]", "href", Severity::Error),
+            ("img[src]", "src", Severity::Warning),
+            ("audio[src]", "src", Severity::Warning),
+            ("video[src]", "src", Severity::Warning),
+            ("source[src]", "src", Severity::Warning),
+        ];
+
+        let mut findings = Vec::new();
+        for (selector, attr, severity) in resources {
+            let selector = Selector::parse(selector).unwrap();
+            for element in page.document.select(&selector) {
+                let resource = element
+                    .value()
+                    .attr(attr)
+                    .and_then(|value| page_url.join(value).ok());
+                if let Some(resource) = resource.filter(|url| url.scheme() == "http") {
+                    findings.push(Finding {
+                        target: Some(resource.to_string()),
+                        owner: None,
+                        ..finding(
+                            self,
+                            severity,
+                            page,
+                            format!(
+                                "Insecure <{}> loaded over http: {}",
+                                element.value().name(),
+                                resource
+                            ),
+                        )
+                    });
+                }
+            }
+        }
+        findings
+    }
+}
+
+/// Default limit of the `urls` check
+pub const DEFAULT_MAX_URL_LENGTH: usize = 2048;
+
+/// Flags link targets that are overly long or contain characters that should
+/// have been encoded, which usually point at broken templating in the source
+pub struct UrlLintCheck {
+    pub max_length: usize,
+}
+
+impl Default for UrlLintCheck {
+    fn default() -> Self {
+        UrlLintCheck {
+            max_length: DEFAULT_MAX_URL_LENGTH,
+        }
+    }
+}
+
+impl Check for UrlLintCheck {
+    fn id(&self) -> &str {
+        "urls"
+    }
+
+    fn check(&self, page: &Page) -> Vec<Finding> {
+        let links = Selector::parse("a[href]").unwrap();
+        let mut reported = HashSet::new();
+        let mut findings = Vec::new();
+
+        for href in page
+            .document
+            .select(&links)
+            .filter_map(|element| element.value().attr("href"))
+            .map(str::trim)
+            .filter(|href| reported.insert(href.to_string()))
+        {
+            let length = href.chars().count();
+            if length > self.max_length {
+                findings.push(Finding {
+                    target: Some(href.to_string()),
+                    owner: None,
+                    ..finding(
+                        self,
+                        Severity::Warning,
+                        page,
+                        format!(
+                            "Link is {} characters long (limit: {})",
+                            length, self.max_length
+                        ),
+                    )
+                });
+            }
+
+            let suspicious: Vec<String> = href
+                .chars()
+                .filter(|c| c.is_whitespace() || matches!(c, '"' | '\'' | '<' | '>'))
+                .map(|c| format!("{:?}", c))
+                .collect::<BTreeSet<_>>()
+                .into_iter()
+                .collect();
+            if !suspicious.is_empty() || href.contains("{{") || href.contains("{%") {
+                let reason = if suspicious.is_empty() {
+                    "template syntax".to_string()
+                } else {
+                    format!("unencoded {}", suspicious.join(", "))
+                };
+                findings.push(Finding {
+                    target: Some(href.to_string()),
+                    owner: None,
+                    ..finding(
+                        self,
+                        Severity::Warning,
+                        page,
+                        format!("Link contains {}, likely broken templating", reason),
+                    )
+                });
+            }
+        }
+        findings
+    }
+}
+
+/// Basic SEO hygiene: page title and meta description
+pub struct SeoCheck;
+
+impl Check for SeoCheck {
+    fn id(&self) -> &str {
+        "seo"
+    }
+
+    fn enabled_by_default(&self) -> bool {
+        false
+    }
+
+    fn check(&self, page: &Page) -> Vec<Finding> {
+        let title = Selector::parse("title").unwrap();
+        let description = Selector::parse("meta[name=description]").unwrap();
+        let mut findings = Vec::new();
+
+        let title_text = page
+            .document
+            .select(&title)
+            .next()
+            .map(|element| element.text().collect::<String>().trim().to_string())
+            .unwrap_or_default();
+        if title_text.is_empty() {
+            findings.push(finding(
+                self,
+                Severity::Warning,
+                page,
+                "Page has no <title>".to_string(),
+            ));
+        } else if title_text.chars().count() > 60 {
+            findings.push(finding(
+                self,
+                Severity::Info,
+                page,
+                format!(
+                    "Page title is {} characters long (recommended: 60 or fewer)",
+                    title_text.chars().count()
+                ),
+            ));
+        }
+
+        let has_description = page
+            .document
+            .select(&description)
+            .filter_map(|element| element.value().attr("content"))
+            .any(|content| !content.trim().is_empty());
+        if !has_description {
+            findings.push(finding(
+                self,
+                Severity::Warning,
+                page,
+                "Page has no meta description".to_string(),
+            ));
+        }
+
+        findings
+    }
+}
+
+/// Basic accessibility: image alt text, link text and document language
+pub struct A11yCheck;
+
+impl Check for A11yCheck {
+    fn id(&self) -> &str {
+        "a11y"
+    }
+
+    fn enabled_by_default(&self) -> bool {
+        false
+    }
+
+    fn check(&self, page: &Page) -> Vec<Finding> {
+        let images = Selector::parse("img").unwrap();
+        let links = Selector::parse("a[href]").unwrap();
+        let html = Selector::parse("html").unwrap();
+        let mut findings = Vec::new();
+
+        for image in page.document.select(&images) {
+            if image.value().attr("alt").is_none() {
+                findings.push(Finding {
+                    target: image.value().attr("src").map(String::from),
+                    owner: None,
+                    ..finding(
+                        self,
+                        Severity::Warning,
+                        page,
+                        "Image has no alt attribute".to_string(),
+                    )
+                });
+            }
+        }
+
+        for link in page.document.select(&links) {
+            let has_text = !link.text().collect::<String>().trim().is_empty();
+            let has_label = ["aria-label", "title"]
+                .iter()
+                .any(|attr| link.value().attr(attr).is_some());
+            let has_image_alt = link
+                .select(&images)
+                .any(|image| image.value().attr("alt").is_some_and(|alt| !alt.is_empty()));
+            if !has_text && !has_label && !has_image_alt {
+                findings.push(Finding {
+                    target: link.value().attr("href").map(String::from),
+                    owner: None,
+                    ..finding(
+                        self,
+                        Severity::Warning,
+                        page,
+                        "Link has no accessible text".to_string(),
+                    )
+                });
+            }
+        }
+
+        let has_lang = page
+            .document
+            .select(&html)
+            .next()
+            .and_then(|element| element.value().attr("lang"))
+            .is_some();
+        if !has_lang {
+            findings.push(finding(
+                self,
+                Severity::Info,
+                page,
+                "Document has no lang attribute".to_string(),
+            ));
+        }
+
+        findings
+    }
+}
+
+/// Heading structure required by docs style guides: at least one heading,
+/// a single `<h1>`, and no level skipped on the way down, such as an `<h2>`
+/// followed by an `<h4>`
+pub struct HeadingCheck;
+
+impl Check for HeadingCheck {
+    fn id(&self) -> &str {
+        "headings"
+    }
+
+    fn enabled_by_default(&self) -> bool {
+        false
+    }
+
+    fn check(&self, page: &Page) -> Vec<Finding> {
+        let headings = Selector::parse("h1, h2, h3, h4, h5, h6").unwrap();
+        let levels: Vec<(usize, String)> = page
+            .document
+            .select(&headings)
+            .map(|heading| {
+                let level = heading.value().name()[1..].parse().unwrap_or(1);
+                let text = heading.text().collect::<String>();
+                (level, text.split_whitespace().collect::<Vec<_>>().join(" "))
+            })
+            .collect();
+        let mut findings = Vec::new();
+
+        if levels.is_empty() {
+            findings.push(finding(
+                self,
+                Severity::Warning,
+                page,
+                "Page has no headings".to_string(),
+            ));
+        }
+
+        let h1s = levels.iter().filter(|(level, _)| *level == 1).count();
+        if h1s > 1 {
+            findings.push(finding(
+                self,
+                Severity::Warning,
+                page,
+                format!("Page has {} h1 headings", h1s),
+            ));
+        }
+
+        for pair in levels.windows(2) {
+            let ((previous, _), (level, text)) = (&pair[0], &pair[1]);
+            if *level > previous + 1 {
+                findings.push(finding(
+                    self,
+                    Severity::Warning,
+                    page,
+                    format!(
+                        "Heading \"{}\" skips from h{} to h{}",
+                        text, previous, level
+                    ),
+                ));
+            }
+        }
+        findings
+    }
+}
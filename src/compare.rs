@@ -0,0 +1,122 @@
+use crate::link::{LinkInfo, LinkStatus};
+use crate::output::ScanReport;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::error::Error;
+use std::fs;
+use url::Url;
+
+/// Old URL or path mapped to its new URL or path
+pub type RedirectMap = BTreeMap<String, String>;
+
+/// Load a redirect map from a YAML mapping of `old: new` entries
+pub fn load_redirect_map(path: &str) -> Result<RedirectMap, Box<dyn Error>> {
+    Ok(serde_yaml::from_str(&fs::read_to_string(path)?)?)
+}
+
+/// How an old URL fares on the new site
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "outcome", rename_all = "snake_case")]
+pub enum MigrationOutcome {
+    /// The same path is valid on the new site
+    Direct,
+    /// The redirect map points at a path that is valid on the new site
+    Redirected { to: String },
+    /// The redirect map points at a path that is missing or broken on the new site
+    BrokenRedirect { to: String },
+    /// Neither the same path nor a redirect resolves on the new site
+    Missing,
+}
+
+/// Result of checking one URL of the old site
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct MigrationCheck {
+    pub url: String,
+    #[serde(flatten)]
+    pub outcome: MigrationOutcome,
+}
+
+impl MigrationCheck {
+    /// Whether the old URL still resolves on the new site
+    pub fn is_resolved(&self) -> bool {
+        matches!(
+            self.outcome,
+            MigrationOutcome::Direct | MigrationOutcome::Redirected { .. }
+        )
+    }
+}
+
+/// Comparison key of a URL: its path (plus query) without a trailing slash
+fn path_key(url: &str) -> String {
+    let (path, query) = match Url::parse(url) {
+        Ok(url) => (url.path().to_string(), url.query().map(String::from)),
+        Err(_) => (url.split('#').next().unwrap_or(url).to_string(), None),
+    };
+    let path = match path.trim_end_matches('/') {
+        "" => "/".to_string(),
+        trimmed => trimmed.to_string(),
+    };
+    match query {
+        Some(query) => format!("{}?{}", path, query),
+        None => path,
+    }
+}
+
+/// Verify that every valid page of the old site resolves on the new one.
+///
+/// URLs are matched by path so the new site may live on another origin.
+/// Redirect map keys may be full URLs or paths.
+pub fn compare_snapshots(
+    before: &ScanReport,
+    after: &ScanReport,
+    redirects: &RedirectMap,
+) -> Vec<MigrationCheck> {
+    let after_pages: HashMap<String, &LinkInfo> = after
+        .links
+        .iter()
+        .map(|link| (path_key(&link.url), link))
+        .collect();
+    let redirects: HashMap<String, &String> = redirects
+        .iter()
+        .map(|(from, to)| (path_key(from), to))
+        .collect();
+    let is_valid = |key: &str| {
+        after_pages
+            .get(key)
+            .is_some_and(|link| link.status == LinkStatus::Valid)
+    };
+
+    let mut checked = HashMap::new();
+    for link in &before.links {
+        if link.status != LinkStatus::Valid || link.url.contains('#') {
+            continue;
+        }
+        let key = path_key(&link.url);
+        if checked.contains_key(&key) {
+            continue;
+        }
+
+        let outcome = if is_valid(&key) {
+            MigrationOutcome::Direct
+        } else if let Some(to) = redirects.get(&key) {
+            if is_valid(&path_key(to)) {
+                MigrationOutcome::Redirected { to: to.to_string() }
+            } else {
+                MigrationOutcome::BrokenRedirect { to: to.to_string() }
+            }
+        } else {
+            MigrationOutcome::Missing
+        };
+        checked.insert(
+            key,
+            MigrationCheck {
+                url: link.url.clone(),
+                outcome,
+            },
+        );
+    }
+
+    let mut checks: Vec<MigrationCheck> = checked.into_values().collect();
+    checks.sort_by(|a, b| a.url.cmp(&b.url));
+    checks
+}
@@ -1,19 +1,330 @@
-use serde::{Deserialize, Serialize};
-use serde_yaml::Value;
+use crate::agents::AGENTS;
+use crate::artifacts::ChecksumSource;
+use crate::gate::{FailOn, FailThreshold};
+use crate::memory::parse_size;
+use crate::pipeline::{CONCURRENCY_RANGE, WORKERS_RANGE};
+use crate::schedule::{is_weekday, parse_time_of_day, BlackoutWindow};
+use crate::scope::IpNetwork;
+use crate::secret::{is_sensitive_header, redacted_headers, serialize_headers, Secret};
+use crate::store::{parse_label, Labels, TrustConfig};
+use log::warn;
+use serde::de::{self, Deserializer, Visitor};
+use serde::{forward_to_deserialize_any, Deserialize, Serialize};
+use serde_yaml::{Mapping, Value};
+use std::collections::BTreeMap;
 use std::error::Error;
+use std::fmt;
 use std::fs;
+use std::ops::RangeInclusive;
 use std::path::PathBuf;
 use thiserror::Error;
 
 /// Configuration structure for the Inspector CLI
-#[derive(Debug, Serialize, Deserialize, Default)]
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct Config {
     pub url: Option<String>,
     pub ignore: Option<IgnoreConfig>,
     pub forbidden_domains: Option<Vec<String>>,
     pub ignored_childs: Option<Vec<String>>,
     pub timeout: Option<u64>,
+    /// Retries of requests failing with a timeout, a dropped connection or a 5xx
+    pub retries: Option<u32>,
     pub default_output: Option<String>,
+    pub outputs: Option<Vec<OutputConfig>>,
+    pub checks: Option<BTreeMap<String, CheckConfig>>,
+    pub manifest: Option<String>,
+    pub dns_workers: Option<usize>,
+    pub store: Option<String>,
+    /// JSON file keeping the latest result of every URL between runs, whose
+    /// pages are revalidated unless `revalidate` is off
+    pub cache: Option<String>,
+    pub trust: Option<TrustConfig>,
+    /// Request pages of the previous stored run conditionally, reusing their
+    /// links when they are not modified
+    pub revalidate: Option<bool>,
+    pub overrides: Option<Vec<OverrideConfig>>,
+    /// URL schemes that are followed; links with any other scheme are reported as unsupported
+    pub allowed_schemes: Option<Vec<String>>,
+    /// Longest link target accepted by the `urls` check
+    pub max_url_length: Option<usize>,
+    /// Indexed URLs of each search index checked by the `search-index` check
+    pub search_sample: Option<usize>,
+    /// Languages to crawl; pages in other language subtrees are checked but not crawled
+    pub langs: Option<Vec<String>>,
+    /// First path segments of language subtrees, taken for languages without
+    /// `hreflang` alternates confirming them
+    pub lang_prefixes: Option<Vec<String>>,
+    /// Time windows during which scans are suspended
+    pub blackout: Option<Vec<BlackoutWindow>>,
+    /// Frontmatter keys whose URLs are checked in local mode
+    pub frontmatter_keys: Option<Vec<String>>,
+    /// What to do with URLs inside code blocks
+    pub code_links: Option<CodeLinkPolicy>,
+    /// Domains reported as placeholders in addition to the built-in example domains
+    pub placeholder_domains: Option<Vec<String>>,
+    /// Regexes of sign-in pages in addition to the built-in ones; links
+    /// redirected to one are reported as `AuthRequired`
+    pub login_urls: Option<Vec<String>>,
+    /// Regexes of analytics endpoints in addition to the built-in ones;
+    /// assets matching one are listed instead of checked
+    pub tracking_urls: Option<Vec<String>>,
+    /// Refuse to request hosts resolving to loopback, private or link-local addresses
+    pub block_private_ips: Option<bool>,
+    /// Skip URLs the robots.txt of their host disallows for us
+    pub respect_robots: Option<bool>,
+    /// Check links outside the start URL with a single request, without crawling them
+    pub check_external: Option<bool>,
+    /// Check the images, scripts, stylesheets and media pages load
+    pub check_assets: Option<bool>,
+    /// Check the AMP, print and feed versions pages announce
+    pub check_alternates: Option<bool>,
+    /// Crawled URLs to probe again as other user agents
+    pub user_agents: Option<UserAgentsConfig>,
+    /// Crawled URLs to probe again with other `Accept-Language` headers
+    pub accept_languages: Option<AcceptLanguagesConfig>,
+    /// Second origin, such as staging, whose copy of every crawled path is
+    /// fetched and compared
+    pub compare_origin: Option<String>,
+    /// Sitemap whose pages seed the crawl, relative to the start URL or absolute
+    pub sitemap: Option<String>,
+    /// IP ranges and ports scans may contact
+    pub network: Option<NetworkConfig>,
+    /// Malware and phishing lookups for external links
+    pub blocklist: Option<BlocklistConfig>,
+    /// Downloads whose SHA-256 is checked against their published checksum
+    pub artifacts: Option<Vec<ArtifactConfig>>,
+    /// Third-party content pages must link to the attribution of
+    pub attribution: Option<Vec<AttributionConfig>>,
+    /// Browser-callable API endpoints checked with a CORS preflight
+    pub cors: Option<Vec<CorsConfig>>,
+    /// In-page tables of contents compared with the headings of their page
+    pub toc: Option<TocConfig>,
+    /// External commands checking URLs matching their patterns instead of a request
+    pub resolvers: Option<Vec<ResolverConfig>>,
+    /// WebAssembly modules run as page checks, with the `plugins` feature
+    pub plugins: Option<Vec<String>>,
+    /// CSV file receiving the old and new URLs of internal links that
+    /// permanently redirect
+    pub rewrite_map: Option<String>,
+    /// Broken links counted against `fail_threshold` (default: any)
+    pub fail_on: Option<FailOn>,
+    /// Broken links a scan may have before exiting non-zero: a count, or a
+    /// percentage of the checked links like `2.5%` (default: 0)
+    pub fail_threshold: Option<String>,
+    /// Age after which pages of the site are reported as stale, e.g. `6months`
+    pub stale_after: Option<String>,
+    /// Site sections that get their own link health rollup
+    pub sections: Option<Vec<SectionConfig>>,
+    /// Team, email or chat channel owning each path prefix of the site
+    pub owners: Option<BTreeMap<String, String>>,
+    /// Directory receiving one report per owner with only their findings
+    pub owner_reports: Option<String>,
+    /// Number of stored runs shown in trend charts
+    pub trend_runs: Option<usize>,
+    /// Title, logo and links shown in the HTML and Markdown reports
+    pub report: Option<ReportConfig>,
+    /// Language of messages and reports, e.g. `es` or `pt-BR`
+    pub locale: Option<String>,
+    /// Directory holding `<locale>.ftl` translations
+    pub locales_dir: Option<String>,
+    /// Approximate memory a crawl may use, e.g. `512MiB`
+    pub max_memory: Option<String>,
+    /// Requests in flight at once during a crawl
+    pub concurrency: Option<usize>,
+    /// Milliseconds between two requests to the same host
+    pub delay_ms: Option<u64>,
+    /// Milliseconds between requests to a domain and its subdomains,
+    /// replacing `delay_ms`
+    pub host_delays: Option<BTreeMap<String, u64>>,
+    /// Threads extracting links from fetched pages
+    pub parse_workers: Option<usize>,
+    /// Threads running page checks
+    pub check_workers: Option<usize>,
+    /// Credentials sent to the scanned site, referenced rather than inline
+    pub auth: Option<AuthConfig>,
+    /// Login form submitted before the crawl, whose session cookies are
+    /// sent with every request to the scanned site
+    pub login: Option<LoginConfig>,
+    /// Proxy every request goes through, for networks without direct access
+    pub proxy: Option<ProxyConfig>,
+    /// Certificate authorities, client certificate and verification of TLS
+    /// connections
+    pub tls: Option<TlsConfig>,
+    /// `key: value` labels attached to reports and stored runs
+    pub labels: Option<Labels>,
+    /// Named sets of settings applied over the rest of the file with `--profile`
+    pub profiles: Option<BTreeMap<String, Value>>,
+}
+
+/// Seconds each HTTP request may take when `timeout` is not set
+pub const DEFAULT_TIMEOUT: u64 = 30;
+
+/// Schemes followed when `allowed_schemes` is not set
+#[cfg(not(feature = "ftp"))]
+pub const DEFAULT_ALLOWED_SCHEMES: &[&str] = &["http", "https"];
+/// Schemes followed when `allowed_schemes` is not set, FTP and SFTP links
+/// being probed for existence
+#[cfg(feature = "ftp")]
+pub const DEFAULT_ALLOWED_SCHEMES: &[&str] = &["http", "https", "ftp", "sftp"];
+/// Request timeouts accepted, in seconds
+pub const TIMEOUT_RANGE: RangeInclusive<u64> = 1..=600;
+/// Retries of transient failures accepted
+pub const RETRIES_RANGE: RangeInclusive<u64> = 0..=10;
+/// Timeout below which slow pages are likely to be reported as broken
+const SHORT_TIMEOUT: u64 = 5;
+/// Delays between requests to a host accepted, in milliseconds
+pub const DELAY_RANGE: RangeInclusive<u64> = 0..=60_000;
+/// Background DNS workers accepted; 0 disables pre-resolution
+pub const DNS_WORKERS_RANGE: RangeInclusive<u64> = 0..=1024;
+
+impl Config {
+    /// Whether links with the given scheme are followed
+    pub fn allows_scheme(&self, scheme: &str) -> bool {
+        match &self.allowed_schemes {
+            Some(schemes) => schemes
+                .iter()
+                .any(|allowed| allowed.eq_ignore_ascii_case(scheme)),
+            None => DEFAULT_ALLOWED_SCHEMES.contains(&scheme),
+        }
+    }
+
+    /// Settings that are valid on their own but likely not what was meant,
+    /// such as rules that contradict each other
+    pub fn warnings(&self) -> Vec<String> {
+        let mut warnings = Vec::new();
+        if let Some(url) = self
+            .url
+            .as_deref()
+            .and_then(|url| url::Url::parse(url).ok())
+        {
+            let host = url.host_str().unwrap_or_default();
+            let ignored = self
+                .ignore
+                .as_ref()
+                .and_then(|ignore| ignore.domains.as_ref())
+                .into_iter()
+                .flatten()
+                .map(|domain| ("ignore.domains", domain))
+                .chain(
+                    self.forbidden_domains
+                        .iter()
+                        .flatten()
+                        .map(|domain| ("forbidden_domains", domain)),
+                );
+            for (list, domain) in ignored {
+                if host.ends_with(domain.as_str()) {
+                    warnings.push(format!(
+                        "{} lists {}, which matches the url {}: nothing will be scanned",
+                        list, domain, url
+                    ));
+                }
+            }
+            if !self.allows_scheme(url.scheme()) {
+                warnings.push(format!(
+                    "allowed_schemes does not include {}, the scheme of the url",
+                    url.scheme()
+                ));
+            }
+        }
+        if let Some(network) = &self.network {
+            for range in network.allow.iter().flatten() {
+                if network.deny.iter().flatten().any(|denied| denied == range) {
+                    warnings.push(format!(
+                        "{} is in both network.allow and network.deny; it is denied",
+                        range
+                    ));
+                }
+            }
+        }
+        if let Some(timeout) = self.timeout.filter(|timeout| *timeout < SHORT_TIMEOUT) {
+            warnings.push(format!(
+                "timeout is {}s; slow pages are likely to be reported as broken",
+                timeout
+            ));
+        }
+        for entry in self.overrides.iter().flatten() {
+            for name in entry.headers.iter().flatten().map(|(name, _)| name) {
+                if is_sensitive_header(name) {
+                    warnings.push(format!(
+                        "overrides[].headers sets {} inline; keep secrets out of the config with auth.bearer_env or auth.bearer_file",
+                        name
+                    ));
+                }
+            }
+        }
+        let login_fields = self
+            .login
+            .iter()
+            .flat_map(|login| login.fields.iter().flatten());
+        for name in login_fields.map(|(name, _)| name) {
+            if is_sensitive_header(name) {
+                warnings.push(format!(
+                    "login.fields sets {} inline; keep secrets out of the config with login.fields_env",
+                    name
+                ));
+            }
+        }
+        if self.tls.as_ref().and_then(|tls| tls.insecure) == Some(true) {
+            warnings.push(
+                "tls.insecure is set; server certificates are not verified, so any server can impersonate the scanned sites".to_string(),
+            );
+        }
+        warnings
+    }
+
+    /// The config as YAML, without unset fields. Secrets are never written:
+    /// credentials are only referenced, and credential headers are redacted.
+    pub fn to_yaml(&self) -> Result<String, serde_yaml::Error> {
+        let mut value = serde_yaml::to_value(self)?;
+        strip_nulls(&mut value);
+        serde_yaml::to_string(&value)
+    }
+}
+
+/// Remove null entries from the mappings in `value`, recursively
+fn strip_nulls(value: &mut Value) {
+    match value {
+        Value::Mapping(mapping) => {
+            *mapping = std::mem::take(mapping)
+                .into_iter()
+                .filter(|(_, value)| !value.is_null())
+                .map(|(key, mut value)| {
+                    strip_nulls(&mut value);
+                    (key, value)
+                })
+                .collect();
+        }
+        Value::Sequence(values) => values.iter_mut().for_each(strip_nulls),
+        _ => {}
+    }
+}
+
+/// Handling of URLs inside fenced code blocks and `<pre>` elements, which are
+/// usually examples rather than real links
+#[derive(Debug, Serialize, Deserialize, Default, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum CodeLinkPolicy {
+    /// Check them like any other link
+    Check,
+    /// Leave them out of the scan
+    #[default]
+    Skip,
+    /// List them in the ignored links with a `CodeBlock` status, unchecked
+    Report,
+}
+
+impl CodeLinkPolicy {
+    pub const NAMES: [&'static str; 3] = ["check", "skip", "report"];
+
+    /// Parse a policy name
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "check" => Some(CodeLinkPolicy::Check),
+            "skip" => Some(CodeLinkPolicy::Skip),
+            "report" => Some(CodeLinkPolicy::Report),
+            _ => None,
+        }
+    }
 }
 
 /// Ignore configuration structure
@@ -23,90 +334,1890 @@ pub struct IgnoreConfig {
     pub regex: Option<Vec<String>>,
 }
 
-#[derive(Error, Debug)]
+/// A single output destination from the `outputs` list
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct OutputConfig {
+    pub format: String,
+    pub file: Option<String>,
+}
+
+/// Per-check settings from the `checks` map, keyed by check id
+#[derive(Debug, Serialize, Deserialize, Default, Clone, PartialEq)]
+pub struct CheckConfig {
+    pub enabled: Option<bool>,
+    /// Path prefixes the check is limited to (all pages when unset)
+    pub include: Option<Vec<String>>,
+    /// Path prefixes the check never runs on
+    pub exclude: Option<Vec<String>>,
+}
+
+impl CheckConfig {
+    /// Whether a page path is within the include/exclude scope of this rule
+    pub fn in_scope(&self, path: &str) -> bool {
+        let included = self.include.as_ref().is_none_or(|prefixes| {
+            prefixes
+                .iter()
+                .any(|prefix| path.starts_with(prefix.as_str()))
+        });
+        let excluded = self.exclude.as_ref().is_some_and(|prefixes| {
+            prefixes
+                .iter()
+                .any(|prefix| path.starts_with(prefix.as_str()))
+        });
+        included && !excluded
+    }
+}
+
+/// Network destinations a scan may contact. Every address a host resolves to
+/// must pass.
+#[derive(Debug, Serialize, Deserialize, Default, Clone, PartialEq)]
+pub struct NetworkConfig {
+    /// CIDR ranges that may be contacted (any when unset)
+    pub allow: Option<Vec<String>>,
+    /// CIDR ranges that are never contacted
+    pub deny: Option<Vec<String>>,
+    /// Ports that may be contacted (any when unset)
+    pub ports: Option<Vec<u16>>,
+}
+
+/// A site section: pages under `path`, reported as `label`
+#[derive(Debug, Serialize, Deserialize, Default, Clone, PartialEq)]
+pub struct SectionConfig {
+    pub path: String,
+    pub label: Option<String>,
+}
+
+/// Branding of the published reports
+#[derive(Debug, Serialize, Deserialize, Default, Clone, PartialEq)]
+pub struct ReportConfig {
+    pub title: Option<String>,
+    pub logo_url: Option<String>,
+    /// Links shown in the report header, e.g. to runbooks
+    pub links: Option<Vec<ReportLink>>,
+}
+
+/// A link shown in the report header
+#[derive(Debug, Serialize, Deserialize, Default, Clone, PartialEq)]
+pub struct ReportLink {
+    pub label: String,
+    pub url: String,
+}
+
+/// Where external link domains are looked up for malware and phishing
+#[derive(Debug, Serialize, Deserialize, Default, Clone, PartialEq)]
+pub struct BlocklistConfig {
+    /// Local blocklist file with one domain per line
+    pub file: Option<String>,
+    /// Also query Google Safe Browsing (needs `SAFE_BROWSING_API_KEY`)
+    pub safe_browsing: Option<bool>,
+}
+
+/// Downloadable artifacts matching `url_regex` and where their checksum is
+/// published: `adjacent <suffix>` for a file next to each artifact, or the
+/// URL of a checksum file, relative to the artifact or absolute
+#[derive(Debug, Serialize, Deserialize, Default, Clone, PartialEq)]
+pub struct ArtifactConfig {
+    pub url_regex: String,
+    pub sha256_from: String,
+}
+
+/// Embedded content matching `embed_regex`, such as CC-licensed images,
+/// whose pages must link to a URL matching `link_regex`
+#[derive(Debug, Serialize, Deserialize, Default, Clone, PartialEq)]
+pub struct AttributionConfig {
+    /// Name of the source or license, used in findings
+    pub name: Option<String>,
+    pub embed_regex: String,
+    pub link_regex: String,
+}
+
+/// API endpoints matching `url_regex` that pages on `origin` call from the
+/// browser, checked with the preflight a browser would send first
+#[derive(Debug, Serialize, Deserialize, Default, Clone, PartialEq)]
+pub struct CorsConfig {
+    pub url_regex: String,
+    /// Origin of the calling page, e.g. `https://docs.example.com`
+    pub origin: String,
+    /// Method of the cross-origin request (default: GET)
+    pub method: Option<String>,
+    /// Request headers the browser asks to send, such as `Authorization`
+    pub headers: Option<Vec<String>>,
+    /// Whether the request is sent with cookies or HTTP authentication
+    pub credentials: Option<bool>,
+}
+
+/// Where the hand-written table of contents of a page is, and the deepest
+/// heading level it must list
+#[derive(Debug, Serialize, Deserialize, Default, Clone, PartialEq)]
+pub struct TocConfig {
+    /// CSS selector of the table of contents, e.g. `nav.toc`
+    pub selector: String,
+    /// Headings from `<h2>` down to this level must be listed (default: 3)
+    pub max_level: Option<usize>,
+}
+
+/// A command checking the URLs matching `url_regex`: the URL is written to
+/// its stdin and it prints a JSON status, see [`crate::resolvers`]
+#[derive(Debug, Serialize, Deserialize, Default, Clone, PartialEq)]
+pub struct ResolverConfig {
+    pub url_regex: String,
+    /// Program and arguments, run without a shell
+    pub command: Vec<String>,
+}
+
+/// Crawled URLs matching `url_regex` are probed once per agent: a built-in
+/// name of [`crate::agents::AGENTS`] or a `User-Agent` header
+#[derive(Debug, Serialize, Deserialize, Default, Clone, PartialEq)]
+pub struct UserAgentsConfig {
+    pub url_regex: String,
+    /// Every built-in agent when unset
+    pub agents: Option<Vec<String>>,
+}
+
+impl UserAgentsConfig {
+    pub fn agents(&self) -> Vec<String> {
+        match &self.agents {
+            Some(agents) => agents.clone(),
+            None => AGENTS.iter().map(|(name, _)| name.to_string()).collect(),
+        }
+    }
+}
+
+/// Crawled URLs matching `url_regex` are probed once per language, sent as
+/// `Accept-Language`
+#[derive(Debug, Serialize, Deserialize, Default, Clone, PartialEq)]
+pub struct AcceptLanguagesConfig {
+    pub url_regex: String,
+    /// Language tags, such as `de` or `pt-BR`
+    pub languages: Vec<String>,
+}
+
+/// Request settings applied to URLs matching `url_regex`
+#[derive(Serialize, Deserialize, Default, Clone, PartialEq)]
+pub struct OverrideConfig {
+    pub url_regex: String,
+    /// Timeout in seconds, replacing the global `timeout`
+    pub timeout: Option<u64>,
+    /// Extra request headers; values of credential headers are never
+    /// serialized back out
+    #[serde(serialize_with = "serialize_headers")]
+    pub headers: Option<BTreeMap<String, String>>,
+    /// How matching links are checked instead of a GET or HEAD request
+    pub probe: Option<ProbeConfig>,
+}
+
+/// A request made instead of the usual GET or HEAD, such as an `OPTIONS`
+/// request for endpoints that only answer CORS preflights, or a GraphQL
+/// query. Probed links are never crawled.
+#[derive(Debug, Serialize, Deserialize, Default, Clone, PartialEq)]
+pub struct ProbeConfig {
+    pub method: Option<String>,
+    /// GraphQL query POSTed to the endpoint, whose answer must be a GraphQL
+    /// response
+    pub graphql: Option<GraphqlProbe>,
+}
+
+/// Query of a GraphQL probe
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum GraphqlProbe {
+    /// `{ __typename }`, which every GraphQL server answers
+    Ping,
+    /// The name of the schema's query type, failing when introspection is
+    /// disabled
+    Introspection,
+}
+
+impl GraphqlProbe {
+    pub const NAMES: [&'static str; 2] = ["ping", "introspection"];
+
+    /// Parse a query name
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "ping" => Some(GraphqlProbe::Ping),
+            "introspection" => Some(GraphqlProbe::Introspection),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Debug for OverrideConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("OverrideConfig")
+            .field("url_regex", &self.url_regex)
+            .field("timeout", &self.timeout)
+            .field("headers", &self.headers.as_ref().map(redacted_headers))
+            .field("probe", &self.probe)
+            .finish()
+    }
+}
+
+/// The method of a `probe`, as sent: upper case, without spaces
+pub fn probe_method(method: &str) -> Option<reqwest::Method> {
+    let method = method.trim().to_ascii_uppercase();
+    if method.is_empty() || !method.chars().all(|c| c.is_ascii_alphabetic()) {
+        return None;
+    }
+    reqwest::Method::from_bytes(method.as_bytes()).ok()
+}
+
+/// Credentials sent to the scanned site. Secrets are referenced by the name
+/// of an environment variable or the path of a file holding them, never
+/// written inline, so the config can be committed with the repository.
+#[derive(Debug, Serialize, Deserialize, Default, Clone, PartialEq)]
+pub struct AuthConfig {
+    /// Environment variable holding a bearer token
+    pub bearer_env: Option<String>,
+    /// File holding a bearer token, e.g. a mounted secret
+    pub bearer_file: Option<String>,
+    /// Environment variable holding `user:password` for basic authentication
+    pub basic_env: Option<String>,
+    /// File holding `user:password` for basic authentication
+    pub basic_file: Option<String>,
+    /// Token of `--auth-bearer`, never read from the config file
+    #[serde(default, skip_deserializing, skip_serializing_if = "Option::is_none")]
+    pub bearer: Option<Secret>,
+    /// `user:password` of `--auth-basic`, never read from the config file
+    #[serde(default, skip_deserializing, skip_serializing_if = "Option::is_none")]
+    pub basic: Option<Secret>,
+}
+
+/// Environment variable read when `--auth-basic` is not given
+pub const AUTH_BASIC_ENV: &str = "INSPECTOR_AUTH_BASIC";
+/// Environment variable read when `--auth-bearer` is not given
+pub const AUTH_BEARER_ENV: &str = "INSPECTOR_AUTH_BEARER";
+
+/// Keys under `auth` that would hold a secret inline
+const INLINE_SECRET_KEYS: [&str; 4] = ["bearer", "basic", "token", "password"];
+
+impl AuthConfig {
+    /// The bearer token, from `--auth-bearer` or read from its environment
+    /// variable or file
+    pub fn bearer_token(&self) -> Result<Option<Secret>, String> {
+        if let Some(token) = &self.bearer {
+            return Ok(Some(token.clone()));
+        }
+        match (&self.bearer_env, &self.bearer_file) {
+            (Some(name), _) => Secret::from_env(name)
+                .map(Some)
+                .map_err(|e| format!("auth.bearer_env: {}", e)),
+            (None, Some(path)) => Secret::from_file(path)
+                .map(Some)
+                .map_err(|e| format!("auth.bearer_file: {}", e)),
+            (None, None) => Ok(None),
+        }
+    }
+
+    /// The `user:password` of basic authentication, from `--auth-basic` or
+    /// read from its environment variable or file
+    pub fn basic_credentials(&self) -> Result<Option<Secret>, String> {
+        let (credentials, source) = match (&self.basic, &self.basic_env, &self.basic_file) {
+            (Some(credentials), _, _) => (credentials.clone(), "--auth-basic"),
+            (None, Some(name), _) => (
+                Secret::from_env(name).map_err(|e| format!("auth.basic_env: {}", e))?,
+                "auth.basic_env",
+            ),
+            (None, None, Some(path)) => (
+                Secret::from_file(path).map_err(|e| format!("auth.basic_file: {}", e))?,
+                "auth.basic_file",
+            ),
+            (None, None, None) => return Ok(None),
+        };
+        if !credentials.expose().contains(':') {
+            return Err(format!("{}: expected user:password", source));
+        }
+        Ok(Some(credentials))
+    }
+}
+
+/// A login form filled in and submitted before the crawl
+#[derive(Debug, Serialize, Deserialize, Default, Clone, PartialEq)]
+pub struct LoginConfig {
+    /// Page holding the login form
+    pub url: String,
+    /// Values of form fields by name, such as the username
+    pub fields: Option<BTreeMap<String, String>>,
+    /// Environment variables holding the values of secret fields, by field
+    /// name, such as the password
+    pub fields_env: Option<BTreeMap<String, String>>,
+    /// CSS selector of an element only shown once logged in
+    pub success_selector: String,
+    /// CSS selector of the form, when the page has several (default: the
+    /// form with a password field, else the first one)
+    pub form_selector: Option<String>,
+}
+
+/// An HTTP(S) or SOCKS5 proxy. Like `auth`, its credentials are referenced
+/// by the name of an environment variable or the path of a file.
+#[derive(Debug, Serialize, Deserialize, Default, Clone, PartialEq)]
+pub struct ProxyConfig {
+    /// `http://`, `https://`, `socks5://` or `socks5h://` URL of the proxy;
+    /// `socks5h` resolves host names through the proxy
+    pub url: String,
+    /// Environment variable holding `user:password` for the proxy
+    pub basic_env: Option<String>,
+    /// File holding `user:password` for the proxy
+    pub basic_file: Option<String>,
+    /// Hosts, domains and IP networks reached directly, in the syntax of the
+    /// `NO_PROXY` environment variable
+    pub no_proxy: Option<Vec<String>>,
+}
+
+/// Schemes of proxy URLs
+const PROXY_SCHEMES: [&str; 4] = ["http", "https", "socks5", "socks5h"];
+
+impl ProxyConfig {
+    /// The `user:password` of the proxy, read from its environment variable
+    /// or file
+    pub fn basic_credentials(&self) -> Result<Option<Secret>, String> {
+        let (credentials, source) = match (&self.basic_env, &self.basic_file) {
+            (Some(name), _) => (
+                Secret::from_env(name).map_err(|e| format!("proxy.basic_env: {}", e))?,
+                "proxy.basic_env",
+            ),
+            (None, Some(path)) => (
+                Secret::from_file(path).map_err(|e| format!("proxy.basic_file: {}", e))?,
+                "proxy.basic_file",
+            ),
+            (None, None) => return Ok(None),
+        };
+        if !credentials.expose().contains(':') {
+            return Err(format!("{}: expected user:password", source));
+        }
+        Ok(Some(credentials))
+    }
+}
+
+/// TLS settings for servers signed by a private certificate authority or
+/// requiring a client certificate
+#[derive(Debug, Serialize, Deserialize, Default, Clone, PartialEq)]
+pub struct TlsConfig {
+    /// PEM file of certificate authorities trusted in addition to the
+    /// system ones, e.g. a corporate root CA
+    pub ca_cert: Option<String>,
+    /// PEM file of the client certificate, for servers requiring mutual TLS
+    pub client_cert: Option<String>,
+    /// PEM file of the PKCS#8 private key of `client_cert`
+    pub client_key: Option<String>,
+    /// Accept any server certificate and host name, for testing only
+    pub insecure: Option<bool>,
+}
+
+#[derive(Error, Debug, PartialEq)]
 pub enum ConfigError {
     #[error("Missing required field: {0}")]
     MissingField(String),
     #[error("Invalid field type: {0}")]
     InvalidFieldType(String),
+    #[error("Unknown config keys: {}", .0.iter().map(ToString::to_string).collect::<Vec<_>>().join(", "))]
+    UnknownKeys(Vec<UnknownKey>),
+    #[error("Invalid config:\n  - {}", .0.iter().map(ToString::to_string).collect::<Vec<_>>().join("\n  - "))]
+    Invalid(Vec<ConfigError>),
+    #[error("Unknown profile {0} (defined: {1})")]
+    UnknownProfile(String, String),
+}
+
+/// A key of the config file that no setting reads, e.g. a typo
+#[derive(Debug, Clone, PartialEq)]
+pub struct UnknownKey {
+    /// Dotted path of the key, e.g. `report.titel` or `sections.0.lable`
+    pub path: String,
+    /// Path of the closest known key, if one is close enough
+    pub suggestion: Option<String>,
+}
+
+impl fmt::Display for UnknownKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "`{}`", self.path)?;
+        if let Some(suggestion) = &self.suggestion {
+            write!(f, " (did you mean `{}`?)", suggestion)?;
+        }
+        Ok(())
+    }
+}
+
+/// Names of the fields of a struct deserialized by serde
+fn struct_fields<'de, T: Deserialize<'de>>() -> &'static [&'static str] {
+    let mut fields: &'static [&'static str] = &[];
+    let _ = T::deserialize(FieldNames(&mut fields));
+    fields
+}
+
+/// Deserializer that records the fields a struct asks for and fails
+struct FieldNames<'a>(&'a mut &'static [&'static str]);
+
+impl<'de> Deserializer<'de> for FieldNames<'_> {
+    type Error = de::value::Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, _visitor: V) -> Result<V::Value, Self::Error> {
+        Err(de::Error::custom("not a struct"))
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        fields: &'static [&'static str],
+        _visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        *self.0 = fields;
+        Err(de::Error::custom("fields recorded"))
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf option unit unit_struct newtype_struct seq tuple
+        tuple_struct map enum identifier ignored_any
+    }
+}
+
+/// Keys accepted under `parent`, the path of a table in the config with
+/// sequence indexes left out
+fn known_keys(parent: &[&str]) -> &'static [&'static str] {
+    match parent {
+        [] => struct_fields::<Config>(),
+        ["ignore"] => struct_fields::<IgnoreConfig>(),
+        ["outputs"] => struct_fields::<OutputConfig>(),
+        ["checks", _] => struct_fields::<CheckConfig>(),
+        ["trust"] => struct_fields::<TrustConfig>(),
+        ["overrides"] => struct_fields::<OverrideConfig>(),
+        ["overrides", "probe"] => struct_fields::<ProbeConfig>(),
+        ["blackout"] => struct_fields::<BlackoutWindow>(),
+        ["network"] => struct_fields::<NetworkConfig>(),
+        ["blocklist"] => struct_fields::<BlocklistConfig>(),
+        ["user_agents"] => struct_fields::<UserAgentsConfig>(),
+        ["accept_languages"] => struct_fields::<AcceptLanguagesConfig>(),
+        ["artifacts"] => struct_fields::<ArtifactConfig>(),
+        ["attribution"] => struct_fields::<AttributionConfig>(),
+        ["cors"] => struct_fields::<CorsConfig>(),
+        ["toc"] => struct_fields::<TocConfig>(),
+        ["resolvers"] => struct_fields::<ResolverConfig>(),
+        ["auth"] => struct_fields::<AuthConfig>(),
+        ["login"] => struct_fields::<LoginConfig>(),
+        ["proxy"] => struct_fields::<ProxyConfig>(),
+        ["tls"] => struct_fields::<TlsConfig>(),
+        ["sections"] => struct_fields::<SectionConfig>(),
+        ["report"] => struct_fields::<ReportConfig>(),
+        ["report", "links"] => struct_fields::<ReportLink>(),
+        _ => &[],
+    }
+}
+
+/// Keys and sequence indexes leading to a value, without the optional and
+/// newtype layers serde passes through
+fn key_path(path: &serde_ignored::Path) -> Vec<String> {
+    use serde_ignored::Path;
+    match path {
+        Path::Root => Vec::new(),
+        Path::Seq { parent, index } => {
+            let mut segments = key_path(parent);
+            segments.push(index.to_string());
+            segments
+        }
+        Path::Map { parent, key } => {
+            let mut segments = key_path(parent);
+            segments.push(key.clone());
+            segments
+        }
+        Path::Some { parent }
+        | Path::NewtypeStruct { parent }
+        | Path::NewtypeVariant { parent } => key_path(parent),
+    }
+}
+
+/// Keys of a YAML config that no setting reads, each with the closest known
+/// key at the same level as a suggestion
+pub fn unknown_keys(config_str: &str) -> Result<Vec<UnknownKey>, Box<dyn Error>> {
+    let mut paths = Vec::new();
+    let _: Config =
+        serde_ignored::deserialize(serde_yaml::Deserializer::from_str(config_str), |path| {
+            paths.push(key_path(&path))
+        })?;
+    Ok(paths
+        .into_iter()
+        .map(|segments| {
+            let (key, parent) = segments.split_last().expect("paths are not empty");
+            let fields: Vec<&str> = parent
+                .iter()
+                .map(String::as_str)
+                .filter(|segment| segment.parse::<usize>().is_err())
+                .collect();
+            let suggestion = known_keys(&fields)
+                .iter()
+                .map(|known| (strsim::damerau_levenshtein(key, known), *known))
+                .filter(|(distance, known)| *distance <= (known.len() / 3).max(2))
+                .min()
+                .map(|(_, known)| {
+                    let mut suggestion = parent.to_vec();
+                    suggestion.push(known.to_string());
+                    suggestion.join(".")
+                });
+            UnknownKey {
+                path: segments.join("."),
+                suggestion,
+            }
+        })
+        .collect())
+}
+
+/// Prefix of the environment variables setting top-level config keys, e.g.
+/// `INSPECTOR_TIMEOUT=60` for `timeout`
+pub const ENV_PREFIX: &str = "INSPECTOR_";
+
+/// A top-level setting taken from an environment variable
+#[derive(Debug, Clone, PartialEq)]
+pub struct EnvSetting {
+    pub key: String,
+    pub var: String,
+    pub value: Value,
 }
 
-/// Load configuration from a file or use default settings
-pub fn load_config(config_path: Option<&str>) -> Result<Option<Config>, Box<dyn Error>> {
-    if let Some(path) = config_path {
-        let config_path = PathBuf::from(path);
-        println!("Attempting to load config from: {:?}", config_path);
+/// Settings from the `INSPECTOR_<KEY>` variables among `vars`, for the
+/// top-level keys of the config. Values are YAML, so
+/// `INSPECTOR_LANGS='[en, es]'` sets a list; other variables are left alone.
+pub fn env_settings(
+    vars: impl IntoIterator<Item = (String, String)>,
+) -> Result<Vec<EnvSetting>, ConfigError> {
+    let keys = known_keys(&[]);
+    let mut settings = Vec::new();
+    for (var, raw) in vars {
+        let Some(name) = var.strip_prefix(ENV_PREFIX) else {
+            continue;
+        };
+        let key = name.to_ascii_lowercase();
+        if !keys.contains(&key.as_str()) {
+            continue;
+        }
+        let value = serde_yaml::from_str(&raw)
+            .map_err(|e| ConfigError::InvalidFieldType(format!("{}: {}", var, e)))?;
+        settings.push(EnvSetting { key, var, value });
+    }
+    settings.sort_by(|a, b| a.key.cmp(&b.key));
+    Ok(settings)
+}
 
-        if config_path.exists() {
+/// A config with what each source provided: the file as written and the
+/// settings environment variables replaced or added
+#[derive(Debug, Default)]
+pub struct LoadedConfig {
+    pub config: Config,
+    pub file: Option<Value>,
+    pub env: Vec<EnvSetting>,
+}
+
+/// Load the config file, if any, with the `INSPECTOR_*` settings among
+/// `vars` applied on top.
+///
+/// Unknown keys are logged as warnings, or rejected when `strict` is set.
+pub fn load_config_layers(
+    config_path: Option<&str>,
+    strict: bool,
+    vars: impl IntoIterator<Item = (String, String)>,
+) -> Result<LoadedConfig, Box<dyn Error>> {
+    let file = match config_path {
+        Some(path) => {
+            let config_path = PathBuf::from(path);
+            println!("Attempting to load config from: {:?}", config_path);
+            if !config_path.exists() {
+                println!("Config file not found at {:?}", config_path);
+                return Err(Box::new(std::io::Error::new(
+                    std::io::ErrorKind::NotFound,
+                    "Configuration file not found",
+                )));
+            }
             println!("Config file found, reading contents...");
             let config_str = fs::read_to_string(&config_path)?;
-            println!("Config file contents:\n{}", config_str);
-
             let config_value: Value = serde_yaml::from_str(&config_str)?;
-            validate_config(&config_value)?;
-
-            let config: Config = serde_yaml::from_str(&config_str)?;
-
-            println!("Loaded configuration:");
-            println!("  url: {:?}", config.url);
-            println!("  ignored_childs: {:?}", config.ignored_childs);
-            println!("  forbidden_domains: {:?}", config.forbidden_domains);
-            println!("  ignore: {:?}", config.ignore);
-            println!("  timeout: {:?}", config.timeout);
-            println!("  default_output: {:?}", config.default_output);
-
-            Ok(Some(config))
-        } else {
-            println!("Config file not found at {:?}", config_path);
-            Err(Box::new(std::io::Error::new(
-                std::io::ErrorKind::NotFound,
-                "Configuration file not found",
-            )))
+            Some(config_value)
         }
-    } else {
-        println!("No config file specified, using default configuration");
+        None => {
+            println!("No config file specified, using default configuration");
+            None
+        }
+    };
+    let env = env_settings(vars)?;
+    if file.is_none() && env.is_empty() {
+        return Ok(LoadedConfig::default());
+    }
+    let config = merge_layers(file.as_ref(), &env, strict)?;
+
+    println!("Loaded configuration:");
+    println!("  url: {:?}", config.url);
+    println!("  ignored_childs: {:?}", config.ignored_childs);
+    println!("  forbidden_domains: {:?}", config.forbidden_domains);
+    println!("  ignore: {:?}", config.ignore);
+    println!("  timeout: {:?}", config.timeout);
+    println!("  retries: {:?}", config.retries);
+    println!("  default_output: {:?}", config.default_output);
+    println!("  outputs: {:?}", config.outputs);
+    println!("  checks: {:?}", config.checks);
+    println!("  manifest: {:?}", config.manifest);
+    println!("  dns_workers: {:?}", config.dns_workers);
+    println!("  store: {:?}", config.store);
+    println!("  cache: {:?}", config.cache);
+    println!("  trust: {:?}", config.trust);
+    println!("  revalidate: {:?}", config.revalidate);
+    println!("  overrides: {:?}", config.overrides);
+    println!("  allowed_schemes: {:?}", config.allowed_schemes);
+    println!("  max_url_length: {:?}", config.max_url_length);
+    println!("  search_sample: {:?}", config.search_sample);
+    println!("  langs: {:?}", config.langs);
+    println!("  lang_prefixes: {:?}", config.lang_prefixes);
+    println!("  blackout: {:?}", config.blackout);
+    println!("  frontmatter_keys: {:?}", config.frontmatter_keys);
+    println!("  code_links: {:?}", config.code_links);
+    println!("  placeholder_domains: {:?}", config.placeholder_domains);
+    println!("  login_urls: {:?}", config.login_urls);
+    println!("  tracking_urls: {:?}", config.tracking_urls);
+    println!("  block_private_ips: {:?}", config.block_private_ips);
+    println!("  respect_robots: {:?}", config.respect_robots);
+    println!("  check_external: {:?}", config.check_external);
+    println!("  check_assets: {:?}", config.check_assets);
+    println!("  check_alternates: {:?}", config.check_alternates);
+    println!("  user_agents: {:?}", config.user_agents);
+    println!("  accept_languages: {:?}", config.accept_languages);
+    println!("  compare_origin: {:?}", config.compare_origin);
+    println!("  sitemap: {:?}", config.sitemap);
+    println!("  network: {:?}", config.network);
+    println!("  blocklist: {:?}", config.blocklist);
+    println!("  artifacts: {:?}", config.artifacts);
+    println!("  attribution: {:?}", config.attribution);
+    println!("  cors: {:?}", config.cors);
+    println!("  toc: {:?}", config.toc);
+    println!("  resolvers: {:?}", config.resolvers);
+    println!("  plugins: {:?}", config.plugins);
+    println!("  rewrite_map: {:?}", config.rewrite_map);
+    println!("  fail_on: {:?}", config.fail_on);
+    println!("  fail_threshold: {:?}", config.fail_threshold);
+    println!("  stale_after: {:?}", config.stale_after);
+    println!("  sections: {:?}", config.sections);
+    println!("  owners: {:?}", config.owners);
+    println!("  owner_reports: {:?}", config.owner_reports);
+    println!("  trend_runs: {:?}", config.trend_runs);
+    println!("  report: {:?}", config.report);
+    println!("  locale: {:?}", config.locale);
+    println!("  locales_dir: {:?}", config.locales_dir);
+    println!("  max_memory: {:?}", config.max_memory);
+    println!("  concurrency: {:?}", config.concurrency);
+    println!("  delay_ms: {:?}", config.delay_ms);
+    println!("  host_delays: {:?}", config.host_delays);
+    println!("  parse_workers: {:?}", config.parse_workers);
+    println!("  check_workers: {:?}", config.check_workers);
+    println!("  auth: {:?}", config.auth);
+    println!("  login: {:?}", config.login);
+    println!("  proxy: {:?}", config.proxy);
+    println!("  tls: {:?}", config.tls);
+    println!("  labels: {:?}", config.labels);
+    println!(
+        "  profiles: {:?}",
+        config
+            .profiles
+            .as_ref()
+            .map(|profiles| profiles.keys().collect::<Vec<_>>())
+    );
+
+    Ok(LoadedConfig { config, file, env })
+}
+
+impl LoadedConfig {
+    /// The config with the settings of profile `name` replacing the top-level
+    /// settings of the file they name. Environment variables still take
+    /// precedence over the profile.
+    pub fn with_profile(&self, name: &str, strict: bool) -> Result<LoadedConfig, Box<dyn Error>> {
+        let file = apply_profile(self.file.as_ref().unwrap_or(&Value::Null), name)?;
+        let config = merge_layers(Some(&file), &self.env, strict)?;
+        Ok(LoadedConfig {
+            config,
+            file: Some(file),
+            env: self.env.clone(),
+        })
+    }
+}
+
+/// The config file with the settings of profile `name` applied over its
+/// top-level settings, and without the `profiles` table
+pub fn apply_profile(file: &Value, name: &str) -> Result<Value, ConfigError> {
+    let profiles = file.get("profiles").and_then(Value::as_mapping);
+    let profile = profiles
+        .and_then(|profiles| profiles.get(&Value::from(name)))
+        .and_then(Value::as_mapping)
+        .ok_or_else(|| {
+            let defined: Vec<String> = profiles
+                .into_iter()
+                .flat_map(|profiles| profiles.iter())
+                .filter_map(|(key, _)| key.as_str().map(String::from))
+                .collect();
+            let defined = if defined.is_empty() {
+                "none".to_string()
+            } else {
+                defined.join(", ")
+            };
+            ConfigError::UnknownProfile(name.to_string(), defined)
+        })?;
+    let mut merged = match file {
+        Value::Mapping(mapping) => mapping.clone(),
+        _ => Mapping::new(),
+    };
+    merged.remove(&Value::from("profiles"));
+    for (key, value) in profile {
+        merged.insert(key.clone(), value.clone());
+    }
+    Ok(Value::Mapping(merged))
+}
+
+/// The config from the file, if any, with the environment settings applied
+/// on top: validated, and checked for unknown keys
+fn merge_layers(
+    file: Option<&Value>,
+    env: &[EnvSetting],
+    strict: bool,
+) -> Result<Config, Box<dyn Error>> {
+    let mut merged = match file {
+        Some(Value::Null) | None => Value::Mapping(Default::default()),
+        Some(value) => value.clone(),
+    };
+    if let Value::Mapping(mapping) = &mut merged {
+        for setting in env {
+            mapping.insert(Value::String(setting.key.clone()), setting.value.clone());
+        }
+    }
+    let mut problems = config_problems(&merged);
+    if file.is_none() {
+        // Without a file, the URL comes from the command line
+        problems.retain(|problem| !matches!(problem, ConfigError::MissingField(_)));
+    }
+    problems_result(problems)?;
+
+    let config_str = serde_yaml::to_string(&merged)?;
+    let unknown = unknown_keys(&config_str)?;
+    if strict && !unknown.is_empty() {
+        return Err(Box::new(ConfigError::UnknownKeys(unknown)));
+    }
+    for key in &unknown {
+        warn!("Ignoring unknown config key {}", key);
+    }
+
+    Ok(serde_yaml::from_value(merged)?)
+}
+
+/// Load configuration from a file and `INSPECTOR_*` environment variables,
+/// or `None` to use default settings.
+///
+/// Unknown keys are logged as warnings, or rejected when `strict` is set.
+pub fn load_config(
+    config_path: Option<&str>,
+    strict: bool,
+) -> Result<Option<Config>, Box<dyn Error>> {
+    let loaded = load_config_layers(config_path, strict, std::env::vars())?;
+    if loaded.file.is_none() && loaded.env.is_empty() {
         Ok(None)
+    } else {
+        Ok(Some(loaded.config))
     }
 }
 
 pub fn validate_config(config: &Value) -> Result<(), ConfigError> {
+    problems_result(config_problems(config))
+}
+
+/// The only problem, or all of them together
+fn problems_result(mut problems: Vec<ConfigError>) -> Result<(), ConfigError> {
+    match problems.len() {
+        0 => Ok(()),
+        1 => Err(problems.remove(0)),
+        _ => Err(ConfigError::Invalid(problems)),
+    }
+}
+
+/// Sequence at `field` of a config table, recording a problem when the field
+/// holds something else
+fn array<'a>(
+    table: &'a Value,
+    field: &str,
+    problems: &mut Vec<ConfigError>,
+) -> Option<&'a serde_yaml::Sequence> {
+    let value = table.get(field)?;
+    if value.as_sequence().is_none() {
+        problems.push(ConfigError::InvalidFieldType(format!(
+            "{} must be an array",
+            field
+        )));
+    }
+    value.as_sequence()
+}
+
+/// Mapping at `field` of a config table, recording a problem when the field
+/// holds something else
+fn object<'a>(
+    table: &'a Value,
+    field: &str,
+    problems: &mut Vec<ConfigError>,
+) -> Option<&'a serde_yaml::Mapping> {
+    let value = table.get(field)?;
+    if value.as_mapping().is_none() {
+        problems.push(ConfigError::InvalidFieldType(format!(
+            "{} must be an object",
+            field
+        )));
+    }
+    value.as_mapping()
+}
+
+/// Whether `value` is an integer within `range`
+fn in_range(value: &Value, range: &RangeInclusive<u64>) -> bool {
+    value.as_u64().is_some_and(|value| range.contains(&value))
+}
+
+/// Every problem of a config, so they can be fixed in one go
+pub fn config_problems(config: &Value) -> Vec<ConfigError> {
+    let mut problems = Vec::new();
+
     // Check for required fields
     if config.get("url").is_none() {
-        return Err(ConfigError::MissingField("url".to_string()));
+        problems.push(ConfigError::MissingField("url".to_string()));
     }
 
     // Validate field types
     if let Some(url) = config.get("url") {
         if !url.is_string() {
-            return Err(ConfigError::InvalidFieldType(
+            problems.push(ConfigError::InvalidFieldType(
                 "url must be a string".to_string(),
             ));
         }
     }
 
+    if config
+        .get("timeout")
+        .is_some_and(|timeout| !in_range(timeout, &TIMEOUT_RANGE))
+    {
+        problems.push(ConfigError::InvalidFieldType(format!(
+            "timeout must be between {} and {} seconds",
+            TIMEOUT_RANGE.start(),
+            TIMEOUT_RANGE.end()
+        )));
+    }
+
+    if config
+        .get("retries")
+        .is_some_and(|retries| !in_range(retries, &RETRIES_RANGE))
+    {
+        problems.push(ConfigError::InvalidFieldType(format!(
+            "retries must be between {} and {}",
+            RETRIES_RANGE.start(),
+            RETRIES_RANGE.end()
+        )));
+    }
+
+    if config
+        .get("dns_workers")
+        .is_some_and(|workers| !in_range(workers, &DNS_WORKERS_RANGE))
+    {
+        problems.push(ConfigError::InvalidFieldType(format!(
+            "dns_workers must be between {} and {}",
+            DNS_WORKERS_RANGE.start(),
+            DNS_WORKERS_RANGE.end()
+        )));
+    }
+
+    if config
+        .get("concurrency")
+        .is_some_and(|concurrency| !in_range(concurrency, &CONCURRENCY_RANGE))
+    {
+        problems.push(ConfigError::InvalidFieldType(format!(
+            "concurrency must be between {} and {}",
+            CONCURRENCY_RANGE.start(),
+            CONCURRENCY_RANGE.end()
+        )));
+    }
+
+    if config
+        .get("delay_ms")
+        .is_some_and(|delay| !in_range(delay, &DELAY_RANGE))
+    {
+        problems.push(ConfigError::InvalidFieldType(format!(
+            "delay_ms must be between {} and {} milliseconds",
+            DELAY_RANGE.start(),
+            DELAY_RANGE.end()
+        )));
+    }
+    if let Some(delays) = config.get("host_delays") {
+        let valid = delays.as_mapping().is_some_and(|delays| {
+            delays
+                .iter()
+                .all(|(domain, delay)| domain.is_string() && in_range(delay, &DELAY_RANGE))
+        });
+        if !valid {
+            problems.push(ConfigError::InvalidFieldType(format!(
+                "host_delays must map domains to delays between {} and {} milliseconds",
+                DELAY_RANGE.start(),
+                DELAY_RANGE.end()
+            )));
+        }
+    }
+
+    for field in ["parse_workers", "check_workers"] {
+        if config
+            .get(field)
+            .is_some_and(|workers| !in_range(workers, &WORKERS_RANGE))
+        {
+            problems.push(ConfigError::InvalidFieldType(format!(
+                "{} must be between {} and {}",
+                field,
+                WORKERS_RANGE.start(),
+                WORKERS_RANGE.end()
+            )));
+        }
+    }
+
     if let Some(ignore) = config.get("ignore") {
         if !ignore.is_mapping() {
-            return Err(ConfigError::InvalidFieldType(
+            problems.push(ConfigError::InvalidFieldType(
                 "ignore must be an object".to_string(),
             ));
         }
         if let Some(domains) = ignore.get("domains") {
             if !domains.is_sequence() {
-                return Err(ConfigError::InvalidFieldType(
+                problems.push(ConfigError::InvalidFieldType(
                     "ignore.domains must be an array".to_string(),
                 ));
             }
         }
         if let Some(regex) = ignore.get("regex") {
             if !regex.is_sequence() {
-                return Err(ConfigError::InvalidFieldType(
+                problems.push(ConfigError::InvalidFieldType(
                     "ignore.regex must be an array".to_string(),
                 ));
             }
         }
     }
 
-    // Add similar checks for other fields...
+    if let Some(outputs) = array(config, "outputs", &mut problems) {
+        for output in outputs {
+            if !output.get("format").is_some_and(Value::is_string) {
+                problems.push(ConfigError::InvalidFieldType(
+                    "outputs[].format must be a string".to_string(),
+                ));
+            }
+        }
+    }
+
+    if let Some(checks) = object(config, "checks", &mut problems) {
+        for (id, rule) in checks {
+            let id = id.as_str().unwrap_or("<key>");
+            if !rule.is_mapping() {
+                problems.push(ConfigError::InvalidFieldType(format!(
+                    "checks.{} must be an object",
+                    id
+                )));
+            }
+            for scope in ["include", "exclude"] {
+                if rule.get(scope).is_some_and(|value| !value.is_sequence()) {
+                    problems.push(ConfigError::InvalidFieldType(format!(
+                        "checks.{}.{} must be an array",
+                        id, scope
+                    )));
+                }
+            }
+        }
+    }
+
+    if let Some(trust) = object(config, "trust", &mut problems) {
+        for (status, window) in trust {
+            let status = status.as_str().unwrap_or("<key>");
+            let valid = window
+                .as_str()
+                .is_some_and(|window| humantime::parse_duration(window).is_ok());
+            if !valid {
+                problems.push(ConfigError::InvalidFieldType(format!(
+                    "trust.{} must be a duration like 7d or 12h",
+                    status
+                )));
+            }
+        }
+    }
+
+    if let Some(overrides) = array(config, "overrides", &mut problems) {
+        for entry in overrides {
+            match entry.get("url_regex").and_then(Value::as_str) {
+                None => problems.push(ConfigError::InvalidFieldType(
+                    "overrides[].url_regex must be a string".to_string(),
+                )),
+                Some(pattern) if regex::Regex::new(pattern).is_err() => {
+                    problems.push(ConfigError::InvalidFieldType(format!(
+                        "overrides[].url_regex is not a valid regex: {}",
+                        pattern
+                    )))
+                }
+                Some(_) => {}
+            }
+            if entry
+                .get("timeout")
+                .is_some_and(|timeout| !in_range(timeout, &TIMEOUT_RANGE))
+            {
+                problems.push(ConfigError::InvalidFieldType(format!(
+                    "overrides[].timeout must be between {} and {} seconds",
+                    TIMEOUT_RANGE.start(),
+                    TIMEOUT_RANGE.end()
+                )));
+            }
+            if let Some(headers) = entry.get("headers") {
+                let valid = headers.as_mapping().is_some_and(|headers| {
+                    headers
+                        .iter()
+                        .all(|(name, value)| name.is_string() && value.is_string())
+                });
+                if !valid {
+                    problems.push(ConfigError::InvalidFieldType(
+                        "overrides[].headers must map header names to strings".to_string(),
+                    ));
+                }
+            }
+            if let Some(probe) = entry.get("probe") {
+                let (method, graphql) = (probe.get("method"), probe.get("graphql"));
+                if method.is_some() == graphql.is_some() {
+                    problems.push(ConfigError::InvalidFieldType(
+                        "overrides[].probe must set either a method or a graphql query".to_string(),
+                    ));
+                }
+                if method.is_some_and(|method| method.as_str().and_then(probe_method).is_none()) {
+                    problems.push(ConfigError::InvalidFieldType(
+                        "overrides[].probe.method must be an HTTP method like OPTIONS or POST"
+                            .to_string(),
+                    ));
+                }
+                let valid = graphql
+                    .is_none_or(|query| query.as_str().and_then(GraphqlProbe::from_name).is_some());
+                if !valid {
+                    problems.push(ConfigError::InvalidFieldType(format!(
+                        "overrides[].probe.graphql must be one of {}",
+                        GraphqlProbe::NAMES.join(", ")
+                    )));
+                }
+            }
+        }
+    }
+
+    if let Some(childs) = array(config, "ignored_childs", &mut problems) {
+        for child in childs {
+            let path = child.as_str().unwrap_or_default();
+            let valid = !path.is_empty() && !path.contains("://") && !path.contains(['?', '#']);
+            if !valid {
+                problems.push(ConfigError::InvalidFieldType(format!(
+                    "ignored_childs entries must be paths: /changelog/ is matched from the site root, api/ relative to the directory of the start URL (got {:?})",
+                    path
+                )));
+            }
+        }
+    }
+
+    for field in [
+        "allowed_schemes",
+        "langs",
+        "lang_prefixes",
+        "frontmatter_keys",
+        "placeholder_domains",
+        "login_urls",
+        "tracking_urls",
+        "plugins",
+    ] {
+        if let Some(values) = config.get(field) {
+            let valid = values
+                .as_sequence()
+                .is_some_and(|values| values.iter().all(Value::is_string));
+            if !valid {
+                problems.push(ConfigError::InvalidFieldType(format!(
+                    "{} must be an array of strings",
+                    field
+                )));
+            }
+        }
+    }
+
+    for field in ["login_urls", "tracking_urls"] {
+        for pattern in config
+            .get(field)
+            .and_then(Value::as_sequence)
+            .into_iter()
+            .flatten()
+            .filter_map(Value::as_str)
+        {
+            if regex::Regex::new(pattern).is_err() {
+                problems.push(ConfigError::InvalidFieldType(format!(
+                    "{} has an invalid regex: {}",
+                    field, pattern
+                )));
+            }
+        }
+    }
+
+    if let Some(network) = config.get("network") {
+        if !network.is_mapping() {
+            problems.push(ConfigError::InvalidFieldType(
+                "network must be an object".to_string(),
+            ));
+        }
+        for list in ["allow", "deny"] {
+            if let Some(ranges) = network.get(list) {
+                let valid = ranges.as_sequence().is_some_and(|ranges| {
+                    ranges.iter().all(|range| {
+                        range
+                            .as_str()
+                            .is_some_and(|range| range.parse::<IpNetwork>().is_ok())
+                    })
+                });
+                if !valid {
+                    problems.push(ConfigError::InvalidFieldType(format!(
+                        "network.{} must be an array of IP ranges like 10.0.0.0/8",
+                        list
+                    )));
+                }
+            }
+        }
+        if let Some(ports) = network.get("ports") {
+            let valid = ports.as_sequence().is_some_and(|ports| {
+                ports
+                    .iter()
+                    .all(|port| port.as_u64().is_some_and(|port| port <= 65535))
+            });
+            if !valid {
+                problems.push(ConfigError::InvalidFieldType(
+                    "network.ports must be an array of port numbers".to_string(),
+                ));
+            }
+        }
+    }
+
+    if let Some(report) = config.get("report") {
+        if !report.is_mapping() {
+            problems.push(ConfigError::InvalidFieldType(
+                "report must be an object".to_string(),
+            ));
+        }
+        for field in ["title", "logo_url"] {
+            if report.get(field).is_some_and(|value| !value.is_string()) {
+                problems.push(ConfigError::InvalidFieldType(format!(
+                    "report.{} must be a string",
+                    field
+                )));
+            }
+        }
+        if let Some(links) = report.get("links") {
+            let valid = links.as_sequence().is_some_and(|links| {
+                links.iter().all(|link| {
+                    link.get("label").is_some_and(Value::is_string)
+                        && link.get("url").is_some_and(Value::is_string)
+                })
+            });
+            if !valid {
+                problems.push(ConfigError::InvalidFieldType(
+                    "report.links must be an array of objects with a label and a url".to_string(),
+                ));
+            }
+        }
+    }
+
+    if let Some(auth) = config.get("auth") {
+        if !auth.is_mapping() {
+            problems.push(ConfigError::InvalidFieldType(
+                "auth must be an object".to_string(),
+            ));
+        }
+        for key in INLINE_SECRET_KEYS {
+            if auth.get(key).is_some() {
+                problems.push(ConfigError::InvalidFieldType(format!(
+                    "auth.{0} cannot hold a secret inline; name an environment variable with auth.bearer_env or a file with auth.bearer_file",
+                    key
+                )));
+            }
+        }
+        for key in ["bearer_env", "bearer_file", "basic_env", "basic_file"] {
+            if auth.get(key).is_some_and(|value| !value.is_string()) {
+                problems.push(ConfigError::InvalidFieldType(format!(
+                    "auth.{} must be a string",
+                    key
+                )));
+            }
+        }
+        if auth.get("bearer_env").is_some() && auth.get("bearer_file").is_some() {
+            problems.push(ConfigError::InvalidFieldType(
+                "auth.bearer_env and auth.bearer_file cannot both be set".to_string(),
+            ));
+        }
+        if auth.get("basic_env").is_some() && auth.get("basic_file").is_some() {
+            problems.push(ConfigError::InvalidFieldType(
+                "auth.basic_env and auth.basic_file cannot both be set".to_string(),
+            ));
+        }
+        let bearer = auth.get("bearer_env").is_some() || auth.get("bearer_file").is_some();
+        let basic = auth.get("basic_env").is_some() || auth.get("basic_file").is_some();
+        if bearer && basic {
+            problems.push(ConfigError::InvalidFieldType(
+                "auth cannot set both a bearer token and basic credentials".to_string(),
+            ));
+        }
+    }
+
+    if let Some(login) = config.get("login") {
+        if !login.is_mapping() {
+            problems.push(ConfigError::InvalidFieldType(
+                "login must be an object".to_string(),
+            ));
+        }
+        let valid = login
+            .get("url")
+            .and_then(Value::as_str)
+            .and_then(|url| url::Url::parse(url).ok())
+            .is_some_and(|url| matches!(url.scheme(), "http" | "https"));
+        if !valid {
+            problems.push(ConfigError::InvalidFieldType(
+                "login.url must be an http(s) URL".to_string(),
+            ));
+        }
+        for key in ["success_selector", "form_selector"] {
+            let required = key == "success_selector";
+            match login.get(key) {
+                None if !required => {}
+                Some(Value::String(selector)) => {
+                    if scraper::Selector::parse(selector).is_err() {
+                        problems.push(ConfigError::InvalidFieldType(format!(
+                            "login.{} is not a valid CSS selector: {}",
+                            key, selector
+                        )));
+                    }
+                }
+                _ => problems.push(ConfigError::InvalidFieldType(format!(
+                    "login.{} must be a string",
+                    key
+                ))),
+            }
+        }
+        for key in ["fields", "fields_env"] {
+            let valid = login.get(key).is_none_or(|fields| {
+                fields.as_mapping().is_some_and(|fields| {
+                    fields
+                        .iter()
+                        .all(|(name, value)| name.is_string() && value.is_string())
+                })
+            });
+            if !valid {
+                problems.push(ConfigError::InvalidFieldType(format!(
+                    "login.{} must map field names to strings",
+                    key
+                )));
+            }
+        }
+    }
+
+    if let Some(proxy) = config.get("proxy") {
+        if !proxy.is_mapping() {
+            problems.push(ConfigError::InvalidFieldType(
+                "proxy must be an object".to_string(),
+            ));
+        }
+        match proxy
+            .get("url")
+            .and_then(Value::as_str)
+            .and_then(|url| url::Url::parse(url).ok())
+        {
+            Some(url) if !PROXY_SCHEMES.contains(&url.scheme()) || !url.has_host() => {
+                problems.push(ConfigError::InvalidFieldType(
+                    "proxy.url must be an http(s) or socks5 URL".to_string(),
+                ))
+            }
+            Some(url) if url.password().is_some() => {
+                problems.push(ConfigError::InvalidFieldType(
+                    "proxy.url cannot hold a password; name an environment variable with proxy.basic_env or a file with proxy.basic_file".to_string(),
+                ))
+            }
+            Some(_) => {}
+            None => problems.push(ConfigError::InvalidFieldType(
+                "proxy.url must be an http(s) or socks5 URL".to_string(),
+            )),
+        }
+        for key in ["basic_env", "basic_file"] {
+            if proxy.get(key).is_some_and(|value| !value.is_string()) {
+                problems.push(ConfigError::InvalidFieldType(format!(
+                    "proxy.{} must be a string",
+                    key
+                )));
+            }
+        }
+        if proxy.get("basic_env").is_some() && proxy.get("basic_file").is_some() {
+            problems.push(ConfigError::InvalidFieldType(
+                "proxy.basic_env and proxy.basic_file cannot both be set".to_string(),
+            ));
+        }
+        let valid = proxy.get("no_proxy").is_none_or(|hosts| {
+            hosts
+                .as_sequence()
+                .is_some_and(|hosts| hosts.iter().all(Value::is_string))
+        });
+        if !valid {
+            problems.push(ConfigError::InvalidFieldType(
+                "proxy.no_proxy must be an array of strings".to_string(),
+            ));
+        }
+    }
+
+    if let Some(tls) = config.get("tls") {
+        if !tls.is_mapping() {
+            problems.push(ConfigError::InvalidFieldType(
+                "tls must be an object".to_string(),
+            ));
+        }
+        for key in ["ca_cert", "client_cert", "client_key"] {
+            if tls.get(key).is_some_and(|value| !value.is_string()) {
+                problems.push(ConfigError::InvalidFieldType(format!(
+                    "tls.{} must be a string",
+                    key
+                )));
+            }
+        }
+        if tls.get("client_cert").is_some() != tls.get("client_key").is_some() {
+            problems.push(ConfigError::InvalidFieldType(
+                "tls.client_cert and tls.client_key must be set together".to_string(),
+            ));
+        }
+        if tls.get("insecure").is_some_and(|value| !value.is_bool()) {
+            problems.push(ConfigError::InvalidFieldType(
+                "tls.insecure must be a boolean".to_string(),
+            ));
+        }
+    }
+
+    if let Some(user_agents) = config.get("user_agents") {
+        if !user_agents.is_mapping() {
+            problems.push(ConfigError::InvalidFieldType(
+                "user_agents must be an object".to_string(),
+            ));
+        }
+        match user_agents.get("url_regex").and_then(Value::as_str) {
+            None => problems.push(ConfigError::InvalidFieldType(
+                "user_agents.url_regex must be a string".to_string(),
+            )),
+            Some(pattern) if regex::Regex::new(pattern).is_err() => {
+                problems.push(ConfigError::InvalidFieldType(format!(
+                    "user_agents.url_regex is not a valid regex: {}",
+                    pattern
+                )))
+            }
+            Some(_) => {}
+        }
+        if let Some(agents) = user_agents.get("agents") {
+            let valid = agents.as_sequence().is_some_and(|agents| {
+                !agents.is_empty()
+                    && agents.iter().all(|agent| {
+                        agent.as_str().is_some_and(|agent| {
+                            reqwest::header::HeaderValue::from_str(agent).is_ok()
+                        })
+                    })
+            });
+            if !valid {
+                problems.push(ConfigError::InvalidFieldType(
+                    "user_agents.agents must be a non-empty list of agent names or User-Agent headers".to_string(),
+                ));
+            }
+        }
+    }
+
+    if let Some(accept_languages) = config.get("accept_languages") {
+        if !accept_languages.is_mapping() {
+            problems.push(ConfigError::InvalidFieldType(
+                "accept_languages must be an object".to_string(),
+            ));
+        }
+        match accept_languages.get("url_regex").and_then(Value::as_str) {
+            None => problems.push(ConfigError::InvalidFieldType(
+                "accept_languages.url_regex must be a string".to_string(),
+            )),
+            Some(pattern) if regex::Regex::new(pattern).is_err() => {
+                problems.push(ConfigError::InvalidFieldType(format!(
+                    "accept_languages.url_regex is not a valid regex: {}",
+                    pattern
+                )))
+            }
+            Some(_) => {}
+        }
+        let languages = accept_languages
+            .get("languages")
+            .and_then(Value::as_sequence);
+        let valid = languages.is_some_and(|languages| {
+            !languages.is_empty()
+                && languages.iter().all(|language| {
+                    language.as_str().is_some_and(|language| {
+                        !language.is_empty()
+                            && language
+                                .chars()
+                                .all(|c| c.is_ascii_alphanumeric() || c == '-')
+                    })
+                })
+        });
+        if !valid {
+            problems.push(ConfigError::InvalidFieldType(
+                "accept_languages.languages must be a non-empty list of language tags like de or pt-BR".to_string(),
+            ));
+        }
+    }
+
+    if let Some(origin) = config.get("compare_origin") {
+        let valid = origin
+            .as_str()
+            .and_then(|origin| url::Url::parse(origin).ok())
+            .is_some_and(|origin| matches!(origin.scheme(), "http" | "https") && origin.has_host());
+        if !valid {
+            problems.push(ConfigError::InvalidFieldType(
+                "compare_origin must be an http(s) URL like https://staging.example.com"
+                    .to_string(),
+            ));
+        }
+    }
+
+    if let Some(blocklist) = config.get("blocklist") {
+        if !blocklist.is_mapping() {
+            problems.push(ConfigError::InvalidFieldType(
+                "blocklist must be an object".to_string(),
+            ));
+        }
+        if blocklist.get("file").is_some_and(|file| !file.is_string()) {
+            problems.push(ConfigError::InvalidFieldType(
+                "blocklist.file must be a string".to_string(),
+            ));
+        }
+        if blocklist
+            .get("safe_browsing")
+            .is_some_and(|enabled| !enabled.is_bool())
+        {
+            problems.push(ConfigError::InvalidFieldType(
+                "blocklist.safe_browsing must be a boolean".to_string(),
+            ));
+        }
+    }
+
+    if let Some(artifacts) = array(config, "artifacts", &mut problems) {
+        for entry in artifacts {
+            match entry.get("url_regex").and_then(Value::as_str) {
+                None => problems.push(ConfigError::InvalidFieldType(
+                    "artifacts[].url_regex must be a string".to_string(),
+                )),
+                Some(pattern) if regex::Regex::new(pattern).is_err() => {
+                    problems.push(ConfigError::InvalidFieldType(format!(
+                        "artifacts[].url_regex is not a valid regex: {}",
+                        pattern
+                    )))
+                }
+                Some(_) => {}
+            }
+            match entry.get("sha256_from").and_then(Value::as_str) {
+                None => problems.push(ConfigError::InvalidFieldType(
+                    "artifacts[].sha256_from must be a string".to_string(),
+                )),
+                Some(source) => {
+                    if let Err(e) = ChecksumSource::parse(source) {
+                        problems.push(ConfigError::InvalidFieldType(format!(
+                            "artifacts[].sha256_from is invalid: {}",
+                            e
+                        )))
+                    }
+                }
+            }
+        }
+    }
+
+    if let Some(attribution) = array(config, "attribution", &mut problems) {
+        for entry in attribution {
+            for field in ["embed_regex", "link_regex"] {
+                match entry.get(field).and_then(Value::as_str) {
+                    None => problems.push(ConfigError::InvalidFieldType(format!(
+                        "attribution[].{} must be a string",
+                        field
+                    ))),
+                    Some(pattern) if regex::Regex::new(pattern).is_err() => {
+                        problems.push(ConfigError::InvalidFieldType(format!(
+                            "attribution[].{} is not a valid regex: {}",
+                            field, pattern
+                        )))
+                    }
+                    Some(_) => {}
+                }
+            }
+            if entry.get("name").is_some_and(|name| !name.is_string()) {
+                problems.push(ConfigError::InvalidFieldType(
+                    "attribution[].name must be a string".to_string(),
+                ));
+            }
+        }
+    }
+
+    if let Some(cors) = array(config, "cors", &mut problems) {
+        for entry in cors {
+            match entry.get("url_regex").and_then(Value::as_str) {
+                None => problems.push(ConfigError::InvalidFieldType(
+                    "cors[].url_regex must be a string".to_string(),
+                )),
+                Some(pattern) if regex::Regex::new(pattern).is_err() => {
+                    problems.push(ConfigError::InvalidFieldType(format!(
+                        "cors[].url_regex is not a valid regex: {}",
+                        pattern
+                    )))
+                }
+                Some(_) => {}
+            }
+            let origin = entry
+                .get("origin")
+                .and_then(Value::as_str)
+                .and_then(|origin| url::Url::parse(origin).ok())
+                .filter(|origin| matches!(origin.scheme(), "http" | "https"));
+            if origin.is_none_or(|origin| origin.path() != "/" || origin.query().is_some()) {
+                problems.push(ConfigError::InvalidFieldType(
+                    "cors[].origin must be an http(s) origin without a path, like https://docs.example.com".to_string(),
+                ));
+            }
+            let method = entry.get("method");
+            if method.is_some_and(|method| method.as_str().and_then(probe_method).is_none()) {
+                problems.push(ConfigError::InvalidFieldType(
+                    "cors[].method must be an HTTP method like PUT or DELETE".to_string(),
+                ));
+            }
+            let valid = entry.get("headers").is_none_or(|headers| {
+                headers.as_sequence().is_some_and(|headers| {
+                    headers.iter().all(|name| {
+                        name.as_str().is_some_and(|name| {
+                            reqwest::header::HeaderName::from_bytes(name.as_bytes()).is_ok()
+                        })
+                    })
+                })
+            });
+            if !valid {
+                problems.push(ConfigError::InvalidFieldType(
+                    "cors[].headers must be an array of header names".to_string(),
+                ));
+            }
+            if entry
+                .get("credentials")
+                .is_some_and(|value| !value.is_bool())
+            {
+                problems.push(ConfigError::InvalidFieldType(
+                    "cors[].credentials must be a boolean".to_string(),
+                ));
+            }
+        }
+    }
+
+    if let Some(toc) = config.get("toc") {
+        if !toc.is_mapping() {
+            problems.push(ConfigError::InvalidFieldType(
+                "toc must be an object".to_string(),
+            ));
+        }
+        match toc.get("selector").and_then(Value::as_str) {
+            None => problems.push(ConfigError::InvalidFieldType(
+                "toc.selector must be a string".to_string(),
+            )),
+            Some(selector) if scraper::Selector::parse(selector).is_err() => {
+                problems.push(ConfigError::InvalidFieldType(format!(
+                    "toc.selector is not a valid CSS selector: {}",
+                    selector
+                )))
+            }
+            Some(_) => {}
+        }
+        let valid_level =
+            |level: &Value| level.as_u64().is_some_and(|level| (2..=6).contains(&level));
+        if toc
+            .get("max_level")
+            .is_some_and(|level| !valid_level(level))
+        {
+            problems.push(ConfigError::InvalidFieldType(
+                "toc.max_level must be a heading level from 2 to 6".to_string(),
+            ));
+        }
+    }
+
+    if let Some(resolvers) = array(config, "resolvers", &mut problems) {
+        for entry in resolvers {
+            match entry.get("url_regex").and_then(Value::as_str) {
+                None => problems.push(ConfigError::InvalidFieldType(
+                    "resolvers[].url_regex must be a string".to_string(),
+                )),
+                Some(pattern) if regex::Regex::new(pattern).is_err() => {
+                    problems.push(ConfigError::InvalidFieldType(format!(
+                        "resolvers[].url_regex is not a valid regex: {}",
+                        pattern
+                    )))
+                }
+                Some(_) => {}
+            }
+            let command = entry.get("command").and_then(Value::as_sequence);
+            if !command
+                .is_some_and(|command| !command.is_empty() && command.iter().all(Value::is_string))
+            {
+                problems.push(ConfigError::InvalidFieldType(
+                    "resolvers[].command must be a non-empty list of strings".to_string(),
+                ));
+            }
+        }
+    }
+
+    if let Some(sections) = array(config, "sections", &mut problems) {
+        for section in sections {
+            if !section.get("path").is_some_and(Value::is_string) {
+                problems.push(ConfigError::InvalidFieldType(
+                    "sections[].path must be a string".to_string(),
+                ));
+            }
+            if section.get("label").is_some_and(|label| !label.is_string()) {
+                problems.push(ConfigError::InvalidFieldType(
+                    "sections[].label must be a string".to_string(),
+                ));
+            }
+        }
+    }
+
+    if let Some(owners) = config.get("owners") {
+        let valid = owners.as_mapping().is_some_and(|owners| {
+            owners
+                .iter()
+                .all(|(prefix, owner)| prefix.is_string() && owner.is_string())
+        });
+        if !valid {
+            problems.push(ConfigError::InvalidFieldType(
+                "owners must map path prefixes to owner names".to_string(),
+            ));
+        }
+    }
+
+    if let Some(labels) = config.get("labels") {
+        let labels = labels.as_mapping().filter(|labels| {
+            labels
+                .iter()
+                .all(|(key, value)| key.is_string() && value.is_string())
+        });
+        match labels {
+            Some(labels) => {
+                for (key, value) in labels {
+                    let label = format!("{}={}", key.as_str().unwrap(), value.as_str().unwrap());
+                    if let Err(e) = parse_label(&label) {
+                        problems.push(ConfigError::InvalidFieldType(format!("labels: {}", e)));
+                    }
+                }
+            }
+            None => problems.push(ConfigError::InvalidFieldType(
+                "labels must map keys to strings; quote numbers like \"1.42\"".to_string(),
+            )),
+        }
+    }
+
+    if config
+        .get("owner_reports")
+        .is_some_and(|dir| !dir.is_string())
+    {
+        problems.push(ConfigError::InvalidFieldType(
+            "owner_reports must be a string".to_string(),
+        ));
+    }
+
+    for field in ["locale", "locales_dir", "rewrite_map"] {
+        if config.get(field).is_some_and(|value| !value.is_string()) {
+            problems.push(ConfigError::InvalidFieldType(format!(
+                "{} must be a string",
+                field
+            )));
+        }
+    }
+
+    if config
+        .get("sitemap")
+        .is_some_and(|sitemap| !sitemap.is_string())
+    {
+        problems.push(ConfigError::InvalidFieldType(
+            "sitemap must be a URL or a path like /sitemap.xml".to_string(),
+        ));
+    }
+
+    if let Some(fail_on) = config.get("fail_on") {
+        if fail_on.as_str().and_then(FailOn::from_name).is_none() {
+            problems.push(ConfigError::InvalidFieldType(format!(
+                "fail_on must be one of {}",
+                FailOn::NAMES.join(", ")
+            )));
+        }
+    }
+
+    if let Some(threshold) = config.get("fail_threshold") {
+        match threshold.as_str().map(FailThreshold::parse) {
+            Some(Ok(_)) => {}
+            Some(Err(error)) => problems.push(ConfigError::InvalidFieldType(format!(
+                "fail_threshold: {}",
+                error
+            ))),
+            None => problems.push(ConfigError::InvalidFieldType(
+                "fail_threshold must be a string like \"5\" or \"2.5%\"".to_string(),
+            )),
+        }
+    }
+
+    if let Some(age) = config.get("stale_after") {
+        let valid = age
+            .as_str()
+            .is_some_and(|age| humantime::parse_duration(age).is_ok());
+        if !valid {
+            problems.push(ConfigError::InvalidFieldType(
+                "stale_after must be a duration like 6months or 90d".to_string(),
+            ));
+        }
+    }
+
+    if let Some(size) = config.get("max_memory") {
+        match size.as_str().map(parse_size) {
+            Some(Ok(_)) => {}
+            Some(Err(error)) => problems.push(ConfigError::InvalidFieldType(format!(
+                "max_memory: {}",
+                error
+            ))),
+            None => problems.push(ConfigError::InvalidFieldType(
+                "max_memory must be a size like 512MiB".to_string(),
+            )),
+        }
+    }
+
+    if config
+        .get("block_private_ips")
+        .is_some_and(|block| !block.is_bool())
+    {
+        problems.push(ConfigError::InvalidFieldType(
+            "block_private_ips must be a boolean".to_string(),
+        ));
+    }
+
+    if config
+        .get("respect_robots")
+        .is_some_and(|respect| !respect.is_bool())
+    {
+        problems.push(ConfigError::InvalidFieldType(
+            "respect_robots must be a boolean".to_string(),
+        ));
+    }
+
+    if config
+        .get("check_external")
+        .is_some_and(|check| !check.is_bool())
+    {
+        problems.push(ConfigError::InvalidFieldType(
+            "check_external must be a boolean".to_string(),
+        ));
+    }
+
+    if config
+        .get("check_assets")
+        .is_some_and(|check| !check.is_bool())
+    {
+        problems.push(ConfigError::InvalidFieldType(
+            "check_assets must be a boolean".to_string(),
+        ));
+    }
+
+    if config
+        .get("revalidate")
+        .is_some_and(|revalidate| !revalidate.is_bool())
+    {
+        problems.push(ConfigError::InvalidFieldType(
+            "revalidate must be a boolean".to_string(),
+        ));
+    }
+
+    if config
+        .get("check_alternates")
+        .is_some_and(|check| !check.is_bool())
+    {
+        problems.push(ConfigError::InvalidFieldType(
+            "check_alternates must be a boolean".to_string(),
+        ));
+    }
+
+    if config
+        .get("max_url_length")
+        .is_some_and(|length| !length.is_u64())
+    {
+        problems.push(ConfigError::InvalidFieldType(
+            "max_url_length must be a positive integer".to_string(),
+        ));
+    }
+
+    if config
+        .get("search_sample")
+        .is_some_and(|sample| !sample.is_u64())
+    {
+        problems.push(ConfigError::InvalidFieldType(
+            "search_sample must be a non-negative integer".to_string(),
+        ));
+    }
+
+    if let Some(runs) = config.get("trend_runs") {
+        let valid = runs.as_u64().is_some_and(|runs| runs > 0);
+        if !valid {
+            problems.push(ConfigError::InvalidFieldType(
+                "trend_runs must be a positive integer".to_string(),
+            ));
+        }
+    }
+
+    if let Some(windows) = array(config, "blackout", &mut problems) {
+        for window in windows {
+            for bound in ["start", "end"] {
+                let valid = window
+                    .get(bound)
+                    .and_then(Value::as_str)
+                    .and_then(parse_time_of_day)
+                    .is_some();
+                if !valid {
+                    problems.push(ConfigError::InvalidFieldType(format!(
+                        "blackout[].{} must be a time like 09:00",
+                        bound
+                    )));
+                }
+            }
+            if let Some(days) = window.get("days") {
+                let valid = days.as_sequence().is_some_and(|days| {
+                    days.iter().all(|day| day.as_str().is_some_and(is_weekday))
+                });
+                if !valid {
+                    problems.push(ConfigError::InvalidFieldType(
+                        "blackout[].days must be an array of weekdays like mon".to_string(),
+                    ));
+                }
+            }
+        }
+    }
+
+    if let Some(policy) = config.get("code_links") {
+        let valid = policy
+            .as_str()
+            .and_then(CodeLinkPolicy::from_name)
+            .is_some();
+        if !valid {
+            problems.push(ConfigError::InvalidFieldType(format!(
+                "code_links must be one of {}",
+                CodeLinkPolicy::NAMES.join(", ")
+            )));
+        }
+    }
+
+    if let Some(profiles) = object(config, "profiles", &mut problems) {
+        for (name, profile) in profiles {
+            let name = name.as_str().unwrap_or_default();
+            if !profile.is_mapping() {
+                problems.push(ConfigError::InvalidFieldType(format!(
+                    "profiles.{} must be an object of settings",
+                    name
+                )));
+            } else if profile.get("profiles").is_some() {
+                problems.push(ConfigError::InvalidFieldType(format!(
+                    "profiles.{} cannot define profiles",
+                    name
+                )));
+            }
+        }
+    }
 
-    Ok(())
+    problems.dedup();
+    problems
 }
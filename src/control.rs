@@ -0,0 +1,47 @@
+use crate::schedule::ScanControl;
+use log::{info, warn};
+use std::error::Error;
+use std::thread::{self, JoinHandle};
+use tiny_http::{Header, Method, Response, Server};
+
+/// Serve the pause/resume API for a scan on `addr`.
+///
+/// - `POST /pause` pauses the scan before its next request
+/// - `POST /resume` resumes it
+/// - `GET /status` returns `{"paused": bool, "blackout": bool}`
+pub fn spawn_control_server(
+    addr: &str,
+    control: ScanControl,
+) -> Result<JoinHandle<()>, Box<dyn Error>> {
+    let server = Server::http(addr).map_err(|e| e as Box<dyn Error>)?;
+    info!("Control API listening on http://{}", addr);
+
+    Ok(thread::spawn(move || {
+        for request in server.incoming_requests() {
+            let response = match (request.method(), request.url()) {
+                (Method::Post, "/pause") => {
+                    control.pause();
+                    status_response(&control)
+                }
+                (Method::Post, "/resume") => {
+                    control.resume();
+                    status_response(&control)
+                }
+                (Method::Get, "/status") => status_response(&control),
+                _ => Response::from_string("not found").with_status_code(404),
+            };
+            if let Err(e) = request.respond(response) {
+                warn!("Failed to answer control request: {}", e);
+            }
+        }
+    }))
+}
+
+fn status_response(control: &ScanControl) -> Response<std::io::Cursor<Vec<u8>>> {
+    let body = serde_json::json!({
+        "paused": control.is_paused(),
+        "blackout": control.in_blackout(),
+    });
+    Response::from_string(body.to_string())
+        .with_header("Content-Type: application/json".parse::<Header>().unwrap())
+}
@@ -0,0 +1,185 @@
+use crate::check::{Finding, Severity};
+use crate::config::{probe_method, CorsConfig};
+use crate::link::LinkInfo;
+use log::info;
+use regex::Regex;
+use reqwest::blocking::Client;
+use reqwest::header::{
+    HeaderMap, ACCESS_CONTROL_ALLOW_CREDENTIALS, ACCESS_CONTROL_ALLOW_HEADERS,
+    ACCESS_CONTROL_ALLOW_METHODS, ACCESS_CONTROL_ALLOW_ORIGIN, ACCESS_CONTROL_REQUEST_HEADERS,
+    ACCESS_CONTROL_REQUEST_METHOD, ORIGIN,
+};
+use reqwest::{Method, StatusCode};
+use std::error::Error;
+
+/// Rule id of CORS preflight findings
+pub const CORS_RULE: &str = "cors";
+/// Methods browsers allow without `Access-Control-Allow-Methods`
+const SIMPLE_METHODS: [Method; 3] = [Method::GET, Method::HEAD, Method::POST];
+
+/// A compiled `cors` entry
+#[derive(Debug, Clone)]
+pub struct CorsRule {
+    pattern: Regex,
+    origin: String,
+    method: Method,
+    /// Requested headers, lower case and sorted as browsers send them
+    headers: Vec<String>,
+    credentials: bool,
+}
+
+impl CorsRule {
+    pub fn from_config(config: &CorsConfig) -> Result<Self, Box<dyn Error>> {
+        let method = match &config.method {
+            Some(method) => {
+                probe_method(method).ok_or_else(|| format!("invalid CORS method {:?}", method))?
+            }
+            None => Method::GET,
+        };
+        let mut headers: Vec<String> = config
+            .headers
+            .iter()
+            .flatten()
+            .map(|name| name.trim().to_ascii_lowercase())
+            .collect();
+        headers.sort();
+        headers.dedup();
+        Ok(CorsRule {
+            pattern: Regex::new(&config.url_regex)?,
+            origin: config.origin.trim_end_matches('/').to_string(),
+            method,
+            headers,
+            credentials: config.credentials.unwrap_or(false),
+        })
+    }
+
+    /// What a browser would object to in the answer to the preflight of the
+    /// rule, following the CORS protocol of the Fetch standard
+    pub fn problems(&self, status: StatusCode, headers: &HeaderMap) -> Vec<String> {
+        if !status.is_success() {
+            return vec![format!("the preflight answers {}", status)];
+        }
+        let header = |name| {
+            headers
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .map(str::trim)
+        };
+        let list = |name| {
+            header(name)
+                .unwrap_or_default()
+                .split(',')
+                .map(str::trim)
+                .filter(|item| !item.is_empty())
+                .collect::<Vec<_>>()
+        };
+        let mut problems = Vec::new();
+        match header(ACCESS_CONTROL_ALLOW_ORIGIN) {
+            None => problems.push("Access-Control-Allow-Origin is missing".to_string()),
+            Some("*") if self.credentials => problems.push(
+                "Access-Control-Allow-Origin is *, which browsers refuse for requests with credentials"
+                    .to_string(),
+            ),
+            Some(allowed) if allowed != "*" && allowed != self.origin => problems.push(format!(
+                "Access-Control-Allow-Origin is {}, not {}",
+                allowed, self.origin
+            )),
+            Some(_) => {}
+        }
+        if self.credentials && header(ACCESS_CONTROL_ALLOW_CREDENTIALS) != Some("true") {
+            problems.push("Access-Control-Allow-Credentials is not true".to_string());
+        }
+        // The wildcards only apply to requests without credentials
+        let wildcard = |items: &[&str]| !self.credentials && items.contains(&"*");
+        let methods = list(ACCESS_CONTROL_ALLOW_METHODS);
+        if !SIMPLE_METHODS.contains(&self.method)
+            && !wildcard(&methods)
+            && !methods.contains(&self.method.as_str())
+        {
+            problems.push(format!(
+                "Access-Control-Allow-Methods does not allow {}",
+                self.method
+            ));
+        }
+        let allowed_headers = list(ACCESS_CONTROL_ALLOW_HEADERS);
+        let missing: Vec<&str> = self
+            .headers
+            .iter()
+            .map(String::as_str)
+            // `*` never covers Authorization
+            .filter(|name| !(wildcard(&allowed_headers) && *name != "authorization"))
+            .filter(|name| {
+                !allowed_headers
+                    .iter()
+                    .any(|allowed| allowed.eq_ignore_ascii_case(name))
+            })
+            .collect();
+        if !missing.is_empty() {
+            problems.push(format!(
+                "Access-Control-Allow-Headers does not allow {}",
+                missing.join(", ")
+            ));
+        }
+        problems
+    }
+}
+
+/// Send the preflight of `rule` for `url`, returning what a browser would
+/// object to in the answer
+fn preflight(client: &Client, url: &str, rule: &CorsRule) -> Vec<String> {
+    let mut request = client
+        .request(Method::OPTIONS, url)
+        .header(ORIGIN, &rule.origin)
+        .header(ACCESS_CONTROL_REQUEST_METHOD, rule.method.as_str());
+    if !rule.headers.is_empty() {
+        request = request.header(ACCESS_CONTROL_REQUEST_HEADERS, rule.headers.join(","));
+    }
+    match request.send() {
+        Ok(response) => rule.problems(response.status(), response.headers()),
+        Err(e) => vec![format!("the preflight failed: {}", e)],
+    }
+}
+
+/// Findings for links matching a `cors` entry whose CORS preflight a browser
+/// would reject, one per page linking to them. Each link is checked with the
+/// first entry it matches; links that were deliberately not requested are
+/// skipped.
+pub fn cors_findings<'a>(
+    configs: &[CorsConfig],
+    links: impl IntoIterator<Item = &'a LinkInfo>,
+    client: &Client,
+) -> Result<Vec<Finding>, Box<dyn Error>> {
+    let rules = configs
+        .iter()
+        .map(CorsRule::from_config)
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(links
+        .into_iter()
+        .filter(|link| !link.status.is_unchecked())
+        .filter_map(|link| {
+            let rule = rules.iter().find(|rule| rule.pattern.is_match(&link.url))?;
+            info!("Sending a CORS preflight to {}", link.url);
+            let problems = preflight(client, &link.url, rule);
+            (!problems.is_empty()).then(|| {
+                let message = format!(
+                    "CORS preflight of {} from {} would be rejected: {}",
+                    rule.method,
+                    rule.origin,
+                    problems.join("; ")
+                );
+                (link, message)
+            })
+        })
+        .flat_map(|(link, message)| {
+            link.sources.iter().map(move |page| Finding {
+                rule: CORS_RULE.to_string(),
+                severity: Severity::Error,
+                page: page.clone(),
+                message: message.clone(),
+                target: Some(link.url.clone()),
+                owner: None,
+                other_pages: Vec::new(),
+            })
+        })
+        .collect())
+}
@@ -0,0 +1,147 @@
+// Dashboard of the serve API: a list of scans, and the broken links of one
+// scan, kept up to date through its event stream while it runs.
+"use strict";
+
+const view = document.getElementById("view");
+let stream = null;
+let refresh = null;
+
+function element(tag, attrs, ...children) {
+  const node = document.createElement(tag);
+  Object.entries(attrs || {}).forEach(([name, value]) => node.setAttribute(name, value));
+  children.forEach((child) => node.append(child));
+  return node;
+}
+
+function table(headers, rows) {
+  const head = element("tr", {}, ...headers.map(([label, cls]) => element("th", { class: cls || "" }, label)));
+  return element("table", {}, element("thead", {}, head), element("tbody", {}, ...rows));
+}
+
+function link(url) {
+  return /^https?:\/\//.test(url) ? element("a", { href: url }, url) : url;
+}
+
+function state(scan) {
+  return element("span", { class: `state state-${scan.state}` }, scan.state);
+}
+
+function total(scan, key) {
+  return scan.summary ? String(scan.summary[key]) : "–";
+}
+
+async function fetchJson(path) {
+  const response = await fetch(path);
+  if (!response.ok) {
+    throw new Error(`${path}: ${response.status} ${await response.text()}`);
+  }
+  return response.json();
+}
+
+function stop() {
+  if (stream) {
+    stream.close();
+    stream = null;
+  }
+  clearTimeout(refresh);
+}
+
+async function showScans() {
+  const scans = await fetchJson("/scans");
+  if (scans.length === 0) {
+    view.replaceChildren(element("p", { class: "muted" }, "No scans yet. Scans start when a deploy webhook arrives."));
+  } else {
+    const rows = scans.map((scan) =>
+      element(
+        "tr",
+        {},
+        element("td", {}, element("a", { href: `#scan/${scan.id}` }, `#${scan.id}`)),
+        element("td", {}, link(scan.url)),
+        element("td", {}, state(scan)),
+        element("td", { class: "number" }, total(scan, "links")),
+        element("td", { class: "number broken" }, total(scan, "broken")),
+        element("td", { class: "number" }, total(scan, "findings")),
+      ),
+    );
+    view.replaceChildren(
+      table([["Scan"], ["Site"], ["State"], ["Links", "number"], ["Broken", "number"], ["Findings", "number"]], rows),
+    );
+  }
+  if (scans.some((scan) => scan.state === "queued" || scan.state === "running")) {
+    refresh = setTimeout(route, 5000);
+  }
+}
+
+function renderScan(scan) {
+  const heading = element("h2", {}, `Scan #${scan.id} of `, link(scan.url), " ", state(scan));
+  const parts = [element("p", {}, element("a", { href: "#" }, "← All scans")), heading];
+  if (scan.summary) {
+    parts.push(
+      element(
+        "p",
+        {},
+        `${scan.summary.links} links scanned, ${scan.summary.broken} broken, ` +
+          `${scan.summary.ignored} not checked, ${scan.summary.findings} findings.`,
+      ),
+    );
+    if (scan.summary.incomplete) {
+      parts.push(element("p", { class: "muted" }, `Incomplete: ${scan.summary.incomplete}`));
+    }
+  }
+  if (scan.error) {
+    parts.push(element("p", { class: "broken" }, scan.error));
+  }
+  if (scan.broken_links.length === 0) {
+    parts.push(element("p", { class: "muted" }, scan.summary ? "No broken links." : "No broken links so far."));
+  } else {
+    const rows = scan.broken_links.map((broken) =>
+      element(
+        "tr",
+        {},
+        element("td", {}, link(broken.url)),
+        element("td", { class: "broken" }, broken.status),
+        element("td", {}, ...broken.sources.flatMap((source, index) => (index ? [element("br"), link(source)] : [link(source)]))),
+      ),
+    );
+    parts.push(element("h3", {}, "Broken links"), table([["Link"], ["Status"], ["Found on"]], rows));
+  }
+  view.replaceChildren(...parts);
+}
+
+async function showScan(id) {
+  const scan = await fetchJson(`/scans/${id}`);
+  renderScan(scan);
+  if (scan.state === "finished" || scan.state === "failed") {
+    return;
+  }
+  stream = new EventSource(`/scans/${id}/events`);
+  let pending = false;
+  const update = () => {
+    if (pending) {
+      return;
+    }
+    pending = true;
+    // Results arrive in bursts; redraw at most once a second
+    setTimeout(async () => {
+      pending = false;
+      renderScan(await fetchJson(`/scans/${id}`));
+    }, 1000);
+  };
+  stream.addEventListener("progress", update);
+  ["finished", "failed"].forEach((kind) =>
+    stream.addEventListener(kind, () => {
+      stop();
+      fetchJson(`/scans/${id}`).then(renderScan);
+    }),
+  );
+}
+
+function route() {
+  stop();
+  const match = location.hash.match(/^#scan\/(\w+)$/);
+  const shown = match ? showScan(match[1]) : showScans();
+  shown.catch((error) => view.replaceChildren(element("p", { class: "broken" }, error.message)));
+}
+
+window.addEventListener("hashchange", route);
+route();
@@ -0,0 +1,29 @@
+/// Files of the web dashboard served by `serve`, as (path, content type,
+/// content). The dashboard only uses the scans API, so it needs no build step.
+const ASSETS: &[(&str, &str, &str)] = &[
+    (
+        "/",
+        "text/html; charset=utf-8",
+        include_str!("assets/index.html"),
+    ),
+    (
+        "/dashboard.js",
+        "text/javascript; charset=utf-8",
+        include_str!("assets/dashboard.js"),
+    ),
+    (
+        "/dashboard.css",
+        "text/css; charset=utf-8",
+        include_str!("assets/dashboard.css"),
+    ),
+];
+
+/// Content type and content of the dashboard file at `path`
+pub fn asset(path: &str) -> Option<(&'static str, &'static str)> {
+    let path = path.split(['?', '#']).next().unwrap_or_default();
+    let path = if path == "/index.html" { "/" } else { path };
+    ASSETS
+        .iter()
+        .find(|(asset, _, _)| *asset == path)
+        .map(|(_, content_type, content)| (*content_type, *content))
+}
@@ -0,0 +1,150 @@
+use crate::link::RetryPolicy;
+use log::debug;
+use std::collections::HashMap;
+use std::net::ToSocketAddrs;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use url::{Host, Url};
+
+/// Default number of background DNS workers
+pub const DEFAULT_DNS_WORKERS: usize = 8;
+
+/// Resolution state of a host
+#[derive(Debug, Clone, PartialEq)]
+pub enum DnsState {
+    Pending,
+    Resolved,
+    Failed(String),
+}
+
+/// Resolves hostnames of queued URLs in a background pool.
+///
+/// The crawler asks for a host as soon as a URL is queued, so by the time the
+/// URL is requested the lookup has usually completed (and is cached by the
+/// system resolver). Failed lookups are retried like failed requests, and
+/// hosts that still fail to resolve are reported without ever attempting an
+/// HTTP request.
+pub struct DnsPrefetcher {
+    sender: Option<Sender<(String, u16)>>,
+    states: Arc<Mutex<HashMap<String, DnsState>>>,
+    workers: Vec<JoinHandle<()>>,
+}
+
+impl DnsPrefetcher {
+    /// Start a prefetcher with the given number of worker threads (0 disables
+    /// it), retrying lookup errors as `retry` allows
+    pub fn new(workers: usize, retry: RetryPolicy) -> Self {
+        let states = Arc::new(Mutex::new(HashMap::new()));
+        if workers == 0 {
+            return DnsPrefetcher {
+                sender: None,
+                states,
+                workers: Vec::new(),
+            };
+        }
+
+        let (sender, receiver) = mpsc::channel();
+        let receiver = Arc::new(Mutex::new(receiver));
+        let workers = (0..workers)
+            .map(|_| {
+                let receiver = Arc::clone(&receiver);
+                let states = Arc::clone(&states);
+                thread::spawn(move || resolve_worker(receiver, states, retry))
+            })
+            .collect();
+
+        DnsPrefetcher {
+            sender: Some(sender),
+            states,
+            workers,
+        }
+    }
+
+    /// Queue the host of a URL for resolution if it has not been seen yet
+    pub fn prefetch(&self, url: &str) {
+        let Some(sender) = &self.sender else {
+            return;
+        };
+        let Some((host, port)) = lookup_target(url) else {
+            return;
+        };
+
+        let mut states = self.states.lock().unwrap();
+        if !states.contains_key(&host) {
+            states.insert(host.clone(), DnsState::Pending);
+            let _ = sender.send((host, port));
+        }
+    }
+
+    /// Current resolution state of the host of a URL, if it was prefetched
+    pub fn state(&self, url: &str) -> Option<DnsState> {
+        let (host, _) = lookup_target(url)?;
+        self.states.lock().unwrap().get(&host).cloned()
+    }
+
+    /// Resolution error for the host of a URL, once its lookup has failed
+    pub fn failure(&self, url: &str) -> Option<String> {
+        match self.state(url)? {
+            DnsState::Failed(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl Drop for DnsPrefetcher {
+    fn drop(&mut self) {
+        self.sender.take();
+        for worker in self.workers.drain(..) {
+            let _ = worker.join();
+        }
+    }
+}
+
+/// Host name and port to resolve for a URL; IP literals need no lookup
+fn lookup_target(url: &str) -> Option<(String, u16)> {
+    let url = Url::parse(url).ok()?;
+    match url.host()? {
+        Host::Domain(domain) => Some((domain.to_lowercase(), url.port_or_known_default()?)),
+        Host::Ipv4(_) | Host::Ipv6(_) => None,
+    }
+}
+
+/// Resolve a host, retrying lookup errors since resolvers fail transiently
+/// (timeouts, `EAI_AGAIN`). A lookup answering no addresses is final.
+fn resolve(host: &str, port: u16, retry: RetryPolicy) -> DnsState {
+    let mut attempt = 0;
+    loop {
+        attempt += 1;
+        match (host, port).to_socket_addrs() {
+            Ok(mut addrs) => {
+                return match addrs.next() {
+                    Some(_) => DnsState::Resolved,
+                    None => DnsState::Failed("no addresses found".to_string()),
+                }
+            }
+            Err(e) if attempt > retry.retries => return DnsState::Failed(e.to_string()),
+            Err(e) => {
+                debug!("DNS lookup for {} failed, retrying: {}", host, e);
+                thread::sleep(retry.base_delay.saturating_mul(1 << (attempt - 1).min(16)));
+            }
+        }
+    }
+}
+
+fn resolve_worker(
+    receiver: Arc<Mutex<Receiver<(String, u16)>>>,
+    states: Arc<Mutex<HashMap<String, DnsState>>>,
+    retry: RetryPolicy,
+) {
+    loop {
+        let next = receiver.lock().unwrap().recv();
+        let Ok((host, port)) = next else {
+            return;
+        };
+
+        let state = resolve(&host, port, retry);
+        debug!("DNS prefetch for {}: {:?}", host, state);
+        states.lock().unwrap().insert(host, state);
+    }
+}
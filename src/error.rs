@@ -0,0 +1,81 @@
+use serde::Serialize;
+use std::error::Error;
+use std::fmt;
+use thiserror::Error;
+
+/// Kind of failure that stopped a run, so wrapping scripts can branch on it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCode {
+    /// Invalid config file, command-line value or locale
+    Misconfiguration,
+    /// The HTTP client could not be set up or the start URL was unreachable
+    NetworkBootstrap,
+    /// A report, manifest or other output could not be written
+    OutputWrite,
+    /// Anything else
+    Runtime,
+}
+
+/// An error tagged with the [`ErrorCode`] reported on stderr
+#[derive(Debug, Error, Serialize)]
+#[error("{message}")]
+pub struct CodedError {
+    pub code: ErrorCode,
+    pub message: String,
+}
+
+impl CodedError {
+    pub fn new(code: ErrorCode, message: impl fmt::Display) -> Self {
+        CodedError {
+            code,
+            message: message.to_string(),
+        }
+    }
+
+    /// Code and message of any error; untagged errors are runtime errors
+    pub fn of(error: &(dyn Error + 'static)) -> Self {
+        match error.downcast_ref::<CodedError>() {
+            Some(coded) => CodedError::new(coded.code, &coded.message),
+            None => CodedError::new(ErrorCode::Runtime, error),
+        }
+    }
+
+    /// The error as a single-line JSON object
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("errors serialize")
+    }
+}
+
+/// Tag the error of a result with an [`ErrorCode`]
+pub trait WithCode<T> {
+    fn code(self, code: ErrorCode) -> Result<T, CodedError>;
+}
+
+impl<T, E: fmt::Display> WithCode<T> for Result<T, E> {
+    fn code(self, code: ErrorCode) -> Result<T, CodedError> {
+        self.map_err(|error| CodedError::new(code, error))
+    }
+}
+
+/// How errors that stop a run are printed on stderr
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorFormat {
+    Text,
+    /// One `{"code": ..., "message": ...}` object per line
+    Json,
+}
+
+impl ErrorFormat {
+    /// Names accepted on the command line
+    pub const NAMES: [&'static str; 2] = ["text", "json"];
+
+    /// Parse a format name
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "text" => Some(ErrorFormat::Text),
+            "json" => Some(ErrorFormat::Json),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,108 @@
+use crate::output::ScanReport;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+const MONTHS: [&str; 12] = [
+    "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+];
+
+/// A page that has not been modified within the freshness window
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StalePage {
+    pub page: String,
+    /// Unix time of the `Last-Modified` header
+    pub last_modified: u64,
+    /// Number of scanned pages linking to it
+    pub inlinks: usize,
+}
+
+/// Parse an HTTP date (`Sun, 06 Nov 1994 08:49:37 GMT`) into Unix seconds
+pub fn parse_http_date(value: &str) -> Option<u64> {
+    let parts: Vec<&str> = value.split_whitespace().collect();
+    let [_, day, month, year, time, "GMT"] = parts.as_slice() else {
+        return None;
+    };
+    let day: u64 = day.parse().ok()?;
+    let month = MONTHS.iter().position(|name| name == month)? as u64 + 1;
+    let year: u64 = year.parse().ok()?;
+    let mut clock = time.split(':').map(|part| part.parse::<u64>().ok());
+    let (hour, minute, second) = (clock.next()??, clock.next()??, clock.next()??);
+    if year < 1970 || !(1..=31).contains(&day) || hour > 23 || minute > 59 || second > 60 {
+        return None;
+    }
+    Some(days_from_civil(year, month, day) * 86_400 + hour * 3600 + minute * 60 + second)
+}
+
+/// Format Unix seconds as an HTTP date, the inverse of [`parse_http_date`]
+pub fn format_http_date(time: u64) -> String {
+    const WEEKDAYS: [&str; 7] = ["Thu", "Fri", "Sat", "Sun", "Mon", "Tue", "Wed"];
+    let (days, seconds) = (time / 86_400, time % 86_400);
+    let (year, month, day) = civil_from_days(days);
+    format!(
+        "{}, {:02} {} {} {:02}:{:02}:{:02} GMT",
+        WEEKDAYS[(days % 7) as usize],
+        day,
+        MONTHS[month as usize - 1],
+        year,
+        seconds / 3600,
+        seconds / 60 % 60,
+        seconds % 60
+    )
+}
+
+/// Proleptic Gregorian date of a day since the Unix epoch
+fn civil_from_days(days: u64) -> (u64, u64, u64) {
+    let days = days + 719_468;
+    let era = days / 146_097;
+    let day_of_era = days - era * 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = (month_index + 2) % 12 + 1;
+    let year = year_of_era + era * 400 + u64::from(month <= 2);
+    (year, month, day)
+}
+
+/// Days since the Unix epoch of a proleptic Gregorian date
+fn days_from_civil(year: u64, month: u64, day: u64) -> u64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year / 400;
+    let year_of_era = year - era * 400;
+    let month_index = (month + 9) % 12;
+    let day_of_year = (153 * month_index + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+/// Pages of the scanned site whose `Last-Modified` is older than `max_age`
+/// at `now`, most linked first. `is_internal` tells pages of the site apart
+/// from external links.
+pub fn stale_pages(
+    report: &ScanReport,
+    now: u64,
+    max_age: Duration,
+    is_internal: impl Fn(&str) -> bool,
+) -> Vec<StalePage> {
+    let cutoff = now.saturating_sub(max_age.as_secs());
+    let mut pages: Vec<StalePage> = report
+        .links
+        .iter()
+        .filter(|link| is_internal(&link.url))
+        .filter_map(|link| {
+            let last_modified = link.last_modified?;
+            (last_modified < cutoff).then(|| StalePage {
+                page: link.url.clone(),
+                last_modified,
+                inlinks: link.sources.len(),
+            })
+        })
+        .collect();
+    pages.sort_by(|a, b| {
+        b.inlinks
+            .cmp(&a.inlinks)
+            .then(a.last_modified.cmp(&b.last_modified))
+    });
+    pages
+}
@@ -0,0 +1,340 @@
+use crate::link::{normalize_url, LinkVariant};
+use crate::memory::string_bytes;
+use log::warn;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::fs::{self, File, OpenOptions};
+use std::hash::{Hash, Hasher};
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Breadth-first crawl queue that remembers visited URLs and where links were found.
+///
+/// Links are deduplicated by their normalized URL (see [`normalize_url`]), so
+/// each resource is checked once; differently written variants are kept with
+/// the pages using them.
+///
+/// When memory runs short the frontier can [spill](Frontier::spill) to disk:
+/// the queue moves to a file, and visited URLs are only kept as 64-bit
+/// fingerprints.
+#[derive(Debug, Default)]
+pub struct Frontier {
+    queue: VecDeque<(String, usize)>,
+    visited: HashSet<String>,
+    /// Fingerprints of visited URLs, once spilled
+    visited_fingerprints: HashSet<u64>,
+    /// Queue continued on disk, once spilled
+    spilled: Option<SpillQueue>,
+    sources: HashMap<String, Vec<String>>,
+    variants: HashMap<String, BTreeMap<String, Vec<String>>>,
+    /// Links with a fragment, keyed by normalized URL
+    fragments: HashMap<String, BTreeMap<String, Vec<String>>>,
+    /// Approximate bytes held in memory
+    bytes: usize,
+}
+
+impl Frontier {
+    /// Create a frontier seeded with the start URL at depth 0, normalized
+    /// like the links that may lead back to it
+    pub fn new(seed: &str) -> Self {
+        let seed = normalize_url(seed);
+        Frontier {
+            bytes: queued_bytes(&seed),
+            queue: VecDeque::from([(seed, 0)]),
+            ..Default::default()
+        }
+    }
+
+    /// Next unvisited URL and its depth, marking it visited
+    pub fn pop(&mut self) -> Option<(String, usize)> {
+        loop {
+            let (url, depth) = match self.queue.pop_front() {
+                Some(entry) => {
+                    self.bytes -= queued_bytes(&entry.0);
+                    entry
+                }
+                None => self.spilled.as_mut()?.pop()?,
+            };
+            if self.mark_visited(&url) {
+                return Some((url, depth));
+            }
+        }
+    }
+
+    /// Queue the links found on `page`, which sits at `depth`
+    pub fn push_links(
+        &mut self,
+        page: &str,
+        links: impl IntoIterator<Item = String>,
+        depth: usize,
+    ) {
+        for link in links {
+            let key = normalize_url(&link);
+            // In-page anchors are not new links to the page itself
+            if key == page {
+                continue;
+            }
+            let sources = self.sources.entry(key.clone()).or_insert_with(|| {
+                self.bytes += string_bytes(&key);
+                Vec::new()
+            });
+            self.bytes += add_source(sources, page);
+            if link
+                .split_once('#')
+                .is_some_and(|(_, fragment)| !fragment.is_empty())
+            {
+                let fragments = self.fragments.entry(key.clone()).or_default();
+                let link_bytes = string_bytes(&link);
+                let sources = fragments.entry(link.clone()).or_insert_with(|| {
+                    self.bytes += link_bytes;
+                    Vec::new()
+                });
+                self.bytes += add_source(sources, page);
+            }
+            if link.split('#').next() != Some(key.as_str()) {
+                let variants = self.variants.entry(key.clone()).or_default();
+                let link_bytes = string_bytes(&link);
+                let sources = variants.entry(link).or_insert_with(|| {
+                    self.bytes += link_bytes;
+                    Vec::new()
+                });
+                self.bytes += add_source(sources, page);
+            }
+            if !self.is_visited(&key) {
+                self.enqueue(key, depth + 1);
+            }
+        }
+    }
+
+    /// Queue URLs found outside of pages, such as in sitemaps, at `depth`.
+    /// They get no sources, and URLs the crawl also links to are checked once.
+    pub fn seed(&mut self, urls: impl IntoIterator<Item = String>, depth: usize) {
+        for url in urls {
+            let key = normalize_url(&url);
+            if !self.is_visited(&key) {
+                self.enqueue(key, depth);
+            }
+        }
+    }
+
+    /// Take the pages a URL was found on
+    pub fn take_sources(&mut self, url: &str) -> Vec<String> {
+        let sources = self.sources.remove(url).unwrap_or_default();
+        self.bytes = self.bytes.saturating_sub(
+            string_bytes(url) + sources.iter().map(|s| string_bytes(s)).sum::<usize>(),
+        );
+        sources
+    }
+
+    /// Take the variants a URL was linked as, beyond a differing fragment
+    pub fn take_variants(&mut self, url: &str) -> Vec<LinkVariant> {
+        let variants = self.variants.remove(url).unwrap_or_default();
+        self.release(variants)
+    }
+
+    /// Take the links to a URL with a fragment, as written on their pages
+    pub fn take_fragments(&mut self, url: &str) -> Vec<LinkVariant> {
+        let fragments = self.fragments.remove(url).unwrap_or_default();
+        self.release(fragments)
+    }
+
+    /// Variants of a taken entry, no longer counted in memory usage
+    fn release(&mut self, links: BTreeMap<String, Vec<String>>) -> Vec<LinkVariant> {
+        let variants: Vec<LinkVariant> = links
+            .into_iter()
+            .map(|(url, sources)| LinkVariant { url, sources })
+            .collect();
+        let bytes: usize = variants
+            .iter()
+            .map(|variant| {
+                string_bytes(&variant.url)
+                    + variant
+                        .sources
+                        .iter()
+                        .map(|s| string_bytes(s))
+                        .sum::<usize>()
+            })
+            .sum();
+        self.bytes = self.bytes.saturating_sub(bytes);
+        variants
+    }
+
+    /// Number of URLs waiting to be crawled, in memory and on disk
+    pub fn len(&self) -> usize {
+        self.queue.len() + self.spilled.as_ref().map_or(0, |spilled| spilled.pending)
+    }
+
+    /// Whether no URLs are waiting to be crawled
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Approximate bytes held in memory by the queue, the visited set and the
+    /// sources, variants and fragments of each link
+    pub fn memory_usage(&self) -> usize {
+        self.bytes
+    }
+
+    /// Whether the queue has moved to disk
+    pub fn is_spilled(&self) -> bool {
+        self.spilled.is_some()
+    }
+
+    /// Move the queue to a file in `dir` and replace visited URLs by their
+    /// fingerprints. The crawl order is unchanged; the file is removed when the
+    /// frontier is dropped.
+    pub fn spill(&mut self, dir: &Path) -> io::Result<()> {
+        if self.spilled.is_some() {
+            return Ok(());
+        }
+        let mut spilled = SpillQueue::create(dir)?;
+        for (url, depth) in &self.queue {
+            spilled.push(url, *depth)?;
+        }
+        for (url, _) in self.queue.drain(..) {
+            self.bytes -= queued_bytes(&url);
+        }
+        for url in self.visited.drain() {
+            self.bytes -= string_bytes(&url);
+            self.visited_fingerprints.insert(fingerprint(&url));
+            self.bytes += size_of_fingerprint();
+        }
+        self.spilled = Some(spilled);
+        Ok(())
+    }
+
+    fn is_visited(&self, url: &str) -> bool {
+        self.visited.contains(url) || self.visited_fingerprints.contains(&fingerprint(url))
+    }
+
+    /// Mark `url` visited, returning whether it was not visited before
+    fn mark_visited(&mut self, url: &str) -> bool {
+        if self.spilled.is_some() {
+            let new = self.visited_fingerprints.insert(fingerprint(url));
+            if new {
+                self.bytes += size_of_fingerprint();
+            }
+            new
+        } else {
+            let new = self.visited.insert(url.to_string());
+            if new {
+                self.bytes += string_bytes(url);
+            }
+            new
+        }
+    }
+
+    fn enqueue(&mut self, url: String, depth: usize) {
+        match &mut self.spilled {
+            Some(spilled) => {
+                if let Err(e) = spilled.push(&url, depth) {
+                    warn!(
+                        "Failed to queue {} on disk, keeping it in memory: {}",
+                        url, e
+                    );
+                    self.bytes += queued_bytes(&url);
+                    self.queue.push_back((url, depth));
+                }
+            }
+            None => {
+                self.bytes += queued_bytes(&url);
+                self.queue.push_back((url, depth));
+            }
+        }
+    }
+}
+
+/// Approximate bytes of a queued URL
+fn queued_bytes(url: &str) -> usize {
+    string_bytes(url) + std::mem::size_of::<usize>()
+}
+
+fn size_of_fingerprint() -> usize {
+    std::mem::size_of::<u64>()
+}
+
+fn fingerprint(url: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    url.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Add `page` to `sources`, returning the bytes it added
+fn add_source(sources: &mut Vec<String>, page: &str) -> usize {
+    if sources.iter().any(|source| source == page) {
+        0
+    } else {
+        sources.push(page.to_string());
+        string_bytes(page)
+    }
+}
+
+/// Crawl queue continued in a file, one JSON `[url, depth]` entry per line
+#[derive(Debug)]
+struct SpillQueue {
+    path: PathBuf,
+    writer: BufWriter<File>,
+    reader: BufReader<File>,
+    pending: usize,
+}
+
+impl SpillQueue {
+    fn create(dir: &Path) -> io::Result<Self> {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_nanos());
+        let path = dir.join(format!(
+            "inspector-frontier-{}-{}.jsonl",
+            std::process::id(),
+            nanos
+        ));
+        let writer = OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&path)?;
+        let reader = File::open(&path)?;
+        Ok(SpillQueue {
+            path,
+            writer: BufWriter::new(writer),
+            reader: BufReader::new(reader),
+            pending: 0,
+        })
+    }
+
+    fn push(&mut self, url: &str, depth: usize) -> io::Result<()> {
+        serde_json::to_writer(&mut self.writer, &(url, depth))?;
+        self.writer.write_all(b"\n")?;
+        self.pending += 1;
+        Ok(())
+    }
+
+    fn pop(&mut self) -> Option<(String, usize)> {
+        if self.pending == 0 {
+            return None;
+        }
+        let entry = self.writer.flush().and_then(|_| {
+            let mut line = String::new();
+            self.reader.read_line(&mut line)?;
+            serde_json::from_str(&line).map_err(io::Error::from)
+        });
+        self.pending -= 1;
+        match entry {
+            Ok(entry) => Some(entry),
+            Err(e) => {
+                warn!(
+                    "Failed to read the crawl queue from {}: {}",
+                    self.path.display(),
+                    e
+                );
+                None
+            }
+        }
+    }
+}
+
+impl Drop for SpillQueue {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
@@ -0,0 +1,213 @@
+//! Existence checks for `ftp://` and `sftp://` links, built with the `ftp`
+//! feature. Files are looked up without being downloaded.
+
+use crate::link::{LinkInfo, LinkStatus};
+use crate::local::percent_decode;
+use log::debug;
+use ssh2::Session;
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::path::Path;
+use std::time::Duration;
+use url::Url;
+
+/// `SSH_FX_NO_SUCH_FILE`, the SFTP status of missing files
+const SFTP_NO_SUCH_FILE: i32 = 2;
+
+/// What a probed path turned out to be
+enum Entry {
+    /// A file, with its size when the server reports it
+    File(Option<u64>),
+    Directory,
+    Missing,
+}
+
+/// Check that the file or directory an FTP or SFTP link points to exists,
+/// reporting the size of files
+pub fn probe(url: &str, timeout: Duration) -> LinkInfo {
+    let result = Url::parse(url)
+        .map_err(|e| e.to_string())
+        .and_then(|parsed| match parsed.scheme() {
+            "ftp" => probe_ftp(&parsed, timeout),
+            "sftp" => probe_sftp(&parsed, timeout),
+            scheme => Err(format!("{} links cannot be probed", scheme)),
+        });
+    match result {
+        Ok(Entry::File(size)) => {
+            let mut info = LinkInfo::new(url, LinkStatus::Valid);
+            info.content_length = size;
+            info
+        }
+        Ok(Entry::Directory) => LinkInfo::new(url, LinkStatus::Valid),
+        Ok(Entry::Missing) => LinkInfo::new(url, LinkStatus::NotFound),
+        Err(e) => LinkInfo::new(url, LinkStatus::Error(e)),
+    }
+}
+
+/// Connect to the host of a URL, giving up after `timeout`
+fn connect(url: &Url, timeout: Duration) -> Result<TcpStream, String> {
+    let host = url.host_str().ok_or("missing host")?;
+    let port = url.port_or_known_default().unwrap_or(22);
+    let address = (host, port)
+        .to_socket_addrs()
+        .map_err(|e| e.to_string())?
+        .next()
+        .ok_or_else(|| format!("{} did not resolve", host))?;
+    let stream = TcpStream::connect_timeout(&address, timeout).map_err(|e| e.to_string())?;
+    stream
+        .set_read_timeout(Some(timeout))
+        .and_then(|_| stream.set_write_timeout(Some(timeout)))
+        .map_err(|e| e.to_string())?;
+    Ok(stream)
+}
+
+/// User and password of a URL, anonymous when it has none
+fn credentials(url: &Url) -> (String, Option<String>) {
+    let user = match url.username() {
+        "" => "anonymous".to_string(),
+        user => percent_decode(user),
+    };
+    (user, url.password().map(percent_decode))
+}
+
+/// A control connection to an FTP server
+struct FtpControl {
+    reader: BufReader<TcpStream>,
+    writer: TcpStream,
+}
+
+impl FtpControl {
+    /// Read a reply, following multi-line replies to their last line
+    fn reply(&mut self) -> io::Result<(u16, String)> {
+        let mut line = String::new();
+        loop {
+            line.clear();
+            if self.reader.read_line(&mut line)? == 0 {
+                return Err(io::ErrorKind::UnexpectedEof.into());
+            }
+            let code = line.get(..3).and_then(|code| code.parse().ok());
+            match (code, line.as_bytes().get(3)) {
+                (Some(code), Some(b' ')) | (Some(code), Some(b'\r' | b'\n')) => {
+                    return Ok((code, line[3..].trim().to_string()))
+                }
+                _ => continue,
+            }
+        }
+    }
+
+    fn command(&mut self, command: &str) -> io::Result<(u16, String)> {
+        self.writer
+            .write_all(format!("{}\r\n", command).as_bytes())?;
+        self.reply()
+    }
+}
+
+/// Look the path of an FTP URL up, logging in anonymously unless the URL
+/// has credentials
+fn probe_ftp(url: &Url, timeout: Duration) -> Result<Entry, String> {
+    let stream = connect(url, timeout)?;
+    let mut control = FtpControl {
+        reader: BufReader::new(stream.try_clone().map_err(|e| e.to_string())?),
+        writer: stream,
+    };
+    let io_error = |e: io::Error| e.to_string();
+    let unexpected = |(code, text): (u16, String)| format!("FTP {} {}", code, text);
+
+    let greeting = control.reply().map_err(io_error)?;
+    if greeting.0 != 220 {
+        return Err(unexpected(greeting));
+    }
+    let (user, password) = credentials(url);
+    let mut login = control
+        .command(&format!("USER {}", user))
+        .map_err(io_error)?;
+    if login.0 == 331 {
+        let password = password.as_deref().unwrap_or("inspector-gadget@");
+        login = control
+            .command(&format!("PASS {}", password))
+            .map_err(io_error)?;
+    }
+    if login.0 != 230 {
+        return Err(format!("login failed: {}", unexpected(login)));
+    }
+
+    let path = percent_decode(url.path());
+    let result = if path.ends_with('/') {
+        change_directory(&mut control, &path)
+    } else {
+        // Sizes are only reliable in binary mode
+        control.command("TYPE I").map_err(io_error)?;
+        match control
+            .command(&format!("SIZE {}", path))
+            .map_err(io_error)?
+        {
+            (213, size) => Ok(Entry::File(size.parse().ok())),
+            // Missing, or a directory written without its trailing slash
+            (550, _) => change_directory(&mut control, &path),
+            // SIZE is an extension some servers lack; MDTM answers for files too
+            (500..=502, _) => {
+                match control
+                    .command(&format!("MDTM {}", path))
+                    .map_err(io_error)?
+                {
+                    (213, _) => Ok(Entry::File(None)),
+                    (550, _) => change_directory(&mut control, &path),
+                    reply => Err(unexpected(reply)),
+                }
+            }
+            reply => Err(unexpected(reply)),
+        }
+    };
+    if let Err(e) = control.command("QUIT") {
+        debug!("Could not close the FTP session with {}: {}", url, e);
+    }
+    result
+}
+
+/// Whether `path` is a directory, or else missing
+fn change_directory(control: &mut FtpControl, path: &str) -> Result<Entry, String> {
+    match control
+        .command(&format!("CWD {}", path))
+        .map_err(|e| e.to_string())?
+    {
+        (250, _) => Ok(Entry::Directory),
+        (550, _) => Ok(Entry::Missing),
+        (code, text) => Err(format!("FTP {} {}", code, text)),
+    }
+}
+
+/// Look the path of an SFTP URL up. Sessions authenticate with the password of the
+/// URL, or else with the keys of the running SSH agent.
+fn probe_sftp(url: &Url, timeout: Duration) -> Result<Entry, String> {
+    let stream = connect(url, timeout)?;
+    let mut session = Session::new().map_err(|e| e.to_string())?;
+    session.set_timeout(timeout.as_millis().try_into().unwrap_or(u32::MAX));
+    session.set_tcp_stream(stream);
+    session.handshake().map_err(|e| e.to_string())?;
+    let (user, password) = match (url.username(), url.password()) {
+        ("", _) => (std::env::var("USER").unwrap_or_default(), None),
+        _ => credentials(url),
+    };
+    let authenticated = match &password {
+        Some(password) => session.userauth_password(&user, password),
+        None => session.userauth_agent(&user),
+    };
+    if let Err(e) = authenticated {
+        return Err(format!("authentication as {} failed: {}", user, e));
+    }
+
+    // `sftp://host/~/file` is relative to the home directory
+    let path = percent_decode(url.path());
+    let path = path.strip_prefix("/~/").unwrap_or(&path);
+    let sftp = session.sftp().map_err(|e| e.to_string())?;
+    let result = match sftp.stat(Path::new(path)) {
+        Ok(stat) if stat.is_dir() => Ok(Entry::Directory),
+        Ok(stat) => Ok(Entry::File(stat.size)),
+        Err(e) if e.code() == ssh2::ErrorCode::SFTP(SFTP_NO_SUCH_FILE) => Ok(Entry::Missing),
+        Err(e) => Err(e.to_string()),
+    };
+    if let Err(e) = session.disconnect(None, "probe done", None) {
+        debug!("Could not close the SFTP session with {}: {}", url, e);
+    }
+    result
+}
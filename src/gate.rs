@@ -0,0 +1,165 @@
+use crate::link::{LinkInfo, LinkStatus};
+use crate::manifest::ThresholdResult;
+use crate::output::ScanReport;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Broken links that count against the `fail_threshold`
+#[derive(Debug, Serialize, Deserialize, Default, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum FailOn {
+    /// Links answering not found
+    NotFound,
+    /// Other broken links: failed requests, DNS errors, parked domains and
+    /// broken anchors
+    Error,
+    /// Every broken link
+    #[default]
+    Any,
+}
+
+impl FailOn {
+    pub const NAMES: [&'static str; 3] = ["notfound", "error", "any"];
+
+    /// Parse a name from the command line or config
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "notfound" => Some(FailOn::NotFound),
+            "error" => Some(FailOn::Error),
+            "any" => Some(FailOn::Any),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            FailOn::NotFound => "notfound",
+            FailOn::Error => "error",
+            FailOn::Any => "any",
+        }
+    }
+
+    /// Whether a link with `status` counts against the threshold
+    pub fn counts(&self, status: &LinkStatus) -> bool {
+        match self {
+            FailOn::NotFound => *status == LinkStatus::NotFound,
+            FailOn::Error => status.is_broken() && *status != LinkStatus::NotFound,
+            FailOn::Any => status.is_broken(),
+        }
+    }
+}
+
+/// Broken links a scan may have before it fails: a count, like `5`, or a
+/// percentage of the checked links, like `2.5%`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FailThreshold {
+    Count(usize),
+    Percent(f64),
+}
+
+impl Default for FailThreshold {
+    fn default() -> Self {
+        FailThreshold::Count(0)
+    }
+}
+
+impl FailThreshold {
+    pub fn parse(value: &str) -> Result<Self, String> {
+        let value = value.trim();
+        let invalid = || format!("{} is not a count or a percentage like 2.5%", value);
+        match value.strip_suffix('%') {
+            Some(percent) => {
+                let percent: f64 = percent.trim().parse().map_err(|_| invalid())?;
+                if !(0.0..=100.0).contains(&percent) {
+                    return Err(format!("{} is not between 0% and 100%", value));
+                }
+                Ok(FailThreshold::Percent(percent))
+            }
+            None => value
+                .parse()
+                .map(FailThreshold::Count)
+                .map_err(|_| invalid()),
+        }
+    }
+
+    /// Whether `failing` of `checked` links stay within the threshold
+    pub fn allows(&self, failing: usize, checked: usize) -> bool {
+        match *self {
+            FailThreshold::Count(max) => failing <= max,
+            FailThreshold::Percent(max) => {
+                checked == 0 || failing as f64 * 100.0 / checked as f64 <= max
+            }
+        }
+    }
+}
+
+impl fmt::Display for FailThreshold {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FailThreshold::Count(max) => write!(f, "{}", max),
+            FailThreshold::Percent(max) => write!(f, "{}%", max),
+        }
+    }
+}
+
+/// Links counted against the threshold and links checked in a report. Links
+/// of the crawl and external links are counted; ignored links are not.
+fn failing_links(report: &ScanReport, fail_on: FailOn) -> (usize, usize) {
+    let checked: Vec<&LinkInfo> = report.links.iter().chain(&report.external_links).collect();
+    let failing = checked
+        .iter()
+        .filter(|link| fail_on.counts(&link.status))
+        .count();
+    (failing, checked.len())
+}
+
+/// Broken links of a report against the threshold, as recorded in the run
+/// manifest
+pub fn threshold_result(
+    report: &ScanReport,
+    fail_on: FailOn,
+    threshold: FailThreshold,
+) -> ThresholdResult {
+    let (failing, checked) = failing_links(report, fail_on);
+    let name = match fail_on {
+        FailOn::NotFound => "notfound_links",
+        FailOn::Error => "error_links",
+        FailOn::Any => "broken_links",
+    };
+    let (name, limit, actual) = match threshold {
+        FailThreshold::Count(max) => (name.to_string(), max as f64, failing as f64),
+        FailThreshold::Percent(max) => {
+            let percent = if checked == 0 {
+                0.0
+            } else {
+                failing as f64 * 100.0 / checked as f64
+            };
+            (format!("{}_percent", name), max, percent)
+        }
+    };
+    ThresholdResult {
+        name,
+        limit,
+        actual,
+        passed: threshold.allows(failing, checked),
+    }
+}
+
+/// Why a report fails the threshold, or `None` when it passes
+pub fn threshold_failure(
+    report: &ScanReport,
+    fail_on: FailOn,
+    threshold: FailThreshold,
+) -> Option<String> {
+    let (failing, checked) = failing_links(report, fail_on);
+    if threshold.allows(failing, checked) {
+        return None;
+    }
+    Some(format!(
+        "{} of {} checked links are broken (--fail-on {}), more than the threshold of {}",
+        failing,
+        checked,
+        fail_on.name(),
+        threshold
+    ))
+}
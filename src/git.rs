@@ -0,0 +1,40 @@
+use std::error::Error;
+use std::path::PathBuf;
+use std::process::Command;
+
+/// Files changed on the current branch, relative to the repository root.
+///
+/// Compares against `base` when given (e.g. `origin/main`), otherwise against
+/// the previous commit. Deleted files are left out.
+pub fn changed_files(base: Option<&str>) -> Result<Vec<PathBuf>, Box<dyn Error>> {
+    let range = match base {
+        Some(base) => format!("{}...HEAD", base),
+        None => "HEAD~1".to_string(),
+    };
+    let output = Command::new("git")
+        .args(["diff", "--name-only", "--diff-filter=d", &range])
+        .output()?;
+    if !output.status.success() {
+        return Err(format!(
+            "git diff failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        )
+        .into());
+    }
+    Ok(String::from_utf8(output.stdout)?
+        .lines()
+        .filter(|line| !line.is_empty())
+        .map(PathBuf::from)
+        .collect())
+}
+
+/// Top-level directory of the repository containing the working directory
+pub fn repo_root() -> Result<PathBuf, Box<dyn Error>> {
+    let output = Command::new("git")
+        .args(["rev-parse", "--show-toplevel"])
+        .output()?;
+    if !output.status.success() {
+        return Err("not inside a git repository".into());
+    }
+    Ok(PathBuf::from(String::from_utf8(output.stdout)?.trim()))
+}
@@ -0,0 +1,197 @@
+use crate::link::LinkInfo;
+use crate::output::ScanReport;
+use reqwest::blocking::Client;
+use serde::Serialize;
+use serde_json::json;
+use std::env;
+use std::error::Error;
+use std::fs;
+use std::path::PathBuf;
+use url::Url;
+
+/// Environment variable holding the token used to report back to GitHub
+pub const GITHUB_TOKEN_ENV: &str = "GITHUB_TOKEN";
+
+const DEFAULT_API_URL: &str = "https://api.github.com";
+
+/// Context under which commit statuses are reported
+pub const STATUS_CONTEXT: &str = "inspector-gadget";
+
+/// State of a commit status
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CommitState {
+    Pending,
+    Success,
+    Failure,
+    Error,
+}
+
+impl CommitState {
+    fn as_str(&self) -> &'static str {
+        match self {
+            CommitState::Pending => "pending",
+            CommitState::Success => "success",
+            CommitState::Failure => "failure",
+            CommitState::Error => "error",
+        }
+    }
+}
+
+/// Minimal client for the GitHub REST API
+pub struct GitHubClient {
+    client: Client,
+    token: String,
+    api_url: String,
+}
+
+impl GitHubClient {
+    pub fn new(token: &str) -> Self {
+        GitHubClient {
+            client: Client::new(),
+            token: token.to_string(),
+            api_url: DEFAULT_API_URL.to_string(),
+        }
+    }
+
+    /// Client authenticated with `GITHUB_TOKEN`, honoring `GITHUB_API_URL` for GitHub Enterprise
+    pub fn from_env() -> Option<Self> {
+        let token = env::var(GITHUB_TOKEN_ENV).ok()?;
+        let mut client = GitHubClient::new(&token);
+        if let Ok(api_url) = env::var("GITHUB_API_URL") {
+            client.api_url = api_url.trim_end_matches('/').to_string();
+        }
+        Some(client)
+    }
+
+    /// Set a commit status on `sha` in `repository` (`owner/repo`)
+    pub fn create_commit_status(
+        &self,
+        repository: &str,
+        sha: &str,
+        state: CommitState,
+        description: &str,
+    ) -> Result<(), Box<dyn Error>> {
+        // GitHub rejects descriptions longer than 140 characters
+        let description: String = description.chars().take(140).collect();
+        self.client
+            .post(format!(
+                "{}/repos/{}/statuses/{}",
+                self.api_url, repository, sha
+            ))
+            .bearer_auth(&self.token)
+            .header("Accept", "application/vnd.github+json")
+            .header("User-Agent", STATUS_CONTEXT)
+            .json(&json!({
+                "state": state.as_str(),
+                "description": description,
+                "context": STATUS_CONTEXT,
+            }))
+            .send()?
+            .error_for_status()?;
+        Ok(())
+    }
+}
+
+/// Name of the check run created by `--github-check`
+pub const CHECK_NAME: &str = "inspector-gadget";
+
+/// GitHub accepts at most 50 annotations per request
+pub const MAX_ANNOTATIONS: usize = 50;
+
+/// An inline annotation on a file of the repository
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Annotation {
+    pub path: String,
+    pub start_line: usize,
+    pub end_line: usize,
+    pub annotation_level: &'static str,
+    pub message: String,
+}
+
+/// Result of a check run
+#[derive(Debug, Clone)]
+pub struct CheckRun {
+    pub passed: bool,
+    pub title: String,
+    pub summary: String,
+    pub annotations: Vec<Annotation>,
+}
+
+impl GitHubClient {
+    /// Create a completed check run on `sha` in `repository` (`owner/repo`)
+    pub fn create_check_run(
+        &self,
+        repository: &str,
+        sha: &str,
+        check: &CheckRun,
+    ) -> Result<(), Box<dyn Error>> {
+        let annotations: Vec<&Annotation> =
+            check.annotations.iter().take(MAX_ANNOTATIONS).collect();
+        self.client
+            .post(format!("{}/repos/{}/check-runs", self.api_url, repository))
+            .bearer_auth(&self.token)
+            .header("Accept", "application/vnd.github+json")
+            .header("User-Agent", STATUS_CONTEXT)
+            .json(&json!({
+                "name": CHECK_NAME,
+                "head_sha": sha,
+                "status": "completed",
+                "conclusion": if check.passed { "success" } else { "failure" },
+                "output": {
+                    "title": check.title,
+                    "summary": check.summary,
+                    "annotations": annotations,
+                },
+            }))
+            .send()?
+            .error_for_status()?;
+        Ok(())
+    }
+}
+
+/// Annotate the lines of `files` that reference a broken link of the report.
+///
+/// A line references a link when it contains the link's full URL or, for
+/// links on the scanned site, its path (as written in relative links).
+pub fn annotate_broken_links(
+    report: &ScanReport,
+    site: &str,
+    files: &[PathBuf],
+) -> Vec<Annotation> {
+    let site = Url::parse(site).ok();
+    let needles: Vec<(&LinkInfo, Vec<String>)> = report
+        .links
+        .iter()
+        .filter(|link| link.is_broken())
+        .map(|link| {
+            let mut needles = vec![link.url.clone()];
+            if let (Some(site), Ok(url)) = (&site, Url::parse(&link.url)) {
+                if url.origin() == site.origin() && url.path().len() > 1 {
+                    needles.push(url.path().to_string());
+                }
+            }
+            (link, needles)
+        })
+        .collect();
+
+    let mut annotations = Vec::new();
+    for file in files {
+        let Ok(content) = fs::read_to_string(file) else {
+            continue;
+        };
+        for (index, line) in content.lines().enumerate() {
+            for (link, needles) in &needles {
+                if needles.iter().any(|needle| line.contains(needle.as_str())) {
+                    annotations.push(Annotation {
+                        path: file.to_string_lossy().replace('\\', "/"),
+                        start_line: index + 1,
+                        end_line: index + 1,
+                        annotation_level: "failure",
+                        message: format!("Broken link {} ({:?})", link.url, link.status),
+                    });
+                }
+            }
+        }
+    }
+    annotations
+}
@@ -0,0 +1,40 @@
+use crate::config::GraphqlProbe;
+use serde_json::{json, Value};
+
+/// JSON body POSTed by a GraphQL probe
+pub fn graphql_body(probe: GraphqlProbe) -> String {
+    let query = match probe {
+        GraphqlProbe::Ping => "{ __typename }",
+        GraphqlProbe::Introspection => "{ __schema { queryType { name } } }",
+    };
+    json!({ "query": query }).to_string()
+}
+
+/// Why the answer of a GraphQL endpoint to a probe is not a working GraphQL
+/// response, if it is not: not JSON, no `data`, or an introspection
+/// without the schema
+pub fn graphql_problem(probe: GraphqlProbe, body: &str) -> Option<String> {
+    let Ok(response) = serde_json::from_str::<Value>(body) else {
+        return Some("Not a GraphQL response".to_string());
+    };
+    let data = response.get("data").filter(|data| !data.is_null());
+    let error = response
+        .get("errors")
+        .and_then(Value::as_array)
+        .and_then(|errors| errors.first())
+        .map(|error| {
+            error
+                .get("message")
+                .and_then(Value::as_str)
+                .unwrap_or("unknown error")
+        });
+    match (data, error) {
+        (None, Some(error)) => Some(format!("GraphQL query failed: {}", error)),
+        (None, None) => Some("Not a GraphQL response".to_string()),
+        (Some(data), _) => {
+            let schema = data.pointer("/__schema/queryType/name");
+            (probe == GraphqlProbe::Introspection && schema.is_none())
+                .then(|| "GraphQL introspection returned no schema".to_string())
+        }
+    }
+}
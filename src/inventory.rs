@@ -0,0 +1,243 @@
+use calamine::{open_workbook_auto, Reader};
+use scraper::{ElementRef, Html, Selector};
+use serde_json::Value;
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Extensions of spreadsheets read through calamine; anything else is read as
+/// CSV, or as TSV for `.tsv` and `.tab`
+pub const SPREADSHEET_EXTENSIONS: [&str; 6] = ["xlsx", "xlsm", "xlsb", "xls", "xla", "ods"];
+
+/// Extensions of Netscape bookmark files, as exported by browsers
+pub const BOOKMARK_EXTENSIONS: [&str; 2] = ["html", "htm"];
+/// Header of the URL column of inventories built from bookmarks and HAR files
+pub const URL_HEADER: &str = "URL";
+
+/// Rows of a URL inventory, header row included
+#[derive(Debug, Clone, PartialEq)]
+pub struct Inventory {
+    pub rows: Vec<Vec<String>>,
+}
+
+fn extension(path: &Path) -> String {
+    path.extension()
+        .and_then(|extension| extension.to_str())
+        .unwrap_or_default()
+        .to_ascii_lowercase()
+}
+
+impl Inventory {
+    /// Read a CSV/TSV file, a sheet of a spreadsheet (the first one unless
+    /// `sheet` names another), a bookmarks export or a HAR file
+    pub fn load(path: &Path, sheet: Option<&str>) -> Result<Self, Box<dyn Error>> {
+        let extension = extension(path);
+        if BOOKMARK_EXTENSIONS.contains(&extension.as_str()) {
+            return Ok(Inventory::from_bookmarks(&fs::read_to_string(path)?));
+        }
+        if extension == "har" {
+            return Inventory::from_har(&fs::read_to_string(path)?);
+        }
+        if SPREADSHEET_EXTENSIONS.contains(&extension.as_str()) {
+            let mut workbook = open_workbook_auto(path)?;
+            let range = match sheet {
+                Some(name) => workbook.worksheet_range(name)?,
+                None => workbook
+                    .worksheet_range_at(0)
+                    .ok_or_else(|| format!("{} has no sheets", path.display()))??,
+            };
+            let rows = range
+                .rows()
+                .map(|row| row.iter().map(|cell| cell.to_string()).collect())
+                .collect();
+            return Ok(Inventory { rows });
+        }
+
+        let delimiter = if matches!(extension.as_str(), "tsv" | "tab") {
+            b'\t'
+        } else {
+            b','
+        };
+        let mut reader = csv::ReaderBuilder::new()
+            .delimiter(delimiter)
+            .has_headers(false)
+            .flexible(true)
+            .from_path(path)?;
+        let rows = reader
+            .records()
+            .map(|record| Ok(record?.iter().map(String::from).collect()))
+            .collect::<Result<_, csv::Error>>()?;
+        Ok(Inventory { rows })
+    }
+
+    /// Bookmarks of a Netscape bookmark file (the export format of all major
+    /// browsers) as `Folder`, `Title` and `URL` columns. Nested folders are
+    /// joined with ` / `.
+    pub fn from_bookmarks(html: &str) -> Self {
+        let document = Html::parse_document(html);
+        let bookmarks = Selector::parse("a[href]").unwrap();
+        let mut rows = vec![vec![
+            "Folder".to_string(),
+            "Title".to_string(),
+            URL_HEADER.to_string(),
+        ]];
+        for bookmark in document.select(&bookmarks) {
+            // A folder is a <DT> holding its <H3> name and a <DL> of entries
+            let mut folders: Vec<String> = bookmark
+                .ancestors()
+                .filter_map(ElementRef::wrap)
+                .filter(|ancestor| ancestor.value().name() == "dt")
+                .filter_map(|folder| {
+                    folder
+                        .children()
+                        .filter_map(ElementRef::wrap)
+                        .find(|child| child.value().name() == "h3")
+                })
+                .map(|name| name.text().collect::<String>().trim().to_string())
+                .collect();
+            folders.reverse();
+            rows.push(vec![
+                folders.join(" / "),
+                bookmark.text().collect::<String>().trim().to_string(),
+                bookmark
+                    .value()
+                    .attr("href")
+                    .unwrap_or_default()
+                    .to_string(),
+            ]);
+        }
+        Inventory { rows }
+    }
+
+    /// Requests recorded in a HAR file as `Method`, `URL` and `Recorded
+    /// status` columns
+    pub fn from_har(har: &str) -> Result<Self, Box<dyn Error>> {
+        let har: Value = serde_json::from_str(har)?;
+        let entries = har["log"]["entries"]
+            .as_array()
+            .ok_or("HAR file has no log.entries")?;
+        let mut rows = vec![vec![
+            "Method".to_string(),
+            URL_HEADER.to_string(),
+            "Recorded status".to_string(),
+        ]];
+        for entry in entries {
+            let Some(url) = entry["request"]["url"].as_str() else {
+                continue;
+            };
+            rows.push(vec![
+                entry["request"]["method"]
+                    .as_str()
+                    .unwrap_or_default()
+                    .to_string(),
+                url.to_string(),
+                entry["response"]["status"]
+                    .as_u64()
+                    .map(|status| status.to_string())
+                    .unwrap_or_default(),
+            ]);
+        }
+        Ok(Inventory { rows })
+    }
+
+    /// Zero-based indexes of the URL columns named by `spec`: comma-separated
+    /// one-based column numbers or header names
+    pub fn columns(&self, spec: &str) -> Result<Vec<usize>, String> {
+        let header = self.rows.first().map(Vec::as_slice).unwrap_or_default();
+        spec.split(',')
+            .map(str::trim)
+            .map(|column| match column.parse::<usize>() {
+                Ok(0) => Err("URL columns are numbered from 1".to_string()),
+                Ok(number) => Ok(number - 1),
+                Err(_) => header
+                    .iter()
+                    .position(|name| name.trim().eq_ignore_ascii_case(column))
+                    .ok_or_else(|| format!("No column named {:?} in the header row", column)),
+            })
+            .collect()
+    }
+
+    /// Distinct URLs in `columns`, in order of appearance, skipping the header
+    /// row when there is one
+    pub fn urls(&self, columns: &[usize], has_header: bool) -> Vec<String> {
+        let mut urls: Vec<String> = Vec::new();
+        for row in self.rows.iter().skip(usize::from(has_header)) {
+            for column in columns {
+                let cell = row.get(*column).map(|cell| cell.trim()).unwrap_or_default();
+                if !cell.is_empty() && !urls.iter().any(|url| url == cell) {
+                    urls.push(cell.to_string());
+                }
+            }
+        }
+        urls
+    }
+
+    /// Copy of the rows with a status column appended for each URL column.
+    /// Status columns are headed `<column> status` when there is a header row.
+    pub fn annotate(
+        &self,
+        columns: &[usize],
+        has_header: bool,
+        status_of: impl Fn(&str) -> Option<String>,
+    ) -> Vec<Vec<String>> {
+        let width = self.rows.iter().map(Vec::len).max().unwrap_or_default();
+        self.rows
+            .iter()
+            .enumerate()
+            .map(|(index, row)| {
+                let mut row = row.clone();
+                let cells: Vec<String> = columns
+                    .iter()
+                    .map(|column| row.get(*column).map(|cell| cell.trim()).unwrap_or_default())
+                    .map(String::from)
+                    .collect();
+                row.resize(width, String::new());
+                for (column, cell) in columns.iter().zip(cells) {
+                    row.push(if has_header && index == 0 {
+                        match cell.as_str() {
+                            "" => format!("column {} status", column + 1),
+                            name => format!("{} status", name),
+                        }
+                    } else if cell.is_empty() {
+                        String::new()
+                    } else {
+                        status_of(&cell).unwrap_or_default()
+                    });
+                }
+                row
+            })
+            .collect()
+    }
+}
+
+/// Default file for the annotated inventory: `<name>-checked.csv` next to
+/// the input. Spreadsheets, bookmarks and HAR files are written as CSV.
+pub fn default_output_path(input: &Path) -> PathBuf {
+    let stem = input
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .unwrap_or("inventory");
+    let extension = match extension(input).as_str() {
+        "tsv" | "tab" => "tsv",
+        _ => "csv",
+    };
+    input.with_file_name(format!("{}-checked.{}", stem, extension))
+}
+
+/// Write rows as CSV, or TSV when the path ends in `.tsv` or `.tab`
+pub fn write_rows(path: &Path, rows: &[Vec<String>]) -> Result<(), Box<dyn Error>> {
+    let delimiter = if matches!(extension(path).as_str(), "tsv" | "tab") {
+        b'\t'
+    } else {
+        b','
+    };
+    let mut writer = csv::WriterBuilder::new()
+        .delimiter(delimiter)
+        .flexible(true)
+        .from_path(path)?;
+    for row in rows {
+        writer.write_record(row)?;
+    }
+    writer.flush()?;
+    Ok(())
+}
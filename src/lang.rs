@@ -0,0 +1,115 @@
+use scraper::{Html, Selector};
+use std::collections::{HashMap, HashSet};
+use url::Url;
+
+/// Restricts the crawl to a set of languages.
+///
+/// Pages in other languages are still requested, so cross-language links are
+/// verified, but their links are not followed. The language of a page comes
+/// from `hreflang` alternates seen so far, or from a `/ja/`-style path prefix.
+/// A first path segment is only taken for a language when it is one of the
+/// configured prefixes or an `hreflang` alternate lives under it, since
+/// `/it/`, `/no/` or `/co-op/` are as likely to be ordinary paths.
+#[derive(Debug, Default)]
+pub struct LanguageFilter {
+    langs: Vec<String>,
+    hreflang: HashMap<String, String>,
+    /// First path segments known to hold a language subtree
+    prefixes: HashSet<String>,
+}
+
+impl LanguageFilter {
+    /// Create a filter keeping the given language codes (e.g. `en`, `pt-BR`)
+    pub fn new(langs: &[String]) -> Self {
+        LanguageFilter {
+            langs: langs.iter().map(|lang| lang.to_ascii_lowercase()).collect(),
+            hreflang: HashMap::new(),
+            prefixes: HashSet::new(),
+        }
+    }
+
+    /// Take these first path segments (e.g. `ja`, `pt-br`) for language
+    /// subtrees without waiting for `hreflang` alternates to confirm them
+    pub fn with_prefixes(mut self, prefixes: &[String]) -> Self {
+        self.prefixes
+            .extend(prefixes.iter().map(|prefix| language_segment(prefix)));
+        self
+    }
+
+    /// Remember the languages announced by `<link rel="alternate" hreflang>` on a page
+    pub fn record_alternates(&mut self, document: &Html, page_url: &str) {
+        let selector = Selector::parse("link[rel=alternate][hreflang][href]").unwrap();
+        let base = match Url::parse(page_url) {
+            Ok(base) => base,
+            Err(_) => return,
+        };
+        for element in document.select(&selector) {
+            let (Some(lang), Some(href)) = (
+                element.value().attr("hreflang"),
+                element.value().attr("href"),
+            ) else {
+                continue;
+            };
+            if let Ok(url) = base.join(href) {
+                self.record_alternate(lang, url.as_str());
+            }
+        }
+    }
+
+    /// Remember that `url` is in language `lang`, as announced by an `hreflang`
+    /// alternate; `x-default` says nothing about the language. A first path
+    /// segment naming that language becomes a known prefix.
+    pub fn record_alternate(&mut self, lang: &str, url: &str) {
+        if lang.eq_ignore_ascii_case("x-default") {
+            return;
+        }
+        let lang = lang.to_ascii_lowercase();
+        if let Some(segment) = first_segment(url).filter(|segment| same_language(&lang, segment)) {
+            self.prefixes.insert(segment);
+        }
+        self.hreflang.insert(url.to_string(), lang);
+    }
+
+    /// Language of a URL, if it can be told
+    pub fn language_of(&self, url: &str) -> Option<String> {
+        if let Some(lang) = self.hreflang.get(url) {
+            return Some(lang.clone());
+        }
+        first_segment(url).filter(|segment| self.prefixes.contains(segment))
+    }
+
+    /// Whether the URL belongs to a language that is not crawled
+    pub fn is_foreign(&self, url: &str) -> bool {
+        if self.langs.is_empty() {
+            return false;
+        }
+        match self.language_of(url) {
+            Some(lang) => !self.langs.iter().any(|wanted| same_language(wanted, &lang)),
+            None => false,
+        }
+    }
+}
+
+/// First path segment of a URL, lowercase and with `-` separating a region
+fn first_segment(url: &str) -> Option<String> {
+    let parsed = Url::parse(url).ok()?;
+    let segment = parsed.path_segments()?.next()?;
+    (!segment.is_empty()).then(|| language_segment(segment))
+}
+
+fn language_segment(segment: &str) -> String {
+    segment
+        .trim_matches('/')
+        .to_ascii_lowercase()
+        .replace('_', "-")
+}
+
+/// `en` matches `en-us`, and `en-us` matches `en`, but `en-us` does not match `en-gb`
+pub fn same_language(wanted: &str, lang: &str) -> bool {
+    let primary = |code: &str| code.split('-').next().unwrap_or("").to_string();
+    if wanted.contains('-') && lang.contains('-') {
+        wanted == lang
+    } else {
+        primary(wanted) == primary(lang)
+    }
+}
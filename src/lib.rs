@@ -0,0 +1,75 @@
+//! Inspector Gadget library
+//!
+//! The building blocks behind the `inspector-gadget` CLI: configuration
+//! loading, link inspection, page checks, and output formatting. Downstream crates can
+//! reuse them directly, e.g. to register custom output formats through
+//! [`output::OutputRegistry`].
+
+pub mod agents;
+pub mod alternates;
+pub mod artifacts;
+pub mod attribution;
+pub mod blocklist;
+pub mod changes;
+pub mod check;
+pub mod compare;
+pub mod config;
+pub mod control;
+pub mod cors;
+pub mod dashboard;
+pub mod dns;
+pub mod error;
+pub mod freshness;
+pub mod frontier;
+#[cfg(feature = "ftp")]
+pub mod ftp;
+pub mod gate;
+pub mod git;
+pub mod github;
+pub mod graphql;
+pub mod inventory;
+pub mod lang;
+pub mod link;
+pub mod local;
+pub mod locale;
+pub mod login;
+pub mod manifest;
+pub mod matcher;
+pub mod memory;
+pub mod ndjson;
+pub mod negotiation;
+pub mod origins;
+pub mod output;
+pub mod owners;
+pub mod parked;
+pub mod pipeline;
+#[cfg(feature = "plugins")]
+pub mod plugins;
+pub mod profiles;
+pub mod provenance;
+pub mod ratelimit;
+pub mod redirects;
+pub mod references;
+pub mod resolvers;
+pub mod revalidation;
+pub mod robots;
+pub mod sarif;
+pub mod scans;
+pub mod schedule;
+pub mod scope;
+pub mod search;
+pub mod secret;
+pub mod sections;
+pub mod simulate;
+pub mod site;
+pub mod sitemap;
+pub mod sourcelinks;
+pub mod sqlite;
+pub mod store;
+pub mod stream;
+pub mod summary;
+pub mod tls;
+pub mod toc;
+pub mod tracking;
+pub mod videos;
+pub mod webhook;
@@ -1,69 +1,1231 @@
-use reqwest::blocking::Client;
-use scraper::{Html, Selector};
-use serde::Serialize;
+use crate::alternates::alternate_kind;
+use crate::config::{probe_method, GraphqlProbe, OverrideConfig, ProxyConfig, DEFAULT_TIMEOUT};
+use crate::freshness::{format_http_date, parse_http_date};
+use crate::graphql::{graphql_body, graphql_problem};
+use crate::local::percent_decode;
+use crate::login::LoginPages;
+use crate::ratelimit::{HostDelays, RateLimiter};
+use crate::redirects::RedirectLog;
+use crate::resolvers::Resolver;
+use crate::revalidation::{Revalidation, Validators};
+use crate::robots::RobotsCache;
+use crate::scope::NetworkRules;
+use crate::secret::Secret;
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use log::debug;
+use regex::Regex;
+use reqwest::blocking::{Client, RequestBuilder};
+use reqwest::header::{
+    HeaderMap, HeaderName, HeaderValue, AUTHORIZATION, CONTENT_LENGTH, CONTENT_RANGE, CONTENT_TYPE,
+    COOKIE, ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED, RANGE,
+};
+use reqwest::{Method, NoProxy, Proxy, StatusCode};
+use scraper::{ElementRef, Html, Selector};
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::RandomState;
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
+use std::future::{ready, Future};
+use std::hash::{BuildHasher, Hasher};
+use std::io;
+use std::pin::pin;
+use std::sync::Arc;
+use std::task::{Context, Poll, Waker};
+use std::thread;
+use std::time::{Duration, Instant};
 use url::Url;
 
 /// Information about a link
-#[derive(Debug, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LinkInfo {
     pub url: String,
     pub status: LinkStatus,
+    /// Number of hops from the start URL
+    #[serde(default)]
+    pub depth: usize,
+    /// Pages on which the link was found
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub sources: Vec<String>,
+    /// Unix time of the previous check when the status was reused from the store
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_checked: Option<u64>,
+    /// SHA-256 of the page body, for fetched pages
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content_hash: Option<String>,
+    /// Final URL when the request was redirected
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub redirected_to: Option<String>,
+    /// Whether every redirect to `redirected_to` was permanent (301 or 308)
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub permanent_redirect: bool,
+    /// Unix time from the `Last-Modified` response header
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_modified: Option<u64>,
+    /// `ETag` response header
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub etag: Option<String>,
+    /// Differently written URLs that normalize to this one, with the pages using them
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub variants: Vec<LinkVariant>,
+    /// Size in bytes the server advertises, for probed binaries
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content_length: Option<u64>,
+    /// Requests made for the link, when transient failures were retried
+    #[serde(default, skip_serializing_if = "is_single_attempt")]
+    pub attempts: u32,
+    /// Milliseconds the last request took to answer with its headers
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub response_time_ms: Option<u64>,
+    /// Answer to a conditional request, for pages revalidated against the
+    /// previous run
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub revalidation: Option<Revalidation>,
+    /// HTTP method of the probe, when an `overrides` entry sets one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub method: Option<String>,
+}
+
+fn is_single_attempt(attempts: &u32) -> bool {
+    *attempts <= 1
+}
+
+fn is_false(value: &bool) -> bool {
+    !*value
+}
+
+/// A raw link target that normalizes to a checked URL
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LinkVariant {
+    pub url: String,
+    pub sources: Vec<String>,
+}
+
+impl LinkInfo {
+    /// Create a link with no crawl metadata
+    pub fn new(url: &str, status: LinkStatus) -> Self {
+        LinkInfo {
+            url: url.to_string(),
+            status,
+            depth: 0,
+            sources: Vec::new(),
+            last_checked: None,
+            content_hash: None,
+            redirected_to: None,
+            permanent_redirect: false,
+            last_modified: None,
+            etag: None,
+            variants: Vec::new(),
+            content_length: None,
+            attempts: 0,
+            response_time_ms: None,
+            revalidation: None,
+            method: None,
+        }
+    }
+
+    /// Whether the link is broken (not found or errored)
+    pub fn is_broken(&self) -> bool {
+        self.status.is_broken()
+    }
 }
 
 /// Status of a link
-#[derive(Debug, Serialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum LinkStatus {
     Valid,
     NotFound,
     Error(String),
+    /// The host did not resolve, so no request was made
+    DnsError(String),
     Ignored,
+    /// The link uses a scheme outside `allowed_schemes`, so it was not followed
+    UnsupportedScheme(String),
+    /// The URL only appears inside code blocks and was not checked
+    CodeBlock,
+    /// The host is a reserved or example domain, so no request was made
+    Placeholder,
+    /// The host resolves to a private address, so no request was made
+    PrivateAddress(String),
+    /// The destination is outside the `network` rules, so no request was made
+    OutOfScope(String),
+    /// The page answered but is a parked or for-sale domain
+    Parked(String),
+    /// The link redirects to the given sign-in page, so readers hit a login
+    /// wall
+    AuthRequired(String),
+    /// robots.txt disallows the URL for our user agent, so no request was made
+    Disallowed,
+    /// The page exists but has no element with the id or anchor name of the
+    /// link's fragment
+    BrokenAnchor(String),
 }
 
-/// Inspect a single link and return its status and HTML content if successful
-pub fn inspect_single_link(client: &Client, url: &str) -> Result<(LinkInfo, String), LinkInfo> {
-    match client.get(url).send() {
-        Ok(response) => {
-            let status = response.status();
-            let link_status = if status.is_success() {
-                LinkStatus::Valid
-            } else if status.as_u16() == 404 {
-                LinkStatus::NotFound
-            } else {
-                LinkStatus::Error(status.to_string())
-            };
+impl LinkStatus {
+    /// Whether the link was deliberately not requested; such links are listed
+    /// with the ignored links
+    pub fn is_unchecked(&self) -> bool {
+        matches!(
+            self,
+            LinkStatus::Ignored
+                | LinkStatus::UnsupportedScheme(_)
+                | LinkStatus::CodeBlock
+                | LinkStatus::Placeholder
+                | LinkStatus::PrivateAddress(_)
+                | LinkStatus::OutOfScope(_)
+                | LinkStatus::Disallowed
+        )
+    }
+
+    /// Whether the status means the link is broken
+    pub fn is_broken(&self) -> bool {
+        matches!(
+            self,
+            LinkStatus::NotFound
+                | LinkStatus::Error(_)
+                | LinkStatus::DnsError(_)
+                | LinkStatus::Parked(_)
+                | LinkStatus::BrokenAnchor(_)
+        )
+    }
+}
+
+/// Short human readable status, e.g. `valid`, `not found` or
+/// `error: connection refused`
+pub fn status_label(status: &LinkStatus) -> String {
+    match status {
+        LinkStatus::Valid => "valid".to_string(),
+        LinkStatus::NotFound => "not found".to_string(),
+        LinkStatus::Error(e) => format!("error: {}", e),
+        LinkStatus::DnsError(e) => format!("dns error: {}", e),
+        LinkStatus::Ignored => "ignored".to_string(),
+        LinkStatus::UnsupportedScheme(scheme) => format!("unsupported scheme: {}", scheme),
+        LinkStatus::CodeBlock => "code block".to_string(),
+        LinkStatus::Placeholder => "placeholder".to_string(),
+        LinkStatus::PrivateAddress(address) => format!("private address: {}", address),
+        LinkStatus::OutOfScope(reason) => format!("out of scope: {}", reason),
+        LinkStatus::Parked(signal) => format!("parked: {}", signal),
+        LinkStatus::AuthRequired(login) => format!("auth required: {}", login),
+        LinkStatus::Disallowed => "disallowed by robots.txt".to_string(),
+        LinkStatus::BrokenAnchor(fragment) => format!("broken anchor: #{}", fragment),
+    }
+}
+
+/// Fragment of `url` when no element of the target page, given its `anchors`,
+/// has it as id or anchor name. Empty fragments, `#top`, text fragments and
+/// client-side routes (`#/path`, `#!/path`) always resolve.
+pub fn missing_anchor(url: &str, anchors: &HashSet<String>) -> Option<String> {
+    let (_, fragment) = url.split_once('#')?;
+    if fragment.is_empty()
+        || fragment == "top"
+        || fragment.starts_with(['/', '!'])
+        || fragment.starts_with(":~:")
+    {
+        return None;
+    }
+    let decoded = percent_decode(fragment);
+    if anchors.contains(fragment) || anchors.contains(&decoded) {
+        None
+    } else {
+        Some(decoded)
+    }
+}
+
+/// The proxy of the `proxy` settings, with its credentials and the hosts
+/// reached without it
+pub fn build_proxy(config: &ProxyConfig) -> Result<Proxy, Box<dyn Error>> {
+    let mut proxy = Proxy::all(&config.url)?;
+    if let Some(credentials) = config.basic_credentials()? {
+        let (user, password) = credentials.expose().split_once(':').unwrap_or_default();
+        proxy = proxy.basic_auth(user, password);
+    }
+    if let Some(hosts) = &config.no_proxy {
+        proxy = proxy.no_proxy(NoProxy::from_string(&hosts.join(",")));
+    }
+    Ok(proxy)
+}
+
+/// A compiled `overrides` entry from the config
+#[derive(Debug, Clone)]
+pub struct RequestOverride {
+    pattern: Regex,
+    timeout: Option<Duration>,
+    headers: HeaderMap,
+    method: Option<Method>,
+    graphql: Option<GraphqlProbe>,
+}
+
+impl RequestOverride {
+    /// Compile an override, rejecting invalid regexes, header names or values
+    /// and probe methods
+    pub fn from_config(config: &OverrideConfig) -> Result<Self, Box<dyn Error>> {
+        let mut headers = HeaderMap::new();
+        for (name, value) in config.headers.iter().flatten() {
+            headers.insert(
+                HeaderName::from_bytes(name.as_bytes())?,
+                HeaderValue::from_str(value)?,
+            );
+        }
+        let probe = config.probe.clone().unwrap_or_default();
+        let method = match (&probe.method, probe.graphql) {
+            (Some(method), _) => Some(
+                probe_method(method).ok_or_else(|| format!("invalid probe method {:?}", method))?,
+            ),
+            (None, Some(_)) => Some(Method::POST),
+            (None, None) => None,
+        };
+        Ok(RequestOverride {
+            pattern: Regex::new(&config.url_regex)?,
+            timeout: config.timeout.map(Duration::from_secs),
+            headers,
+            method,
+            graphql: probe.graphql,
+        })
+    }
+
+    /// Override sending `Authorization: Bearer <token>` to the origin of
+    /// `base_url` only, never to the sites it links to
+    pub fn bearer(base_url: &str, token: &Secret) -> Result<Self, Box<dyn Error>> {
+        Self::credential(
+            base_url,
+            AUTHORIZATION,
+            &format!("Bearer {}", token.expose()),
+        )
+    }
+
+    /// Override sending `Authorization: Basic` with `user:password` to the
+    /// origin of `base_url` only, like [`RequestOverride::bearer`]
+    pub fn basic(base_url: &str, credentials: &Secret) -> Result<Self, Box<dyn Error>> {
+        let encoded = STANDARD.encode(credentials.expose());
+        Self::credential(base_url, AUTHORIZATION, &format!("Basic {}", encoded))
+    }
+
+    /// Override sending the session cookies of a form login to the origin
+    /// of `base_url` only, like [`RequestOverride::bearer`]
+    pub fn cookies(base_url: &str, cookies: &Secret) -> Result<Self, Box<dyn Error>> {
+        Self::credential(base_url, COOKIE, cookies.expose())
+    }
+
+    fn credential(base_url: &str, name: HeaderName, value: &str) -> Result<Self, Box<dyn Error>> {
+        let origin = Url::parse(base_url)?.origin().ascii_serialization();
+        let mut value = HeaderValue::from_str(value)?;
+        value.set_sensitive(true);
+        let mut headers = HeaderMap::new();
+        headers.insert(name, value);
+        Ok(RequestOverride {
+            pattern: Regex::new(&format!("^{}([/?#]|$)", regex::escape(&origin)))?,
+            timeout: None,
+            headers,
+            method: None,
+            graphql: None,
+        })
+    }
+
+    /// Override sending a header with every request, such as another
+    /// `User-Agent`
+    pub fn header(name: HeaderName, value: &str) -> Result<Self, Box<dyn Error>> {
+        let mut headers = HeaderMap::new();
+        headers.insert(name, HeaderValue::from_str(value)?);
+        Ok(RequestOverride {
+            pattern: Regex::new("")?,
+            timeout: None,
+            headers,
+            method: None,
+            graphql: None,
+        })
+    }
+
+    /// Whether the override applies to a URL
+    pub fn matches(&self, url: &str) -> bool {
+        self.pattern.is_match(url)
+    }
+}
+
+/// File extensions of installers, archives and datasets, which are probed
+/// with a one-byte range request instead of being downloaded
+pub const BINARY_EXTENSIONS: [&str; 29] = [
+    "7z",
+    "apk",
+    "appimage",
+    "arrow",
+    "bin",
+    "bz2",
+    "deb",
+    "dmg",
+    "exe",
+    "gz",
+    "h5",
+    "hdf5",
+    "img",
+    "iso",
+    "jar",
+    "msi",
+    "npz",
+    "nupkg",
+    "onnx",
+    "parquet",
+    "pkg",
+    "rar",
+    "rpm",
+    "safetensors",
+    "tar",
+    "tgz",
+    "whl",
+    "xz",
+    "zip",
+];
+
+/// Whether the path of a URL ends with one of the [`BINARY_EXTENSIONS`]
+pub fn is_binary_link(url: &str) -> bool {
+    let Ok(url) = Url::parse(url) else {
+        return false;
+    };
+    url.path()
+        .rsplit_once('.')
+        .filter(|(_, extension)| !extension.contains('/'))
+        .is_some_and(|(_, extension)| {
+            BINARY_EXTENSIONS.contains(&extension.to_ascii_lowercase().as_str())
+        })
+}
+
+/// Whether a URL uses `ftp://` or `sftp://`, which are probed for existence
+/// when built with the `ftp` feature
+pub fn is_ftp_link(url: &str) -> bool {
+    Url::parse(url).is_ok_and(|url| matches!(url.scheme(), "ftp" | "sftp"))
+}
+
+/// Delay before the first retry, doubled for each one after it
+const RETRY_BASE_DELAY: Duration = Duration::from_millis(500);
+/// Retries of 429 responses when fewer retries are configured
+pub const RATE_LIMITED_RETRIES: u32 = 3;
+/// Longest delay between two attempts
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(30);
+
+/// How requests failing transiently are retried
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Retries after the first attempt
+    pub retries: u32,
+    pub base_delay: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            retries: 0,
+            base_delay: RETRY_BASE_DELAY,
+        }
+    }
+}
+
+impl RetryPolicy {
+    pub fn new(retries: u32) -> Self {
+        RetryPolicy {
+            retries,
+            ..Self::default()
+        }
+    }
+
+    /// How long to wait before retrying after attempt number `attempt`, or
+    /// `None` when the outcome is final. Delays double with each attempt and
+    /// are jittered between half and all of that, so clients that failed
+    /// together do not retry together. Rate-limited requests are retried at
+    /// least [`RATE_LIMITED_RETRIES`] times, after the `Retry-After` pause the
+    /// [`RateLimiter`] enforces.
+    pub fn backoff(
+        &self,
+        attempt: u32,
+        outcome: Result<StatusCode, &reqwest::Error>,
+    ) -> Option<Duration> {
+        let retries = match outcome {
+            Ok(StatusCode::TOO_MANY_REQUESTS) => self.retries.max(RATE_LIMITED_RETRIES),
+            _ => self.retries,
+        };
+        if attempt > retries || !is_transient(outcome) {
+            return None;
+        }
+        let delay = self
+            .base_delay
+            .saturating_mul(1 << (attempt - 1).min(16))
+            .min(MAX_RETRY_DELAY);
+        Some(delay / 2 + delay.mul_f64(jitter() / 2.0))
+    }
+}
+
+/// Whether a request outcome may differ on a retry: timeouts, connections
+/// refused or reset, 429 and 5xx responses
+pub fn is_transient(outcome: Result<StatusCode, &reqwest::Error>) -> bool {
+    match outcome {
+        Ok(status) => status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error(),
+        Err(e) => e.is_timeout() || e.is_connect() || is_connection_reset(e),
+    }
+}
+
+fn is_connection_reset(error: &(dyn Error + 'static)) -> bool {
+    let mut source = Some(error);
+    while let Some(error) = source {
+        let reset = error.downcast_ref::<io::Error>().is_some_and(|e| {
+            matches!(
+                e.kind(),
+                io::ErrorKind::ConnectionReset
+                    | io::ErrorKind::ConnectionAborted
+                    | io::ErrorKind::BrokenPipe
+                    | io::ErrorKind::UnexpectedEof
+            )
+        });
+        if reset {
+            return true;
+        }
+        source = error.source();
+    }
+    false
+}
+
+/// A random number in `[0, 1)`, from the randomly keyed std hasher
+fn jitter() -> f64 {
+    RandomState::new().build_hasher().finish() as f64 / u64::MAX as f64
+}
+
+/// Value of the `User-Agent` header sent with every request
+pub const USER_AGENT: &str = concat!("inspector-gadget/", env!("CARGO_PKG_VERSION"));
+
+/// Fetches pages with a shared client, pacing requests per host and applying
+/// per-URL overrides
+pub struct Fetcher {
+    client: Client,
+    async_client: Option<reqwest::Client>,
+    limiter: RateLimiter,
+    overrides: Vec<RequestOverride>,
+    network: NetworkRules,
+    robots: Option<Arc<RobotsCache>>,
+    /// Timeout of FTP and SFTP probes and resolvers, which do not go through the clients
+    timeout: Duration,
+    retry: RetryPolicy,
+    resolvers: Vec<Resolver>,
+    redirects: RedirectLog,
+    login: LoginPages,
+    validators: Arc<HashMap<String, Validators>>,
+}
+
+impl Fetcher {
+    pub fn new(client: Client) -> Self {
+        Fetcher {
+            client,
+            async_client: None,
+            limiter: RateLimiter::new(),
+            overrides: Vec::new(),
+            network: NetworkRules::default(),
+            robots: None,
+            timeout: Duration::from_secs(DEFAULT_TIMEOUT),
+            retry: RetryPolicy::default(),
+            resolvers: Vec::new(),
+            redirects: RedirectLog::default(),
+            login: LoginPages::default(),
+            validators: Arc::default(),
+        }
+    }
+
+    /// Retry requests failing transiently, see [`RetryPolicy::backoff`]
+    pub fn with_retries(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    /// Check URLs matching a resolver with its command instead of a request
+    pub fn with_resolvers(mut self, resolvers: Vec<Resolver>) -> Self {
+        self.resolvers = resolvers;
+        self
+    }
+
+    /// The first resolver matching a URL
+    fn resolver_for(&self, url: &str) -> Option<&Resolver> {
+        self.resolvers.iter().find(|resolver| resolver.matches(url))
+    }
+
+    /// Tell permanent redirects from temporary ones with the hops the
+    /// redirect policy of the clients records, see
+    /// [`crate::scope::guarded_redirects`]
+    pub fn with_redirect_log(mut self, redirects: RedirectLog) -> Self {
+        self.redirects = redirects;
+        self
+    }
+
+    /// Report links redirected to one of these sign-in pages as
+    /// [`LinkStatus::AuthRequired`]; the built-in ones are used by default
+    pub fn with_login_pages(mut self, login: LoginPages) -> Self {
+        self.login = login;
+        self
+    }
+
+    /// Mark a redirected link whose every hop was permanent, or that ends
+    /// at a sign-in page
+    fn note_redirect(&self, mut link_info: LinkInfo) -> LinkInfo {
+        if let Some(final_url) = &link_info.redirected_to {
+            link_info.permanent_redirect = self.redirects.is_permanent(&link_info.url, final_url);
+        }
+        self.login.classify(link_info)
+    }
+
+    /// Request the pages with validators conditionally. A page answering 304
+    /// comes back on the error side as valid, marked
+    /// [`Revalidation::NotModified`], without content.
+    pub fn with_validators(mut self, validators: HashMap<String, Validators>) -> Self {
+        self.validators = Arc::new(validators);
+        self
+    }
+
+    /// `If-None-Match` and `If-Modified-Since` headers for a URL with validators
+    fn conditional_headers(&self, url: &str) -> Option<HeaderMap> {
+        let validators = self.validators.get(url)?;
+        let mut headers = HeaderMap::new();
+        if let Some(etag) = validators
+            .etag
+            .as_deref()
+            .and_then(|etag| HeaderValue::from_str(etag).ok())
+        {
+            headers.insert(IF_NONE_MATCH, etag);
+        }
+        if let Some(time) = validators.last_modified {
+            let date = format_http_date(time);
+            headers.insert(IF_MODIFIED_SINCE, HeaderValue::from_str(&date).ok()?);
+        }
+        Some(headers)
+    }
+
+    /// Timeout of FTP and SFTP probes and resolvers, which should match the clients'
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Space requests to each host by at least its configured delay
+    pub fn with_delays(mut self, delays: HostDelays) -> Self {
+        self.limiter = self.limiter.with_delays(delays);
+        self
+    }
+
+    /// Refuse to request URLs the network rules forbid. The client should also
+    /// use [`crate::scope::guarded_redirects`] so redirects cannot reach them.
+    pub fn with_network_rules(mut self, network: NetworkRules) -> Self {
+        self.network = network;
+        self
+    }
+
+    /// Consult the robots.txt of each host before requesting it, and report
+    /// disallowed URLs as [`LinkStatus::Disallowed`]
+    pub fn with_robots(mut self, robots: Arc<RobotsCache>) -> Self {
+        self.robots = Some(robots);
+        self
+    }
+
+    /// Sitemaps listed by the robots.txt of the URL's host, when robots.txt
+    /// files are consulted
+    pub fn robots_sitemaps(&self, url: &str) -> Vec<String> {
+        match &self.robots {
+            Some(robots) => robots.sitemaps(&self.client, url),
+            None => Vec::new(),
+        }
+    }
+
+    /// Apply overrides to matching requests; later entries win on conflicts
+    pub fn with_overrides(mut self, overrides: Vec<RequestOverride>) -> Self {
+        self.overrides = overrides;
+        self
+    }
+
+    /// Client for [`Fetcher::fetch`], which should be set up like the
+    /// blocking one. Without it, `fetch` runs blocking requests on the
+    /// runtime's blocking threads.
+    pub fn with_async_client(mut self, client: reqwest::Client) -> Self {
+        self.async_client = Some(client);
+        self
+    }
+
+    /// Timeout of the last matching override with one, or the default
+    fn timeout_for(&self, url: &str) -> Duration {
+        self.overrides
+            .iter()
+            .filter(|rule| rule.matches(url))
+            .filter_map(|rule| rule.timeout)
+            .next_back()
+            .unwrap_or(self.timeout)
+    }
+
+    /// Probe of the last matching override with one: its method, and the
+    /// GraphQL query it sends if any
+    fn probe_for(&self, url: &str) -> Option<(Method, Option<GraphqlProbe>)> {
+        self.overrides
+            .iter()
+            .filter(|rule| rule.matches(url))
+            .filter_map(|rule| Some((rule.method.clone()?, rule.graphql)))
+            .next_back()
+    }
 
-            let link_info = LinkInfo {
-                url: url.to_string(),
-                status: link_status,
+    /// Build the request for a URL with every matching override applied
+    fn request(&self, method: Method, url: &str) -> RequestBuilder {
+        let mut request = self.client.request(method, url);
+        for rule in self.overrides.iter().filter(|rule| rule.matches(url)) {
+            if let Some(timeout) = rule.timeout {
+                request = request.timeout(timeout);
+            }
+            request = request.headers(rule.headers.clone());
+        }
+        request
+    }
+
+    /// Same as [`Fetcher::request`], for the async client
+    fn async_request(&self, client: &reqwest::Client, url: &str) -> reqwest::RequestBuilder {
+        let mut request = client.get(url);
+        for rule in self.overrides.iter().filter(|rule| rule.matches(url)) {
+            if let Some(timeout) = rule.timeout {
+                request = request.timeout(timeout);
+            }
+            request = request.headers(rule.headers.clone());
+        }
+        request
+    }
+
+    /// Inspect a single link and return its status and HTML content if successful.
+    /// Binaries are only probed, so they come back on the error side, with
+    /// their advertised size and no content to crawl, and so do links
+    /// redirected to a sign-in page.
+    // The error side carries the full LinkInfo so failures are reported like successes
+    #[allow(clippy::result_large_err)]
+    pub fn inspect(&self, url: &str) -> Result<(LinkInfo, String), LinkInfo> {
+        if let Some(resolver) = self.resolver_for(url) {
+            return Err(resolver.resolve(url, self.timeout_for(url)));
+        }
+        if !is_ftp_link(url) && self.probe_for(url).is_some() {
+            return Err(self.probe(url));
+        }
+        if let Some(status) = self.network.check(url) {
+            return Err(LinkInfo::new(url, status));
+        }
+        if let Some(robots) = &self.robots {
+            if !robots.allows(&self.client, url) {
+                return Err(LinkInfo::new(url, LinkStatus::Disallowed));
+            }
+        }
+        if is_ftp_link(url) {
+            self.limiter.wait(url);
+            #[cfg(feature = "ftp")]
+            return Err(crate::ftp::probe(url, self.timeout_for(url)));
+            #[cfg(not(feature = "ftp"))]
+            return Err(LinkInfo::new(
+                url,
+                LinkStatus::Error("FTP links require building with the `ftp` feature".to_string()),
+            ));
+        }
+        let (link_info, response) = run_ready(self.exchange(
+            url,
+            |headers| ready(self.request(Method::GET, url).headers(headers).send()),
+            |delay| {
+                thread::sleep(delay);
+                ready(())
+            },
+        ))?;
+        let html = response
+            .text()
+            .map_err(|e| LinkInfo::new(url, LinkStatus::Error(e.to_string())))?;
+        Ok((link_info, html))
+    }
+
+    /// The GET of a page, shared by [`Fetcher::inspect`] and
+    /// [`Fetcher::fetch`]: paced, retried, a one-byte range request for
+    /// binaries and a conditional request for pages with validators. `send`
+    /// sends an attempt with the given headers and `sleep` waits, with either
+    /// client. Comes back with the response when its body is the page.
+    #[allow(clippy::result_large_err)]
+    async fn exchange<R, S, SF, W, WF>(
+        &self,
+        url: &str,
+        mut send: S,
+        mut sleep: W,
+    ) -> Result<(LinkInfo, R), LinkInfo>
+    where
+        R: PageResponse,
+        S: FnMut(HeaderMap) -> SF,
+        SF: Future<Output = reqwest::Result<R>>,
+        W: FnMut(Duration) -> WF,
+        WF: Future<Output = ()>,
+    {
+        let binary = is_binary_link(url);
+        let conditional = if binary {
+            None
+        } else {
+            self.conditional_headers(url)
+        };
+        let mut headers = conditional.clone().unwrap_or_default();
+        if binary {
+            headers.insert(RANGE, HeaderValue::from_static("bytes=0-0"));
+        }
+        let mut attempts = 0;
+        let mut response_time;
+        let outcome = loop {
+            attempts += 1;
+            let delay = self.limiter.delay(url);
+            if !delay.is_zero() {
+                debug!("Waiting {:?} before requesting {}", delay, url);
+                sleep(delay).await;
+            }
+            let sent = Instant::now();
+            let outcome = send(headers.clone()).await;
+            response_time = sent.elapsed();
+            if let Ok(response) = &outcome {
+                self.limiter
+                    .observe_response(url, response.status(), response.headers());
+            }
+            let status = outcome.as_ref().map(|response| response.status());
+            match self.retry.backoff(attempts, status) {
+                Some(delay) => {
+                    debug!("Retrying {} in {:?} after attempt {}", url, delay, attempts);
+                    sleep(delay).await;
+                }
+                None => break outcome,
+            }
+        };
+        let counted = |mut link_info: LinkInfo| {
+            link_info.attempts = attempts;
+            link_info.response_time_ms = Some(response_time.as_millis() as u64);
+            self.note_redirect(link_info)
+        };
+        let response =
+            outcome.map_err(|e| counted(LinkInfo::new(url, LinkStatus::Error(e.to_string()))))?;
+        let status = response.status();
+        if binary {
+            return Err(counted(probe_info(
+                url,
+                status,
+                response.url(),
+                response.headers(),
+            )));
+        }
+        if conditional.is_some() && status == StatusCode::NOT_MODIFIED {
+            return Err(counted(not_modified_info(
+                url,
+                response.url(),
+                response.headers(),
+            )));
+        }
+        let mut link_info = counted(response_info(
+            url,
+            status,
+            response.url(),
+            response.headers(),
+        ));
+        if conditional.is_some() && status.is_success() {
+            link_info.revalidation = Some(Revalidation::Modified);
+        }
+        // The links of a sign-in page are not the page's
+        if status.is_success() && !matches!(link_info.status, LinkStatus::AuthRequired(_)) {
+            Ok((link_info, response))
+        } else {
+            Err(link_info)
+        }
+    }
+
+    /// Check a link without reading or crawling its content: a HEAD request,
+    /// or a GET when the server does not allow HEAD. URLs matching an
+    /// override with a `probe` method are only ever sent that method, and
+    /// GraphQL endpoints their query, whose answer must be a GraphQL response.
+    pub fn probe(&self, url: &str) -> LinkInfo {
+        if is_ftp_link(url) || self.resolver_for(url).is_some() {
+            return match self.inspect(url) {
+                Ok((link_info, _)) | Err(link_info) => link_info,
             };
+        }
+        if let Some(status) = self.network.check(url) {
+            return LinkInfo::new(url, status);
+        }
+        if let Some(robots) = &self.robots {
+            if !robots.allows(&self.client, url) {
+                return LinkInfo::new(url, LinkStatus::Disallowed);
+            }
+        }
+        let configured = self.probe_for(url);
+        let graphql = configured.as_ref().and_then(|(_, graphql)| *graphql);
+        let mut method = configured
+            .as_ref()
+            .map_or(Method::HEAD, |(method, _)| method.clone());
+        loop {
+            self.limiter.wait(url);
+            let sent = Instant::now();
+            let mut request = self.request(method.clone(), url);
+            if let Some(query) = graphql {
+                request = request
+                    .header(CONTENT_TYPE, "application/json")
+                    .body(graphql_body(query));
+            }
+            let response = match request.send() {
+                Ok(response) => response,
+                Err(e) => return LinkInfo::new(url, LinkStatus::Error(e.to_string())),
+            };
+            let response_time = sent.elapsed();
+            let status = response.status();
+            self.limiter
+                .observe_response(url, status, response.headers());
+            let rejected = matches!(
+                status,
+                StatusCode::METHOD_NOT_ALLOWED | StatusCode::NOT_IMPLEMENTED
+            );
+            if configured.is_none() && method == Method::HEAD && rejected {
+                method = Method::GET;
+                continue;
+            }
+            // The body of a GET is dropped unread
+            let mut link_info = response_info(url, status, response.url(), response.headers());
+            link_info.response_time_ms = Some(response_time.as_millis() as u64);
+            link_info.method = configured.map(|(method, _)| method.to_string());
+            if let Some(query) = graphql.filter(|_| status.is_success()) {
+                let problem = match response.text() {
+                    Ok(body) => graphql_problem(query, &body),
+                    Err(e) => Some(e.to_string()),
+                };
+                if let Some(problem) = problem {
+                    link_info.status = LinkStatus::Error(problem);
+                }
+            }
+            return self.note_redirect(link_info);
+        }
+    }
 
-            if status.is_success() {
-                let html = response.text().map_err(|e| LinkInfo {
-                    url: url.to_string(),
-                    status: LinkStatus::Error(e.to_string()),
-                })?;
-                Ok((link_info, html))
-            } else {
-                Err(link_info)
+    /// [`Fetcher::inspect`] without holding a thread while the request is in
+    /// flight, for crawls running many requests at once
+    #[allow(clippy::result_large_err)]
+    pub async fn fetch(self: Arc<Self>, url: String) -> Result<(LinkInfo, String), LinkInfo> {
+        let failed =
+            |url: &str, e: &dyn Error| LinkInfo::new(url, LinkStatus::Error(e.to_string()));
+        // FTP and SFTP probes, resolvers and probes with a configured method
+        // block, like requests without an async client
+        let client = self.async_client.clone().filter(|_| {
+            !is_ftp_link(&url)
+                && self.resolver_for(&url).is_none()
+                && self.probe_for(&url).is_none()
+        });
+        let Some(client) = client else {
+            let fetcher = Arc::clone(&self);
+            let task_url = url.clone();
+            return tokio::task::spawn_blocking(move || fetcher.inspect(&task_url))
+                .await
+                .unwrap_or_else(|e| Err(failed(&url, &e)));
+        };
+        if self.network.is_restricted() {
+            // Checking the rules resolves the host
+            let (network, task_url) = (self.network.clone(), url.clone());
+            let status = tokio::task::spawn_blocking(move || network.check(&task_url))
+                .await
+                .map_err(|e| failed(&url, &e))?;
+            if let Some(status) = status {
+                return Err(LinkInfo::new(&url, status));
             }
         }
-        Err(e) => Err(LinkInfo {
-            url: url.to_string(),
-            status: LinkStatus::Error(e.to_string()),
-        }),
+        if let Some(robots) = &self.robots {
+            // The rules of a host are fetched with the blocking client once
+            let (robots, blocking, task_url) =
+                (Arc::clone(robots), self.client.clone(), url.clone());
+            let allowed = tokio::task::spawn_blocking(move || robots.allows(&blocking, &task_url))
+                .await
+                .map_err(|e| failed(&url, &e))?;
+            if !allowed {
+                return Err(LinkInfo::new(&url, LinkStatus::Disallowed));
+            }
+        }
+        let (link_info, response) = self
+            .exchange(
+                &url,
+                |headers| self.async_request(&client, &url).headers(headers).send(),
+                tokio::time::sleep,
+            )
+            .await?;
+        let html = response.text().await.map_err(|e| failed(&url, &e))?;
+        Ok((link_info, html))
     }
 }
 
+/// What [`Fetcher::exchange`] reads of a response of either client
+trait PageResponse {
+    fn status(&self) -> StatusCode;
+    fn url(&self) -> &Url;
+    fn headers(&self) -> &HeaderMap;
+}
+
+impl PageResponse for reqwest::blocking::Response {
+    fn status(&self) -> StatusCode {
+        self.status()
+    }
+
+    fn url(&self) -> &Url {
+        self.url()
+    }
+
+    fn headers(&self) -> &HeaderMap {
+        self.headers()
+    }
+}
+
+impl PageResponse for reqwest::Response {
+    fn status(&self) -> StatusCode {
+        self.status()
+    }
+
+    fn url(&self) -> &Url {
+        self.url()
+    }
+
+    fn headers(&self) -> &HeaderMap {
+        self.headers()
+    }
+}
+
+/// Run a future whose every await is ready at once, as the blocking requests
+/// and sleeps [`Fetcher::inspect`] hands to [`Fetcher::exchange`] are
+fn run_ready<F: Future>(future: F) -> F::Output {
+    let mut future = pin!(future);
+    match future
+        .as_mut()
+        .poll(&mut Context::from_waker(Waker::noop()))
+    {
+        Poll::Ready(output) => output,
+        Poll::Pending => unreachable!("a blocking request went pending"),
+    }
+}
+
+/// Status, final URL and freshness of a response to a request for `url`
+fn response_info(url: &str, status: StatusCode, final_url: &Url, headers: &HeaderMap) -> LinkInfo {
+    let link_status = if status.is_success() {
+        LinkStatus::Valid
+    } else if status.as_u16() == 404 {
+        LinkStatus::NotFound
+    } else {
+        LinkStatus::Error(status.to_string())
+    };
+
+    let mut link_info = LinkInfo::new(url, link_status);
+    if final_url.as_str() != url {
+        link_info.redirected_to = Some(final_url.to_string());
+    }
+    link_info.last_modified = headers
+        .get(LAST_MODIFIED)
+        .and_then(|value| value.to_str().ok())
+        .and_then(parse_http_date);
+    link_info.etag = headers
+        .get(ETAG)
+        .and_then(|value| value.to_str().ok())
+        .map(String::from);
+    link_info
+}
+
+/// A page the server reports unchanged since the validators sent for it
+fn not_modified_info(url: &str, final_url: &Url, headers: &HeaderMap) -> LinkInfo {
+    let mut link_info = response_info(url, StatusCode::OK, final_url, headers);
+    link_info.revalidation = Some(Revalidation::NotModified);
+    link_info
+}
+
+/// Status and advertised size from the response to a one-byte range request.
+/// An unsatisfiable range means the file exists but is empty.
+fn probe_info(url: &str, status: StatusCode, final_url: &Url, headers: &HeaderMap) -> LinkInfo {
+    let ranged = matches!(
+        status,
+        StatusCode::PARTIAL_CONTENT | StatusCode::RANGE_NOT_SATISFIABLE
+    );
+    let effective = if ranged { StatusCode::OK } else { status };
+    let mut link_info = response_info(url, effective, final_url, headers);
+    let header = |name| {
+        headers
+            .get(name)
+            .and_then(|value: &HeaderValue| value.to_str().ok())
+    };
+    link_info.content_length = if ranged {
+        // `bytes 0-0/1048576`, or `bytes */0` for an empty file
+        header(CONTENT_RANGE)
+            .and_then(|range| range.rsplit_once('/'))
+            .and_then(|(_, total)| total.trim().parse().ok())
+    } else if status.is_success() {
+        // The server ignored the range; the body is not read
+        header(CONTENT_LENGTH).and_then(|length| length.trim().parse().ok())
+    } else {
+        None
+    };
+    link_info
+}
+
+/// Inspect a single link and return its status and HTML content if successful
+#[allow(clippy::result_large_err)]
+pub fn inspect_single_link(client: &Client, url: &str) -> Result<(LinkInfo, String), LinkInfo> {
+    Fetcher::new(client.clone()).inspect(url)
+}
+
+/// Key under which a URL is checked: the fragment, an empty query and
+/// repeated slashes in the path do not change the fetched resource
+pub fn normalize_url(url: &str) -> String {
+    let mut parsed = match Url::parse(url) {
+        Ok(parsed) => parsed,
+        Err(_) => return url.to_string(),
+    };
+    parsed.set_fragment(None);
+    if parsed.query() == Some("") {
+        parsed.set_query(None);
+    }
+    if parsed.path().contains("//") {
+        let mut path = String::with_capacity(parsed.path().len());
+        for c in parsed.path().chars() {
+            if !(c == '/' && path.ends_with('/')) {
+                path.push(c);
+            }
+        }
+        parsed.set_path(&path);
+    }
+    parsed.to_string()
+}
+
 /// Extract links from HTML content and add them to the to_visit queue
 pub fn extract_links_from_html(html: &str, base_url: &str, to_visit: &mut Vec<String>) {
-    let document = Html::parse_document(html);
+    to_visit.extend(extract_links(&Html::parse_document(html), base_url));
+}
+
+/// Extract absolute link URLs from a parsed document
+pub fn extract_links(document: &Html, base_url: &str) -> Vec<String> {
     let selector = Selector::parse("a").unwrap();
+    let base = match Url::parse(base_url) {
+        Ok(base) => base,
+        Err(_) => return Vec::new(),
+    };
+
+    document
+        .select(&selector)
+        .filter_map(|element| element.value().attr("href"))
+        .filter_map(|href| base.join(href).ok())
+        .map(String::from)
+        .collect()
+}
+
+/// `rel` values of `<link>` elements pointing at pages or origins rather
+/// than resources the page loads
+const NON_ASSET_RELS: &[&str] = &[
+    "alternate",
+    "canonical",
+    "next",
+    "prev",
+    "preconnect",
+    "dns-prefetch",
+];
 
-    for element in document.select(&selector) {
-        if let Some(href) = element.value().attr("href") {
-            if let Ok(absolute_url) = Url::parse(base_url).and_then(|base| base.join(href)) {
-                to_visit.push(absolute_url.into());
+/// URLs an element loads as assets, as written in its attributes: `src` of
+/// images, scripts and media, the candidates of `srcset`, and `href` of
+/// `<link>` elements such as stylesheets and icons. `data:` URLs and alternate
+/// representations, such as print stylesheets, are left out.
+pub(crate) fn asset_sources<'a>(
+    element: &str,
+    attr: impl Fn(&str) -> Option<&'a str>,
+) -> Vec<&'a str> {
+    let mut sources = Vec::new();
+    match element {
+        "img" | "script" | "video" | "audio" | "source" => sources.extend(attr("src")),
+        "link" => {
+            let rels = attr("rel").unwrap_or_default().to_ascii_lowercase();
+            if !rels
+                .split_whitespace()
+                .any(|rel| NON_ASSET_RELS.contains(&rel))
+                && alternate_kind(&attr).is_none()
+            {
+                sources.extend(attr("href"));
             }
         }
+        _ => {}
     }
+    if matches!(element, "img" | "source") {
+        // `srcset`: candidates separated by commas, each a URL and a descriptor
+        if let Some(srcset) = attr("srcset") {
+            sources.extend(
+                srcset
+                    .split(',')
+                    .filter_map(|candidate| candidate.split_whitespace().next()),
+            );
+        }
+    }
+    sources.retain(|source| {
+        let source = source.trim();
+        !source.is_empty()
+            && !source
+                .get(..5)
+                .is_some_and(|s| s.eq_ignore_ascii_case("data:"))
+    });
+    sources
+}
+
+/// Absolute URLs of the images, scripts, stylesheets and media a parsed
+/// document loads, deduplicated
+pub fn extract_asset_links(document: &Html, base_url: &str) -> Vec<String> {
+    let elements = Selector::parse("img, script, link, source, video, audio").unwrap();
+    let Ok(base) = Url::parse(base_url) else {
+        return Vec::new();
+    };
+    let mut seen = HashSet::new();
+    document
+        .select(&elements)
+        .flat_map(|element| {
+            asset_sources(element.value().name(), |name| element.value().attr(name))
+        })
+        .filter_map(|source| base.join(source.trim()).ok())
+        .map(String::from)
+        .filter(|url| seen.insert(url.clone()))
+        .collect()
+}
+
+/// Split the links of a parsed document into links in running text and URLs
+/// inside `<pre>` blocks: the `href` of their anchors and bare `http(s)` URLs
+/// in their text. Both lists are deduplicated.
+pub fn partition_code_links(document: &Html, base_url: &str) -> (Vec<String>, Vec<String>) {
+    let anchors = Selector::parse("a[href]").unwrap();
+    let blocks = Selector::parse("pre").unwrap();
+    let Ok(base) = Url::parse(base_url) else {
+        return (Vec::new(), Vec::new());
+    };
+
+    let in_code_block = |element: &ElementRef| {
+        element
+            .ancestors()
+            .filter_map(ElementRef::wrap)
+            .any(|ancestor| ancestor.value().name() == "pre")
+    };
+    let mut links = Vec::new();
+    let mut code_links = Vec::new();
+    for element in document.select(&anchors) {
+        let Some(url) = element
+            .value()
+            .attr("href")
+            .and_then(|href| base.join(href).ok())
+        else {
+            continue;
+        };
+        let list = if in_code_block(&element) {
+            &mut code_links
+        } else {
+            &mut links
+        };
+        list.push(String::from(url));
+    }
+    for block in document.select(&blocks) {
+        code_links.extend(bare_urls(&block.text().collect::<String>()));
+    }
+
+    for list in [&mut links, &mut code_links] {
+        let mut seen = HashSet::new();
+        list.retain(|url| seen.insert(url.clone()));
+    }
+    (links, code_links)
+}
+
+/// `http(s)` URLs written out in plain text, without trailing punctuation
+pub fn bare_urls(text: &str) -> Vec<String> {
+    let pattern = Regex::new(r#"https?://[^\s<>"'`]+"#).unwrap();
+    pattern
+        .find_iter(text)
+        .map(|url| {
+            url.as_str()
+                .trim_end_matches(['.', ',', ';', ':', ')', ']', '}'])
+                .to_string()
+        })
+        .collect()
 }
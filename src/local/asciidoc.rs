@@ -0,0 +1,85 @@
+//! AsciiDoc link extraction for local mode
+
+use regex::Regex;
+use std::collections::{HashMap, HashSet};
+
+/// Links of an AsciiDoc document with their line numbers: URL and `link:`
+/// macros, `xref:` macros and `<<id>>` cross references (ids become `#id`),
+/// and `include::`/`image:` targets. Targets using attributes like
+/// `{partialsdir}` are skipped.
+pub fn extract_links(content: &str) -> Vec<(usize, String)> {
+    let url = Regex::new(r"(?:link:([^\s\[]+)|(https?://[^\s\[]+))\[").unwrap();
+    let xref = Regex::new(r"xref:([^\s\[]+)\[").unwrap();
+    let cross_reference = Regex::new(r"<<([^>,]+)(?:,[^>]*)?>>").unwrap();
+    let resource = Regex::new(r"(?:include|image)::?([^\s\[]+)\[").unwrap();
+
+    let mut links = Vec::new();
+    for (index, line) in content.lines().enumerate() {
+        let line_number = index + 1;
+        let mut push = |target: String| {
+            if !target.contains('{') {
+                links.push((line_number, target));
+            }
+        };
+        for captures in url.captures_iter(line) {
+            let target = captures.get(1).or_else(|| captures.get(2)).unwrap();
+            push(target.as_str().to_string());
+        }
+        for pattern in [&xref, &cross_reference] {
+            for captures in pattern.captures_iter(line) {
+                let target = captures[1].trim();
+                if target.contains('#') || target.ends_with(".adoc") {
+                    push(target.to_string());
+                } else {
+                    push(format!("#{}", target));
+                }
+            }
+        }
+        for captures in resource.captures_iter(line) {
+            push(captures[1].to_string());
+        }
+    }
+    links
+}
+
+/// Ids an AsciiDoc document defines: `[[id]]`, `[#id]` and `anchor:id[]`, and
+/// the ids generated for section titles (`== Getting Started` becomes
+/// `_getting_started`, repeats get `_2`, `_3`, ...)
+pub fn anchors(content: &str) -> HashSet<String> {
+    let explicit =
+        Regex::new(r"\[\[([^\],]+)(?:,[^\]]*)?\]\]|\[#([\w-]+)|anchor:([\w-]+)\[").unwrap();
+    let section = Regex::new(r"^=+\s+(.+?)\s*$").unwrap();
+
+    let mut anchors = HashSet::new();
+    let mut occurrences: HashMap<String, usize> = HashMap::new();
+    for line in content.lines() {
+        for captures in explicit.captures_iter(line) {
+            let id = (1..=3).find_map(|group| captures.get(group)).unwrap();
+            anchors.insert(id.as_str().trim().to_string());
+        }
+        if let Some(captures) = section.captures(line) {
+            let id = section_id(&captures[1]);
+            let count = occurrences.entry(id.clone()).or_insert(0);
+            *count += 1;
+            anchors.insert(match *count {
+                1 => id,
+                n => format!("{}_{}", id, n),
+            });
+        }
+    }
+    anchors
+}
+
+/// Id Asciidoctor generates for a section title with the default `_` prefix
+/// and separator
+fn section_id(title: &str) -> String {
+    let mut id = String::from("_");
+    for c in title.to_lowercase().chars() {
+        if c.is_alphanumeric() {
+            id.push(c);
+        } else if !id.ends_with('_') {
+            id.push('_');
+        }
+    }
+    id.trim_end_matches('_').to_string()
+}
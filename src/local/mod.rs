@@ -0,0 +1,566 @@
+use crate::link::{bare_urls, is_ftp_link, LinkStatus};
+use crate::redirects::RewriteMap;
+use pulldown_cmark::{Event, Parser, Tag};
+use regex::Regex;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::error::Error;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use walkdir::WalkDir;
+
+pub mod asciidoc;
+pub mod rst;
+
+/// File extensions treated as Markdown in local mode
+pub const MARKDOWN_EXTENSIONS: [&str; 2] = ["md", "markdown"];
+/// File extensions treated as reStructuredText in local mode
+pub const RST_EXTENSIONS: [&str; 1] = ["rst"];
+/// File extensions treated as AsciiDoc in local mode
+pub const ASCIIDOC_EXTENSIONS: [&str; 3] = ["adoc", "asciidoc", "asc"];
+
+/// Markup language of a documentation source file
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SourceFormat {
+    Markdown,
+    Rst,
+    AsciiDoc,
+}
+
+impl SourceFormat {
+    /// Format of a file, from its extension
+    pub fn of(path: &Path) -> Option<Self> {
+        let ext = path.extension()?.to_str()?.to_ascii_lowercase();
+        if MARKDOWN_EXTENSIONS.contains(&ext.as_str()) {
+            Some(SourceFormat::Markdown)
+        } else if RST_EXTENSIONS.contains(&ext.as_str()) {
+            Some(SourceFormat::Rst)
+        } else if ASCIIDOC_EXTENSIONS.contains(&ext.as_str()) {
+            Some(SourceFormat::AsciiDoc)
+        } else {
+            None
+        }
+    }
+}
+
+/// A link written in a local documentation file
+#[derive(Debug, Clone, PartialEq)]
+pub struct LocalLink {
+    /// File the link appears in, relative to the root
+    pub file: PathBuf,
+    /// 1-based line of the link
+    pub line: usize,
+    /// Target as written
+    pub target: String,
+}
+
+impl LocalLink {
+    /// `file:line` label used as the link source in reports
+    pub fn location(&self) -> String {
+        format!("{}:{}", display_path(&self.file), self.line)
+    }
+}
+
+/// Where a local link points
+#[derive(Debug, Clone, PartialEq)]
+pub enum LocalTarget {
+    /// An absolute URL, checked over the network
+    External(String),
+    /// A file of the tree, relative to the root
+    File {
+        path: PathBuf,
+        fragment: Option<String>,
+    },
+    /// An anchor within the same file
+    Anchor(String),
+    /// A reStructuredText label from a `:ref:` role, defined in any file
+    Label(String),
+    /// `mailto:` and other links that are not checked
+    Other,
+}
+
+/// Whether a path has a Markdown extension
+pub fn is_markdown(path: &Path) -> bool {
+    SourceFormat::of(path) == Some(SourceFormat::Markdown)
+}
+
+/// Whether a path is a Markdown, reStructuredText or AsciiDoc source
+pub fn is_source(path: &Path) -> bool {
+    SourceFormat::of(path).is_some()
+}
+
+/// Documentation sources under `root`, relative to it, skipping hidden
+/// directories and `node_modules`
+pub fn source_files(root: &Path) -> Vec<PathBuf> {
+    let mut files: Vec<PathBuf> = WalkDir::new(root)
+        .into_iter()
+        .filter_entry(|entry| {
+            let name = entry.file_name().to_string_lossy();
+            entry.depth() == 0 || !(name.starts_with('.') || name == "node_modules")
+        })
+        .filter_map(Result::ok)
+        .filter(|entry| entry.file_type().is_file() && is_source(entry.path()))
+        .filter_map(|entry| entry.path().strip_prefix(root).ok().map(Path::to_path_buf))
+        .collect();
+    files.sort();
+    files
+}
+
+/// Frontmatter keys whose values are checked when `frontmatter_keys` is not set
+pub const DEFAULT_FRONTMATTER_KEYS: [&str; 2] = ["canonical", "image"];
+
+/// YAML frontmatter of a document: the lines between a leading `---` and the
+/// next `---` or `...`
+pub fn frontmatter(content: &str) -> Option<&str> {
+    split_frontmatter(content).map(|(yaml, _)| yaml)
+}
+
+/// Frontmatter YAML and the length of the whole block, delimiters included
+fn split_frontmatter(content: &str) -> Option<(&str, usize)> {
+    let opening = if content.starts_with("---\n") {
+        4
+    } else if content.starts_with("---\r\n") {
+        5
+    } else {
+        return None;
+    };
+    let rest = &content[opening..];
+    let mut offset = 0;
+    for line in rest.split_inclusive('\n') {
+        if matches!(line.trim_end(), "---" | "...") {
+            return Some((&rest[..offset], opening + offset + line.len()));
+        }
+        offset += line.len();
+    }
+    None
+}
+
+/// The document with its frontmatter blanked out, keeping line numbers intact
+fn without_frontmatter(content: &str) -> String {
+    let end = split_frontmatter(content).map_or(0, |(_, end)| end);
+    content[..end]
+        .chars()
+        .map(|c| if c == '\n' { c } else { ' ' })
+        .chain(content[end..].chars())
+        .collect()
+}
+
+/// URLs in the given frontmatter keys with their line numbers. Values may be a
+/// string or a list of strings.
+pub fn extract_frontmatter_links(content: &str, keys: &[String]) -> Vec<(usize, String)> {
+    let Some(yaml) = frontmatter(content) else {
+        return Vec::new();
+    };
+    let Ok(serde_yaml::Value::Mapping(fields)) = serde_yaml::from_str(yaml) else {
+        return Vec::new();
+    };
+    let lines: Vec<&str> = yaml.lines().collect();
+
+    let mut links = Vec::new();
+    for key in keys {
+        let values: Vec<String> = match fields.get(&serde_yaml::Value::from(key.as_str())) {
+            Some(serde_yaml::Value::String(value)) => vec![value.clone()],
+            Some(serde_yaml::Value::Sequence(values)) => values
+                .iter()
+                .filter_map(|value| value.as_str().map(String::from))
+                .collect(),
+            _ => continue,
+        };
+        let key_line = lines
+            .iter()
+            .position(|line| line.starts_with(&format!("{}:", key)))
+            .unwrap_or(0);
+        for value in values.into_iter().filter(|value| !value.is_empty()) {
+            let line = lines[key_line..]
+                .iter()
+                .position(|line| line.contains(value.as_str()))
+                .map_or(key_line, |index| key_line + index);
+            // The frontmatter starts on the line after the opening `---`
+            links.push((line + 2, value));
+        }
+    }
+    links
+}
+
+/// Links and images of a Markdown document with their line numbers
+pub fn extract_markdown_links(content: &str) -> Vec<(usize, String)> {
+    let content = &without_frontmatter(content);
+    let line_starts: Vec<usize> = std::iter::once(0)
+        .chain(content.match_indices('\n').map(|(index, _)| index + 1))
+        .collect();
+    let line_of = |offset: usize| line_starts.partition_point(|&start| start <= offset);
+
+    Parser::new(content)
+        .into_offset_iter()
+        .filter_map(|(event, range)| match event {
+            Event::Start(Tag::Link(_, target, _)) | Event::Start(Tag::Image(_, target, _)) => {
+                Some((line_of(range.start), target.to_string()))
+            }
+            _ => None,
+        })
+        .filter(|(_, target)| !target.is_empty())
+        .collect()
+}
+
+/// Bare `http(s)` URLs inside the code blocks of a Markdown document, with
+/// their line numbers
+pub fn extract_markdown_code_urls(content: &str) -> Vec<(usize, String)> {
+    let content = &without_frontmatter(content);
+    let line_starts: Vec<usize> = std::iter::once(0)
+        .chain(content.match_indices('\n').map(|(index, _)| index + 1))
+        .collect();
+    let line_of = |offset: usize| line_starts.partition_point(|&start| start <= offset);
+
+    let mut urls = Vec::new();
+    let mut in_code_block = false;
+    for (event, range) in Parser::new(content).into_offset_iter() {
+        match event {
+            Event::Start(Tag::CodeBlock(_)) => in_code_block = true,
+            Event::End(Tag::CodeBlock(_)) => in_code_block = false,
+            Event::Text(text) if in_code_block => {
+                let mut offset = range.start;
+                for line in text.split_inclusive('\n') {
+                    urls.extend(
+                        bare_urls(line)
+                            .into_iter()
+                            .map(|url| (line_of(offset), url)),
+                    );
+                    offset += line.len();
+                }
+            }
+            _ => {}
+        }
+    }
+    urls
+}
+
+/// GitHub-style slug of a heading: lowercased, punctuation removed and spaces
+/// turned into hyphens
+pub fn heading_slug(text: &str) -> String {
+    text.trim()
+        .to_lowercase()
+        .chars()
+        .filter(|c| c.is_alphanumeric() || matches!(c, ' ' | '-' | '_'))
+        .map(|c| if c == ' ' { '-' } else { c })
+        .collect()
+}
+
+/// Anchors a Markdown document defines: heading slugs (numbered `-1`, `-2`, ...
+/// for duplicate headings, like GitHub) and explicit HTML `id`/`name` attributes
+pub fn markdown_anchors(content: &str) -> HashSet<String> {
+    let content = &without_frontmatter(content);
+    let html_anchor = Regex::new(r#"(?:id|name)\s*=\s*["']([^"']+)["']"#).unwrap();
+    let mut anchors = HashSet::new();
+    let mut occurrences: HashMap<String, usize> = HashMap::new();
+    let mut heading: Option<String> = None;
+
+    for event in Parser::new(content) {
+        match event {
+            Event::Start(Tag::Heading(..)) => heading = Some(String::new()),
+            Event::End(Tag::Heading(..)) => {
+                let slug = heading_slug(&heading.take().unwrap_or_default());
+                let count = occurrences.entry(slug.clone()).or_insert(0);
+                let anchor = match *count {
+                    0 => slug,
+                    n => format!("{}-{}", slug, n),
+                };
+                *count += 1;
+                anchors.insert(anchor);
+            }
+            Event::Text(text) | Event::Code(text) => {
+                if let Some(heading) = heading.as_mut() {
+                    heading.push_str(&text);
+                }
+            }
+            Event::Html(html) => {
+                anchors.extend(
+                    html_anchor
+                        .captures_iter(&html)
+                        .map(|captures| captures[1].to_string()),
+                );
+            }
+            _ => {}
+        }
+    }
+    anchors
+}
+
+/// Whether a fragment names one of the anchors (GitHub matches case-insensitively)
+pub fn has_anchor(anchors: &HashSet<String>, fragment: &str) -> bool {
+    let fragment = percent_decode(fragment);
+    anchors.contains(&fragment) || anchors.contains(&fragment.to_lowercase())
+}
+
+/// Anchors of the sources under a root, and the reStructuredText labels of
+/// the whole tree, read on first use
+#[derive(Debug)]
+pub struct AnchorIndex {
+    root: PathBuf,
+    anchors: HashMap<PathBuf, HashSet<String>>,
+    labels: Option<HashSet<String>>,
+}
+
+impl AnchorIndex {
+    pub fn new(root: &Path) -> Self {
+        AnchorIndex {
+            root: root.to_path_buf(),
+            anchors: HashMap::new(),
+            labels: None,
+        }
+    }
+
+    /// Status of `#fragment` in the file at `path`, relative to the root.
+    /// Markdown and AsciiDoc files are checked; an empty fragment is the top
+    /// of the file.
+    pub fn check(&mut self, path: &Path, fragment: &str) -> LinkStatus {
+        let read_anchors = match SourceFormat::of(path) {
+            Some(SourceFormat::Markdown) => markdown_anchors,
+            Some(SourceFormat::AsciiDoc) => asciidoc::anchors,
+            _ => return LinkStatus::Valid,
+        };
+        if fragment.is_empty() {
+            return LinkStatus::Valid;
+        }
+        let root = &self.root;
+        let anchors = self.anchors.entry(path.to_path_buf()).or_insert_with(|| {
+            fs::read_to_string(root.join(path))
+                .map(|content| read_anchors(&content))
+                .unwrap_or_default()
+        });
+        if has_anchor(anchors, fragment) {
+            LinkStatus::Valid
+        } else {
+            LinkStatus::Error(format!("no heading or anchor for #{}", fragment))
+        }
+    }
+
+    /// Status of a `:ref:` label against the labels of every
+    /// reStructuredText file under the root
+    pub fn check_label(&mut self, label: &str) -> LinkStatus {
+        let root = &self.root;
+        let labels = self.labels.get_or_insert_with(|| {
+            source_files(root)
+                .iter()
+                .filter(|path| SourceFormat::of(path) == Some(SourceFormat::Rst))
+                .filter_map(|path| fs::read_to_string(root.join(path)).ok())
+                .flat_map(|content| rst::labels(&content))
+                .collect()
+        });
+        if labels.contains(&label.to_lowercase()) {
+            LinkStatus::Valid
+        } else {
+            LinkStatus::Error(format!("no label {}", label))
+        }
+    }
+}
+
+/// Links of a source in the given format; for Markdown this includes the
+/// URLs in its `frontmatter_keys`
+pub fn extract_source_links(
+    format: SourceFormat,
+    content: &str,
+    frontmatter_keys: &[String],
+) -> Vec<(usize, String)> {
+    match format {
+        SourceFormat::Markdown => {
+            let mut links = extract_frontmatter_links(content, frontmatter_keys);
+            links.extend(extract_markdown_links(content));
+            links
+        }
+        SourceFormat::Rst => rst::extract_links(content),
+        SourceFormat::AsciiDoc => asciidoc::extract_links(content),
+    }
+}
+
+/// Read the links of the given files, relative to `root`. Files that are not
+/// documentation sources are skipped.
+pub fn collect_links(
+    root: &Path,
+    files: &[PathBuf],
+    frontmatter_keys: &[String],
+) -> Result<Vec<LocalLink>, Box<dyn Error>> {
+    let mut links = Vec::new();
+    for file in files {
+        let Some(format) = SourceFormat::of(file) else {
+            continue;
+        };
+        let content = fs::read_to_string(root.join(file))?;
+        links.extend(
+            extract_source_links(format, &content, frontmatter_keys)
+                .into_iter()
+                .map(|(line, target)| LocalLink {
+                    file: file.clone(),
+                    line,
+                    target,
+                }),
+        );
+    }
+    Ok(links)
+}
+
+/// Replace the targets of `links` that `map` rewrites in their files under
+/// `root`, updating `links` to match. Returns the number of links replaced.
+pub fn fix_links(
+    root: &Path,
+    links: &mut [LocalLink],
+    map: &RewriteMap,
+) -> Result<usize, Box<dyn Error>> {
+    let mut files: BTreeMap<PathBuf, Vec<(&mut LocalLink, String)>> = BTreeMap::new();
+    for link in links.iter_mut() {
+        if let Some(replacement) = map.rewrite(&link.target) {
+            files
+                .entry(link.file.clone())
+                .or_default()
+                .push((link, replacement));
+        }
+    }
+    let mut fixed = 0;
+    for (file, links) in files {
+        let path = root.join(&file);
+        let content = fs::read_to_string(&path)?;
+        let mut lines: Vec<String> = content.split_inclusive('\n').map(String::from).collect();
+        for (link, replacement) in links {
+            let Some(line) = link.line.checked_sub(1).and_then(|i| lines.get_mut(i)) else {
+                continue;
+            };
+            // The target as a whole, not the start of a longer URL
+            let found = line
+                .match_indices(&link.target)
+                .map(|(at, _)| at)
+                .find(|at| {
+                    line[at + link.target.len()..]
+                        .chars()
+                        .next()
+                        .is_none_or(|next| !is_url_char(next))
+                });
+            if let Some(at) = found {
+                line.replace_range(at..at + link.target.len(), &replacement);
+                link.target = replacement;
+                fixed += 1;
+            }
+        }
+        fs::write(&path, lines.concat())?;
+    }
+    Ok(fixed)
+}
+
+fn is_url_char(c: char) -> bool {
+    c.is_alphanumeric() || "-_.~/%?=&+#:@".contains(c)
+}
+
+/// Read the URLs inside the code blocks of the given Markdown files
+pub fn collect_code_links(
+    root: &Path,
+    files: &[PathBuf],
+) -> Result<Vec<LocalLink>, Box<dyn Error>> {
+    let mut links = Vec::new();
+    for file in files.iter().filter(|file| is_markdown(file)) {
+        let content = fs::read_to_string(root.join(file))?;
+        links.extend(
+            extract_markdown_code_urls(&content)
+                .into_iter()
+                .map(|(line, target)| LocalLink {
+                    file: file.clone(),
+                    line,
+                    target,
+                }),
+        );
+    }
+    Ok(links)
+}
+
+/// Resolve a link target written in `file`. Relative targets resolve against
+/// the file's directory, and targets starting with `/` against the root.
+pub fn resolve_target(file: &Path, target: &str) -> LocalTarget {
+    if let Some(anchor) = target.strip_prefix('#') {
+        return LocalTarget::Anchor(anchor.to_string());
+    }
+    if let Some(label) = target.strip_prefix(":ref:") {
+        return LocalTarget::Label(label.to_string());
+    }
+    if target.starts_with("http://")
+        || target.starts_with("https://")
+        || (cfg!(feature = "ftp") && is_ftp_link(target))
+    {
+        return LocalTarget::External(target.to_string());
+    }
+    if target.starts_with("//") || has_scheme(target) {
+        return LocalTarget::Other;
+    }
+
+    let (path, fragment) = match target.split_once('#') {
+        Some((path, fragment)) => (path, Some(fragment.to_string())),
+        None => (target, None),
+    };
+    let path = path.split('?').next().unwrap_or(path);
+    let path = percent_decode(path);
+    let joined = match path.strip_prefix('/') {
+        Some(absolute) => PathBuf::from(absolute),
+        None => file.parent().unwrap_or(Path::new("")).join(path),
+    };
+    LocalTarget::File {
+        path: normalize(&joined),
+        fragment,
+    }
+}
+
+/// Status of a file target against the full tree under `root`
+pub fn check_file(root: &Path, path: &Path) -> LinkStatus {
+    if path.components().next() == Some(Component::ParentDir) {
+        return LinkStatus::Error("points outside the documentation root".to_string());
+    }
+    if root.join(path).exists() {
+        LinkStatus::Valid
+    } else {
+        LinkStatus::NotFound
+    }
+}
+
+/// Forward-slash rendering of a relative path
+pub fn display_path(path: &Path) -> String {
+    path.to_string_lossy().replace('\\', "/")
+}
+
+/// Unique external URLs among the links
+pub fn external_urls(links: &[LocalLink]) -> BTreeSet<String> {
+    links
+        .iter()
+        .filter_map(|link| match resolve_target(&link.file, &link.target) {
+            LocalTarget::External(url) => Some(url),
+            _ => None,
+        })
+        .collect()
+}
+
+fn has_scheme(target: &str) -> bool {
+    target.split_once(':').is_some_and(|(scheme, _)| {
+        !scheme.is_empty()
+            && scheme
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'))
+    })
+}
+
+/// Lexically resolve `.` and `..`, keeping leading `..` that escape the root
+fn normalize(path: &Path) -> PathBuf {
+    let mut parts: Vec<Component> = Vec::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => match parts.last() {
+                Some(Component::Normal(_)) => {
+                    parts.pop();
+                }
+                _ => parts.push(component),
+            },
+            _ => parts.push(component),
+        }
+    }
+    parts.iter().collect()
+}
+
+pub(crate) fn percent_decode(path: &str) -> String {
+    url::form_urlencoded::parse(format!("p={}", path.replace('+', "%2B")).as_bytes())
+        .next()
+        .map(|(_, value)| value.into_owned())
+        .unwrap_or_else(|| path.to_string())
+}
@@ -0,0 +1,62 @@
+//! reStructuredText link extraction for local mode
+
+use regex::Regex;
+use std::collections::HashSet;
+
+/// Links of a reStructuredText document with their line numbers:
+/// `` `text <target>`_ `` links, `.. _name: target` definitions,
+/// `include`/`literalinclude`/`image`/`figure` directives, `:doc:` roles
+/// (resolved to `.rst` files) and `:ref:` roles (kept as `:ref:label`).
+pub fn extract_links(content: &str) -> Vec<(usize, String)> {
+    let embedded = Regex::new(r"`[^`]*<([^`<>]+)>`__?").unwrap();
+    let definition = Regex::new(r"^\s*\.\. _[^:]+:\s+(\S+)\s*$").unwrap();
+    let directive =
+        Regex::new(r"^\s*\.\. (?:include|literalinclude|image|figure)::\s+(\S+)").unwrap();
+    let role = Regex::new(r":(ref|doc):`([^`]+)`").unwrap();
+
+    let mut links = Vec::new();
+    for (index, line) in content.lines().enumerate() {
+        let line_number = index + 1;
+        for captures in embedded.captures_iter(line) {
+            let target = captures[1].trim();
+            // `text <name_>`_ points at a named target, not a URL
+            if !target.ends_with('_') {
+                links.push((line_number, target.to_string()));
+            }
+        }
+        for pattern in [&definition, &directive] {
+            if let Some(captures) = pattern.captures(line) {
+                links.push((line_number, captures[1].to_string()));
+            }
+        }
+        for captures in role.captures_iter(line) {
+            let target = role_target(&captures[2]);
+            let target = match &captures[1] {
+                "ref" => format!(":ref:{}", target.to_lowercase()),
+                _ if target.ends_with(".rst") => target.to_string(),
+                _ => format!("{}.rst", target),
+            };
+            links.push((line_number, target));
+        }
+    }
+    links
+}
+
+/// Labels a reStructuredText document defines with `.. _label:`, lowercased
+/// as Sphinx matches them case-insensitively
+pub fn labels(content: &str) -> HashSet<String> {
+    let label = Regex::new(r"^\s*\.\. _([^:]+):\s*$").unwrap();
+    content
+        .lines()
+        .filter_map(|line| label.captures(line))
+        .map(|captures| captures[1].trim().to_lowercase())
+        .collect()
+}
+
+/// Target of a role: `label` or the `<label>` of `title <label>`
+fn role_target(text: &str) -> &str {
+    match text.rsplit_once('<') {
+        Some((_, target)) if text.ends_with('>') => target.trim_end_matches('>').trim(),
+        _ => text.trim(),
+    }
+}
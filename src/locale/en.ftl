@@ -0,0 +1,92 @@
+# Messages of inspector-gadget in English. This file is the template for
+# translations: copy it to <locale>.ftl in the locales directory and translate
+# the values. Variables ($name) are filled in by the tool; severities are
+# passed as `error`, `warning` and `info`.
+
+## Messages printed while running
+
+cli-discovered = Discovered { $count } valid links to scan.
+cli-findings = Checks reported { $count } findings.
+cli-ignored = Ignored { $count } links.
+cli-external = Checked { $count } external links, { $broken } broken.
+cli-revalidated = { $count } pages revalidated: { $unchanged } not modified (304), { $changed } changed (200).
+
+## Summary printed at the end of a run
+
+summary-totals = Summary: { $links } links scanned, { $broken } broken, { $findings } findings ({ $errors } errors, { $warnings } warnings, { $info } info)
+summary-sections = Sections:
+summary-section = { $label }: { $pages } pages, { $links } links, { $broken } broken ({ $health }% healthy)
+summary-nothing-to-fix = Nothing to fix.
+summary-next-steps = Next steps:
+summary-deferred = { $count } { $severity }-level
+summary-deferred-separator = { " " }and{ " " }
+summary-later = Later: { $issues } issues can wait.
+
+## Prioritized action items
+
+action-fix-broken = Fix { $count } internal { $kind ->
+        [not-found] 404s
+       *[other] errors
+    } on { $page } (linked from { $inlinks } pages)
+action-resolve-findings = Resolve { $count } { $rule } { $severity ->
+        [error] errors
+        [warning] warnings
+       *[info] infos
+    } on { $page }
+
+## Markdown summary (GitHub checks)
+
+markdown-totals = **{ $links }** links scanned, **{ $broken }** broken, **{ $findings }** findings.
+markdown-next-steps = Next steps
+markdown-more = ...and { $count } more
+markdown-broken-links = Broken links
+
+## Labels of the site output
+
+site-title = Link health
+site-totals = { $links } links scanned, { $broken } broken, { $unchecked } not checked, { $findings } findings.
+site-trend = Trend over the last { $runs } runs
+site-trend-broken = Broken links
+site-trend-links = Links scanned
+site-statuses = Statuses
+site-sections = Sections
+site-findings = Findings
+site-pages = Pages
+site-links-on = Links on { $page }
+column-link = Link
+column-links = Links
+column-status = Status
+column-found-on = Found on
+column-section = Section
+column-pages = Pages
+column-broken = Broken
+column-health = Health
+column-trend = Trend
+column-first = First
+column-latest = Latest
+column-severity = Severity
+column-rule = Rule
+column-page = Page
+column-message = Message
+
+## Link statuses and severities
+
+status-valid = Valid
+status-not-found = Not found
+status-error = Errors
+status-dns-error = DNS errors
+status-parked = Parked domains
+status-auth-required = Sign-in required
+status-broken-anchor = Broken anchors
+status-ignored = Ignored
+status-unsupported-scheme = Unsupported schemes
+status-code-block = Code blocks
+status-placeholder = Placeholders
+status-private-address = Private addresses
+status-out-of-scope = Out of scope
+status-disallowed = Disallowed by robots.txt
+severity = { $severity ->
+        [error] error
+        [warning] warning
+       *[info] info
+    }
@@ -0,0 +1,143 @@
+use fluent_bundle::concurrent::FluentBundle;
+use fluent_bundle::{FluentArgs, FluentResource, FluentValue};
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use unic_langid::LanguageIdentifier;
+
+/// Built-in English messages, also the template for translations
+pub const ENGLISH: &str = include_str!("en.ftl");
+/// Directory searched for `<locale>.ftl` files when `locales_dir` is not set
+pub const DEFAULT_LOCALES_DIR: &str = "locales";
+
+/// CLI and report messages in the configured locale, falling back to English
+/// for messages a translation lacks
+pub struct Messages {
+    locale: String,
+    /// Translation first, English last
+    bundles: Vec<FluentBundle<FluentResource>>,
+}
+
+impl fmt::Debug for Messages {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Messages")
+            .field("locale", &self.locale)
+            .finish()
+    }
+}
+
+impl Default for Messages {
+    fn default() -> Self {
+        Messages::english()
+    }
+}
+
+fn bundle(locale: &str, source: &str) -> Result<FluentBundle<FluentResource>, String> {
+    let langid: LanguageIdentifier = locale
+        .parse()
+        .map_err(|_| format!("Invalid locale {:?}", locale))?;
+    let resource = FluentResource::try_new(source.to_string())
+        .map_err(|(_, errors)| format!("Invalid messages for {}: {:?}", locale, errors))?;
+    let mut bundle = FluentBundle::new_concurrent(vec![langid]);
+    // Output goes to terminals and Markdown, where bidi isolation marks show up
+    bundle.set_use_isolating(false);
+    bundle
+        .add_resource(resource)
+        .map_err(|errors| format!("Invalid messages for {}: {:?}", locale, errors))?;
+    Ok(bundle)
+}
+
+impl Messages {
+    pub fn english() -> Self {
+        Messages {
+            locale: "en".to_string(),
+            bundles: vec![bundle("en", ENGLISH).expect("built-in messages are valid")],
+        }
+    }
+
+    /// Messages of `locale` from Fluent `source`, with English as fallback
+    pub fn from_ftl(locale: &str, source: &str) -> Result<Self, Box<dyn Error>> {
+        let mut messages = Messages::english();
+        messages.bundles.insert(0, bundle(locale, source)?);
+        messages.locale = locale.to_string();
+        Ok(messages)
+    }
+
+    /// Messages of `locale` from `<dir>/<locale>.ftl`, or from the file of its
+    /// language (`es.ftl` for `es-MX`). English needs no file.
+    pub fn load(locale: &str, dir: &Path) -> Result<Self, Box<dyn Error>> {
+        let language = locale.split(['-', '_']).next().unwrap_or(locale);
+        if language.eq_ignore_ascii_case("en") && !dir.join(format!("{}.ftl", locale)).exists() {
+            return Ok(Messages::english());
+        }
+        let path = [locale, language]
+            .iter()
+            .map(|name| dir.join(format!("{}.ftl", name)))
+            .find(|path| path.exists())
+            .ok_or_else(|| format!("No messages for locale {} in {}", locale, dir.display()))?;
+        Messages::from_ftl(locale, &fs::read_to_string(path)?)
+    }
+
+    pub fn locale(&self) -> &str {
+        &self.locale
+    }
+
+    /// Message `id` with `args` filled in. Numeric arguments are passed as
+    /// numbers so translations can select plural forms. Unknown ids are
+    /// returned as is.
+    pub fn get(&self, id: &str, args: &[(&str, &dyn fmt::Display)]) -> String {
+        let values: Vec<(&str, String)> = args
+            .iter()
+            .map(|(name, value)| (*name, value.to_string()))
+            .collect();
+        let mut fluent_args = FluentArgs::new();
+        for (name, value) in &values {
+            fluent_args.set(*name, FluentValue::try_number(value));
+        }
+        for bundle in &self.bundles {
+            let Some(pattern) = bundle.get_message(id).and_then(|message| message.value()) else {
+                continue;
+            };
+            let mut errors = Vec::new();
+            let text = bundle.format_pattern(pattern, Some(&fluent_args), &mut errors);
+            if errors.is_empty() {
+                return text.into_owned();
+            }
+        }
+        id.to_string()
+    }
+
+    /// Message `id` without arguments
+    pub fn text(&self, id: &str) -> String {
+        self.get(id, &[])
+    }
+}
+
+/// Ids of the messages defined in Fluent `source`
+pub fn message_ids(source: &str) -> Vec<String> {
+    source
+        .lines()
+        .filter(|line| line.starts_with(|c: char| c.is_ascii_alphabetic()))
+        .filter_map(|line| line.split_once('='))
+        .map(|(id, _)| id.trim().to_string())
+        .collect()
+}
+
+/// Ids of the English messages missing from a translation, and ids of the
+/// translation the tool does not know
+pub fn compare_messages(translation: &str) -> (Vec<String>, Vec<String>) {
+    let english = message_ids(ENGLISH);
+    let translated = message_ids(translation);
+    let missing = english
+        .iter()
+        .filter(|id| !translated.contains(id))
+        .cloned()
+        .collect();
+    let unknown = translated
+        .iter()
+        .filter(|id| !english.contains(id))
+        .cloned()
+        .collect();
+    (missing, unknown)
+}
@@ -0,0 +1,250 @@
+use crate::config::LoginConfig;
+use crate::link::{LinkInfo, LinkStatus};
+use crate::secret::Secret;
+use log::info;
+use regex::Regex;
+use reqwest::blocking::{Client, ClientBuilder, Response};
+use reqwest::header::{COOKIE, LOCATION, SET_COOKIE};
+use reqwest::redirect::Policy;
+use scraper::{ElementRef, Html, Selector};
+use std::collections::BTreeMap;
+use std::error::Error;
+use url::Url;
+
+/// Sign-in pages of common identity providers and login paths, matched
+/// against the URL a link ends up at after redirects
+pub const DEFAULT_LOGIN_PATTERNS: [&str; 9] = [
+    r"^https://accounts\.google\.com/",
+    r"^https://login\.microsoftonline\.com/",
+    r"^https://[^/]+\.okta\.com/",
+    r"^https://[^/]+\.auth0\.com/",
+    r"^https://[^/]+\.onelogin\.com/",
+    r"^https://github\.com/(login|session)([/?#]|$)",
+    r"^https://gitlab\.com/users/sign_in([/?#]|$)",
+    r"^https?://[^/]+(/[^?#]*)?/(login|signin|sign-in|sign_in|sso)([/?#.]|$)",
+    r"^https?://[^/]+(/[^?#]*)?/saml2?/",
+];
+
+/// Patterns of sign-in pages: the built-in ones and those of `login_urls`
+#[derive(Debug, Clone)]
+pub struct LoginPages {
+    patterns: Vec<Regex>,
+}
+
+impl Default for LoginPages {
+    fn default() -> Self {
+        LoginPages::new(&[]).expect("built-in login patterns compile")
+    }
+}
+
+impl LoginPages {
+    /// The built-in patterns and `extra` ones, rejecting invalid regexes
+    pub fn new(extra: &[String]) -> Result<Self, regex::Error> {
+        let patterns = DEFAULT_LOGIN_PATTERNS
+            .iter()
+            .copied()
+            .chain(extra.iter().map(String::as_str))
+            .map(Regex::new)
+            .collect::<Result<_, _>>()?;
+        Ok(LoginPages { patterns })
+    }
+
+    /// Whether `url` is a sign-in page
+    pub fn matches(&self, url: &str) -> bool {
+        self.patterns.iter().any(|pattern| pattern.is_match(url))
+    }
+
+    /// A link redirected to a sign-in page becomes
+    /// [`LinkStatus::AuthRequired`], whatever the sign-in page answered.
+    /// Links linking to a sign-in page directly are left alone.
+    pub fn classify(&self, mut link_info: LinkInfo) -> LinkInfo {
+        if let Some(final_url) = &link_info.redirected_to {
+            if !self.matches(&link_info.url) && self.matches(final_url) {
+                link_info.status = LinkStatus::AuthRequired(final_url.clone());
+            }
+        }
+        link_info
+    }
+}
+
+/// Redirects followed by the login flow
+const MAX_LOGIN_REDIRECTS: usize = 10;
+
+/// Cookies set during the login flow, by host. Cookies are host-only: their
+/// `Domain`, `Path` and expiry are not tracked, and an empty value deletes
+/// a cookie.
+#[derive(Debug, Default)]
+struct CookieJar {
+    hosts: BTreeMap<String, BTreeMap<String, String>>,
+}
+
+impl CookieJar {
+    fn store(&mut self, url: &Url, response: &Response) {
+        let cookies = self
+            .hosts
+            .entry(url.host_str().unwrap_or_default().to_string())
+            .or_default();
+        for header in response.headers().get_all(SET_COOKIE) {
+            let Some(pair) = header
+                .to_str()
+                .ok()
+                .and_then(|value| value.split(';').next())
+            else {
+                continue;
+            };
+            let Some((name, value)) = pair.split_once('=') else {
+                continue;
+            };
+            if value.trim().is_empty() {
+                cookies.remove(name.trim());
+            } else {
+                cookies.insert(name.trim().to_string(), value.trim().to_string());
+            }
+        }
+    }
+
+    /// `Cookie` header of the requests to `url`'s host, if it has cookies
+    fn header(&self, url: &Url) -> Option<String> {
+        let cookies = self.hosts.get(url.host_str()?)?;
+        let pairs: Vec<String> = cookies
+            .iter()
+            .map(|(name, value)| format!("{}={}", name, value))
+            .collect();
+        (!pairs.is_empty()).then(|| pairs.join("; "))
+    }
+}
+
+/// Send a request with the cookies of its host and follow its redirects,
+/// returning the final URL and body
+fn follow(
+    client: &Client,
+    jar: &mut CookieJar,
+    mut url: Url,
+    form: Option<(bool, &[(String, String)])>,
+) -> Result<(Url, String), Box<dyn Error>> {
+    let mut form = form;
+    for _ in 0..=MAX_LOGIN_REDIRECTS {
+        let mut request = match form.take() {
+            Some((true, fields)) => client.post(url.clone()).form(fields),
+            Some((false, fields)) => client.get(url.clone()).query(fields),
+            None => client.get(url.clone()),
+        };
+        if let Some(cookies) = jar.header(&url) {
+            request = request.header(COOKIE, cookies);
+        }
+        let response = request.send()?;
+        jar.store(&url, &response);
+        if response.status().is_redirection() {
+            let location = response
+                .headers()
+                .get(LOCATION)
+                .and_then(|location| location.to_str().ok())
+                .ok_or_else(|| format!("{} redirected without a Location", url))?;
+            url = url.join(location)?;
+            continue;
+        }
+        let response = response.error_for_status()?;
+        return Ok((url, response.text()?));
+    }
+    Err(format!("too many redirects logging in at {}", url).into())
+}
+
+/// Name and value of the fields a form submits as is: inputs with a value,
+/// checked boxes, and text areas. Buttons and files are left out.
+fn form_fields(form: &ElementRef) -> Vec<(String, String)> {
+    let fields = Selector::parse("input[name], textarea[name]").unwrap();
+    form.select(&fields)
+        .filter_map(|field| {
+            let element = field.value();
+            let kind = element.attr("type").unwrap_or("text").to_ascii_lowercase();
+            let value = match (element.name(), kind.as_str()) {
+                ("textarea", _) => field.text().collect(),
+                (_, "submit" | "button" | "image" | "reset" | "file") => return None,
+                (_, "checkbox" | "radio") if element.attr("checked").is_none() => return None,
+                (_, "checkbox" | "radio") => element.attr("value").unwrap_or("on").to_string(),
+                _ => element.attr("value").unwrap_or_default().to_string(),
+            };
+            Some((element.attr("name")?.to_string(), value))
+        })
+        .collect()
+}
+
+/// Fill in and submit the login form of `config`, returning the `Cookie`
+/// header of the session on `base_url`'s host. Hidden fields of the form,
+/// such as CSRF tokens, are sent along with the configured ones. The login
+/// fails unless the page it lands on matches `success_selector`. The client
+/// is built from `client` with redirects followed by hand.
+pub fn log_in(
+    config: &LoginConfig,
+    base_url: &str,
+    client: ClientBuilder,
+) -> Result<Secret, Box<dyn Error>> {
+    let client = client.redirect(Policy::none()).build()?;
+    let mut jar = CookieJar::default();
+    info!("Logging in at {}", config.url);
+    let (page_url, body) = follow(&client, &mut jar, Url::parse(&config.url)?, None)?;
+
+    let (action, post, mut fields) = {
+        let document = Html::parse_document(&body);
+        let forms = Selector::parse(config.form_selector.as_deref().unwrap_or("form"))
+            .map_err(|e| format!("login.form_selector is invalid: {:?}", e))?;
+        let password = Selector::parse("input[type=password]").unwrap();
+        let candidates: Vec<ElementRef> = document.select(&forms).collect();
+        let form = candidates
+            .iter()
+            .find(|form| config.form_selector.is_some() || form.select(&password).next().is_some())
+            .or(candidates.first())
+            .ok_or_else(|| format!("no login form found on {}", page_url))?;
+        let action = match form.value().attr("action").map(str::trim) {
+            Some(action) if !action.is_empty() => page_url.join(action)?,
+            _ => page_url.clone(),
+        };
+        let post = !form
+            .value()
+            .attr("method")
+            .is_some_and(|method| method.eq_ignore_ascii_case("get"));
+        (action, post, form_fields(form))
+    };
+
+    let mut values: Vec<(String, String)> = config
+        .fields
+        .iter()
+        .flatten()
+        .map(|(name, value)| (name.clone(), value.clone()))
+        .collect();
+    for (name, variable) in config.fields_env.iter().flatten() {
+        let value =
+            Secret::from_env(variable).map_err(|e| format!("login.fields_env.{}: {}", name, e))?;
+        values.push((name.clone(), value.expose().to_string()));
+    }
+    for (name, value) in values {
+        match fields.iter_mut().find(|(field, _)| *field == name) {
+            Some(field) => field.1 = value,
+            None => fields.push((name, value)),
+        }
+    }
+
+    let (landed, body) = follow(&client, &mut jar, action, Some((post, &fields)))?;
+    let success = Selector::parse(&config.success_selector)
+        .map_err(|e| format!("login.success_selector is invalid: {:?}", e))?;
+    if Html::parse_document(&body)
+        .select(&success)
+        .next()
+        .is_none()
+    {
+        return Err(format!(
+            "login failed: {} does not match {}",
+            landed, config.success_selector
+        )
+        .into());
+    }
+    let base = Url::parse(base_url)?;
+    let cookies = jar.header(&base).ok_or_else(|| {
+        format!(
+            "login set no cookie for {}",
+            base.host_str().unwrap_or_default()
+        )
+    })?;
+    info!("Logged in; crawling with the session cookies");
+    Ok(Secret::new(cookies))
+}
@@ -0,0 +1,135 @@
+use crate::check::Severity;
+use crate::output::ScanReport;
+use crate::store::Labels;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Default file name of the run manifest
+pub const MANIFEST_FILE: &str = "run.json";
+
+/// Small machine-readable summary of a run for downstream pipeline steps
+#[derive(Debug, Serialize)]
+pub struct RunManifest {
+    pub url: String,
+    #[serde(skip_serializing_if = "Labels::is_empty")]
+    pub labels: Labels,
+    pub status: RunStatus,
+    pub exit_code: i32,
+    pub started_at: u64,
+    pub finished_at: u64,
+    pub thresholds: Vec<ThresholdResult>,
+    pub counts: RunCounts,
+    pub artifacts: Vec<Artifact>,
+}
+
+/// Overall outcome of a run
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RunStatus {
+    Passed,
+    Failed,
+}
+
+/// A threshold that was evaluated to decide the exit status
+#[derive(Debug, Clone, Serialize)]
+pub struct ThresholdResult {
+    pub name: String,
+    pub limit: f64,
+    pub actual: f64,
+    pub passed: bool,
+}
+
+/// Link and finding totals
+#[derive(Debug, Default, Serialize)]
+pub struct RunCounts {
+    pub scanned: usize,
+    pub valid: usize,
+    pub broken: usize,
+    pub ignored: usize,
+    pub findings: BTreeMap<Severity, usize>,
+}
+
+impl RunCounts {
+    /// Count links and findings in a report
+    pub fn from_report(report: &ScanReport) -> Self {
+        let broken = report.links.iter().filter(|link| link.is_broken()).count();
+        let mut findings = BTreeMap::new();
+        for finding in &report.findings {
+            *findings.entry(finding.severity).or_default() += 1;
+        }
+
+        RunCounts {
+            scanned: report.links.len(),
+            valid: report.links.len() - broken,
+            broken,
+            ignored: report.ignored_links.len(),
+            findings,
+        }
+    }
+}
+
+/// A file generated by the run
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Artifact {
+    pub format: String,
+    pub path: String,
+}
+
+impl RunManifest {
+    /// Build a manifest from a finished report
+    pub fn new(
+        url: &str,
+        report: &ScanReport,
+        started_at: SystemTime,
+        thresholds: Vec<ThresholdResult>,
+        artifacts: Vec<Artifact>,
+    ) -> Self {
+        let exit_code = if thresholds.iter().all(|threshold| threshold.passed) {
+            0
+        } else {
+            1
+        };
+
+        RunManifest {
+            url: url.to_string(),
+            labels: report.labels.clone(),
+            status: if exit_code == 0 {
+                RunStatus::Passed
+            } else {
+                RunStatus::Failed
+            },
+            exit_code,
+            started_at: unix_seconds(started_at),
+            finished_at: unix_seconds(SystemTime::now()),
+            thresholds,
+            counts: RunCounts::from_report(report),
+            artifacts,
+        }
+    }
+
+    /// Write the manifest as pretty-printed JSON
+    pub fn write(&self, path: &Path) -> Result<(), Box<dyn Error>> {
+        fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+}
+
+/// Default manifest location: next to the first file artifact, or the working directory
+pub fn default_manifest_path(artifacts: &[Artifact]) -> PathBuf {
+    artifacts
+        .first()
+        .and_then(|artifact| Path::new(&artifact.path).parent())
+        .map(|dir| dir.join(MANIFEST_FILE))
+        .unwrap_or_else(|| PathBuf::from(MANIFEST_FILE))
+}
+
+/// Seconds since the Unix epoch
+pub fn unix_seconds(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or_default()
+}
@@ -0,0 +1,213 @@
+use crate::config::Config;
+use crate::link::LinkStatus;
+use crate::scope::is_placeholder;
+use regex::Regex;
+use std::fmt;
+use url::Url;
+
+/// The rule that keeps a URL from being requested
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MatchRule {
+    /// The scheme is not in `allowed_schemes`
+    UnsupportedScheme(String),
+    /// The host is a placeholder domain such as `example.com`
+    Placeholder,
+    /// The URL does not parse
+    InvalidUrl(String),
+    /// The URL is outside the start URL (strict mode)
+    OutOfScope { base: String },
+    /// The host ends with an `ignore.domains` entry
+    IgnoredDomain(String),
+    /// The URL matches an `ignore.regex` pattern
+    IgnoredRegex(String),
+    /// The host ends with a `forbidden_domains` entry
+    ForbiddenDomain(String),
+    /// The path starts with the prefix an `ignored_childs` entry resolves to
+    IgnoredChild { entry: String, prefix: String },
+}
+
+impl MatchRule {
+    /// Status of a URL skipped by this rule
+    pub fn status(&self) -> LinkStatus {
+        match self {
+            MatchRule::UnsupportedScheme(scheme) => LinkStatus::UnsupportedScheme(scheme.clone()),
+            MatchRule::Placeholder => LinkStatus::Placeholder,
+            _ => LinkStatus::Ignored,
+        }
+    }
+}
+
+impl fmt::Display for MatchRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MatchRule::UnsupportedScheme(scheme) => {
+                write!(f, "scheme {} is not in allowed_schemes", scheme)
+            }
+            MatchRule::Placeholder => write!(f, "host is a placeholder domain"),
+            MatchRule::InvalidUrl(error) => write!(f, "not a valid URL: {}", error),
+            MatchRule::OutOfScope { base } => {
+                write!(f, "outside the start URL {} (strict mode)", base)
+            }
+            MatchRule::IgnoredDomain(domain) => write!(f, "ignore.domains entry {}", domain),
+            MatchRule::IgnoredRegex(pattern) => write!(f, "ignore.regex pattern {}", pattern),
+            MatchRule::ForbiddenDomain(domain) => {
+                write!(f, "forbidden_domains entry {}", domain)
+            }
+            MatchRule::IgnoredChild { entry, prefix } => {
+                write!(f, "ignored_childs entry {} (path prefix {})", entry, prefix)
+            }
+        }
+    }
+}
+
+/// Whether a URL is requested, and if not, why
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MatchDecision {
+    Follow,
+    Skip(MatchRule),
+}
+
+impl MatchDecision {
+    pub fn is_skipped(&self) -> bool {
+        matches!(self, MatchDecision::Skip(_))
+    }
+}
+
+/// Path prefix an `ignored_childs` entry stands for when crawling from `seed`.
+///
+/// Entries starting with `/` are paths from the site root. Other entries are
+/// relative to the seed's directory: its path up to the last `/`, where a last
+/// segment without an extension (`/docs`) counts as a directory.
+pub fn ignored_child_prefix(seed: &Url, child: &str) -> String {
+    if child.starts_with('/') {
+        return child.to_string();
+    }
+    let path = seed.path();
+    let directory = match path.rsplit_once('/') {
+        Some((parent, last)) if last.contains('.') => format!("{}/", parent),
+        _ if path.ends_with('/') => path.to_string(),
+        _ => format!("{}/", path),
+    };
+    format!("{}{}", directory, child.trim_start_matches("./"))
+}
+
+/// Decides which URLs are requested, from the scheme, placeholder, scope and
+/// ignore rules of the config. Rules are tried in a fixed order and the first
+/// one that matches wins.
+pub struct UrlMatcher<'a> {
+    config: &'a Config,
+    /// Start URL of a crawl; `None` for links checked outside a crawl
+    base: Option<(String, Url)>,
+    /// Valid `ignore.regex` patterns, compiled once
+    regexes: Vec<(&'a str, Regex)>,
+}
+
+impl<'a> UrlMatcher<'a> {
+    /// Matcher for a crawl from `base_url`, which also enforces strict scope,
+    /// `ignored_childs` and `allowed_schemes`
+    pub fn new(config: &'a Config, base_url: &str) -> Self {
+        let mut matcher = UrlMatcher::external(config);
+        matcher.base = Url::parse(base_url)
+            .ok()
+            .map(|base| (base_url.to_string(), base));
+        matcher
+    }
+
+    /// Matcher for links checked outside a crawl, such as external links of
+    /// local sources and inventories: only placeholder and ignore rules apply
+    pub fn external(config: &'a Config) -> Self {
+        let regexes = config
+            .ignore
+            .iter()
+            .flat_map(|ignore| ignore.regex.iter().flatten())
+            .filter_map(|pattern| Some((pattern.as_str(), Regex::new(pattern).ok()?)))
+            .collect();
+        UrlMatcher {
+            config,
+            base: None,
+            regexes,
+        }
+    }
+
+    /// Decide whether `url` is requested
+    pub fn decide(&self, url: &str) -> MatchDecision {
+        match self.first_rule(url) {
+            Some(rule) => MatchDecision::Skip(rule),
+            None => MatchDecision::Follow,
+        }
+    }
+
+    fn first_rule(&self, url: &str) -> Option<MatchRule> {
+        if let (Some(_), Some((scheme, _))) = (&self.base, url.split_once(':')) {
+            let scheme = scheme.to_ascii_lowercase();
+            if !self.config.allows_scheme(&scheme) {
+                return Some(MatchRule::UnsupportedScheme(scheme));
+            }
+        }
+
+        let parsed = match Url::parse(url) {
+            Ok(parsed) => parsed,
+            Err(error) if self.base.is_some() => {
+                return Some(MatchRule::InvalidUrl(error.to_string()))
+            }
+            // Outside a crawl the request reports the error, as a broken link
+            Err(_) => return None,
+        };
+        // The host being scanned never counts, so local sites on `localhost`
+        // can still be crawled
+        let base_host = self.base.as_ref().and_then(|(_, base)| base.host_str());
+        if parsed.host_str() != base_host
+            && is_placeholder(
+                url,
+                self.config
+                    .placeholder_domains
+                    .as_deref()
+                    .unwrap_or_default(),
+            )
+        {
+            return Some(MatchRule::Placeholder);
+        }
+
+        if let Some((base_url, base)) = &self.base {
+            if !url.starts_with(base_url.as_str()) || parsed.domain() != base.domain() {
+                return Some(MatchRule::OutOfScope {
+                    base: base_url.clone(),
+                });
+            }
+        }
+
+        let domain = parsed.domain().unwrap_or_default();
+        let ignored_domains = self
+            .config
+            .ignore
+            .iter()
+            .flat_map(|ignore| ignore.domains.iter().flatten());
+        for ignored in ignored_domains {
+            if domain.ends_with(ignored.as_str()) {
+                return Some(MatchRule::IgnoredDomain(ignored.clone()));
+            }
+        }
+        for (pattern, regex) in &self.regexes {
+            if regex.is_match(url) {
+                return Some(MatchRule::IgnoredRegex(pattern.to_string()));
+            }
+        }
+        for forbidden in self.config.forbidden_domains.iter().flatten() {
+            if domain.ends_with(forbidden.as_str()) {
+                return Some(MatchRule::ForbiddenDomain(forbidden.clone()));
+            }
+        }
+
+        let (_, base) = self.base.as_ref()?;
+        self.config
+            .ignored_childs
+            .iter()
+            .flatten()
+            .map(|entry| (entry, ignored_child_prefix(base, entry)))
+            .find(|(_, prefix)| parsed.path().starts_with(prefix.as_str()))
+            .map(|(entry, prefix)| MatchRule::IgnoredChild {
+                entry: entry.clone(),
+                prefix,
+            })
+    }
+}
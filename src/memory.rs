@@ -0,0 +1,162 @@
+use crate::check::Finding;
+use crate::link::LinkInfo;
+use crate::output::ScanReport;
+use std::fmt;
+use std::mem::size_of;
+
+/// Approximate heap and inline bytes held by a string
+pub fn string_bytes(value: &str) -> usize {
+    size_of::<String>() + value.len()
+}
+
+/// Approximate bytes held by a buffered link result
+pub fn link_bytes(link: &LinkInfo) -> usize {
+    size_of::<LinkInfo>()
+        + link.url.len()
+        + link
+            .sources
+            .iter()
+            .map(|source| string_bytes(source))
+            .sum::<usize>()
+        + link.content_hash.as_deref().map_or(0, str::len)
+        + link.redirected_to.as_deref().map_or(0, str::len)
+        + link
+            .variants
+            .iter()
+            .map(|variant| {
+                string_bytes(&variant.url)
+                    + variant
+                        .sources
+                        .iter()
+                        .map(|source| string_bytes(source))
+                        .sum::<usize>()
+            })
+            .sum::<usize>()
+}
+
+/// Approximate bytes held by a buffered finding
+pub fn finding_bytes(finding: &Finding) -> usize {
+    size_of::<Finding>()
+        + finding.rule.len()
+        + finding.page.len()
+        + finding.message.len()
+        + finding.target.as_deref().map_or(0, str::len)
+}
+
+/// Parse a size such as `512MiB`, `2G` or `100000` (bytes). Units are binary:
+/// `K`, `M` and `G`, optionally followed by `B` or `iB`.
+pub fn parse_size(value: &str) -> Result<u64, String> {
+    let value = value.trim();
+    let split = value
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(value.len());
+    let (number, unit) = value.split_at(split);
+    let number: u64 = number.parse().map_err(|_| {
+        format!(
+            "Invalid size {:?}: expected a number of bytes, e.g. 512MiB",
+            value
+        )
+    })?;
+    let shift = match unit.trim().to_ascii_uppercase().as_str() {
+        "" | "B" => 0,
+        "K" | "KB" | "KIB" => 10,
+        "M" | "MB" | "MIB" => 20,
+        "G" | "GB" | "GIB" => 30,
+        _ => {
+            return Err(format!(
+                "Invalid size {:?}: unknown unit {:?}",
+                value,
+                unit.trim()
+            ))
+        }
+    };
+    number
+        .checked_mul(1 << shift)
+        .filter(|bytes| *bytes > 0)
+        .ok_or_else(|| {
+            format!(
+                "Invalid size {:?}: must be between 1 byte and 16 EiB",
+                value
+            )
+        })
+}
+
+/// Human-readable size with a binary unit, e.g. `3.2 MiB`
+pub fn format_size(bytes: usize) -> String {
+    const UNITS: [&str; 4] = ["B", "KiB", "MiB", "GiB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} B", bytes)
+    } else {
+        format!("{:.1} {}", size, UNITS[unit])
+    }
+}
+
+/// Approximate memory held by a crawl, by structure
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MemoryUsage {
+    /// Queued URLs, the visited set and the sources of each link
+    pub frontier: usize,
+    /// Link results, findings and outlinks buffered for the report
+    pub results: usize,
+}
+
+impl MemoryUsage {
+    pub fn total(&self) -> usize {
+        self.frontier + self.results
+    }
+}
+
+impl fmt::Display for MemoryUsage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "~{} (frontier {}, results {})",
+            format_size(self.total()),
+            format_size(self.frontier),
+            format_size(self.results)
+        )
+    }
+}
+
+/// Running estimate of the results buffered in a [`ScanReport`] while it is
+/// being filled. Links and findings are counted as they are appended, so an
+/// update only looks at the entries added since the previous one.
+#[derive(Debug, Default)]
+pub struct ResultMeter {
+    bytes: usize,
+    links: usize,
+    ignored_links: usize,
+    findings: usize,
+}
+
+impl ResultMeter {
+    /// Count the entries appended to `report` since the last update and
+    /// return the estimate
+    pub fn update(&mut self, report: &ScanReport) -> usize {
+        self.bytes += report.links[self.links..]
+            .iter()
+            .chain(&report.ignored_links[self.ignored_links..])
+            .map(link_bytes)
+            .sum::<usize>();
+        self.bytes += report.findings[self.findings..]
+            .iter()
+            .map(finding_bytes)
+            .sum::<usize>();
+        self.links = report.links.len();
+        self.ignored_links = report.ignored_links.len();
+        self.findings = report.findings.len();
+        self.bytes
+    }
+
+    /// Count the outlinks recorded for a page
+    pub fn add_outlinks(&mut self, page: &str, links: &[String]) {
+        self.bytes +=
+            string_bytes(page) + links.iter().map(|link| string_bytes(link)).sum::<usize>();
+    }
+}
@@ -0,0 +1,116 @@
+use crate::link::LinkInfo;
+use crate::output::{OutputFormatter, OutputOptions, ScanReport};
+use log::warn;
+use std::error::Error;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::sync::Mutex;
+
+/// File name standing for stdout
+pub const STDOUT: &str = "-";
+
+/// Write `links` as one JSON object per line
+pub fn write_links<'a>(
+    out: &mut dyn Write,
+    links: impl IntoIterator<Item = &'a LinkInfo>,
+) -> io::Result<()> {
+    for link in links {
+        serde_json::to_writer(&mut *out, link)?;
+        out.write_all(b"\n")?;
+    }
+    out.flush()
+}
+
+/// Links of a report in the order they are streamed: scanned links,
+/// ignored links when `detailed`, then external links
+fn report_links(report: &ScanReport, detailed: bool) -> impl Iterator<Item = &LinkInfo> {
+    let ignored: &[LinkInfo] = if detailed { &report.ignored_links } else { &[] };
+    report
+        .links
+        .iter()
+        .chain(ignored)
+        .chain(&report.external_links)
+}
+
+/// Open `file` for writing, or stdout for [`STDOUT`]
+fn open(file: &str) -> io::Result<Box<dyn Write + Send>> {
+    if file == STDOUT {
+        Ok(Box::new(io::stdout()))
+    } else {
+        Ok(Box::new(BufWriter::new(File::create(file)?)))
+    }
+}
+
+/// NDJSON destination links are written to while the crawl checks them,
+/// flushed after every batch so a crash loses nothing already checked
+pub struct LinkStream {
+    file: String,
+    detailed: bool,
+    /// Closed after the first failed write
+    out: Mutex<Option<Box<dyn Write + Send>>>,
+}
+
+impl fmt::Debug for LinkStream {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LinkStream")
+            .field("file", &self.file)
+            .field("detailed", &self.detailed)
+            .finish()
+    }
+}
+
+impl LinkStream {
+    /// Create `file` (or write to stdout for [`STDOUT`]); ignored links are
+    /// only streamed when `detailed`
+    pub fn create(file: &str, detailed: bool) -> io::Result<Self> {
+        Ok(LinkStream {
+            file: file.to_string(),
+            detailed,
+            out: Mutex::new(Some(open(file)?)),
+        })
+    }
+
+    pub fn file(&self) -> &str {
+        &self.file
+    }
+
+    /// Append newly checked links
+    pub fn push(
+        &self,
+        links: &[LinkInfo],
+        ignored_links: &[LinkInfo],
+        external_links: &[LinkInfo],
+    ) {
+        let ignored: &[LinkInfo] = if self.detailed { ignored_links } else { &[] };
+        let mut out = self.out.lock().unwrap();
+        let Some(writer) = out.as_mut() else {
+            return;
+        };
+        let written = write_links(
+            writer.as_mut(),
+            links.iter().chain(ignored).chain(external_links),
+        );
+        if let Err(e) = written {
+            warn!("Stopped streaming links to {}: {}", self.file, e);
+            *out = None;
+        }
+    }
+}
+
+/// Built-in formatter writing one JSON object per link. Scans stream it
+/// while they crawl; this writes it in one go, for reports checked without
+/// a crawl.
+pub struct NdjsonFormatter;
+
+impl OutputFormatter for NdjsonFormatter {
+    fn name(&self) -> &str {
+        "ndjson"
+    }
+
+    fn write(&self, report: &ScanReport, options: &OutputOptions) -> Result<(), Box<dyn Error>> {
+        let mut out = open(&options.file)?;
+        write_links(out.as_mut(), report_links(report, options.detailed))?;
+        Ok(())
+    }
+}
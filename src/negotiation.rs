@@ -0,0 +1,58 @@
+use crate::lang::{same_language, LanguageFilter};
+use crate::link::{LinkInfo, LinkStatus};
+use serde::{Deserialize, Serialize};
+
+/// What went wrong when a page was requested in a language
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NegotiationProblem {
+    /// The page is broken in that language
+    Broken,
+    /// The page sent readers to a page in another language
+    Fallback,
+}
+
+/// A page answering an `Accept-Language` unexpectedly
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LanguageVariant {
+    pub url: String,
+    /// Language sent in `Accept-Language`
+    pub language: String,
+    pub problem: NegotiationProblem,
+    pub status: LinkStatus,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub redirected_to: Option<String>,
+    /// Language of the page served, when it can be told
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub served: Option<String>,
+}
+
+/// The variant of a page requested in `language`, if something went wrong.
+/// The language served comes from `languages`, so pages whose language
+/// cannot be told never count as a fallback.
+pub fn check_variant(
+    link: LinkInfo,
+    language: &str,
+    languages: &LanguageFilter,
+) -> Option<LanguageVariant> {
+    let final_url = link.redirected_to.as_deref().unwrap_or(&link.url);
+    let served = languages.language_of(final_url);
+    let problem = if link.is_broken() {
+        NegotiationProblem::Broken
+    } else if served
+        .as_deref()
+        .is_some_and(|served| !same_language(&language.to_ascii_lowercase(), served))
+    {
+        NegotiationProblem::Fallback
+    } else {
+        return None;
+    };
+    Some(LanguageVariant {
+        url: link.url,
+        language: language.to_string(),
+        problem,
+        status: link.status,
+        redirected_to: link.redirected_to,
+        served,
+    })
+}
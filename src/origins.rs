@@ -0,0 +1,92 @@
+use crate::changes::content_hash;
+use crate::link::{status_label, LinkInfo, LinkStatus};
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+/// What differs between a page and the same path on the compared origin
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OriginDifferenceKind {
+    /// The two origins answered with different statuses
+    Status,
+    /// Both answered alike but served different content
+    Content,
+}
+
+/// A path answering differently on the compared origin
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OriginDifference {
+    pub url: String,
+    /// The same path on the compared origin
+    pub compared_url: String,
+    pub difference: OriginDifferenceKind,
+    pub status: LinkStatus,
+    pub compared_status: LinkStatus,
+}
+
+/// The origin of a crawl and a second origin its paths are fetched from,
+/// such as production and staging
+#[derive(Debug, Clone)]
+pub struct OriginComparison {
+    origin: Url,
+    compared: Url,
+}
+
+impl OriginComparison {
+    /// Compare the origin of `base_url` with that of `compared`; `None` when
+    /// either is not an http(s) URL
+    pub fn new(base_url: &str, compared: &str) -> Option<Self> {
+        let origin = Url::parse(base_url).ok()?;
+        let compared = Url::parse(compared).ok()?;
+        let web = |url: &Url| matches!(url.scheme(), "http" | "https") && url.has_host();
+        (web(&origin) && web(&compared)).then_some(OriginComparison { origin, compared })
+    }
+
+    /// `url` on the compared origin, keeping its path and query; `None` for
+    /// URLs of other origins
+    pub fn compared_url(&self, url: &str) -> Option<String> {
+        let mut url = Url::parse(url).ok()?;
+        if url.origin() != self.origin.origin() {
+            return None;
+        }
+        url.set_scheme(self.compared.scheme()).ok()?;
+        url.set_host(self.compared.host_str()).ok()?;
+        url.set_port(self.compared.port()).ok()?;
+        Some(url.to_string())
+    }
+
+    /// How `link` differs from the same path fetched from the compared
+    /// origin, given the page body when it was read. Mentions of the
+    /// compared origin in bodies and errors are read as the crawled one, so
+    /// absolute self links do not count as differences.
+    pub fn compare(
+        &self,
+        link: &LinkInfo,
+        compared: LinkInfo,
+        body: Option<&str>,
+    ) -> Option<OriginDifference> {
+        let compared_origin = self.compared.origin().ascii_serialization();
+        let origin = self.origin.origin().ascii_serialization();
+        let same_status = status_label(&link.status)
+            == status_label(&compared.status).replace(&compared_origin, &origin);
+        let difference = if !same_status {
+            OriginDifferenceKind::Status
+        } else {
+            match (&link.content_hash, body) {
+                (Some(hash), Some(body))
+                    if *hash != content_hash(&body.replace(&compared_origin, &origin)) =>
+                {
+                    OriginDifferenceKind::Content
+                }
+                _ => return None,
+            }
+        };
+        Some(OriginDifference {
+            url: link.url.clone(),
+            compared_url: compared.url,
+            difference,
+            status: link.status.clone(),
+            compared_status: compared.status,
+        })
+    }
+}
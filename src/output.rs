@@ -1,23 +1,309 @@
+use crate::agents::AgentDifference;
+use crate::alternates::AlternateLink;
+use crate::changes::PageChange;
+use crate::check::Finding;
+use crate::config::ReportConfig;
+use crate::freshness::StalePage;
 use crate::link::LinkInfo;
+use crate::locale::Messages;
+use crate::ndjson::NdjsonFormatter;
+use crate::negotiation::LanguageVariant;
+use crate::origins::OriginDifference;
+use crate::revalidation::RevalidatedPage;
+use crate::sarif::SarifFormatter;
+use crate::sections::SectionSummary;
+use crate::site::SiteFormatter;
+use crate::sqlite::SqliteFormatter;
+use crate::store::{Labels, TrendPoint};
+use crate::tracking::TrackingEndpoint;
 use clipboard::{ClipboardContext, ClipboardProvider};
-use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
 use std::error::Error;
-use std::fs::File;
+use std::fs::{self, File};
 use std::io::Write;
+use std::sync::Arc;
+
+/// Results of a scan, as handed to output formatters.
+///
+/// Deserializes from the JSON/YAML outputs so earlier reports can be loaded back.
+#[derive(Debug, Default, Deserialize)]
+pub struct ScanReport {
+    /// Start URL of the scan, or the root directory of a local check
+    #[serde(skip)]
+    pub start_url: String,
+    /// Unix time the scan started
+    #[serde(skip)]
+    pub started_at: u64,
+    /// Labels of the scan, such as `env=prod`
+    #[serde(default)]
+    pub labels: Labels,
+    #[serde(rename = "scanned_links")]
+    pub links: Vec<LinkInfo>,
+    #[serde(default)]
+    pub ignored_links: Vec<LinkInfo>,
+    /// Links outside the start URL checked with `check_external`, not crawled
+    #[serde(default)]
+    pub external_links: Vec<LinkInfo>,
+    /// AMP, print and feed versions announced by crawled pages
+    #[serde(default)]
+    pub alternate_links: Vec<AlternateLink>,
+    /// Analytics and telemetry endpoints pages load, not checked
+    #[serde(default)]
+    pub tracking_endpoints: Vec<TrackingEndpoint>,
+    /// URLs probed with `user_agents` that answered agents differently
+    #[serde(default)]
+    pub user_agent_differences: Vec<AgentDifference>,
+    /// Pages probed with `accept_languages` that broke or fell back to
+    /// another language
+    #[serde(default)]
+    pub language_variants: Vec<LanguageVariant>,
+    /// Paths answering differently on `compare_origin`
+    #[serde(default)]
+    pub origin_differences: Vec<OriginDifference>,
+    #[serde(default)]
+    pub findings: Vec<Finding>,
+    /// Links found on each fetched page, keyed by page URL
+    #[serde(skip)]
+    pub outlinks: HashMap<String, Vec<String>>,
+    /// Findings of the page checks on each checked page, keyed by page URL
+    #[serde(skip)]
+    pub page_findings: HashMap<String, Vec<Finding>>,
+    /// Anchors of each checked page, keyed by page URL
+    #[serde(skip)]
+    pub anchors: HashMap<String, HashSet<String>>,
+    /// Pages changed since the previous stored run
+    #[serde(default)]
+    pub changed_pages: Vec<PageChange>,
+    /// Pages of the previous run requested conditionally, and whether they
+    /// changed
+    #[serde(default)]
+    pub revalidated_pages: Vec<RevalidatedPage>,
+    /// Pages not modified within `stale_after`, most linked first
+    #[serde(default)]
+    pub stale_pages: Vec<StalePage>,
+    /// Link health of each configured section
+    #[serde(default)]
+    pub sections: Vec<SectionSummary>,
+    /// Pages listed in the sitemaps that no crawled page links to
+    #[serde(default)]
+    pub orphan_pages: Vec<String>,
+    /// Link counts of the latest stored runs, oldest first, for trend charts
+    #[serde(skip)]
+    pub trend: Vec<TrendPoint>,
+    /// Why the crawl stopped before every queued URL was checked
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub incomplete: Option<String>,
+}
+
+/// Load a report previously written in the JSON or YAML format
+pub fn load_report(path: &str) -> Result<ScanReport, Box<dyn Error>> {
+    let contents = fs::read_to_string(path)?;
+    if path.ends_with(".yaml") || path.ends_with(".yml") {
+        Ok(serde_yaml::from_str(&contents)?)
+    } else {
+        Ok(serde_json::from_str(&contents)?)
+    }
+}
+
+/// Serialized shape of a report in the JSON and YAML outputs
+#[derive(Serialize)]
+struct ReportDocument<'a> {
+    #[serde(skip_serializing_if = "Labels::is_empty")]
+    labels: &'a Labels,
+    scanned_links: &'a [LinkInfo],
+    #[serde(skip_serializing_if = "Option::is_none")]
+    ignored_links: Option<&'a [LinkInfo]>,
+    #[serde(skip_serializing_if = "<[LinkInfo]>::is_empty")]
+    external_links: &'a [LinkInfo],
+    #[serde(skip_serializing_if = "<[AlternateLink]>::is_empty")]
+    alternate_links: &'a [AlternateLink],
+    #[serde(skip_serializing_if = "<[TrackingEndpoint]>::is_empty")]
+    tracking_endpoints: &'a [TrackingEndpoint],
+    #[serde(skip_serializing_if = "<[AgentDifference]>::is_empty")]
+    user_agent_differences: &'a [AgentDifference],
+    #[serde(skip_serializing_if = "<[LanguageVariant]>::is_empty")]
+    language_variants: &'a [LanguageVariant],
+    #[serde(skip_serializing_if = "<[OriginDifference]>::is_empty")]
+    origin_differences: &'a [OriginDifference],
+    #[serde(skip_serializing_if = "<[Finding]>::is_empty")]
+    findings: &'a [Finding],
+    #[serde(skip_serializing_if = "<[PageChange]>::is_empty")]
+    changed_pages: &'a [PageChange],
+    #[serde(skip_serializing_if = "<[RevalidatedPage]>::is_empty")]
+    revalidated_pages: &'a [RevalidatedPage],
+    #[serde(skip_serializing_if = "<[StalePage]>::is_empty")]
+    stale_pages: &'a [StalePage],
+    #[serde(skip_serializing_if = "<[SectionSummary]>::is_empty")]
+    sections: &'a [SectionSummary],
+    #[serde(skip_serializing_if = "<[String]>::is_empty")]
+    orphan_pages: &'a [String],
+    #[serde(skip_serializing_if = "Option::is_none")]
+    incomplete: Option<&'a str>,
+}
+
+impl<'a> ReportDocument<'a> {
+    fn new(report: &'a ScanReport, detailed: bool) -> Self {
+        Self {
+            labels: &report.labels,
+            scanned_links: &report.links,
+            ignored_links: detailed.then_some(report.ignored_links.as_slice()),
+            external_links: &report.external_links,
+            alternate_links: &report.alternate_links,
+            tracking_endpoints: &report.tracking_endpoints,
+            user_agent_differences: &report.user_agent_differences,
+            language_variants: &report.language_variants,
+            origin_differences: &report.origin_differences,
+            findings: &report.findings,
+            changed_pages: &report.changed_pages,
+            revalidated_pages: &report.revalidated_pages,
+            stale_pages: &report.stale_pages,
+            sections: &report.sections,
+            orphan_pages: &report.orphan_pages,
+            incomplete: report.incomplete.as_deref(),
+        }
+    }
+}
+
+/// Options for a single output destination
+#[derive(Debug, Clone)]
+pub struct OutputOptions {
+    pub file: String,
+    pub detailed: bool,
+    /// Title, logo and links of formats rendered for people (HTML)
+    pub branding: ReportConfig,
+    /// Messages of formats rendered for people, in the configured locale
+    pub messages: Arc<Messages>,
+}
+
+/// A named output format that can render a [`ScanReport`]
+pub trait OutputFormatter {
+    /// Name used to select the format (`--output-format`, `outputs[].format`)
+    fn name(&self) -> &str;
+
+    /// Write the report to the destination described by `options`
+    fn write(&self, report: &ScanReport, options: &OutputOptions) -> Result<(), Box<dyn Error>>;
+
+    /// Whether the format writes to `options.file` (listed as a run artifact)
+    fn writes_file(&self) -> bool {
+        true
+    }
+}
+
+/// Registry of output formatters, looked up by name
+#[derive(Default)]
+pub struct OutputRegistry {
+    formatters: Vec<Box<dyn OutputFormatter>>,
+}
+
+impl OutputRegistry {
+    /// Create an empty registry
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a registry with the built-in JSON, NDJSON, YAML, TXT, static
+    /// site, SARIF, SQLite and clipboard formats
+    pub fn with_builtin() -> Self {
+        let mut registry = Self::new();
+        registry.register(Box::new(JsonFormatter));
+        registry.register(Box::new(NdjsonFormatter));
+        registry.register(Box::new(YamlFormatter));
+        registry.register(Box::new(TxtFormatter));
+        registry.register(Box::new(SiteFormatter));
+        registry.register(Box::new(SarifFormatter));
+        registry.register(Box::new(SqliteFormatter));
+        registry.register(Box::new(ClipboardFormatter));
+        registry
+    }
+
+    /// Register a formatter, replacing any existing formatter with the same name
+    pub fn register(&mut self, formatter: Box<dyn OutputFormatter>) {
+        self.formatters
+            .retain(|existing| existing.name() != formatter.name());
+        self.formatters.push(formatter);
+    }
+
+    /// Look up a formatter by name
+    pub fn get(&self, name: &str) -> Option<&dyn OutputFormatter> {
+        self.formatters
+            .iter()
+            .find(|formatter| formatter.name() == name)
+            .map(|formatter| formatter.as_ref())
+    }
+
+    /// Names of all registered formats, in registration order
+    pub fn names(&self) -> Vec<&str> {
+        self.formatters
+            .iter()
+            .map(|formatter| formatter.name())
+            .collect()
+    }
+}
+
+/// Built-in JSON formatter
+pub struct JsonFormatter;
+
+impl OutputFormatter for JsonFormatter {
+    fn name(&self) -> &str {
+        "json"
+    }
+
+    fn write(&self, report: &ScanReport, options: &OutputOptions) -> Result<(), Box<dyn Error>> {
+        output_json(report, options.detailed, &options.file)
+    }
+}
+
+/// Built-in YAML formatter
+pub struct YamlFormatter;
+
+impl OutputFormatter for YamlFormatter {
+    fn name(&self) -> &str {
+        "yaml"
+    }
+
+    fn write(&self, report: &ScanReport, options: &OutputOptions) -> Result<(), Box<dyn Error>> {
+        output_yaml(report, options.detailed, &options.file)
+    }
+}
+
+/// Built-in plain text formatter
+pub struct TxtFormatter;
+
+impl OutputFormatter for TxtFormatter {
+    fn name(&self) -> &str {
+        "txt"
+    }
+
+    fn write(&self, report: &ScanReport, options: &OutputOptions) -> Result<(), Box<dyn Error>> {
+        output_txt(&report.links, &options.file)
+    }
+}
+
+/// Built-in clipboard formatter
+pub struct ClipboardFormatter;
+
+impl OutputFormatter for ClipboardFormatter {
+    fn name(&self) -> &str {
+        "clipboard"
+    }
+
+    fn write(&self, report: &ScanReport, _options: &OutputOptions) -> Result<(), Box<dyn Error>> {
+        output_clipboard(&report.links)
+    }
+
+    fn writes_file(&self) -> bool {
+        false
+    }
+}
 
 /// Output results in JSON format
 pub fn output_json(
-    links: &[LinkInfo],
-    ignored_links: &[LinkInfo],
+    report: &ScanReport,
     detailed: bool,
     output_file: &str,
 ) -> Result<(), Box<dyn Error>> {
-    let mut output = HashMap::new();
-    output.insert("scanned_links", links);
-    if detailed {
-        output.insert("ignored_links", ignored_links);
-    }
-    let json = serde_json::to_string_pretty(&output)?;
+    let json = serde_json::to_string_pretty(&ReportDocument::new(report, detailed))?;
     let mut file = File::create(output_file)?;
 
     file.write_all(json.as_bytes())?;
@@ -27,17 +313,11 @@ pub fn output_json(
 
 /// Output results in YAML format
 pub fn output_yaml(
-    links: &[LinkInfo],
-    ignored_links: &[LinkInfo],
+    report: &ScanReport,
     detailed: bool,
     output_file: &str,
 ) -> Result<(), Box<dyn Error>> {
-    let mut output = HashMap::new();
-    output.insert("scanned_links", links);
-    if detailed {
-        output.insert("ignored_links", ignored_links);
-    }
-    let yaml = serde_yaml::to_string(&output)?;
+    let yaml = serde_yaml::to_string(&ReportDocument::new(report, detailed))?;
     let mut file = File::create(output_file)?;
 
     file.write_all(yaml.as_bytes())?;
@@ -0,0 +1,110 @@
+use crate::check::Finding;
+use crate::link::LinkInfo;
+use crate::output::ScanReport;
+use crate::sections::{section_path, within_prefix};
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Owner of a page: the owner of the longest configured path prefix
+/// containing it
+pub fn owner_of(owners: &BTreeMap<String, String>, page: &str) -> Option<String> {
+    let path = section_path(page);
+    owners
+        .iter()
+        .filter(|(prefix, _)| within_prefix(&path, prefix))
+        .max_by_key(|(prefix, _)| prefix.trim_matches('/').len())
+        .map(|(_, owner)| owner.clone())
+}
+
+/// Set the owner of every finding from the page it was reported on
+pub fn assign_owners(owners: &BTreeMap<String, String>, findings: &mut [Finding]) {
+    for finding in findings {
+        finding.owner = owner_of(owners, &finding.page);
+    }
+}
+
+/// The findings and broken links one owner is responsible for
+#[derive(Debug, Clone, Serialize)]
+pub struct OwnerReport {
+    pub owner: String,
+    pub findings: Vec<Finding>,
+    /// Broken links found on the owner's pages
+    pub broken_links: Vec<LinkInfo>,
+}
+
+impl OwnerReport {
+    fn new(owner: &str) -> Self {
+        OwnerReport {
+            owner: owner.to_string(),
+            findings: Vec::new(),
+            broken_links: Vec::new(),
+        }
+    }
+
+    /// File name of the report: the owner with characters unsafe in file
+    /// names replaced, e.g. `#docs` becomes `docs.json`
+    pub fn file_name(&self) -> String {
+        let name: String = self
+            .owner
+            .chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | '@') {
+                    c
+                } else {
+                    '-'
+                }
+            })
+            .collect();
+        format!("{}.json", name.trim_matches('-'))
+    }
+}
+
+/// Split a report by owner. Findings go to the owner assigned to them, and a
+/// broken link goes to the owner of every page it was found on. Owners with
+/// nothing to fix get no report.
+pub fn owner_reports(owners: &BTreeMap<String, String>, report: &ScanReport) -> Vec<OwnerReport> {
+    let mut reports: BTreeMap<String, OwnerReport> = BTreeMap::new();
+    for finding in &report.findings {
+        if let Some(owner) = &finding.owner {
+            reports
+                .entry(owner.clone())
+                .or_insert_with(|| OwnerReport::new(owner))
+                .findings
+                .push(finding.clone());
+        }
+    }
+    for link in report.links.iter().filter(|link| link.is_broken()) {
+        let link_owners: BTreeSet<String> = link
+            .sources
+            .iter()
+            .filter_map(|source| owner_of(owners, source))
+            .collect();
+        for owner in link_owners {
+            reports
+                .entry(owner.clone())
+                .or_insert_with(|| OwnerReport::new(&owner))
+                .broken_links
+                .push(link.clone());
+        }
+    }
+    reports.into_values().collect()
+}
+
+/// Write each owner's report as JSON into `dir`, returning the files written
+pub fn write_owner_reports(
+    dir: &Path,
+    reports: &[OwnerReport],
+) -> Result<Vec<PathBuf>, Box<dyn Error>> {
+    fs::create_dir_all(dir)?;
+    reports
+        .iter()
+        .map(|report| {
+            let path = dir.join(report.file_name());
+            fs::write(&path, serde_json::to_string_pretty(report)?)?;
+            Ok(path)
+        })
+        .collect()
+}
@@ -0,0 +1,73 @@
+use scraper::{Html, Selector};
+
+/// Hosts and paths of domain parking and domain sale services, matched
+/// against the sources of frames, scripts and redirects
+pub const PARKING_SERVICES: [&str; 12] = [
+    "sedoparking.com",
+    "sedo.com/search/details",
+    "parkingcrew.net",
+    "bodis.com",
+    "above.com",
+    "parklogic.com",
+    "dan.com/buy-domain",
+    "afternic.com",
+    "hugedomains.com",
+    "undeveloped.com",
+    "domainmarket.com",
+    "parking-lander",
+];
+
+/// Title phrases of parking and for-sale pages
+pub const PARKING_TITLES: [&str; 7] = [
+    "domain is for sale",
+    "domain may be for sale",
+    "domain for sale",
+    "buy this domain",
+    "this domain is parked",
+    "parked domain",
+    "domain parking",
+];
+
+/// Why a fetched page looks like a parked or for-sale domain, if it does:
+/// a frame, script or meta refresh pointing at a parking service, or a
+/// tell-tale title
+pub fn parking_signal(document: &Html) -> Option<String> {
+    let embeds = Selector::parse("frame[src], iframe[src], script[src]").unwrap();
+    let refresh = Selector::parse("meta[http-equiv][content]").unwrap();
+    let title = Selector::parse("title").unwrap();
+
+    let sources = document
+        .select(&embeds)
+        .filter_map(|element| element.value().attr("src"))
+        .chain(
+            document
+                .select(&refresh)
+                .filter(|element| {
+                    element
+                        .value()
+                        .attr("http-equiv")
+                        .is_some_and(|equiv| equiv.eq_ignore_ascii_case("refresh"))
+                })
+                .filter_map(|element| element.value().attr("content")),
+        );
+    for source in sources {
+        let source = source.to_ascii_lowercase();
+        if let Some(service) = PARKING_SERVICES
+            .iter()
+            .find(|service| source.contains(*service))
+        {
+            return Some(format!("loads {}", service));
+        }
+    }
+
+    let title = document
+        .select(&title)
+        .next()?
+        .text()
+        .collect::<String>()
+        .to_lowercase();
+    PARKING_TITLES
+        .iter()
+        .find(|phrase| title.contains(*phrase))
+        .map(|phrase| format!("title says \"{}\"", phrase))
+}
@@ -0,0 +1,384 @@
+use crate::alternates::AlternateKind;
+use crate::changes::content_hash;
+use crate::check::{CheckRegistry, Finding, Page};
+use crate::config::{CheckConfig, CodeLinkPolicy, Config};
+use crate::link::{Fetcher, LinkInfo};
+use crate::parked::parking_signal;
+use crate::stream::stream_links;
+use scraper::Html;
+use std::collections::BTreeMap;
+use std::io;
+use std::ops::RangeInclusive;
+use std::sync::mpsc::{self, Receiver};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender};
+use tokio::sync::Semaphore;
+use url::Url;
+
+/// Default number of requests in flight at once
+pub const DEFAULT_CONCURRENCY: usize = 32;
+/// Requests in flight accepted for a crawl
+pub const CONCURRENCY_RANGE: RangeInclusive<u64> = 1..=1024;
+/// Default number of threads extracting links from fetched pages
+pub const DEFAULT_PARSE_WORKERS: usize = 2;
+/// Default number of threads running page checks
+pub const DEFAULT_CHECK_WORKERS: usize = 2;
+/// Worker counts accepted for each stage
+pub const WORKERS_RANGE: RangeInclusive<u64> = 1..=256;
+
+/// Size of each stage: requests in flight for the fetch stage, threads for
+/// the others
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StageWorkers {
+    pub concurrency: usize,
+    pub parsers: usize,
+    pub checkers: usize,
+}
+
+impl StageWorkers {
+    /// Worker counts from the config, with the defaults for unset stages
+    pub fn from_config(config: &Config) -> Self {
+        StageWorkers {
+            concurrency: config.concurrency.unwrap_or(DEFAULT_CONCURRENCY),
+            parsers: config.parse_workers.unwrap_or(DEFAULT_PARSE_WORKERS),
+            checkers: config.check_workers.unwrap_or(DEFAULT_CHECK_WORKERS),
+        }
+    }
+}
+
+/// A page the fetch stage retrieved, on its way to the parser stage
+struct FetchedPage {
+    url: String,
+    depth: usize,
+    link_info: LinkInfo,
+    html: String,
+}
+
+/// A page with its links extracted, on its way to the checker stage
+struct ParsedPage {
+    fetched: FetchedPage,
+    links: Vec<String>,
+    code_links: Vec<String>,
+    alternates: Vec<(String, String)>,
+    representations: Vec<(AlternateKind, String)>,
+    anchors: Vec<String>,
+    assets: Vec<String>,
+}
+
+/// A fetched page with its links and findings
+#[derive(Debug)]
+pub struct CheckedPage {
+    pub url: String,
+    pub depth: usize,
+    pub link_info: LinkInfo,
+    /// Links to crawl, including code block links when they are checked
+    pub links: Vec<String>,
+    /// Links found in code blocks and not checked
+    pub code_links: Vec<String>,
+    /// `(hreflang, url)` alternates announced by the page
+    pub alternates: Vec<(String, String)>,
+    /// AMP, print and feed versions announced by the page
+    pub representations: Vec<(AlternateKind, String)>,
+    /// Ids and anchor names defined on the page
+    pub anchors: Vec<String>,
+    /// Assets the page loads, when they are checked
+    pub assets: Vec<String>,
+    pub findings: Vec<Finding>,
+    /// Why the page looks like a parked domain, for pages of other hosts
+    pub parked: Option<String>,
+}
+
+/// Result of a URL handed to the pipeline
+#[derive(Debug)]
+pub enum PageEvent {
+    /// The request failed, the page was not a success, or the link is a
+    /// binary that was only probed
+    Failed {
+        url: String,
+        depth: usize,
+        link_info: Box<LinkInfo>,
+    },
+    Checked(Box<CheckedPage>),
+}
+
+/// The CPU-bound work on fetched pages: link extraction and page checks
+pub struct PageProcessor {
+    base_url: String,
+    code_policy: CodeLinkPolicy,
+    checks: CheckRegistry,
+    check_config: Option<BTreeMap<String, CheckConfig>>,
+    needs_dom: bool,
+    /// Whether the assets of pages are collected for checking
+    check_assets: bool,
+}
+
+impl PageProcessor {
+    /// Processor for a crawl from `base_url`, running the checks `config` enables
+    pub fn new(checks: CheckRegistry, config: &Config, base_url: &str) -> Self {
+        let needs_dom = checks.any_enabled(config.checks.as_ref());
+        PageProcessor {
+            base_url: base_url.to_string(),
+            code_policy: config.code_links.unwrap_or_default(),
+            checks,
+            check_config: config.checks.clone(),
+            needs_dom,
+            check_assets: config.check_assets == Some(true),
+        }
+    }
+
+    /// Whether pages are parsed into a DOM for checks
+    pub fn needs_dom(&self) -> bool {
+        self.needs_dom
+    }
+
+    /// Parser stage: links from the HTML token stream
+    fn parse(&self, mut fetched: FetchedPage) -> ParsedPage {
+        fetched.link_info.content_hash = Some(content_hash(&fetched.html));
+        // Relative links resolve against the final URL after redirects
+        let page_url = fetched
+            .link_info
+            .redirected_to
+            .clone()
+            .unwrap_or_else(|| fetched.url.clone());
+        let page_links = stream_links(&fetched.html, &page_url);
+        let (mut links, mut code_links) = (page_links.links, page_links.code_links);
+        match self.code_policy {
+            CodeLinkPolicy::Check => {
+                for url in code_links.drain(..) {
+                    if !links.contains(&url) {
+                        links.push(url);
+                    }
+                }
+            }
+            CodeLinkPolicy::Skip => code_links.clear(),
+            CodeLinkPolicy::Report => {}
+        }
+        ParsedPage {
+            fetched,
+            links,
+            code_links,
+            alternates: page_links.alternates,
+            representations: page_links.representations,
+            anchors: page_links.anchors,
+            assets: if self.check_assets {
+                page_links.assets
+            } else {
+                Vec::new()
+            },
+        }
+    }
+
+    /// Checker stage: page checks, and parking signals on pages of other hosts
+    fn check(&self, parsed: ParsedPage) -> CheckedPage {
+        let ParsedPage {
+            fetched,
+            links,
+            code_links,
+            alternates,
+            representations,
+            anchors,
+            assets,
+        } = parsed;
+        let page_host = host(&fetched.url);
+        let external = page_host.is_none() || page_host != host(&self.base_url);
+        let mut findings = Vec::new();
+        let mut parked = None;
+        if self.needs_dom || external {
+            let document = Html::parse_document(&fetched.html);
+            parked = parking_signal(&document).filter(|_| external);
+            if parked.is_none() && self.needs_dom {
+                let page = Page {
+                    url: &fetched.url,
+                    document: &document,
+                    links: &links,
+                };
+                findings = self.checks.run(&page, self.check_config.as_ref());
+            }
+        }
+        CheckedPage {
+            url: fetched.url,
+            depth: fetched.depth,
+            link_info: fetched.link_info,
+            links,
+            code_links,
+            alternates,
+            representations,
+            anchors,
+            assets,
+            findings,
+            parked,
+        }
+    }
+}
+
+fn host(url: &str) -> Option<String> {
+    Url::parse(url)
+        .ok()
+        .and_then(|url| url.host_str().map(String::from))
+}
+
+/// Crawl stages connected by channels: an async engine requests pages, up
+/// to `concurrency` at a time, parsers extract their links and checkers run
+/// the page checks, each in its own pool. Network I/O keeps going while pages
+/// are parsed, and each stage can be sized to where the time goes.
+pub struct Pipeline {
+    jobs: Option<UnboundedSender<(String, usize)>>,
+    events: Receiver<PageEvent>,
+    in_flight: usize,
+    capacity: usize,
+    workers: Vec<JoinHandle<()>>,
+}
+
+impl Pipeline {
+    /// Start the stages, failing if the async runtime cannot be set up
+    pub fn start(
+        fetcher: Fetcher,
+        processor: PageProcessor,
+        workers: StageWorkers,
+    ) -> io::Result<Self> {
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .thread_name("fetch")
+            .enable_all()
+            .build()?;
+        let processor = Arc::new(processor);
+        let (jobs, job_queue) = unbounded_channel::<(String, usize)>();
+        let (fetched, fetched_queue) = mpsc::channel::<FetchedPage>();
+        let (parsed, parsed_queue) = mpsc::channel::<ParsedPage>();
+        let (event_sender, events) = mpsc::channel();
+
+        let mut handles = Vec::new();
+        let engine = FetchEngine {
+            fetcher: Arc::new(fetcher),
+            concurrency: workers.concurrency,
+            fetched,
+            events: event_sender.clone(),
+        };
+        handles.push(thread::spawn(move || {
+            runtime.block_on(engine.run(job_queue));
+            // The fetcher holds a blocking client, which cannot be dropped
+            // inside the runtime
+            drop(runtime);
+            drop(engine);
+        }));
+
+        let fetched_queue = Arc::new(Mutex::new(fetched_queue));
+        for _ in 0..workers.parsers {
+            let (processor, parsed) = (Arc::clone(&processor), parsed.clone());
+            let queue = Arc::clone(&fetched_queue);
+            handles.push(thread::spawn(move || {
+                while let Some(page) = next_job(&queue) {
+                    let _ = parsed.send(processor.parse(page));
+                }
+            }));
+        }
+        drop(parsed);
+
+        let parsed_queue = Arc::new(Mutex::new(parsed_queue));
+        for _ in 0..workers.checkers {
+            let (processor, events) = (Arc::clone(&processor), event_sender.clone());
+            let queue = Arc::clone(&parsed_queue);
+            handles.push(thread::spawn(move || {
+                while let Some(page) = next_job(&queue) {
+                    let page = processor.check(page);
+                    let _ = events.send(PageEvent::Checked(Box::new(page)));
+                }
+            }));
+        }
+
+        Ok(Pipeline {
+            jobs: Some(jobs),
+            events,
+            in_flight: 0,
+            capacity: workers.concurrency + workers.parsers + workers.checkers,
+            workers: handles,
+        })
+    }
+
+    /// Hand a URL to the fetch stage
+    pub fn submit(&mut self, url: String, depth: usize) {
+        if let Some(jobs) = &self.jobs {
+            if jobs.send((url, depth)).is_ok() {
+                self.in_flight += 1;
+            }
+        }
+    }
+
+    /// Whether more URLs can be submitted without piling up in the queues
+    pub fn has_capacity(&self) -> bool {
+        self.in_flight < self.capacity
+    }
+
+    /// Number of submitted URLs whose result has not been taken yet
+    pub fn in_flight(&self) -> usize {
+        self.in_flight
+    }
+
+    /// Wait for the next result; `None` once every submitted URL has one
+    pub fn recv(&mut self) -> Option<PageEvent> {
+        if self.in_flight == 0 {
+            return None;
+        }
+        let event = self.events.recv().ok()?;
+        self.in_flight -= 1;
+        Some(event)
+    }
+}
+
+impl Drop for Pipeline {
+    fn drop(&mut self) {
+        // Each pool stops once the queue before it is closed and drained
+        self.jobs.take();
+        for worker in self.workers.drain(..) {
+            let _ = worker.join();
+        }
+    }
+}
+
+/// Fetch stage: requests the submitted URLs concurrently, holding a permit
+/// of a semaphore for each request in flight
+struct FetchEngine {
+    fetcher: Arc<Fetcher>,
+    concurrency: usize,
+    fetched: mpsc::Sender<FetchedPage>,
+    events: mpsc::Sender<PageEvent>,
+}
+
+impl FetchEngine {
+    async fn run(&self, mut jobs: UnboundedReceiver<(String, usize)>) {
+        let permits = Arc::new(Semaphore::new(self.concurrency));
+        while let Some((url, depth)) = jobs.recv().await {
+            let Ok(permit) = Arc::clone(&permits).acquire_owned().await else {
+                break;
+            };
+            let fetcher = Arc::clone(&self.fetcher);
+            let (fetched, events) = (self.fetched.clone(), self.events.clone());
+            tokio::spawn(async move {
+                match fetcher.fetch(url.clone()).await {
+                    Ok((link_info, html)) => {
+                        let _ = fetched.send(FetchedPage {
+                            url,
+                            depth,
+                            link_info,
+                            html,
+                        });
+                    }
+                    Err(link_info) => {
+                        let _ = events.send(PageEvent::Failed {
+                            url,
+                            depth,
+                            link_info: Box::new(link_info),
+                        });
+                    }
+                }
+                drop(permit);
+            });
+        }
+        // Let the requests still in flight finish before the parsers stop
+        let _ = permits.acquire_many(self.concurrency as u32).await;
+    }
+}
+
+fn next_job<T>(queue: &Mutex<Receiver<T>>) -> Option<T> {
+    queue.lock().unwrap().recv().ok()
+}
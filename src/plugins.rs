@@ -0,0 +1,156 @@
+//! Page checks loaded from WebAssembly modules, built with the `plugins`
+//! feature.
+//!
+//! A plugin is a single `.wasm` file exporting its `memory`, an
+//! `alloc(len: i32) -> i32` function and a `check(ptr: i32, len: i32) -> i64`
+//! function. For each page, the page is written as JSON
+//! (`{"url": ..., "html": ..., "links": [...]}`) to memory from `alloc` and
+//! handed to `check`, which returns the location of its findings as
+//! `ptr << 32 | len`: a JSON array of `{"severity": "error" | "warning" |
+//! "info", "message": ..., "target": ...}` objects, `target` being optional.
+//!
+//! Plugins run sandboxed: they get no imports, so no file system, network or
+//! clock, a fresh instance per page, and bounded memory and instructions.
+
+use crate::check::{Check, Finding, Page, Severity};
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+use wasmi::{Config, Engine, Linker, Module, Store, StoreLimits, StoreLimitsBuilder};
+
+/// Instructions a plugin may run on one page, roughly
+const PLUGIN_FUEL: u64 = 2_000_000_000;
+/// Linear memory a plugin may grow to
+const PLUGIN_MEMORY: usize = 64 << 20;
+
+/// What a plugin gets to see of a page
+#[derive(Serialize)]
+struct PluginInput<'a> {
+    url: &'a str,
+    html: String,
+    links: &'a [String],
+}
+
+/// A finding as plugins report it
+#[derive(Deserialize)]
+struct PluginFinding {
+    severity: Severity,
+    message: String,
+    #[serde(default)]
+    target: Option<String>,
+}
+
+/// A check implemented by a WebAssembly module, named after its file
+pub struct WasmCheck {
+    id: String,
+    engine: Engine,
+    module: Module,
+}
+
+impl WasmCheck {
+    /// Compile the plugin at `path`, rejecting modules that import anything
+    /// or lack the plugin exports
+    pub fn load(path: &Path) -> Result<Self, Box<dyn Error>> {
+        let id = path
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .ok_or_else(|| format!("{} has no file name", path.display()))?
+            .to_string();
+        let wasm = fs::read(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        let mut config = Config::default();
+        config.consume_fuel(true);
+        let engine = Engine::new(&config);
+        let module =
+            Module::new(&engine, &wasm[..]).map_err(|e| format!("{}: {}", path.display(), e))?;
+        if let Some(import) = module.imports().next() {
+            return Err(format!(
+                "{} imports {}::{}, but plugins get no imports",
+                path.display(),
+                import.module(),
+                import.name()
+            )
+            .into());
+        }
+        for export in ["memory", "alloc", "check"] {
+            if module.get_export(export).is_none() {
+                return Err(format!("{} does not export `{}`", path.display(), export).into());
+            }
+        }
+        Ok(WasmCheck { id, engine, module })
+    }
+
+    /// Run the plugin on a page in a fresh instance
+    fn run(&self, input: &[u8]) -> Result<Vec<PluginFinding>, String> {
+        let limits = StoreLimitsBuilder::new().memory_size(PLUGIN_MEMORY).build();
+        let mut store: Store<StoreLimits> = Store::new(&self.engine, limits);
+        store.limiter(|limits| limits);
+        store.set_fuel(PLUGIN_FUEL).map_err(|e| e.to_string())?;
+        let instance = Linker::new(&self.engine)
+            .instantiate(&mut store, &self.module)
+            .and_then(|instance| instance.start(&mut store))
+            .map_err(|e| e.to_string())?;
+        let memory = instance
+            .get_memory(&store, "memory")
+            .ok_or("`memory` is not a memory")?;
+        let alloc = instance
+            .get_typed_func::<i32, i32>(&store, "alloc")
+            .map_err(|e| e.to_string())?;
+        let check = instance
+            .get_typed_func::<(i32, i32), i64>(&store, "check")
+            .map_err(|e| e.to_string())?;
+
+        let len = i32::try_from(input.len()).map_err(|_| "the page is too large")?;
+        let ptr = alloc.call(&mut store, len).map_err(|e| e.to_string())?;
+        memory
+            .write(&mut store, ptr as u32 as usize, input)
+            .map_err(|e| e.to_string())?;
+        let result = check
+            .call(&mut store, (ptr, len))
+            .map_err(|e| e.to_string())? as u64;
+        let (ptr, len) = ((result >> 32) as usize, (result & 0xffff_ffff) as usize);
+        // The plugin chooses the length, so check it before allocating
+        if ptr.saturating_add(len) > memory.data(&store).len() {
+            return Err("the findings are outside the plugin memory".to_string());
+        }
+        let mut output = vec![0; len];
+        memory
+            .read(&store, ptr, &mut output)
+            .map_err(|e| e.to_string())?;
+        serde_json::from_slice(&output).map_err(|e| format!("invalid findings: {}", e))
+    }
+}
+
+impl Check for WasmCheck {
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    fn check(&self, page: &Page) -> Vec<Finding> {
+        let input = PluginInput {
+            url: page.url,
+            html: page.document.root_element().html(),
+            links: page.links,
+        };
+        let input = serde_json::to_vec(&input).expect("pages serialize");
+        let findings = self.run(&input).unwrap_or_else(|e| {
+            vec![PluginFinding {
+                severity: Severity::Warning,
+                message: format!("Plugin {} failed: {}", self.id, e),
+                target: None,
+            }]
+        });
+        findings
+            .into_iter()
+            .map(|finding| Finding {
+                rule: self.id.clone(),
+                severity: finding.severity,
+                page: page.url.to_string(),
+                message: finding.message,
+                target: finding.target,
+                owner: None,
+                other_pages: Vec::new(),
+            })
+            .collect()
+    }
+}
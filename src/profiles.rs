@@ -0,0 +1,115 @@
+use crate::link::status_label;
+use crate::output::ScanReport;
+use std::collections::BTreeMap;
+use std::path::Path;
+use url::Url;
+
+/// Outcome of scanning the site of one profile
+#[derive(Debug)]
+pub struct ProfileRun<'a> {
+    pub profile: &'a str,
+    pub url: &'a str,
+    /// The report, or why the scan failed
+    pub result: Result<&'a ScanReport, &'a str>,
+}
+
+/// `path` with `-<profile>` added to its file name, before the extension, so
+/// the outputs of several profiles can sit side by side
+pub fn profile_path(path: &str, profile: &str) -> String {
+    let file = Path::new(path);
+    let name = match (
+        file.file_stem().and_then(|stem| stem.to_str()),
+        file.extension().and_then(|extension| extension.to_str()),
+    ) {
+        (Some(stem), Some(extension)) => format!("{}-{}.{}", stem, profile, extension),
+        (Some(stem), None) => format!("{}-{}", stem, profile),
+        _ => return format!("{}-{}", path, profile),
+    };
+    file.with_file_name(name).to_string_lossy().into_owned()
+}
+
+/// Where a link points, comparable across sites: the path and query for
+/// links within the scanned site, the full URL for the others
+fn link_key(link: &str, site: &str) -> String {
+    match (Url::parse(link), Url::parse(site)) {
+        (Ok(link), Ok(site)) if link.host_str() == site.host_str() => match link.query() {
+            Some(query) => format!("{}?{}", link.path(), query),
+            None => link.path().to_string(),
+        },
+        _ => link.to_string(),
+    }
+}
+
+/// Summary comparing the scans of several profiles: the totals of each, then
+/// the links broken in some profiles but not in all of them
+pub fn render_comparison(runs: &[ProfileRun]) -> String {
+    let width = runs
+        .iter()
+        .map(|run| run.profile.len())
+        .max()
+        .unwrap_or(0)
+        .max("profile".len());
+    let mut out = format!(
+        "Profile comparison:\n  {:<width$}  {:>7}  {:>7}  {:>8}  url\n",
+        "profile",
+        "links",
+        "broken",
+        "findings",
+        width = width
+    );
+    let mut broken: BTreeMap<String, Vec<(&str, String)>> = BTreeMap::new();
+    let mut scanned = 0;
+    for run in runs {
+        match run.result {
+            Ok(report) => {
+                scanned += 1;
+                let mut count = 0;
+                for link in report.links.iter().filter(|link| link.is_broken()) {
+                    count += 1;
+                    broken
+                        .entry(link_key(&link.url, run.url))
+                        .or_default()
+                        .push((run.profile, status_label(&link.status)));
+                }
+                out.push_str(&format!(
+                    "  {:<width$}  {:>7}  {:>7}  {:>8}  {}\n",
+                    run.profile,
+                    report.links.len(),
+                    count,
+                    report.findings.len(),
+                    run.url,
+                    width = width
+                ));
+            }
+            Err(error) => out.push_str(&format!(
+                "  {:<width$}  failed: {}\n",
+                run.profile,
+                error,
+                width = width
+            )),
+        }
+    }
+
+    let differing: Vec<_> = broken
+        .into_iter()
+        .filter(|(_, profiles)| profiles.len() < scanned)
+        .collect();
+    if scanned > 1 {
+        if differing.is_empty() {
+            out.push_str("The profiles have the same broken links.\n");
+        } else {
+            out.push_str(&format!(
+                "{} links are broken in some profiles only:\n",
+                differing.len()
+            ));
+            for (link, profiles) in differing {
+                let profiles: Vec<String> = profiles
+                    .iter()
+                    .map(|(profile, status)| format!("{} ({})", profile, status))
+                    .collect();
+                out.push_str(&format!("  {}: {}\n", link, profiles.join(", ")));
+            }
+        }
+    }
+    out
+}
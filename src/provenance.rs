@@ -0,0 +1,223 @@
+use crate::check::DEFAULT_MAX_URL_LENGTH;
+use crate::config::{
+    CodeLinkPolicy, Config, LoadedConfig, DEFAULT_ALLOWED_SCHEMES, DEFAULT_TIMEOUT,
+};
+use crate::dns::DEFAULT_DNS_WORKERS;
+use crate::gate::{FailOn, FailThreshold};
+use crate::local::DEFAULT_FRONTMATTER_KEYS;
+use crate::locale::DEFAULT_LOCALES_DIR;
+use crate::pipeline::{DEFAULT_CHECK_WORKERS, DEFAULT_CONCURRENCY, DEFAULT_PARSE_WORKERS};
+use crate::store::DEFAULT_TREND_RUNS;
+use serde_yaml::{Mapping, Value};
+use std::fmt;
+
+/// Where the effective value of a setting comes from, from the lowest
+/// precedence to the highest: defaults, the config file, `INSPECTOR_*`
+/// environment variables and command-line flags
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Source {
+    Default,
+    File,
+    /// The environment variable that set it
+    Env(String),
+    Flag,
+}
+
+impl fmt::Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Source::Default => write!(f, "default"),
+            Source::File => write!(f, "config file"),
+            Source::Env(var) => write!(f, "env {}", var),
+            Source::Flag => write!(f, "command line"),
+        }
+    }
+}
+
+/// Values used for settings that are not set, as serialized in the config
+pub fn default_settings() -> Vec<(&'static str, Value)> {
+    vec![
+        ("timeout", Value::from(DEFAULT_TIMEOUT)),
+        ("retries", Value::from(0)),
+        ("default_output", Value::from("json")),
+        ("max_url_length", Value::from(DEFAULT_MAX_URL_LENGTH as u64)),
+        ("dns_workers", Value::from(DEFAULT_DNS_WORKERS as u64)),
+        ("concurrency", Value::from(DEFAULT_CONCURRENCY as u64)),
+        ("delay_ms", Value::from(0)),
+        ("parse_workers", Value::from(DEFAULT_PARSE_WORKERS as u64)),
+        ("check_workers", Value::from(DEFAULT_CHECK_WORKERS as u64)),
+        ("allowed_schemes", string_list(DEFAULT_ALLOWED_SCHEMES)),
+        ("frontmatter_keys", string_list(&DEFAULT_FRONTMATTER_KEYS)),
+        (
+            "code_links",
+            serde_yaml::to_value(CodeLinkPolicy::default()).expect("policies serialize"),
+        ),
+        ("block_private_ips", Value::from(false)),
+        ("respect_robots", Value::from(false)),
+        ("check_external", Value::from(false)),
+        ("check_assets", Value::from(false)),
+        ("check_alternates", Value::from(false)),
+        ("revalidate", Value::from(false)),
+        ("fail_on", Value::from(FailOn::default().name())),
+        (
+            "fail_threshold",
+            Value::from(FailThreshold::default().to_string()),
+        ),
+        ("trend_runs", Value::from(DEFAULT_TREND_RUNS as u64)),
+        ("locales_dir", Value::from(DEFAULT_LOCALES_DIR)),
+    ]
+}
+
+fn string_list(values: &[&str]) -> Value {
+    Value::Sequence(values.iter().map(|value| Value::from(*value)).collect())
+}
+
+/// The effective configuration of a run: every setting with its value and
+/// where the value comes from
+#[derive(Debug)]
+pub struct EffectiveConfig {
+    settings: Mapping,
+    sources: Vec<(String, Source)>,
+}
+
+impl EffectiveConfig {
+    /// Trace the settings of `config`, the result of applying the command-line
+    /// flags to `loaded`. Secrets stay redacted as in [`Config::to_yaml`].
+    pub fn new(loaded: &LoadedConfig, config: &Config) -> Result<Self, serde_yaml::Error> {
+        let before = settings(&loaded.config)?;
+        let mut after = settings(config)?;
+        let mut sources = Vec::new();
+        for (key, value) in default_settings() {
+            let key = Value::from(key);
+            if !after.contains_key(&key) {
+                after.insert(key.clone(), value);
+                sources.push((key_name(&key), Source::Default));
+            }
+        }
+        let mut effective = EffectiveConfig {
+            settings: after.clone(),
+            sources,
+        };
+        effective.trace(&mut Vec::new(), &after, Some(&before), &|key: &str| {
+            loaded
+                .env
+                .iter()
+                .find(|setting| setting.key == key)
+                .map(|setting| Source::Env(setting.var.clone()))
+        });
+        Ok(effective)
+    }
+
+    /// Record the source of each leaf setting under `path`: flags changed
+    /// what differs from the loaded config, and the rest comes from the
+    /// environment variable setting its top-level key or else from the file
+    fn trace(
+        &mut self,
+        path: &mut Vec<String>,
+        after: &Mapping,
+        before: Option<&Mapping>,
+        env: &dyn Fn(&str) -> Option<Source>,
+    ) {
+        for (key, value) in after {
+            let name = key_name(key);
+            path.push(name);
+            let dotted = path.join(".");
+            let previous = before.and_then(|before| before.get(key));
+            match value {
+                Value::Mapping(children) if !children.is_empty() => {
+                    self.trace(path, children, previous.and_then(Value::as_mapping), env)
+                }
+                _ if self.source(&dotted).is_some() => {}
+                _ => {
+                    let source = if previous != Some(value) {
+                        Source::Flag
+                    } else if let Some(source) = env(&path[0]) {
+                        source
+                    } else {
+                        Source::File
+                    };
+                    self.sources.push((dotted, source));
+                }
+            }
+            path.pop();
+        }
+    }
+
+    /// Where the setting at a dotted path, such as `ignore.domains`, comes from
+    pub fn source(&self, path: &str) -> Option<&Source> {
+        self.sources
+            .iter()
+            .find(|(setting, _)| setting == path)
+            .map(|(_, source)| source)
+    }
+
+    /// The configuration as YAML, each setting followed by a comment naming
+    /// its source
+    pub fn to_yaml(&self) -> Result<String, serde_yaml::Error> {
+        let mut out = String::new();
+        self.write_mapping(&mut out, &mut Vec::new(), &self.settings)?;
+        Ok(out)
+    }
+
+    fn write_mapping(
+        &self,
+        out: &mut String,
+        path: &mut Vec<String>,
+        mapping: &Mapping,
+    ) -> Result<(), serde_yaml::Error> {
+        let indent = "  ".repeat(path.len());
+        for (key, value) in mapping {
+            path.push(key_name(key));
+            let key = render(key)?;
+            match value {
+                Value::Mapping(children) if !children.is_empty() => {
+                    out.push_str(&format!("{}{}:\n", indent, key));
+                    self.write_mapping(out, path, children)?;
+                }
+                _ => {
+                    let comment = match self.source(&path.join(".")) {
+                        Some(source) => format!("  # {}", source),
+                        None => String::new(),
+                    };
+                    let block = matches!(value, Value::Sequence(items) if !items.is_empty());
+                    let value = render(value)?;
+                    if block || value.contains('\n') {
+                        out.push_str(&format!("{}{}:{}\n", indent, key, comment));
+                        for line in value.lines() {
+                            out.push_str(&format!("{}  {}\n", indent, line));
+                        }
+                    } else {
+                        out.push_str(&format!("{}{}: {}{}\n", indent, key, value, comment));
+                    }
+                }
+            }
+            path.pop();
+        }
+        Ok(())
+    }
+}
+
+/// The settings of a config as a mapping, without unset fields
+fn settings(config: &Config) -> Result<Mapping, serde_yaml::Error> {
+    match serde_yaml::from_str(&config.to_yaml()?)? {
+        Value::Mapping(mapping) => Ok(mapping),
+        _ => Ok(Mapping::new()),
+    }
+}
+
+fn key_name(key: &Value) -> String {
+    match key {
+        Value::String(key) => key.clone(),
+        other => render(other).unwrap_or_default(),
+    }
+}
+
+/// A value as YAML, without the document marker
+fn render(value: &Value) -> Result<String, serde_yaml::Error> {
+    let yaml = serde_yaml::to_string(value)?;
+    Ok(yaml
+        .strip_prefix("---\n")
+        .unwrap_or(&yaml)
+        .trim_end()
+        .to_string())
+}
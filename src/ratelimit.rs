@@ -0,0 +1,208 @@
+use crate::freshness::parse_http_date;
+use log::debug;
+use reqwest::header::{HeaderMap, RETRY_AFTER};
+use reqwest::StatusCode;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use url::Url;
+
+/// Longest pause honored for a single host, so a far-off reset cannot stall a scan
+pub const MAX_RATE_LIMIT_WAIT: Duration = Duration::from_secs(300);
+
+/// Reset values above this are Unix timestamps (GitHub), below it delays in seconds
+const EPOCH_THRESHOLD: u64 = 1_000_000_000;
+
+/// Remaining request budget advertised by a host
+#[derive(Debug, Clone, Copy)]
+struct Budget {
+    remaining: u64,
+    reset_at: Instant,
+}
+
+/// Configured time between two requests to the same host
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct HostDelays {
+    default: Duration,
+    /// Delays of domains, which also apply to their subdomains
+    domains: Vec<(String, Duration)>,
+}
+
+impl HostDelays {
+    pub fn new(default: Duration, domains: impl IntoIterator<Item = (String, Duration)>) -> Self {
+        HostDelays {
+            default,
+            domains: domains
+                .into_iter()
+                .map(|(domain, delay)| (domain.trim_start_matches('.').to_lowercase(), delay))
+                .collect(),
+        }
+    }
+
+    /// Delay for a host: that of the most specific domain it belongs to, or
+    /// the default
+    pub fn for_host(&self, host: &str) -> Duration {
+        let host = host.to_lowercase();
+        self.domains
+            .iter()
+            .filter(|(domain, _)| {
+                host == *domain
+                    || host
+                        .strip_suffix(domain.as_str())
+                        .is_some_and(|rest| rest.ends_with('.'))
+            })
+            .max_by_key(|(domain, _)| domain.len())
+            .map_or(self.default, |(_, delay)| *delay)
+    }
+}
+
+/// Paces requests per host.
+///
+/// Requests to a host are spaced by its configured delay. For hosts that
+/// advertise `X-RateLimit-*` headers, the remaining budget is also spread
+/// evenly over the time left until the reset, and requests wait for the
+/// reset once the budget is exhausted. A `Retry-After` on a 429 or 503
+/// response pauses requests to the host for as long as it asks.
+#[derive(Default)]
+pub struct RateLimiter {
+    delays: HostDelays,
+    budgets: Mutex<HashMap<String, Budget>>,
+    next_request: Mutex<HashMap<String, Instant>>,
+}
+
+impl RateLimiter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Space requests to each host by at least its delay
+    pub fn with_delays(mut self, delays: HostDelays) -> Self {
+        self.delays = delays;
+        self
+    }
+
+    /// Record the rate-limit headers of a response from the URL's host
+    pub fn observe(&self, url: &str, headers: &HeaderMap) {
+        let host = match host_of(url) {
+            Some(host) => host,
+            None => return,
+        };
+        let header = |names: &[&str]| {
+            names
+                .iter()
+                .filter_map(|name| headers.get(*name))
+                .filter_map(|value| value.to_str().ok())
+                .find_map(|value| value.trim().parse::<u64>().ok())
+        };
+        let remaining = header(&["x-ratelimit-remaining", "ratelimit-remaining"]);
+        let reset = header(&["x-ratelimit-reset", "ratelimit-reset"]);
+
+        if let (Some(remaining), Some(reset)) = (remaining, reset) {
+            let budget = Budget {
+                remaining,
+                reset_at: Instant::now() + reset_delay(reset, SystemTime::now()),
+            };
+            debug!("Rate limit for {}: {:?}", host, budget);
+            self.budgets.lock().unwrap().insert(host, budget);
+        }
+    }
+
+    /// Record a response from the URL's host: its rate-limit headers, and the
+    /// `Retry-After` of a 429 or 503 response
+    pub fn observe_response(&self, url: &str, status: StatusCode, headers: &HeaderMap) {
+        self.observe(url, headers);
+        if !matches!(
+            status,
+            StatusCode::TOO_MANY_REQUESTS | StatusCode::SERVICE_UNAVAILABLE
+        ) {
+            return;
+        }
+        let pause = headers
+            .get(RETRY_AFTER)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| retry_after(value, SystemTime::now()));
+        if let (Some(host), Some(pause)) = (host_of(url), pause) {
+            debug!("{} asked to retry after {:?}", host, pause);
+            // An exhausted budget holds requests to the host until it resets
+            let budget = Budget {
+                remaining: 0,
+                reset_at: Instant::now() + pause,
+            };
+            self.budgets.lock().unwrap().insert(host, budget);
+        }
+    }
+
+    /// How long a request to the URL should wait to stay under the host's limit
+    pub fn delay(&self, url: &str) -> Duration {
+        let host = match host_of(url) {
+            Some(host) => host,
+            None => return Duration::ZERO,
+        };
+        let budget = self.budgets.lock().unwrap().get(&host).copied();
+
+        let now = Instant::now();
+        let mut spacing = self.delays.for_host(&host);
+        if let Some(budget) = budget {
+            let until_reset = budget.reset_at.saturating_duration_since(now);
+            if !until_reset.is_zero() {
+                if budget.remaining == 0 {
+                    return until_reset.min(MAX_RATE_LIMIT_WAIT);
+                }
+                // Budgets beyond u32::MAX are clamped rather than truncated
+                let remaining = u32::try_from(budget.remaining).unwrap_or(u32::MAX);
+                spacing = spacing.max(until_reset / remaining);
+            }
+        }
+        if spacing.is_zero() {
+            return Duration::ZERO;
+        }
+        let mut next_request = self.next_request.lock().unwrap();
+        let slot = next_request.get(&host).copied().unwrap_or(now).max(now);
+        next_request.insert(host, slot + spacing);
+        (slot - now).min(MAX_RATE_LIMIT_WAIT)
+    }
+
+    /// Sleep until a request to the URL may be made
+    pub fn wait(&self, url: &str) {
+        let delay = self.delay(url);
+        if !delay.is_zero() {
+            debug!("Waiting {:?} before requesting {}", delay, url);
+            thread::sleep(delay);
+        }
+    }
+}
+
+/// Delay asked for by a `Retry-After` value, given in seconds or as an
+/// HTTP date
+pub fn retry_after(value: &str, now: SystemTime) -> Option<Duration> {
+    let value = value.trim();
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+    let at = parse_http_date(value)?;
+    let now = now
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or(0);
+    Some(Duration::from_secs(at.saturating_sub(now)))
+}
+
+/// Time left until a reset given either as a Unix timestamp or as seconds
+fn reset_delay(reset: u64, now: SystemTime) -> Duration {
+    if reset > EPOCH_THRESHOLD {
+        let now = now
+            .duration_since(UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_secs())
+            .unwrap_or(0);
+        Duration::from_secs(reset.saturating_sub(now))
+    } else {
+        Duration::from_secs(reset)
+    }
+}
+
+fn host_of(url: &str) -> Option<String> {
+    Url::parse(url)
+        .ok()
+        .and_then(|url| url.host_str().map(String::from))
+}
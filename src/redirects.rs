@@ -0,0 +1,244 @@
+use crate::output::ScanReport;
+use reqwest::StatusCode;
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
+use std::fmt::Write;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use url::Url;
+
+/// Redirects a chain is followed through before giving up, like reqwest
+const MAX_REDIRECTS: usize = 10;
+
+/// Server formats a redirect map can be emitted in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RedirectFormat {
+    /// `location` blocks for an nginx server config
+    Nginx,
+    /// Netlify `_redirects` file
+    Netlify,
+    /// Cloudflare bulk redirects CSV
+    Cloudflare,
+}
+
+impl RedirectFormat {
+    /// Names accepted on the command line
+    pub const NAMES: [&'static str; 3] = ["nginx", "netlify", "cloudflare"];
+
+    /// Parse a format name
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "nginx" => Some(RedirectFormat::Nginx),
+            "netlify" => Some(RedirectFormat::Netlify),
+            "cloudflare" => Some(RedirectFormat::Cloudflare),
+            _ => None,
+        }
+    }
+}
+
+/// A URL that moved, as observed during a crawl
+#[derive(Debug, Clone, PartialEq)]
+pub struct Redirect {
+    pub from: String,
+    pub to: String,
+}
+
+/// Collect the redirects followed during a crawl, deduplicated and sorted
+pub fn collect_redirects(report: &ScanReport) -> Vec<Redirect> {
+    let mut redirects: Vec<Redirect> = report
+        .links
+        .iter()
+        .filter(|link| !link.url.contains('#'))
+        .filter_map(|link| {
+            link.redirected_to.as_ref().map(|to| Redirect {
+                from: link.url.clone(),
+                to: to.clone(),
+            })
+        })
+        .collect();
+    redirects.sort_by(|a, b| a.from.cmp(&b.from));
+    redirects.dedup_by(|a, b| a.from == b.from);
+    redirects
+}
+
+/// Source path of a redirect, and its target relative to the source origin when possible
+fn relative_pair(redirect: &Redirect) -> (String, String) {
+    let from = Url::parse(&redirect.from).ok();
+    let to = Url::parse(&redirect.to).ok();
+    let source = from
+        .as_ref()
+        .map(path_and_query)
+        .unwrap_or_else(|| redirect.from.clone());
+    let target = match (&from, &to) {
+        (Some(from), Some(to)) if from.origin() == to.origin() => path_and_query(to),
+        _ => redirect.to.clone(),
+    };
+    (source, target)
+}
+
+fn path_and_query(url: &Url) -> String {
+    match url.query() {
+        Some(query) => format!("{}?{}", url.path(), query),
+        None => url.path().to_string(),
+    }
+}
+
+/// Render redirects as a permanent (301) redirect map in the given format
+pub fn render_redirects(redirects: &[Redirect], format: RedirectFormat) -> String {
+    let mut output = String::new();
+    match format {
+        RedirectFormat::Nginx => {
+            for redirect in redirects {
+                let (source, target) = relative_pair(redirect);
+                let _ = writeln!(output, "location = {} {{ return 301 {}; }}", source, target);
+            }
+        }
+        RedirectFormat::Netlify => {
+            for redirect in redirects {
+                let (source, target) = relative_pair(redirect);
+                let _ = writeln!(output, "{}  {}  301", source, target);
+            }
+        }
+        RedirectFormat::Cloudflare => {
+            output.push_str("source_url,target_url,status_code\n");
+            for redirect in redirects {
+                let _ = writeln!(output, "{},{},301", redirect.from, redirect.to);
+            }
+        }
+    }
+    output
+}
+
+/// Redirect responses seen by a client, recorded by its redirect policy (see
+/// [`crate::scope::guarded_redirects`]) so permanent moves can be told from
+/// temporary ones once the client has followed them
+#[derive(Debug, Clone, Default)]
+pub struct RedirectLog {
+    hops: Arc<Mutex<HashMap<String, (StatusCode, String)>>>,
+}
+
+impl RedirectLog {
+    /// Record that `from` answered `status`, redirecting to `to`
+    pub fn record(&self, from: &Url, status: StatusCode, to: &Url) {
+        self.hops
+            .lock()
+            .unwrap()
+            .insert(from.to_string(), (status, to.to_string()));
+    }
+
+    /// Whether every hop from `url` to `final_url` was a permanent redirect
+    /// (301 or 308)
+    pub fn is_permanent(&self, url: &str, final_url: &str) -> bool {
+        let Ok(mut current) = Url::parse(url).map(String::from) else {
+            return false;
+        };
+        let hops = self.hops.lock().unwrap();
+        for _ in 0..MAX_REDIRECTS {
+            match hops.get(&current) {
+                Some((status, next))
+                    if matches!(
+                        *status,
+                        StatusCode::MOVED_PERMANENTLY | StatusCode::PERMANENT_REDIRECT
+                    ) =>
+                {
+                    if next == final_url {
+                        return true;
+                    }
+                    current = next.clone();
+                }
+                _ => return false,
+            }
+        }
+        false
+    }
+}
+
+/// Internal links of a crawl that permanently redirect, as old and new URLs
+/// to replace in the sources of the site. Temporary redirects are left out,
+/// as their targets are not meant to be linked to.
+pub fn canonical_rewrites(report: &ScanReport, base_url: &str) -> Vec<Redirect> {
+    let host = |url: &str| Url::parse(url).ok()?.host_str().map(String::from);
+    let base_host = host(base_url);
+    let permanent: HashSet<&str> = report
+        .links
+        .iter()
+        .filter(|link| link.permanent_redirect)
+        .map(|link| link.url.as_str())
+        .collect();
+    collect_redirects(report)
+        .into_iter()
+        .filter(|redirect| base_host.is_some() && host(&redirect.from) == base_host)
+        .filter(|redirect| permanent.contains(redirect.from.as_str()))
+        .collect()
+}
+
+/// Write rewrites as an `old_url,new_url` CSV table
+pub fn write_rewrite_map(path: &Path, rewrites: &[Redirect]) -> Result<(), Box<dyn Error>> {
+    let mut writer = csv::Writer::from_path(path)?;
+    writer.write_record(["old_url", "new_url"])?;
+    for rewrite in rewrites {
+        writer.write_record([&rewrite.from, &rewrite.to])?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+/// Link targets to replace, read from an `old_url,new_url` table as written
+/// by [`write_rewrite_map`]
+#[derive(Debug, Clone, Default)]
+pub struct RewriteMap {
+    targets: HashMap<String, String>,
+}
+
+impl RewriteMap {
+    pub fn new(rewrites: &[Redirect]) -> Self {
+        let mut targets = HashMap::new();
+        for rewrite in rewrites {
+            targets.insert(rewrite.from.clone(), rewrite.to.clone());
+            // Sources often link to pages of their own site by path
+            let (source, target) = relative_pair(rewrite);
+            if source.starts_with('/') {
+                targets.insert(source, target);
+            }
+        }
+        RewriteMap { targets }
+    }
+
+    pub fn load(path: &Path) -> Result<Self, Box<dyn Error>> {
+        let mut reader = csv::Reader::from_path(path)?;
+        let rewrites = reader
+            .records()
+            .map(|record| {
+                let record = record?;
+                match (record.get(0), record.get(1)) {
+                    (Some(from), Some(to)) => Ok(Redirect {
+                        from: from.to_string(),
+                        to: to.to_string(),
+                    }),
+                    _ => Err(format!("{}: rows need an old and a new URL", path.display()).into()),
+                }
+            })
+            .collect::<Result<Vec<_>, Box<dyn Error>>>()?;
+        Ok(RewriteMap::new(&rewrites))
+    }
+
+    /// Replacement of a link target, as a URL or a path of the site, keeping
+    /// its fragment
+    pub fn rewrite(&self, target: &str) -> Option<String> {
+        let (base, fragment) = match target.split_once('#') {
+            Some((base, fragment)) => (base, Some(fragment)),
+            None => (target, None),
+        };
+        let replacement = self.targets.get(base)?;
+        Some(match fragment {
+            Some(fragment) if !replacement.contains('#') => {
+                format!("{}#{}", replacement, fragment)
+            }
+            _ => replacement.clone(),
+        })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.targets.is_empty()
+    }
+}
@@ -0,0 +1,219 @@
+use crate::check::{Check, Finding, Page, Severity};
+use crate::link::USER_AGENT;
+use crate::local::percent_decode;
+use log::debug;
+use regex::Regex;
+use reqwest::blocking::Client;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+use url::Url;
+
+const DOI_API_URL: &str = "https://doi.org/api/handles";
+const ARXIV_API_URL: &str = "https://export.arxiv.org/api/query";
+/// Time allowed for one API request
+const API_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// A link to a paper through a persistent identifier, such as
+/// `https://doi.org/10.1038/nature12373` or `https://arxiv.org/abs/2101.00001`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Reference {
+    Doi(String),
+    /// arXiv identifier, with its version if the link has one
+    Arxiv(String),
+}
+
+impl Reference {
+    /// Parse a `doi.org` link, or an arXiv `abs` or `pdf` link. The identifier
+    /// is returned as written, valid or not.
+    pub fn parse(url: &str) -> Option<Self> {
+        let url = Url::parse(url).ok()?;
+        let host = url.host_str()?.to_ascii_lowercase();
+        let path = percent_decode(url.path().trim_start_matches('/'));
+        match host.as_str() {
+            "doi.org" | "dx.doi.org" | "www.doi.org" if !path.is_empty() => {
+                Some(Reference::Doi(path))
+            }
+            "arxiv.org" | "www.arxiv.org" | "export.arxiv.org" => {
+                let id = path
+                    .strip_prefix("abs/")
+                    .or_else(|| path.strip_prefix("pdf/"))?;
+                let id = id.strip_suffix(".pdf").unwrap_or(id);
+                (!id.is_empty()).then(|| Reference::Arxiv(id.to_string()))
+            }
+            _ => None,
+        }
+    }
+
+    /// The identifier, as written in the link
+    pub fn id(&self) -> &str {
+        match self {
+            Reference::Doi(id) | Reference::Arxiv(id) => id,
+        }
+    }
+
+    /// Whether the identifier is well-formed: `10.<registrant>/<suffix>` for
+    /// DOIs, `YYMM.NNNNN` or the older `archive/YYMMNNN` for arXiv, with an
+    /// optional version
+    pub fn is_well_formed(&self) -> bool {
+        match self {
+            Reference::Doi(id) => Regex::new(r"^10\.\d{4,9}(\.\d+)*/\S+$")
+                .unwrap()
+                .is_match(id),
+            Reference::Arxiv(id) => {
+                let modern = Regex::new(r"^\d{2}(0[1-9]|1[0-2])\.\d{4,5}(v\d+)?$").unwrap();
+                let legacy =
+                    Regex::new(r"^[a-z-]+(\.[A-Z]{2})?/\d{2}(0[1-9]|1[0-2])\d{3}(v\d+)?$").unwrap();
+                modern.is_match(id) || legacy.is_match(id)
+            }
+        }
+    }
+
+    fn kind(&self) -> &str {
+        match self {
+            Reference::Doi(_) => "DOI",
+            Reference::Arxiv(_) => "arXiv identifier",
+        }
+    }
+
+    /// URL of the resolver API entry for the identifier
+    pub fn api_url(&self) -> String {
+        let mut url = match self {
+            Reference::Doi(_) => Url::parse(DOI_API_URL),
+            Reference::Arxiv(_) => Url::parse(ARXIV_API_URL),
+        }
+        .expect("API URLs are valid");
+        match self {
+            Reference::Doi(id) => {
+                url.path_segments_mut()
+                    .expect("API URLs have a path")
+                    .extend(id.split('/'));
+            }
+            Reference::Arxiv(id) => {
+                url.query_pairs_mut().append_pair("id_list", id);
+            }
+        }
+        url.to_string()
+    }
+}
+
+/// Whether an arXiv API feed lists an article: unknown identifiers come back
+/// as an empty feed or as an entry describing the error
+pub fn arxiv_feed_has_entry(feed: &str) -> bool {
+    feed.contains("<entry>") && !feed.contains("arxiv.org/api/errors")
+}
+
+/// What the resolver says about an identifier
+#[derive(Debug, Clone)]
+enum Lookup {
+    Found,
+    Missing,
+    /// The identifier could not be looked up, e.g. because of rate limits
+    Unknown(String),
+}
+
+/// Verifies DOI and arXiv links through the APIs of their resolvers, which
+/// answer where the sites themselves often rate-limit crawlers or require
+/// JavaScript. Malformed identifiers are reported without a request, and each
+/// identifier is looked up once per scan.
+#[derive(Default)]
+pub struct ReferenceCheck {
+    client: OnceLock<Result<Client, String>>,
+    identifiers: Mutex<HashMap<String, Lookup>>,
+}
+
+impl ReferenceCheck {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn lookup(&self, reference: &Reference) -> Lookup {
+        let api_url = reference.api_url();
+        if let Some(lookup) = self.identifiers.lock().unwrap().get(&api_url) {
+            return lookup.clone();
+        }
+        let lookup = self.fetch(reference, &api_url);
+        self.identifiers
+            .lock()
+            .unwrap()
+            .insert(api_url, lookup.clone());
+        lookup
+    }
+
+    fn fetch(&self, reference: &Reference, api_url: &str) -> Lookup {
+        let client = self.client.get_or_init(|| {
+            Client::builder()
+                .timeout(API_TIMEOUT)
+                .user_agent(USER_AGENT)
+                .build()
+                .map_err(|e| e.to_string())
+        });
+        let client = match client {
+            Ok(client) => client,
+            Err(e) => return Lookup::Unknown(e.clone()),
+        };
+        debug!("Looking up {}", api_url);
+        let response = match client.get(api_url).send() {
+            Ok(response) => response,
+            Err(e) => return Lookup::Unknown(e.to_string()),
+        };
+        match (reference, response.status().as_u16()) {
+            (Reference::Doi(_), 200) => Lookup::Found,
+            (Reference::Doi(_), 404) => Lookup::Missing,
+            (Reference::Arxiv(_), 200) => match response.text() {
+                Ok(feed) if arxiv_feed_has_entry(&feed) => Lookup::Found,
+                Ok(_) => Lookup::Missing,
+                Err(e) => Lookup::Unknown(e.to_string()),
+            },
+            _ => Lookup::Unknown(response.status().to_string()),
+        }
+    }
+}
+
+impl Check for ReferenceCheck {
+    fn id(&self) -> &str {
+        "references"
+    }
+
+    fn enabled_by_default(&self) -> bool {
+        false
+    }
+
+    fn check(&self, page: &Page) -> Vec<Finding> {
+        let mut reported = HashSet::new();
+        page.links
+            .iter()
+            .filter(|link| reported.insert(link.as_str()))
+            .filter_map(|link| Some((link, Reference::parse(link)?)))
+            .filter_map(|(link, reference)| {
+                let (severity, message) = if !reference.is_well_formed() {
+                    (
+                        Severity::Error,
+                        format!("{} is not a valid {}", reference.id(), reference.kind()),
+                    )
+                } else {
+                    match self.lookup(&reference) {
+                        Lookup::Found => return None,
+                        Lookup::Missing => (
+                            Severity::Error,
+                            format!("{} {} does not exist", reference.kind(), reference.id()),
+                        ),
+                        Lookup::Unknown(reason) => (
+                            Severity::Warning,
+                            format!("Could not look {} up: {}", reference.id(), reason),
+                        ),
+                    }
+                };
+                Some(Finding {
+                    rule: self.id().to_string(),
+                    severity,
+                    page: page.url.to_string(),
+                    message,
+                    target: Some(link.clone()),
+                    owner: None,
+                    other_pages: Vec::new(),
+                })
+            })
+            .collect()
+    }
+}
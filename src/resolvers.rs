@@ -0,0 +1,128 @@
+//! Link checks delegated to external commands, for URLs a plain request
+//! cannot verify, such as internal shorteners or ticket links.
+//!
+//! A resolver runs its command with the URL on stdin and reads one JSON
+//! object from its stdout: `{"status": "valid"}`, `{"status": "not_found"}`,
+//! `{"status": "ignored"}` or `{"status": "error", "message": "..."}`.
+
+use crate::config::ResolverConfig;
+use crate::link::{LinkInfo, LinkStatus};
+use log::debug;
+use regex::Regex;
+use serde::Deserialize;
+use std::error::Error;
+use std::io::{Read, Write};
+use std::process::{Command, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// How often a running command is polled for its exit
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// A compiled `resolvers` entry
+#[derive(Debug, Clone)]
+pub struct Resolver {
+    pattern: Regex,
+    command: Vec<String>,
+}
+
+/// The answer of a resolver command
+#[derive(Debug, Deserialize)]
+struct Verdict {
+    status: String,
+    #[serde(default)]
+    message: Option<String>,
+}
+
+impl Resolver {
+    pub fn from_config(config: &ResolverConfig) -> Result<Self, Box<dyn Error>> {
+        if config.command.is_empty() {
+            return Err("resolvers[].command is empty".into());
+        }
+        Ok(Resolver {
+            pattern: Regex::new(&config.url_regex)?,
+            command: config.command.clone(),
+        })
+    }
+
+    pub fn matches(&self, url: &str) -> bool {
+        self.pattern.is_match(url)
+    }
+
+    /// Check `url` with the command, killing it after `timeout`
+    pub fn resolve(&self, url: &str, timeout: Duration) -> LinkInfo {
+        let status = self
+            .run(url, timeout)
+            .and_then(|output| parse_verdict(&output))
+            .unwrap_or_else(|e| LinkStatus::Error(format!("resolver {}: {}", self.command[0], e)));
+        LinkInfo::new(url, status)
+    }
+
+    fn run(&self, url: &str, timeout: Duration) -> Result<String, String> {
+        debug!("Resolving {} with {}", url, self.command.join(" "));
+        let mut child = Command::new(&self.command[0])
+            .args(&self.command[1..])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| e.to_string())?;
+        let mut stdin = child.stdin.take().expect("stdin is piped");
+        // Commands that do not read their input may have exited already
+        let _ = writeln!(stdin, "{}", url);
+        drop(stdin);
+
+        // Pipes are drained while waiting, so chatty commands cannot block
+        let mut stdout = child.stdout.take().expect("stdout is piped");
+        let mut stderr = child.stderr.take().expect("stderr is piped");
+        let reader = thread::spawn(move || {
+            let mut output = String::new();
+            let _ = stdout.read_to_string(&mut output);
+            output
+        });
+        let errors = thread::spawn(move || {
+            let mut output = String::new();
+            let _ = stderr.read_to_string(&mut output);
+            output
+        });
+
+        let deadline = Instant::now() + timeout;
+        let status = loop {
+            match child.try_wait().map_err(|e| e.to_string())? {
+                Some(status) => break status,
+                None if Instant::now() >= deadline => {
+                    let _ = child.kill();
+                    let _ = child.wait();
+                    return Err(format!("timed out after {:?}", timeout));
+                }
+                None => thread::sleep(POLL_INTERVAL),
+            }
+        };
+        let output = reader.join().unwrap_or_default();
+        let errors = errors.join().unwrap_or_default();
+        if !status.success() {
+            return Err(format!("{}: {}", status, errors.trim()));
+        }
+        Ok(output)
+    }
+}
+
+/// Status of a link from the JSON a resolver printed
+pub fn parse_verdict(output: &str) -> Result<LinkStatus, String> {
+    let verdict: Verdict =
+        serde_json::from_str(output.trim()).map_err(|e| format!("invalid output: {}", e))?;
+    match verdict.status.as_str() {
+        "valid" => Ok(LinkStatus::Valid),
+        "not_found" => Ok(LinkStatus::NotFound),
+        "ignored" => Ok(LinkStatus::Ignored),
+        "error" => Ok(LinkStatus::Error(
+            verdict.message.unwrap_or_else(|| "error".to_string()),
+        )),
+        status => Err(format!("unknown status {:?}", status)),
+    }
+}
+
+/// Compile the `resolvers` entries of a config
+pub fn compile_resolvers(configs: &[ResolverConfig]) -> Result<Vec<Resolver>, Box<dyn Error>> {
+    configs.iter().map(Resolver::from_config).collect()
+}
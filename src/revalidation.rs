@@ -0,0 +1,64 @@
+use crate::link::{LinkInfo, LinkStatus};
+use crate::store::{StoredLink, StoredRun};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// What the server answered a conditional request for a page of the
+/// previous run
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Revalidation {
+    /// 304: the page is unchanged and its stored links were reused
+    NotModified,
+    /// 200: the page was sent again and crawled
+    Modified,
+}
+
+/// A page requested with the validators of the previous run
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RevalidatedPage {
+    pub page: String,
+    pub result: Revalidation,
+}
+
+/// Validators a page was served with, sent back as `If-None-Match` and
+/// `If-Modified-Since`
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Validators {
+    pub etag: Option<String>,
+    /// Unix time of the `Last-Modified` header
+    pub last_modified: Option<u64>,
+}
+
+impl Validators {
+    pub fn of(link: &StoredLink) -> Self {
+        Validators {
+            etag: link.etag.clone(),
+            last_modified: link.last_modified,
+        }
+    }
+}
+
+/// Pages of a stored run that can be revalidated: valid pages served with
+/// a validator
+pub fn revalidation_candidates(run: &StoredRun) -> HashMap<String, StoredLink> {
+    run.links
+        .iter()
+        .filter(|link| link.status == LinkStatus::Valid)
+        .filter(|link| link.etag.is_some() || link.last_modified.is_some())
+        .map(|link| (link.url.clone(), link.clone()))
+        .collect()
+}
+
+/// Pages of a crawl that were revalidated, in crawl order
+pub fn revalidated_pages(links: &[LinkInfo]) -> Vec<RevalidatedPage> {
+    links
+        .iter()
+        .filter_map(|link| {
+            Some(RevalidatedPage {
+                page: link.url.clone(),
+                result: link.revalidation?,
+            })
+        })
+        .collect()
+}
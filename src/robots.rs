@@ -0,0 +1,221 @@
+use log::debug;
+use reqwest::blocking::Client;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use url::Url;
+
+/// Product token matched against the `User-agent` lines of robots.txt files
+pub const ROBOTS_AGENT: &str = "inspector-gadget";
+
+/// An `Allow` or `Disallow` line
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Rule {
+    allow: bool,
+    pattern: String,
+}
+
+/// Rules of a group of robots.txt lines, for the agents it names
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+struct Group {
+    agents: Vec<String>,
+    rules: Vec<Rule>,
+}
+
+/// The rules of a robots.txt file, as specified by RFC 9309
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RobotsRules {
+    groups: Vec<Group>,
+    /// Targets of the `Sitemap` lines, which apply to every agent
+    sitemaps: Vec<String>,
+}
+
+impl RobotsRules {
+    /// Parse a robots.txt file. Lines that are not understood are skipped,
+    /// so an unparsable file allows everything.
+    pub fn parse(text: &str) -> Self {
+        let mut groups: Vec<Group> = Vec::new();
+        let mut sitemaps = Vec::new();
+        // Whether the current group has rules, so the next agent starts a new one
+        let mut in_rules = true;
+        for line in text.lines() {
+            let line = line.split('#').next().unwrap_or_default();
+            let Some((key, value)) = line.split_once(':') else {
+                continue;
+            };
+            let value = value.trim();
+            match key.trim().to_ascii_lowercase().as_str() {
+                "user-agent" => {
+                    if in_rules {
+                        groups.push(Group::default());
+                        in_rules = false;
+                    }
+                    if let Some(group) = groups.last_mut() {
+                        group.agents.push(value.to_ascii_lowercase());
+                    }
+                }
+                key @ ("allow" | "disallow") => {
+                    in_rules = true;
+                    // An empty `Disallow` allows everything
+                    if let (Some(group), false) = (groups.last_mut(), value.is_empty()) {
+                        group.rules.push(Rule {
+                            allow: key == "allow",
+                            pattern: value.to_string(),
+                        });
+                    }
+                }
+                "sitemap" if !value.is_empty() => sitemaps.push(value.to_string()),
+                _ => {}
+            }
+        }
+        RobotsRules { groups, sitemaps }
+    }
+
+    /// Sitemaps listed by the file, as written
+    pub fn sitemaps(&self) -> &[String] {
+        &self.sitemaps
+    }
+
+    /// Whether `agent` may request `path`, the path and query of a URL. The
+    /// groups naming the agent apply, or else the `*` groups; among their
+    /// rules the longest match wins, and `Allow` wins ties.
+    pub fn allows(&self, agent: &str, path: &str) -> bool {
+        if path == "/robots.txt" {
+            return true;
+        }
+        let agent = agent.to_ascii_lowercase();
+        let mut groups: Vec<&Group> = self
+            .groups
+            .iter()
+            .filter(|group| group.agents.contains(&agent))
+            .collect();
+        if groups.is_empty() {
+            groups = self
+                .groups
+                .iter()
+                .filter(|group| group.agents.iter().any(|name| name == "*"))
+                .collect();
+        }
+        groups
+            .iter()
+            .flat_map(|group| &group.rules)
+            .filter(|rule| pattern_matches(&rule.pattern, path))
+            .max_by_key(|rule| (rule.pattern.len(), rule.allow))
+            .is_none_or(|rule| rule.allow)
+    }
+}
+
+/// Whether a rule pattern matches a path: `*` matches any characters, a
+/// trailing `$` anchors the end, and otherwise the pattern is a prefix
+fn pattern_matches(pattern: &str, path: &str) -> bool {
+    let (pattern, anchored) = match pattern.strip_suffix('$') {
+        Some(pattern) => (pattern, true),
+        None => (pattern, false),
+    };
+    let parts: Vec<&str> = pattern.split('*').collect();
+    let Some(mut rest) = path.strip_prefix(parts[0]) else {
+        return false;
+    };
+    for (index, part) in parts.iter().enumerate().skip(1) {
+        if anchored && index == parts.len() - 1 {
+            return rest.ends_with(part);
+        }
+        match rest.find(part) {
+            Some(start) => rest = &rest[start + part.len()..],
+            None => return false,
+        }
+    }
+    !anchored || rest.is_empty()
+}
+
+/// The path and query of a URL, as matched by robots.txt rules
+fn request_path(url: &Url) -> String {
+    match url.query() {
+        Some(query) => format!("{}?{}", url.path(), query),
+        None => url.path().to_string(),
+    }
+}
+
+/// robots.txt rules of the hosts a crawl requests, each fetched once
+#[derive(Debug)]
+pub struct RobotsCache {
+    agent: String,
+    rules: Mutex<HashMap<String, Arc<RobotsRules>>>,
+}
+
+impl RobotsCache {
+    pub fn new(agent: &str) -> Self {
+        RobotsCache {
+            agent: agent.to_string(),
+            rules: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Whether the robots.txt of the URL's origin lets the agent request it,
+    /// fetching the file with `client` on first use. Files that cannot be
+    /// fetched allow everything, so the link is still checked and a host
+    /// that is down is reported as broken.
+    pub fn allows(&self, client: &Client, url: &str) -> bool {
+        let Ok(parsed) = Url::parse(url) else {
+            return true;
+        };
+        match self.rules_for(client, &parsed) {
+            Some(rules) => rules.allows(&self.agent, &request_path(&parsed)),
+            None => true,
+        }
+    }
+
+    /// Absolute URLs of the sitemaps the robots.txt of the URL's origin lists
+    pub fn sitemaps(&self, client: &Client, url: &str) -> Vec<String> {
+        let Ok(parsed) = Url::parse(url) else {
+            return Vec::new();
+        };
+        let Some(rules) = self.rules_for(client, &parsed) else {
+            return Vec::new();
+        };
+        rules
+            .sitemaps()
+            .iter()
+            .filter_map(|sitemap| parsed.join(sitemap).ok())
+            .map(String::from)
+            .collect()
+    }
+
+    /// Rules of the URL's origin, fetched on first use; `None` for schemes
+    /// without robots.txt
+    fn rules_for(&self, client: &Client, url: &Url) -> Option<Arc<RobotsRules>> {
+        if !matches!(url.scheme(), "http" | "https") {
+            return None;
+        }
+        let origin = url.origin().ascii_serialization();
+        let cached = self.rules.lock().unwrap().get(&origin).cloned();
+        Some(match cached {
+            Some(rules) => rules,
+            None => {
+                let rules = Arc::new(fetch_rules(client, &origin));
+                self.rules
+                    .lock()
+                    .unwrap()
+                    .insert(origin, Arc::clone(&rules));
+                rules
+            }
+        })
+    }
+}
+
+/// The rules of `<origin>/robots.txt`, or none when it is missing or fails
+fn fetch_rules(client: &Client, origin: &str) -> RobotsRules {
+    let url = format!("{}/robots.txt", origin);
+    match client.get(&url).send() {
+        Ok(response) if response.status().is_success() => {
+            RobotsRules::parse(&response.text().unwrap_or_default())
+        }
+        Ok(response) => {
+            debug!("No robots.txt rules from {}: {}", url, response.status());
+            RobotsRules::default()
+        }
+        Err(e) => {
+            debug!("Could not fetch {}: {}", url, e);
+            RobotsRules::default()
+        }
+    }
+}
@@ -0,0 +1,218 @@
+use crate::link::{status_label, LinkInfo, LinkStatus};
+use crate::output::{OutputFormatter, OutputOptions, ScanReport};
+use serde::Serialize;
+use std::error::Error;
+use std::fs;
+
+const SARIF_SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+const SARIF_VERSION: &str = "2.1.0";
+
+/// Rules of the broken link statuses, by id and description
+const RULES: [(&str, &str); 5] = [
+    ("NotFound", "The link target answers 404 Not Found"),
+    (
+        "Error",
+        "The request for the link target failed or answered an error status",
+    ),
+    ("DnsError", "The host of the link target does not resolve"),
+    ("Parked", "The link target is a parked or for-sale domain"),
+    (
+        "BrokenAnchor",
+        "The target page has no element with the fragment of the link as id",
+    ),
+];
+
+/// Rule id of a status, named after its variant; `None` for statuses that
+/// are not broken
+fn rule_id(status: &LinkStatus) -> Option<&'static str> {
+    match status {
+        LinkStatus::NotFound => Some("NotFound"),
+        LinkStatus::Error(_) => Some("Error"),
+        LinkStatus::DnsError(_) => Some("DnsError"),
+        LinkStatus::Parked(_) => Some("Parked"),
+        LinkStatus::BrokenAnchor(_) => Some("BrokenAnchor"),
+        LinkStatus::Valid
+        | LinkStatus::Ignored
+        | LinkStatus::UnsupportedScheme(_)
+        | LinkStatus::CodeBlock
+        | LinkStatus::Placeholder
+        | LinkStatus::PrivateAddress(_)
+        | LinkStatus::OutOfScope(_)
+        | LinkStatus::AuthRequired(_)
+        | LinkStatus::Disallowed => None,
+    }
+}
+
+#[derive(Serialize)]
+struct SarifLog {
+    #[serde(rename = "$schema")]
+    schema: &'static str,
+    version: &'static str,
+    runs: Vec<Run>,
+}
+
+#[derive(Serialize)]
+struct Run {
+    tool: Tool,
+    results: Vec<SarifResult>,
+}
+
+#[derive(Serialize)]
+struct Tool {
+    driver: Driver,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Driver {
+    name: &'static str,
+    version: &'static str,
+    information_uri: &'static str,
+    rules: Vec<Rule>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Rule {
+    id: &'static str,
+    short_description: Message,
+    default_configuration: RuleConfiguration,
+}
+
+#[derive(Serialize)]
+struct RuleConfiguration {
+    level: &'static str,
+}
+
+#[derive(Serialize)]
+struct Message {
+    text: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SarifResult {
+    rule_id: &'static str,
+    rule_index: usize,
+    level: &'static str,
+    message: Message,
+    locations: Vec<Location>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Location {
+    physical_location: PhysicalLocation,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct PhysicalLocation {
+    artifact_location: ArtifactLocation,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    region: Option<Region>,
+}
+
+#[derive(Serialize)]
+struct ArtifactLocation {
+    uri: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Region {
+    start_line: usize,
+}
+
+/// Where a link was found: a `file:line` source of local mode becomes a
+/// line of a file, and a page URL the page itself
+fn location(source: &str) -> Location {
+    let line = source
+        .rsplit_once(':')
+        .filter(|_| !source.contains("://"))
+        .and_then(|(file, line)| Some((file, line.parse().ok()?)));
+    let (uri, region) = match line {
+        Some((file, start_line)) => (file.to_string(), Some(Region { start_line })),
+        None => (source.to_string(), None),
+    };
+    Location {
+        physical_location: PhysicalLocation {
+            artifact_location: ArtifactLocation { uri },
+            region,
+        },
+    }
+}
+
+/// A result per broken link, located at the pages or lines using it, or at
+/// the link itself when its sources are unknown
+fn link_result(link: &LinkInfo) -> Option<SarifResult> {
+    let rule_id = rule_id(&link.status)?;
+    let rule_index = RULES.iter().position(|(id, _)| *id == rule_id)?;
+    let mut locations: Vec<Location> = link.sources.iter().map(|source| location(source)).collect();
+    if locations.is_empty() {
+        locations.push(location(&link.url));
+    }
+    Some(SarifResult {
+        rule_id,
+        rule_index,
+        level: "error",
+        message: Message {
+            text: format!(
+                "Broken link to {}: {}",
+                link.url,
+                status_label(&link.status)
+            ),
+        },
+        locations,
+    })
+}
+
+/// Render the broken links of a report as a SARIF 2.1.0 log
+pub fn sarif_log(report: &ScanReport) -> Result<String, serde_json::Error> {
+    let rules = RULES
+        .iter()
+        .map(|(id, description)| Rule {
+            id,
+            short_description: Message {
+                text: description.to_string(),
+            },
+            default_configuration: RuleConfiguration { level: "error" },
+        })
+        .collect();
+    let results = report
+        .links
+        .iter()
+        .chain(&report.external_links)
+        .filter_map(link_result)
+        .collect();
+    let log = SarifLog {
+        schema: SARIF_SCHEMA,
+        version: SARIF_VERSION,
+        runs: vec![Run {
+            tool: Tool {
+                driver: Driver {
+                    name: env!("CARGO_PKG_NAME"),
+                    version: env!("CARGO_PKG_VERSION"),
+                    information_uri: env!("CARGO_PKG_REPOSITORY"),
+                    rules,
+                },
+            },
+            results,
+        }],
+    };
+    serde_json::to_string_pretty(&log)
+}
+
+/// Built-in formatter writing broken links as SARIF, for code scanning
+pub struct SarifFormatter;
+
+impl OutputFormatter for SarifFormatter {
+    fn name(&self) -> &str {
+        "sarif"
+    }
+
+    fn write(&self, report: &ScanReport, options: &OutputOptions) -> Result<(), Box<dyn Error>> {
+        fs::write(&options.file, sarif_log(report)?)?;
+        Ok(())
+    }
+}
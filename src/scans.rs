@@ -0,0 +1,347 @@
+use crate::link::{status_label, LinkInfo};
+use crate::ndjson::LinkStream;
+use crate::output::ScanReport;
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::collections::VecDeque;
+use std::io::{self, Write};
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::Duration;
+
+/// Number of scans the serve API remembers, including finished ones
+pub const SCAN_HISTORY: usize = 50;
+/// Longest an event stream stays silent before a keep-alive comment
+pub const KEEP_ALIVE_INTERVAL: Duration = Duration::from_secs(15);
+
+/// Lifecycle of a scan requested through the serve API
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ScanState {
+    Queued,
+    Running,
+    Finished,
+    Failed,
+}
+
+impl ScanState {
+    /// Whether no more events will follow
+    pub fn is_done(&self) -> bool {
+        matches!(self, ScanState::Finished | ScanState::Failed)
+    }
+}
+
+/// Something that happened during a scan, as streamed to subscribers
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScanEvent {
+    /// SSE event name: `queued`, `started`, `link`, `finding`, `progress`,
+    /// `finished` or `failed`
+    pub kind: &'static str,
+    pub data: Value,
+}
+
+impl ScanEvent {
+    /// The event in the Server-Sent Events wire format, with its index as id
+    pub fn to_sse(&self, id: usize) -> String {
+        format!("id: {}\nevent: {}\ndata: {}\n\n", id, self.kind, self.data)
+    }
+}
+
+#[derive(Debug)]
+struct RecordState {
+    state: ScanState,
+    events: Vec<ScanEvent>,
+    summary: Option<Value>,
+    error: Option<String>,
+    /// Broken links found so far, with their pages once the scan finished
+    broken_links: Vec<Value>,
+}
+
+/// Events and result of one scan, kept after it ends so late subscribers
+/// can replay them
+#[derive(Debug)]
+pub struct ScanRecord {
+    id: String,
+    url: String,
+    state: Mutex<RecordState>,
+    changed: Condvar,
+}
+
+impl ScanRecord {
+    fn new(id: String, url: &str) -> Self {
+        let record = ScanRecord {
+            id,
+            url: url.to_string(),
+            state: Mutex::new(RecordState {
+                state: ScanState::Queued,
+                events: Vec::new(),
+                summary: None,
+                error: None,
+                broken_links: Vec::new(),
+            }),
+            changed: Condvar::new(),
+        };
+        record.push("queued", json!({ "url": url }));
+        record
+    }
+
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    pub fn state(&self) -> ScanState {
+        self.state.lock().unwrap().state
+    }
+
+    /// Append an event and wake the subscribers
+    pub fn push(&self, kind: &'static str, data: Value) {
+        self.state
+            .lock()
+            .unwrap()
+            .events
+            .push(ScanEvent { kind, data });
+        self.changed.notify_all();
+    }
+
+    /// Stream a link result, and keep it if it is broken
+    pub fn push_link(&self, link: &LinkInfo) {
+        if link.is_broken() {
+            self.state
+                .lock()
+                .unwrap()
+                .broken_links
+                .push(broken_link(link));
+        }
+        self.push("link", json!(link));
+    }
+
+    pub fn start(&self) {
+        self.transition(ScanState::Running, "started", json!({ "url": self.url }));
+    }
+
+    /// Mark the scan finished with the totals of its report
+    pub fn finish(&self, report: &ScanReport) {
+        let summary = json!({
+            "links": report.links.len(),
+            "broken": report.links.iter().filter(|link| link.is_broken()).count(),
+            "ignored": report.ignored_links.len(),
+            "findings": report.findings.len(),
+            "incomplete": report.incomplete,
+        });
+        let mut record = self.state.lock().unwrap();
+        record.summary = Some(summary.clone());
+        record.broken_links = report
+            .links
+            .iter()
+            .filter(|link| link.is_broken())
+            .map(broken_link)
+            .collect();
+        drop(record);
+        self.transition(ScanState::Finished, "finished", summary);
+    }
+
+    pub fn fail(&self, error: &str) {
+        self.state.lock().unwrap().error = Some(error.to_string());
+        self.transition(ScanState::Failed, "failed", json!({ "error": error }));
+    }
+
+    fn transition(&self, state: ScanState, kind: &'static str, data: Value) {
+        let mut record = self.state.lock().unwrap();
+        record.state = state;
+        record.events.push(ScanEvent { kind, data });
+        drop(record);
+        self.changed.notify_all();
+    }
+
+    /// Events from index `next` on, waiting up to `timeout` when there are
+    /// none yet. The flag is set once the scan is over and every event has
+    /// been returned.
+    pub fn events_after(&self, next: usize, timeout: Duration) -> (Vec<ScanEvent>, bool) {
+        let record = self.state.lock().unwrap();
+        let (record, _) = self
+            .changed
+            .wait_timeout_while(record, timeout, |record| {
+                record.events.len() <= next && !record.state.is_done()
+            })
+            .unwrap();
+        let events = record.events.get(next..).unwrap_or_default().to_vec();
+        (events, record.state.is_done())
+    }
+
+    /// State of the scan, as listed by `GET /scans`; `detailed` adds the
+    /// broken links, as returned by `GET /scans/{id}`
+    pub fn to_json(&self, detailed: bool) -> Value {
+        let record = self.state.lock().unwrap();
+        let mut scan = json!({
+            "id": self.id,
+            "url": self.url,
+            "state": record.state,
+            "events": record.events.len(),
+            "summary": record.summary,
+            "error": record.error,
+        });
+        if detailed {
+            scan["broken_links"] = json!(record.broken_links);
+        }
+        scan
+    }
+
+    /// Write the events from index `next` on to `out` as a chunked
+    /// `text/event-stream` body, until the scan is over or the subscriber
+    /// goes away. A comment is sent every `keep_alive` without events.
+    pub fn stream_events(
+        &self,
+        mut next: usize,
+        keep_alive: Duration,
+        out: &mut dyn Write,
+    ) -> io::Result<()> {
+        loop {
+            let (events, done) = self.events_after(next, keep_alive);
+            let mut body = String::new();
+            for event in &events {
+                body.push_str(&event.to_sse(next));
+                next += 1;
+            }
+            if body.is_empty() && !done {
+                body.push_str(": keep-alive\n\n");
+            }
+            if !body.is_empty() {
+                write!(out, "{:x}\r\n{}\r\n", body.len(), body)?;
+                out.flush()?;
+            }
+            if done {
+                write!(out, "0\r\n\r\n")?;
+                return out.flush();
+            }
+        }
+    }
+}
+
+/// A broken link as listed by the dashboard
+fn broken_link(link: &LinkInfo) -> Value {
+    json!({
+        "url": link.url,
+        "status": status_label(&link.status),
+        "sources": link.sources,
+    })
+}
+
+/// Scans known to the serve API, newest last
+#[derive(Debug, Default)]
+pub struct ScanRegistry {
+    scans: Mutex<VecDeque<Arc<ScanRecord>>>,
+    next_id: Mutex<u64>,
+}
+
+impl ScanRegistry {
+    /// Record a newly queued scan of `url`. The oldest finished scans are
+    /// forgotten beyond [`SCAN_HISTORY`].
+    pub fn create(&self, url: &str) -> Arc<ScanRecord> {
+        let mut next_id = self.next_id.lock().unwrap();
+        *next_id += 1;
+        let record = Arc::new(ScanRecord::new(next_id.to_string(), url));
+        drop(next_id);
+
+        let mut scans = self.scans.lock().unwrap();
+        scans.push_back(Arc::clone(&record));
+        while scans.len() > SCAN_HISTORY {
+            match scans.iter().position(|scan| scan.state().is_done()) {
+                Some(index) => {
+                    scans.remove(index);
+                }
+                None => break,
+            }
+        }
+        record
+    }
+
+    /// Known scans, newest first
+    pub fn list(&self) -> Vec<Arc<ScanRecord>> {
+        self.scans.lock().unwrap().iter().rev().cloned().collect()
+    }
+
+    pub fn get(&self, id: &str) -> Option<Arc<ScanRecord>> {
+        self.scans
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|scan| scan.id == id)
+            .cloned()
+    }
+}
+
+/// Forwards the results a crawl appends to its report to a scan record and
+/// link streams, so subscribers see links and findings as they are checked
+#[derive(Debug, Default)]
+pub struct ProgressFeed {
+    record: Option<Arc<ScanRecord>>,
+    streams: Vec<Arc<LinkStream>>,
+    links: usize,
+    ignored_links: usize,
+    external_links: usize,
+    findings: usize,
+    broken: usize,
+}
+
+impl ProgressFeed {
+    pub fn new(record: Option<Arc<ScanRecord>>) -> Self {
+        ProgressFeed {
+            record,
+            ..Default::default()
+        }
+    }
+
+    /// The same feed, also writing links to `streams`
+    pub fn streaming_to(self, streams: Vec<Arc<LinkStream>>) -> Self {
+        ProgressFeed { streams, ..self }
+    }
+
+    /// Send the entries appended to `report` since the last update, followed
+    /// by the crawl counters
+    pub fn update(&mut self, report: &ScanReport, crawled: usize, queued: usize) {
+        for stream in &self.streams {
+            stream.push(
+                &report.links[self.links..],
+                &report.ignored_links[self.ignored_links..],
+                &report.external_links[self.external_links..],
+            );
+        }
+        self.external_links = report.external_links.len();
+        let Some(record) = &self.record else {
+            self.links = report.links.len();
+            self.ignored_links = report.ignored_links.len();
+            return;
+        };
+        let mut sent = false;
+        for link in &report.links[self.links..] {
+            self.broken += usize::from(link.is_broken());
+            record.push_link(link);
+            sent = true;
+        }
+        for link in &report.ignored_links[self.ignored_links..] {
+            record.push_link(link);
+            sent = true;
+        }
+        for finding in &report.findings[self.findings..] {
+            record.push("finding", json!(finding));
+            sent = true;
+        }
+        self.links = report.links.len();
+        self.ignored_links = report.ignored_links.len();
+        self.findings = report.findings.len();
+        if sent {
+            record.push(
+                "progress",
+                json!({
+                    "crawled": crawled,
+                    "queued": queued,
+                    "links": self.links,
+                    "broken": self.broken,
+                }),
+            );
+        }
+    }
+}
@@ -0,0 +1,186 @@
+use crate::ndjson::LinkStream;
+use crate::scans::ScanRecord;
+use log::info;
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// How often a paused or blacked-out scan rechecks whether it may continue
+const POLL_INTERVAL: Duration = Duration::from_secs(30);
+
+const WEEKDAYS: [&str; 7] = ["sun", "mon", "tue", "wed", "thu", "fri", "sat"];
+
+/// A recurring time window (UTC) during which no requests are made
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct BlackoutWindow {
+    /// Start time as `HH:MM`
+    pub start: String,
+    /// End time as `HH:MM`; windows ending before they start span midnight
+    pub end: String,
+    /// Weekdays (`mon`..`sun`) the window applies to, every day when unset
+    pub days: Option<Vec<String>>,
+}
+
+/// Parse `HH:MM` into minutes since midnight
+pub fn parse_time_of_day(value: &str) -> Option<u32> {
+    let (hours, minutes) = value.trim().split_once(':')?;
+    let (hours, minutes): (u32, u32) = (hours.parse().ok()?, minutes.parse().ok()?);
+    (hours < 24 && minutes < 60).then_some(hours * 60 + minutes)
+}
+
+/// Whether a weekday name is accepted in `days`
+pub fn is_weekday(day: &str) -> bool {
+    WEEKDAYS
+        .iter()
+        .any(|weekday| day.eq_ignore_ascii_case(weekday))
+}
+
+impl BlackoutWindow {
+    /// Whether the window covers the given instant
+    pub fn contains(&self, at: SystemTime) -> bool {
+        let (Some(start), Some(end)) =
+            (parse_time_of_day(&self.start), parse_time_of_day(&self.end))
+        else {
+            return false;
+        };
+        let seconds = at
+            .duration_since(UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_secs())
+            .unwrap_or(0);
+        let minute = ((seconds % 86_400) / 60) as u32;
+        // 1970-01-01 was a Thursday
+        let weekday = ((seconds / 86_400 + 4) % 7) as usize;
+
+        // A window spanning midnight belongs to the day it started on
+        let (in_window, day) = if start <= end {
+            (minute >= start && minute < end, weekday)
+        } else if minute >= start {
+            (true, weekday)
+        } else {
+            (minute < end, (weekday + 6) % 7)
+        };
+        in_window
+            && self.days.as_ref().is_none_or(|days| {
+                days.iter()
+                    .any(|name| name.eq_ignore_ascii_case(WEEKDAYS[day]))
+            })
+    }
+}
+
+#[derive(Debug, Default)]
+struct ControlState {
+    paused: Mutex<bool>,
+    changed: Condvar,
+}
+
+/// Shared handle to pause and resume an in-flight scan.
+///
+/// The crawler calls [`ScanControl::checkpoint`] before every request, which
+/// blocks while the scan is paused or inside a blackout window. The crawl
+/// state stays in memory, so a resumed scan continues where it stopped.
+/// Scans started through the serve API also report their progress to a
+/// [`ScanRecord`] through it, and scans with NDJSON outputs stream their
+/// links to a [`LinkStream`].
+#[derive(Debug, Clone, Default)]
+pub struct ScanControl {
+    state: Arc<ControlState>,
+    blackouts: Arc<Vec<BlackoutWindow>>,
+    record: Option<Arc<ScanRecord>>,
+    streams: Vec<Arc<LinkStream>>,
+}
+
+impl ScanControl {
+    pub fn new(blackouts: Vec<BlackoutWindow>) -> Self {
+        ScanControl {
+            state: Arc::default(),
+            blackouts: Arc::new(blackouts),
+            record: None,
+            streams: Vec::new(),
+        }
+    }
+
+    /// The same control, with the scan reporting its progress to `record`
+    pub fn reporting_to(&self, record: Arc<ScanRecord>) -> Self {
+        ScanControl {
+            record: Some(record),
+            ..self.clone()
+        }
+    }
+
+    /// Record the scan reports its progress to, if any
+    pub fn record(&self) -> Option<Arc<ScanRecord>> {
+        self.record.clone()
+    }
+
+    /// The same control, with the scan writing links to `streams` as they
+    /// are checked
+    pub fn streaming_to(&self, streams: Vec<Arc<LinkStream>>) -> Self {
+        ScanControl {
+            streams,
+            ..self.clone()
+        }
+    }
+
+    /// NDJSON outputs the scan streams its links to
+    pub fn streams(&self) -> Vec<Arc<LinkStream>> {
+        self.streams.clone()
+    }
+
+    pub fn pause(&self) {
+        *self.state.paused.lock().unwrap() = true;
+        self.state.changed.notify_all();
+    }
+
+    pub fn resume(&self) {
+        *self.state.paused.lock().unwrap() = false;
+        self.state.changed.notify_all();
+    }
+
+    pub fn is_paused(&self) -> bool {
+        *self.state.paused.lock().unwrap()
+    }
+
+    /// Whether a blackout window is currently active
+    pub fn in_blackout(&self) -> bool {
+        let now = SystemTime::now();
+        self.blackouts.iter().any(|window| window.contains(now))
+    }
+
+    /// Block until the scan is neither paused nor inside a blackout window
+    pub fn checkpoint(&self) {
+        let mut announced = false;
+        loop {
+            let paused = self.state.paused.lock().unwrap();
+            if *paused {
+                if !announced {
+                    info!("Scan paused, waiting to be resumed");
+                    announced = true;
+                }
+                let _ = self
+                    .state
+                    .changed
+                    .wait_timeout(paused, POLL_INTERVAL)
+                    .unwrap();
+                continue;
+            }
+            drop(paused);
+
+            if !self.in_blackout() {
+                if announced {
+                    info!("Scan resumed");
+                }
+                return;
+            }
+            if !announced {
+                info!("Inside a blackout window, scan suspended");
+                announced = true;
+            }
+            let paused = self.state.paused.lock().unwrap();
+            let _ = self
+                .state
+                .changed
+                .wait_timeout(paused, POLL_INTERVAL)
+                .unwrap();
+        }
+    }
+}
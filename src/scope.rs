@@ -0,0 +1,308 @@
+use crate::config::Config;
+use crate::link::LinkStatus;
+use crate::redirects::RedirectLog;
+use hyper::client::connect::dns::Name;
+use reqwest::dns::{Addrs, Resolve, Resolving};
+use reqwest::redirect::Policy;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr, ToSocketAddrs};
+use std::str::FromStr;
+use url::{Host, Url};
+
+/// Reserved and example domains that are never fetched. An entry matches the
+/// domain itself and its subdomains; `*.test` style entries match a whole TLD.
+pub const PLACEHOLDER_DOMAINS: [&str; 7] = [
+    "example.com",
+    "example.net",
+    "example.org",
+    "*.example",
+    "*.test",
+    "*.invalid",
+    "localhost",
+];
+
+/// RFC 5737 documentation networks
+const DOCUMENTATION_NETWORKS: [IpNetwork; 3] = [
+    IpNetwork {
+        addr: IpAddr::V4(Ipv4Addr::new(192, 0, 2, 0)),
+        prefix: 24,
+    },
+    IpNetwork {
+        addr: IpAddr::V4(Ipv4Addr::new(198, 51, 100, 0)),
+        prefix: 24,
+    },
+    IpNetwork {
+        addr: IpAddr::V4(Ipv4Addr::new(203, 0, 113, 0)),
+        prefix: 24,
+    },
+];
+
+/// Whether a URL points at a placeholder host: one of [`PLACEHOLDER_DOMAINS`],
+/// the `extra` domains from the config, or an RFC 5737 documentation address
+pub fn is_placeholder(url: &str, extra: &[String]) -> bool {
+    let Ok(url) = Url::parse(url) else {
+        return false;
+    };
+    match url.host() {
+        Some(Host::Domain(domain)) => {
+            let domain = domain.trim_end_matches('.').to_ascii_lowercase();
+            PLACEHOLDER_DOMAINS
+                .iter()
+                .copied()
+                .chain(extra.iter().map(String::as_str))
+                .any(|entry| matches_domain(&domain, entry))
+        }
+        Some(Host::Ipv4(ip)) => DOCUMENTATION_NETWORKS
+            .iter()
+            .any(|network| network.contains(IpAddr::V4(ip))),
+        _ => false,
+    }
+}
+
+fn matches_domain(domain: &str, entry: &str) -> bool {
+    let entry = entry.trim_start_matches("*.").to_ascii_lowercase();
+    domain == entry || domain.ends_with(&format!(".{}", entry))
+}
+
+/// Whether an address is loopback, private (RFC 1918 / unique local),
+/// link-local or unspecified
+pub fn is_private_ip(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            ip.is_loopback() || ip.is_private() || ip.is_link_local() || ip.is_unspecified()
+        }
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(mapped) => is_private_ip(IpAddr::V4(mapped)),
+            None => {
+                let first = ip.segments()[0];
+                ip.is_loopback()
+                    || ip.is_unspecified()
+                    || first & 0xfe00 == 0xfc00
+                    || first & 0xffc0 == 0xfe80
+            }
+        },
+    }
+}
+
+/// First private address the host of a URL resolves to, if any. Hosts that
+/// do not resolve are left to the request to report.
+pub fn private_address(url: &str) -> Option<IpAddr> {
+    let url = Url::parse(url).ok()?;
+    resolve(&url).into_iter().find(|ip| is_private_ip(*ip))
+}
+
+/// Addresses the host of a URL resolves to; IP literals need no lookup
+fn resolve(url: &Url) -> Vec<IpAddr> {
+    let port = url.port_or_known_default().unwrap_or(80);
+    match url.host() {
+        Some(Host::Ipv4(ip)) => vec![IpAddr::V4(ip)],
+        Some(Host::Ipv6(ip)) => vec![IpAddr::V6(ip)],
+        Some(Host::Domain(domain)) => (domain, port)
+            .to_socket_addrs()
+            .map(|addrs| addrs.map(|addr| addr.ip()).collect())
+            .unwrap_or_default(),
+        None => Vec::new(),
+    }
+}
+
+/// An IP range in CIDR notation; a bare address is a single-host range
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct IpNetwork {
+    addr: IpAddr,
+    prefix: u8,
+}
+
+impl FromStr for IpNetwork {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("invalid IP range: {}", value);
+        let (addr, prefix) = match value.split_once('/') {
+            Some((addr, prefix)) => (addr, Some(prefix)),
+            None => (value, None),
+        };
+        let addr: IpAddr = addr.trim().parse().map_err(|_| invalid())?;
+        let max = if addr.is_ipv4() { 32 } else { 128 };
+        let prefix = match prefix {
+            Some(prefix) => prefix.trim().parse().map_err(|_| invalid())?,
+            None => max,
+        };
+        if prefix > max {
+            return Err(invalid());
+        }
+        Ok(IpNetwork { addr, prefix })
+    }
+}
+
+impl IpNetwork {
+    /// Whether the range contains an address of the same family
+    pub fn contains(&self, ip: IpAddr) -> bool {
+        match (self.addr, ip) {
+            (IpAddr::V4(network), IpAddr::V4(ip)) => {
+                let mask = u32::MAX
+                    .checked_shl(32 - u32::from(self.prefix))
+                    .unwrap_or(0);
+                u32::from(ip) & mask == u32::from(network) & mask
+            }
+            (IpAddr::V6(network), IpAddr::V6(ip)) => {
+                let mask = u128::MAX
+                    .checked_shl(128 - u32::from(self.prefix))
+                    .unwrap_or(0);
+                u128::from(ip) & mask == u128::from(network) & mask
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Network destinations a scan may contact: `block_private_ips` and the
+/// `network` allow/deny ranges and port allowlist from the config
+#[derive(Debug, Clone, Default)]
+pub struct NetworkRules {
+    block_private: bool,
+    allow: Vec<IpNetwork>,
+    deny: Vec<IpNetwork>,
+    ports: Option<Vec<u16>>,
+}
+
+impl NetworkRules {
+    pub fn from_config(config: &Config) -> Result<Self, String> {
+        let network = config.network.clone().unwrap_or_default();
+        let parse = |ranges: Option<Vec<String>>| {
+            ranges
+                .unwrap_or_default()
+                .iter()
+                .map(|range| range.parse())
+                .collect::<Result<Vec<IpNetwork>, String>>()
+        };
+        Ok(NetworkRules {
+            block_private: config.block_private_ips.unwrap_or(false),
+            allow: parse(network.allow)?,
+            deny: parse(network.deny)?,
+            ports: network.ports,
+        })
+    }
+
+    /// Whether any destination is restricted
+    pub fn is_restricted(&self) -> bool {
+        self.block_private
+            || !self.allow.is_empty()
+            || !self.deny.is_empty()
+            || self.ports.is_some()
+    }
+
+    /// Status of a URL the rules forbid requesting, or `None` when it may be
+    /// requested. Every address the host resolves to must pass.
+    pub fn check(&self, url: &str) -> Option<LinkStatus> {
+        if !self.is_restricted() {
+            return None;
+        }
+        let url = Url::parse(url).ok()?;
+        if let (Some(ports), Some(port)) = (&self.ports, url.port_or_known_default()) {
+            if !ports.contains(&port) {
+                return Some(LinkStatus::OutOfScope(format!(
+                    "port {} is not allowed",
+                    port
+                )));
+            }
+        }
+        resolve(&url).into_iter().find_map(|ip| self.refusal(ip))
+    }
+
+    /// Status of an address the rules forbid connecting to
+    fn refusal(&self, ip: IpAddr) -> Option<LinkStatus> {
+        if self.block_private && is_private_ip(ip) {
+            return Some(LinkStatus::PrivateAddress(ip.to_string()));
+        }
+        if self.deny.iter().any(|range| range.contains(ip)) {
+            return Some(LinkStatus::OutOfScope(format!(
+                "{} is in a denied range",
+                ip
+            )));
+        }
+        if !self.allow.is_empty() && !self.allow.iter().any(|range| range.contains(ip)) {
+            return Some(LinkStatus::OutOfScope(format!(
+                "{} is outside the allowed ranges",
+                ip
+            )));
+        }
+        None
+    }
+}
+
+/// Why a request was refused, for the error of the request
+fn refusal_reason(status: LinkStatus) -> String {
+    match status {
+        LinkStatus::PrivateAddress(ip) => format!("{} is a private address", ip),
+        LinkStatus::OutOfScope(reason) => reason,
+        other => format!("{:?}", other),
+    }
+}
+
+/// Resolver applying the network rules to the addresses connections are made
+/// to. [`NetworkRules::check`] resolves hosts on its own, so a host answering
+/// a public address there and a private one to the client (DNS rebinding)
+/// would get through; with this resolver on the clients it cannot. Lookups
+/// that fail or answer no address are refused too.
+#[derive(Debug, Clone)]
+pub struct GuardedResolver {
+    rules: NetworkRules,
+}
+
+impl GuardedResolver {
+    pub fn new(rules: NetworkRules) -> Self {
+        GuardedResolver { rules }
+    }
+
+    /// Addresses of `host` when the rules allow every one of them
+    fn lookup(&self, host: &str) -> Result<Vec<SocketAddr>, String> {
+        let addrs: Vec<SocketAddr> = (host, 0)
+            .to_socket_addrs()
+            .map_err(|e| format!("could not resolve {}: {}", host, e))?
+            .collect();
+        if addrs.is_empty() {
+            return Err(format!("{} has no addresses", host));
+        }
+        match addrs.iter().find_map(|addr| self.rules.refusal(addr.ip())) {
+            Some(status) => Err(format!(
+                "connection to {} refused: {}",
+                host,
+                refusal_reason(status)
+            )),
+            None => Ok(addrs),
+        }
+    }
+}
+
+impl Resolve for GuardedResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        let resolver = self.clone();
+        let host = name.as_str().to_string();
+        Box::pin(async move {
+            let addrs = tokio::task::spawn_blocking(move || resolver.lookup(&host)).await??;
+            Ok(Box::new(addrs.into_iter()) as Addrs)
+        })
+    }
+}
+
+/// Redirect policy that records each hop in `log` and refuses redirects the
+/// network rules forbid, following up to 10 redirects otherwise like
+/// reqwest's default
+pub fn guarded_redirects(rules: NetworkRules, log: RedirectLog) -> Policy {
+    Policy::custom(move |attempt| {
+        if let Some(from) = attempt.previous().last() {
+            log.record(from, attempt.status(), attempt.url());
+        }
+        if attempt.previous().len() >= 10 {
+            attempt.error("too many redirects")
+        } else if let Some(status) = rules.check(attempt.url().as_str()) {
+            let message = format!(
+                "redirect to {} refused: {}",
+                attempt.url(),
+                refusal_reason(status)
+            );
+            attempt.error(message)
+        } else {
+            attempt.follow()
+        }
+    })
+}
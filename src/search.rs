@@ -0,0 +1,315 @@
+use crate::check::{Check, Finding, Page, Severity};
+use crate::link::USER_AGENT;
+use log::debug;
+use regex::Regex;
+use reqwest::blocking::Client;
+use scraper::{Html, Selector};
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+use url::Url;
+
+/// Time allowed for one request of the check
+const SEARCH_TIMEOUT: Duration = Duration::from_secs(30);
+/// Keys holding the URL of a document in common search index formats:
+/// lunr and MkDocs (`location`), Hugo (`permalink`), Algolia (`url`)
+const URL_KEYS: [&str; 6] = [
+    "url",
+    "location",
+    "permalink",
+    "relpermalink",
+    "href",
+    "uri",
+];
+
+/// A search index a page loads
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum SearchIndex {
+    /// A JSON file, such as a lunr or MkDocs `search_index.json`
+    Json(String),
+    /// An Algolia index, as configured for DocSearch
+    Algolia {
+        app_id: String,
+        api_key: String,
+        index_name: String,
+    },
+}
+
+impl SearchIndex {
+    /// What findings name the index after; the API key is left out
+    pub fn label(&self) -> String {
+        match self {
+            SearchIndex::Json(url) => url.clone(),
+            SearchIndex::Algolia {
+                app_id, index_name, ..
+            } => format!("Algolia index {} ({})", index_name, app_id),
+        }
+    }
+}
+
+/// Search indexes referenced by the inline scripts of a page: quoted `.json`
+/// paths naming a search or lunr index, the `search/search_index.json` of
+/// MkDocs sites, and DocSearch's `appId`, `apiKey` and `indexName`
+pub fn search_indexes(document: &Html, page_url: &str) -> Vec<SearchIndex> {
+    let Ok(page_url) = Url::parse(page_url) else {
+        return Vec::new();
+    };
+    let scripts = Selector::parse("script:not([src])").unwrap();
+    let generator = Selector::parse("meta[name=generator]").unwrap();
+    let json =
+        Regex::new(r#"["'`]([^"'`\s]*(?i:search|lunr)[^"'`\s/]*\.json)(?:\?[^"'`\s]*)?["'`]"#)
+            .unwrap();
+    let mkdocs_base = Regex::new(r#"(?:base_url\s*=\s*|"base"\s*:\s*)"([^"]*)""#).unwrap();
+    let setting =
+        |name: &str| Regex::new(&format!(r#"\b{}\s*:\s*["']([^"']+)["']"#, name)).unwrap();
+    let (app_id, api_key, index_name) = (setting("appId"), setting("apiKey"), setting("indexName"));
+    let is_mkdocs = document
+        .select(&generator)
+        .filter_map(|meta| meta.value().attr("content"))
+        .any(|content| content.to_ascii_lowercase().contains("mkdocs"));
+
+    let mut indexes = Vec::new();
+    let mut push = |index: SearchIndex| {
+        if !indexes.contains(&index) {
+            indexes.push(index);
+        }
+    };
+    for script in document.select(&scripts) {
+        let text = script.text().collect::<String>();
+        for captures in json.captures_iter(&text) {
+            if let Ok(url) = page_url.join(&captures[1]) {
+                push(SearchIndex::Json(url.to_string()));
+            }
+        }
+        if is_mkdocs {
+            if let Some(base) = mkdocs_base.captures(&text) {
+                let path = match base[1].trim_end_matches('/') {
+                    "" | "." => "search/search_index.json".to_string(),
+                    base => format!("{}/search/search_index.json", base),
+                };
+                if let Ok(url) = page_url.join(&path) {
+                    push(SearchIndex::Json(url.to_string()));
+                }
+            }
+        }
+        let found = (
+            app_id.captures(&text),
+            api_key.captures(&text),
+            index_name.captures(&text),
+        );
+        if let (Some(app_id), Some(api_key), Some(index_name)) = found {
+            push(SearchIndex::Algolia {
+                app_id: app_id[1].to_string(),
+                api_key: api_key[1].to_string(),
+                index_name: index_name[1].to_string(),
+            });
+        }
+    }
+    indexes
+}
+
+/// Document URLs listed by a search index, in index order and without
+/// fragments. Relative URLs are resolved against the directory above the
+/// index, where MkDocs and lunr indexes expect the site root.
+pub fn indexed_urls(index: &Value, index_url: Option<&Url>) -> Vec<String> {
+    fn collect(value: &Value, found: &mut Vec<String>) {
+        match value {
+            Value::Object(object) => {
+                for (key, value) in object {
+                    match value {
+                        Value::String(url)
+                            if URL_KEYS.contains(&key.to_ascii_lowercase().as_str()) =>
+                        {
+                            found.push(url.clone())
+                        }
+                        _ => collect(value, found),
+                    }
+                }
+            }
+            Value::Array(values) => values.iter().for_each(|value| collect(value, found)),
+            _ => {}
+        }
+    }
+    let mut found = Vec::new();
+    collect(index, &mut found);
+    let root = index_url.and_then(|url| url.join("..").ok());
+    let mut seen = HashSet::new();
+    found
+        .into_iter()
+        .filter_map(|url| {
+            let mut url = match &root {
+                Some(root) => root.join(&url).ok()?,
+                None => Url::parse(&url).ok()?,
+            };
+            url.set_fragment(None);
+            Some(url.to_string())
+        })
+        .filter(|url| seen.insert(url.clone()))
+        .collect()
+}
+
+/// Up to `count` items spread evenly over `items`
+pub fn spread_sample<T: Clone>(items: &[T], count: usize) -> Vec<T> {
+    if count == 0 || items.is_empty() {
+        return Vec::new();
+    }
+    if items.len() <= count {
+        return items.to_vec();
+    }
+    (0..count)
+        .map(|index| items[index * items.len() / count].clone())
+        .collect()
+}
+
+/// Verifies that the search indexes pages load exist and parse, so search
+/// does not break silently, and with a sample size that some of the URLs
+/// they list are alive. Each index and URL is requested once per scan.
+#[derive(Default)]
+pub struct SearchIndexCheck {
+    /// Indexed URLs checked per index
+    pub sample: usize,
+    client: OnceLock<Result<Client, String>>,
+    indexes: Mutex<HashMap<SearchIndex, Result<Vec<String>, String>>>,
+    urls: Mutex<HashMap<String, Option<String>>>,
+}
+
+impl SearchIndexCheck {
+    pub fn new(sample: usize) -> Self {
+        SearchIndexCheck {
+            sample,
+            ..Self::default()
+        }
+    }
+
+    fn client(&self) -> Result<&Client, String> {
+        self.client
+            .get_or_init(|| {
+                Client::builder()
+                    .timeout(SEARCH_TIMEOUT)
+                    .user_agent(USER_AGENT)
+                    .build()
+                    .map_err(|e| e.to_string())
+            })
+            .as_ref()
+            .map_err(Clone::clone)
+    }
+
+    /// The sampled URLs of an index, or why it is broken
+    fn lookup(&self, index: &SearchIndex) -> Result<Vec<String>, String> {
+        if let Some(lookup) = self.indexes.lock().unwrap().get(index) {
+            return lookup.clone();
+        }
+        let lookup = self.fetch(index);
+        self.indexes
+            .lock()
+            .unwrap()
+            .insert(index.clone(), lookup.clone());
+        lookup
+    }
+
+    fn fetch(&self, index: &SearchIndex) -> Result<Vec<String>, String> {
+        let client = self.client()?;
+        let (request, index_url) = match index {
+            SearchIndex::Json(url) => (client.get(url), Url::parse(url).ok()),
+            SearchIndex::Algolia {
+                app_id,
+                api_key,
+                index_name,
+            } => {
+                let url = format!(
+                    "https://{}-dsn.algolia.net/1/indexes/{}",
+                    app_id.to_ascii_lowercase(),
+                    index_name
+                );
+                let request = client
+                    .get(url)
+                    .query(&[("hitsPerPage", self.sample.max(1).to_string())])
+                    .header("X-Algolia-Application-Id", app_id)
+                    .header("X-Algolia-API-Key", api_key);
+                (request, None)
+            }
+        };
+        debug!("Fetching search index {}", index.label());
+        let response = request.send().map_err(|e| e.to_string())?;
+        if !response.status().is_success() {
+            return Err(format!("answers {}", response.status()));
+        }
+        let body = response.text().map_err(|e| e.to_string())?;
+        let parsed: Value =
+            serde_json::from_str(&body).map_err(|e| format!("is not valid JSON: {}", e))?;
+        Ok(spread_sample(
+            &indexed_urls(&parsed, index_url.as_ref()),
+            self.sample,
+        ))
+    }
+
+    /// Why an indexed URL is not alive, if it is not
+    fn probe(&self, url: &str) -> Option<String> {
+        if let Some(problem) = self.urls.lock().unwrap().get(url) {
+            return problem.clone();
+        }
+        let problem = match self.client() {
+            Ok(client) => match client.get(url).send() {
+                Ok(response) if response.status().is_success() => None,
+                Ok(response) => Some(response.status().to_string()),
+                Err(e) => Some(e.to_string()),
+            },
+            Err(e) => Some(e),
+        };
+        self.urls
+            .lock()
+            .unwrap()
+            .insert(url.to_string(), problem.clone());
+        problem
+    }
+}
+
+impl Check for SearchIndexCheck {
+    fn id(&self) -> &str {
+        "search-index"
+    }
+
+    fn enabled_by_default(&self) -> bool {
+        false
+    }
+
+    fn check(&self, page: &Page) -> Vec<Finding> {
+        let finding = |severity, message, target: &str| Finding {
+            rule: self.id().to_string(),
+            severity,
+            page: page.url.to_string(),
+            message,
+            target: Some(target.to_string()),
+            owner: None,
+            other_pages: Vec::new(),
+        };
+        let mut findings = Vec::new();
+        for index in search_indexes(page.document, page.url) {
+            let label = index.label();
+            let target = match &index {
+                SearchIndex::Json(url) => url.as_str(),
+                SearchIndex::Algolia { index_name, .. } => index_name.as_str(),
+            };
+            match self.lookup(&index) {
+                Err(problem) => findings.push(finding(
+                    Severity::Error,
+                    format!("Search index {} {}", label, problem),
+                    target,
+                )),
+                Ok(urls) => {
+                    for url in urls {
+                        if let Some(problem) = self.probe(&url) {
+                            findings.push(finding(
+                                Severity::Warning,
+                                format!("Search index {} lists {}: {}", label, url, problem),
+                                &url,
+                            ));
+                        }
+                    }
+                }
+            }
+        }
+        findings
+    }
+}
@@ -0,0 +1,88 @@
+use serde::{Serialize, Serializer};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+
+/// Written in place of a secret value
+pub const REDACTED: &str = "<redacted>";
+
+/// A credential read from the environment or a file. Its value is never
+/// printed or serialized: `Debug` and `Serialize` write [`REDACTED`] instead.
+#[derive(Clone, PartialEq, Eq)]
+pub struct Secret(String);
+
+impl Secret {
+    pub fn new(value: impl Into<String>) -> Self {
+        Secret(value.into())
+    }
+
+    /// The value of the environment variable `name`
+    pub fn from_env(name: &str) -> Result<Self, String> {
+        match std::env::var(name) {
+            Ok(value) if !value.trim().is_empty() => Ok(Secret(value.trim().to_string())),
+            _ => Err(format!("environment variable {} is not set", name)),
+        }
+    }
+
+    /// The content of the file at `path`, such as a mounted Docker or
+    /// Kubernetes secret, without surrounding whitespace
+    pub fn from_file(path: &str) -> Result<Self, String> {
+        let value =
+            fs::read_to_string(path).map_err(|e| format!("could not read {}: {}", path, e))?;
+        if value.trim().is_empty() {
+            return Err(format!("{} is empty", path));
+        }
+        Ok(Secret(value.trim().to_string()))
+    }
+
+    /// The secret value, to put in a request
+    pub fn expose(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Debug for Secret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Secret({})", REDACTED)
+    }
+}
+
+impl Serialize for Secret {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(REDACTED)
+    }
+}
+
+/// Whether a request header usually carries a credential
+pub fn is_sensitive_header(name: &str) -> bool {
+    let name = name.to_ascii_lowercase();
+    matches!(
+        name.as_str(),
+        "authorization" | "proxy-authorization" | "cookie"
+    ) || ["token", "secret", "key", "password", "auth"]
+        .iter()
+        .any(|word| name.contains(word))
+}
+
+/// Request headers with the values of sensitive ones redacted
+pub fn redacted_headers(headers: &BTreeMap<String, String>) -> BTreeMap<&str, &str> {
+    headers
+        .iter()
+        .map(|(name, value)| {
+            let value = if is_sensitive_header(name) {
+                REDACTED
+            } else {
+                value.as_str()
+            };
+            (name.as_str(), value)
+        })
+        .collect()
+}
+
+/// Serialize request headers with the values of sensitive ones redacted
+pub fn serialize_headers<S: Serializer>(
+    headers: &Option<BTreeMap<String, String>>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    headers.as_ref().map(redacted_headers).serialize(serializer)
+}
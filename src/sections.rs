@@ -0,0 +1,100 @@
+use crate::config::SectionConfig;
+use crate::link::LinkInfo;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use url::Url;
+
+/// Link health of one configured site section
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SectionSummary {
+    pub label: String,
+    pub path: String,
+    /// Scanned pages within the section
+    pub pages: usize,
+    /// Distinct links found on the section's pages
+    pub links: usize,
+    /// How many of those links are broken
+    pub broken: usize,
+    /// Share of working links, in percent
+    pub health: f64,
+}
+
+/// Path used to place a page in a section: the path of an http(s) URL, or
+/// the value itself with a leading `/` (local `file.md:12` sources)
+pub(crate) fn section_path(page: &str) -> String {
+    match Url::parse(page) {
+        Ok(url) if matches!(url.scheme(), "http" | "https") => url.path().to_string(),
+        _ => format!("/{}", page.trim_start_matches('/')),
+    }
+}
+
+/// Whether a path from [`section_path`] lies under a configured prefix
+pub(crate) fn within_prefix(path: &str, prefix: &str) -> bool {
+    let prefix = format!("/{}", prefix.trim_matches('/'));
+    path == prefix || path.starts_with(&format!("{}/", prefix.trim_end_matches('/')))
+}
+
+/// Index of the most specific section containing a page
+fn section_of(sections: &[SectionConfig], page: &str) -> Option<usize> {
+    let path = section_path(page);
+    sections
+        .iter()
+        .enumerate()
+        .filter(|(_, section)| within_prefix(&path, &section.path))
+        .max_by_key(|(_, section)| section.path.trim_matches('/').len())
+        .map(|(index, _)| index)
+}
+
+/// Roll the scanned `pages` and their links up per section. A page belongs to
+/// the section with the longest matching path prefix, and a link counts for
+/// every section it was found in.
+pub fn section_rollup(
+    sections: &[SectionConfig],
+    pages: &[String],
+    links: &[LinkInfo],
+) -> Vec<SectionSummary> {
+    let mut page_counts = vec![0; sections.len()];
+    for page in pages {
+        if let Some(index) = section_of(sections, page) {
+            page_counts[index] += 1;
+        }
+    }
+
+    let mut found: Vec<HashSet<&str>> = vec![HashSet::new(); sections.len()];
+    let mut broken = vec![0; sections.len()];
+    for link in links {
+        let containing: HashSet<usize> = link
+            .sources
+            .iter()
+            .filter_map(|source| section_of(sections, source))
+            .collect();
+        for index in containing {
+            if found[index].insert(&link.url) && link.is_broken() {
+                broken[index] += 1;
+            }
+        }
+    }
+
+    sections
+        .iter()
+        .enumerate()
+        .map(|(index, section)| {
+            let links = found[index].len();
+            SectionSummary {
+                label: section
+                    .label
+                    .clone()
+                    .unwrap_or_else(|| section.path.clone()),
+                path: section.path.clone(),
+                pages: page_counts[index],
+                links,
+                broken: broken[index],
+                health: if links == 0 {
+                    100.0
+                } else {
+                    (links - broken[index]) as f64 * 100.0 / links as f64
+                },
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,275 @@
+use crate::link::{normalize_url, status_label};
+use crate::output::ScanReport;
+use log::warn;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fmt;
+use std::path::Path;
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use tiny_http::{Header, Response, Server};
+use url::Url;
+use walkdir::WalkDir;
+
+/// File of a corpus listing the expected results; it is not served
+pub const EXPECTED_FILE: &str = "expected.yaml";
+
+/// The synthetic site bundled with the crate, as (path, content) pairs
+const BUNDLED_SITE: &[(&str, &str)] = &[
+    ("/index.html", include_str!("site/index.html")),
+    ("/guide/index.html", include_str!("site/guide/index.html")),
+    ("/guide/faq.html", include_str!("site/guide/faq.html")),
+];
+const BUNDLED_EXPECTED: &str = include_str!("site/expected.yaml");
+
+/// Results a crawl of a corpus must produce
+#[derive(Debug, Clone, Default, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct Expectations {
+    /// Status label of each link, keyed by path on the served site or by
+    /// absolute URL
+    #[serde(default)]
+    pub links: BTreeMap<String, String>,
+    /// Findings that must be reported
+    #[serde(default)]
+    pub findings: Vec<ExpectedFinding>,
+}
+
+/// A finding of `rule` on `page`, a path on the served site or an absolute URL
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct ExpectedFinding {
+    pub rule: String,
+    pub page: String,
+}
+
+/// A site to serve locally: files by path, and the results expected from
+/// crawling it
+#[derive(Debug, Clone)]
+pub struct Corpus {
+    files: BTreeMap<String, Vec<u8>>,
+    pub expected: Expectations,
+}
+
+impl Corpus {
+    /// The synthetic site bundled with the crate
+    pub fn bundled() -> Self {
+        Corpus {
+            files: BUNDLED_SITE
+                .iter()
+                .map(|(path, content)| (path.to_string(), content.as_bytes().to_vec()))
+                .collect(),
+            expected: serde_yaml::from_str(BUNDLED_EXPECTED).expect("bundled expectations parse"),
+        }
+    }
+
+    /// A recorded site: every file under `dir` is served at its relative
+    /// path, and `expected.yaml` at the root lists the expected results
+    pub fn load(dir: &Path) -> Result<Self, Box<dyn Error>> {
+        let expected_path = dir.join(EXPECTED_FILE);
+        let expected = std::fs::read_to_string(&expected_path)
+            .map_err(|e| format!("Failed to read {}: {}", expected_path.display(), e))?;
+        let expected = serde_yaml::from_str(&expected)
+            .map_err(|e| format!("Invalid {}: {}", expected_path.display(), e))?;
+
+        let mut files = BTreeMap::new();
+        for entry in WalkDir::new(dir) {
+            let entry = entry?;
+            if !entry.file_type().is_file() || entry.path() == expected_path {
+                continue;
+            }
+            let relative = entry.path().strip_prefix(dir)?;
+            let path = relative
+                .components()
+                .map(|component| component.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/");
+            files.insert(format!("/{}", path), std::fs::read(entry.path())?);
+        }
+        Ok(Corpus { files, expected })
+    }
+
+    /// Content served at a request path; directories serve their `index.html`
+    fn lookup(&self, path: &str) -> Option<&[u8]> {
+        let path = path.split(['?', '#']).next().unwrap_or_default();
+        let file = if path.ends_with('/') {
+            format!("{}index.html", path)
+        } else {
+            path.to_string()
+        };
+        self.files
+            .get(&file)
+            .or_else(|| self.files.get(&format!("{}/index.html", path)))
+            .map(Vec::as_slice)
+    }
+}
+
+/// Content type of a served file, from its extension
+fn content_type(path: &str) -> &'static str {
+    let extension = path.rsplit_once('.').map(|(_, extension)| extension);
+    match extension {
+        Some("css") => "text/css",
+        Some("js") => "application/javascript",
+        Some("json") => "application/json",
+        Some("png") => "image/png",
+        Some("jpg" | "jpeg") => "image/jpeg",
+        Some("svg") => "image/svg+xml",
+        Some("pdf") => "application/pdf",
+        Some("txt") => "text/plain; charset=utf-8",
+        _ => "text/html; charset=utf-8",
+    }
+}
+
+/// A corpus served over HTTP on a free localhost port until dropped
+pub struct SimulatedSite {
+    url: String,
+    server: Arc<Server>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl SimulatedSite {
+    /// Serve `corpus`; unknown paths answer 404
+    pub fn serve(corpus: Arc<Corpus>) -> Result<Self, Box<dyn Error>> {
+        let server = Arc::new(Server::http("127.0.0.1:0").map_err(|e| e as Box<dyn Error>)?);
+        let url = format!("http://{}/", server.server_addr());
+        let listener = Arc::clone(&server);
+        let thread = thread::spawn(move || {
+            for request in listener.incoming_requests() {
+                let path = request.url().to_string();
+                let response = match corpus.lookup(&path) {
+                    Some(content) => Response::from_data(content).with_header(
+                        format!("Content-Type: {}", content_type(&path))
+                            .parse::<Header>()
+                            .unwrap(),
+                    ),
+                    None => Response::from_string("not found").with_status_code(404),
+                };
+                if let Err(e) = request.respond(response) {
+                    warn!("Failed to answer simulated request: {}", e);
+                }
+            }
+        });
+        Ok(SimulatedSite {
+            url,
+            server,
+            thread: Some(thread),
+        })
+    }
+
+    /// Root URL of the served site
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+}
+
+impl Drop for SimulatedSite {
+    fn drop(&mut self) {
+        self.server.unblock();
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+/// A difference between a simulated crawl and the expectations
+#[derive(Debug, Clone, PartialEq)]
+pub enum Mismatch {
+    /// A link has another status than expected, or was not found at all
+    Status {
+        url: String,
+        expected: String,
+        actual: Option<String>,
+    },
+    /// A broken link the expectations do not list
+    UnexpectedBroken { url: String, status: String },
+    /// An expected finding was not reported
+    MissingFinding { rule: String, page: String },
+}
+
+impl fmt::Display for Mismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Mismatch::Status {
+                url,
+                expected,
+                actual: Some(actual),
+            } => write!(f, "{}: expected {}, got {}", url, expected, actual),
+            Mismatch::Status {
+                url,
+                expected,
+                actual: None,
+            } => write!(f, "{}: expected {}, but it was not found", url, expected),
+            Mismatch::UnexpectedBroken { url, status } => {
+                write!(f, "{}: unexpectedly broken ({})", url, status)
+            }
+            Mismatch::MissingFinding { rule, page } => {
+                write!(f, "{}: expected a {} finding", page, rule)
+            }
+        }
+    }
+}
+
+/// URL a key of the expectations stands for on the site served at `base`
+fn expected_url(base: &Url, key: &str) -> String {
+    if key.starts_with('/') {
+        base.join(key)
+            .map(String::from)
+            .unwrap_or_else(|_| key.to_string())
+    } else {
+        key.to_string()
+    }
+}
+
+/// Compare the report of a crawl of the site served at `base_url` with the
+/// expectations. Links and findings not listed are not checked, except broken
+/// links, so regressions surface.
+pub fn verify(report: &ScanReport, base_url: &str, expected: &Expectations) -> Vec<Mismatch> {
+    let base = Url::parse(base_url).expect("simulated sites have a valid URL");
+    let statuses: BTreeMap<String, String> = report
+        .links
+        .iter()
+        .chain(&report.ignored_links)
+        .map(|link| (normalize_url(&link.url), status_label(&link.status)))
+        .collect();
+
+    let mut mismatches = Vec::new();
+    let mut listed = Vec::new();
+    for (key, status) in &expected.links {
+        let url = normalize_url(&expected_url(&base, key));
+        let actual = statuses.get(&url);
+        if actual != Some(status) {
+            mismatches.push(Mismatch::Status {
+                url: url.clone(),
+                expected: status.clone(),
+                actual: actual.cloned(),
+            });
+        }
+        listed.push(url);
+    }
+    for link in report.links.iter().filter(|link| link.is_broken()) {
+        let url = normalize_url(&link.url);
+        if !listed.contains(&url) {
+            mismatches.push(Mismatch::UnexpectedBroken {
+                url,
+                status: status_label(&link.status),
+            });
+        }
+    }
+    for finding in &expected.findings {
+        let page = normalize_url(&expected_url(&base, &finding.page));
+        let reported = report.findings.iter().any(|reported| {
+            reported.rule == finding.rule
+                && std::iter::once(&reported.page)
+                    .chain(&reported.other_pages)
+                    .any(|reported| normalize_url(reported) == page)
+        });
+        if !reported {
+            mismatches.push(Mismatch::MissingFinding {
+                rule: finding.rule.clone(),
+                page,
+            });
+        }
+    }
+    mismatches
+}
@@ -0,0 +1,472 @@
+use crate::config::ReportConfig;
+use crate::link::{status_label, LinkInfo, LinkStatus};
+use crate::locale::Messages;
+use crate::output::{OutputFormatter, OutputOptions, ScanReport};
+use crate::store::TrendPoint;
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+use std::time::{Duration, UNIX_EPOCH};
+
+/// Stylesheet shared by every page of the site
+const STYLE: &str = "body { font-family: system-ui, sans-serif; margin: 2rem auto; max-width: 72rem; padding: 0 1rem; color: #1f2328; }
+nav { margin-bottom: 1rem; }
+table { border-collapse: collapse; width: 100%; margin-bottom: 2rem; }
+th, td { border-bottom: 1px solid #d0d7de; padding: 0.4rem 0.6rem; text-align: left; vertical-align: top; }
+td { word-break: break-all; }
+.broken { color: #cf222e; font-weight: 600; }
+.ok { color: #1a7f37; }
+.muted { color: #656d76; }
+.logo { height: 1.5rem; vertical-align: middle; }
+.sparkline { vertical-align: middle; }
+.sparkline polyline { fill: none; stroke: #0969da; stroke-width: 1.5; }
+.sparkline circle { fill: #0969da; }
+";
+
+/// Size of trend sparklines, in pixels
+const SPARKLINE_WIDTH: f64 = 240.0;
+const SPARKLINE_HEIGHT: f64 = 32.0;
+
+/// Built-in formatter rendering the report as a static site in the
+/// directory given as the output file
+pub struct SiteFormatter;
+
+impl OutputFormatter for SiteFormatter {
+    fn name(&self) -> &str {
+        "site"
+    }
+
+    fn write(&self, report: &ScanReport, options: &OutputOptions) -> Result<(), Box<dyn Error>> {
+        write_site(
+            report,
+            &options.branding,
+            &options.messages,
+            Path::new(&options.file),
+        )
+    }
+}
+
+/// Slug of the status page listing links with a given status; its title is
+/// the `status-<slug>` message
+fn status_kind(status: &LinkStatus) -> &'static str {
+    match status {
+        LinkStatus::Valid => "valid",
+        LinkStatus::NotFound => "not-found",
+        LinkStatus::Error(_) => "error",
+        LinkStatus::DnsError(_) => "dns-error",
+        LinkStatus::Parked(_) => "parked",
+        LinkStatus::AuthRequired(_) => "auth-required",
+        LinkStatus::BrokenAnchor(_) => "broken-anchor",
+        LinkStatus::Ignored => "ignored",
+        LinkStatus::UnsupportedScheme(_) => "unsupported-scheme",
+        LinkStatus::CodeBlock => "code-block",
+        LinkStatus::Placeholder => "placeholder",
+        LinkStatus::PrivateAddress(_) => "private-address",
+        LinkStatus::OutOfScope(_) => "out-of-scope",
+        LinkStatus::Disallowed => "disallowed",
+    }
+}
+
+/// Escape text for HTML element content and attribute values
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// File name of a source page's page, stable across runs
+fn page_file(page: &str) -> String {
+    format!(
+        "{}.html",
+        &format!("{:x}", Sha256::digest(page.as_bytes()))[..12]
+    )
+}
+
+/// A URL as a link when it can be opened, or as plain text (local sources)
+fn url_cell(url: &str) -> String {
+    if url.starts_with("http://") || url.starts_with("https://") {
+        format!("<a href=\"{0}\">{0}</a>", escape(url))
+    } else {
+        escape(url)
+    }
+}
+
+fn status_cell(status: &LinkStatus, root: &str) -> String {
+    let slug = status_kind(status);
+    let class = if status.is_broken() { "broken" } else { "ok" };
+    format!(
+        "<a class=\"{}\" href=\"{}status/{}.html\">{}</a>",
+        class,
+        root,
+        slug,
+        escape(&status_label(status))
+    )
+}
+
+/// Inline SVG sparkline of `values`, one point per run; hovering a point
+/// shows the run's start time and value
+fn sparkline(points: &[TrendPoint], value: impl Fn(&TrendPoint) -> usize) -> String {
+    let max = points.iter().map(&value).max().unwrap_or_default().max(1) as f64;
+    let step = SPARKLINE_WIDTH / (points.len().max(2) - 1) as f64;
+    let coordinates: Vec<(f64, f64, &TrendPoint)> = points
+        .iter()
+        .enumerate()
+        .map(|(index, point)| {
+            let x = index as f64 * step;
+            let y = 2.0 + (SPARKLINE_HEIGHT - 4.0) * (1.0 - value(point) as f64 / max);
+            (x, y, point)
+        })
+        .collect();
+    let polyline: Vec<String> = coordinates
+        .iter()
+        .map(|(x, y, _)| format!("{:.1},{:.1}", x, y))
+        .collect();
+    let circles: String = coordinates
+        .iter()
+        .map(|(x, y, point)| {
+            format!(
+                "<circle cx=\"{:.1}\" cy=\"{:.1}\" r=\"2\"><title>{}: {}</title></circle>",
+                x,
+                y,
+                humantime::format_rfc3339_seconds(
+                    UNIX_EPOCH + Duration::from_secs(point.started_at)
+                ),
+                value(point)
+            )
+        })
+        .collect();
+    format!(
+        "<svg class=\"sparkline\" width=\"{0}\" height=\"{1}\" viewBox=\"-3 0 {2} {1}\" role=\"img\"><polyline points=\"{3}\"/>{4}</svg>",
+        SPARKLINE_WIDTH,
+        SPARKLINE_HEIGHT,
+        SPARKLINE_WIDTH + 6.0,
+        polyline.join(" "),
+        circles
+    )
+}
+
+/// Branding and messages shared by every page of the site
+struct Site<'a> {
+    branding: &'a ReportConfig,
+    messages: &'a Messages,
+}
+
+impl Site<'_> {
+    /// Escaped message without arguments
+    fn text(&self, id: &str) -> String {
+        escape(&self.messages.text(id))
+    }
+
+    /// Title of the site: the configured one, or the `site-title` message
+    fn title(&self) -> String {
+        self.branding
+            .title
+            .clone()
+            .unwrap_or_else(|| self.messages.text("site-title"))
+    }
+
+    /// Wrap a page body in the site's header: the configured logo and title,
+    /// a link back to the overview and the configured links
+    fn layout(&self, title: &str, root: &str, body: &str) -> String {
+        let site_title = self.title();
+        let document_title = if title == site_title {
+            escape(title)
+        } else {
+            format!("{} · {}", escape(title), escape(&site_title))
+        };
+        let logo = self
+            .branding
+            .logo_url
+            .as_ref()
+            .map(|url| format!("<img class=\"logo\" src=\"{}\" alt=\"\"> ", escape(url)))
+            .unwrap_or_default();
+        let links: String = self
+            .branding
+            .links
+            .iter()
+            .flatten()
+            .map(|link| {
+                format!(
+                    " · <a href=\"{}\">{}</a>",
+                    escape(&link.url),
+                    escape(&link.label)
+                )
+            })
+            .collect();
+        let language = self
+            .messages
+            .locale()
+            .split(['-', '_'])
+            .next()
+            .unwrap_or("en");
+        format!(
+            "<!DOCTYPE html>\n<html lang=\"{0}\">\n<head>\n<meta charset=\"utf-8\">\n<title>{1}</title>\n<link rel=\"stylesheet\" href=\"{2}style.css\">\n</head>\n<body>\n<nav>{3}<a href=\"{2}index.html\">{4}</a>{5}</nav>\n<h1>{6}</h1>\n{7}</body>\n</html>\n",
+            escape(language),
+            document_title,
+            root,
+            logo,
+            escape(&site_title),
+            links,
+            escape(title),
+            body
+        )
+    }
+
+    /// Table headed by the `column-*` messages in `columns`
+    fn table(&self, columns: &[&str], rows: Vec<String>) -> String {
+        let headers: String = columns
+            .iter()
+            .map(|column| match *column {
+                "" => "<th></th>".to_string(),
+                id => format!("<th>{}</th>", self.text(id)),
+            })
+            .collect();
+        format!(
+            "<table>\n<thead><tr>{}</tr></thead>\n<tbody>\n{}</tbody>\n</table>\n",
+            headers,
+            rows.concat()
+        )
+    }
+}
+
+/// Render the report as a small static site in `dir`: an overview, a page
+/// per link status and a page per source page, ready to publish as is
+/// (e.g. to GitHub Pages)
+pub fn write_site(
+    report: &ScanReport,
+    branding: &ReportConfig,
+    messages: &Messages,
+    dir: &Path,
+) -> Result<(), Box<dyn Error>> {
+    let site = Site { branding, messages };
+    fs::create_dir_all(dir.join("status"))?;
+    fs::create_dir_all(dir.join("pages"))?;
+    fs::write(dir.join("style.css"), STYLE)?;
+
+    let links: Vec<&LinkInfo> = report.links.iter().chain(&report.ignored_links).collect();
+    let mut by_status: BTreeMap<(bool, &str), Vec<&LinkInfo>> = BTreeMap::new();
+    let mut by_page: BTreeMap<&str, Vec<&LinkInfo>> = BTreeMap::new();
+    for link in &links {
+        by_status
+            .entry((!link.is_broken(), status_kind(&link.status)))
+            .or_default()
+            .push(link);
+        for source in &link.sources {
+            by_page.entry(source).or_default().push(link);
+        }
+    }
+
+    for ((_, slug), links) in &by_status {
+        let rows = links
+            .iter()
+            .map(|link| {
+                let sources: Vec<String> = link
+                    .sources
+                    .iter()
+                    .map(|source| {
+                        format!(
+                            "<a href=\"../pages/{}\">{}</a>",
+                            page_file(source),
+                            escape(source)
+                        )
+                    })
+                    .collect();
+                format!(
+                    "<tr><td>{}</td><td>{}</td><td>{}</td></tr>\n",
+                    url_cell(&link.url),
+                    escape(&status_label(&link.status)),
+                    sources.join("<br>")
+                )
+            })
+            .collect();
+        let body = site.table(&["column-link", "column-status", "column-found-on"], rows);
+        let title = messages.text(&format!("status-{}", slug));
+        fs::write(
+            dir.join("status").join(format!("{}.html", slug)),
+            site.layout(&format!("{} ({})", title, links.len()), "../", &body),
+        )?;
+    }
+
+    for (page, links) in &by_page {
+        let rows = links
+            .iter()
+            .map(|link| {
+                format!(
+                    "<tr><td>{}</td><td>{}</td></tr>\n",
+                    url_cell(&link.url),
+                    status_cell(&link.status, "../")
+                )
+            })
+            .collect();
+        let body = format!(
+            "<p>{}</p>\n{}",
+            url_cell(page),
+            site.table(&["column-link", "column-status"], rows)
+        );
+        let title = messages.get("site-links-on", &[("page", page)]);
+        fs::write(
+            dir.join("pages").join(page_file(page)),
+            site.layout(&title, "../", &body),
+        )?;
+    }
+
+    fs::write(
+        dir.join("index.html"),
+        render_index(&site, report, &by_status, &by_page),
+    )?;
+    Ok(())
+}
+
+fn render_index(
+    site: &Site,
+    report: &ScanReport,
+    by_status: &BTreeMap<(bool, &str), Vec<&LinkInfo>>,
+    by_page: &BTreeMap<&str, Vec<&LinkInfo>>,
+) -> String {
+    let broken = report.links.iter().filter(|link| link.is_broken()).count();
+    let totals = site.messages.get(
+        "site-totals",
+        &[
+            ("links", &report.links.len()),
+            ("broken", &broken),
+            ("unchecked", &report.ignored_links.len()),
+            ("findings", &report.findings.len()),
+        ],
+    );
+    let mut body = format!(
+        "<p class=\"{}\">{}</p>\n",
+        if broken > 0 { "broken" } else { "ok" },
+        escape(&totals)
+    );
+
+    if report.trend.len() > 1 {
+        let heading = site
+            .messages
+            .get("site-trend", &[("runs", &report.trend.len())]);
+        body.push_str(&format!("<h2>{}</h2>\n", escape(&heading)));
+        let first = report.trend[0];
+        let latest = report.trend[report.trend.len() - 1];
+        let rows = vec![
+            format!(
+                "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>\n",
+                site.text("site-trend-broken"),
+                sparkline(&report.trend, |point| point.broken),
+                first.broken,
+                latest.broken
+            ),
+            format!(
+                "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>\n",
+                site.text("site-trend-links"),
+                sparkline(&report.trend, |point| point.links),
+                first.links,
+                latest.links
+            ),
+        ];
+        body.push_str(&site.table(&["", "column-trend", "column-first", "column-latest"], rows));
+    }
+
+    body.push_str(&format!("<h2>{}</h2>\n", site.text("site-statuses")));
+    let rows = by_status
+        .iter()
+        .map(|((_, slug), links)| {
+            format!(
+                "<tr><td><a href=\"status/{}.html\">{}</a></td><td>{}</td></tr>\n",
+                slug,
+                site.text(&format!("status-{}", slug)),
+                links.len()
+            )
+        })
+        .collect();
+    body.push_str(&site.table(&["column-status", "column-links"], rows));
+
+    if !report.sections.is_empty() {
+        body.push_str(&format!("<h2>{}</h2>\n", site.text("site-sections")));
+        let rows = report
+            .sections
+            .iter()
+            .map(|section| {
+                format!(
+                    "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{:.1}%</td></tr>\n",
+                    escape(&section.label),
+                    section.pages,
+                    section.links,
+                    section.broken,
+                    section.health
+                )
+            })
+            .collect();
+        body.push_str(&site.table(
+            &[
+                "column-section",
+                "column-pages",
+                "column-links",
+                "column-broken",
+                "column-health",
+            ],
+            rows,
+        ));
+    }
+
+    if !report.findings.is_empty() {
+        body.push_str(&format!("<h2>{}</h2>\n", site.text("site-findings")));
+        let rows = report
+            .findings
+            .iter()
+            .map(|finding| {
+                format!(
+                    "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>\n",
+                    escape(
+                        &site
+                            .messages
+                            .get("severity", &[("severity", &finding.severity)])
+                    ),
+                    escape(&finding.rule),
+                    std::iter::once(&finding.page)
+                        .chain(&finding.other_pages)
+                        .map(|page| url_cell(page))
+                        .collect::<Vec<_>>()
+                        .join("<br>"),
+                    escape(&finding.message)
+                )
+            })
+            .collect();
+        body.push_str(&site.table(
+            &[
+                "column-severity",
+                "column-rule",
+                "column-page",
+                "column-message",
+            ],
+            rows,
+        ));
+    }
+
+    body.push_str(&format!("<h2>{}</h2>\n", site.text("site-pages")));
+    let mut pages: Vec<(&str, usize, usize)> = by_page
+        .iter()
+        .map(|(page, links)| {
+            let broken = links.iter().filter(|link| link.is_broken()).count();
+            (*page, links.len(), broken)
+        })
+        .collect();
+    pages.sort_by(|a, b| b.2.cmp(&a.2).then(a.0.cmp(b.0)));
+    let rows = pages
+        .iter()
+        .map(|(page, links, broken)| {
+            format!(
+                "<tr><td><a href=\"pages/{}\">{}</a></td><td>{}</td><td class=\"{}\">{}</td></tr>\n",
+                page_file(page),
+                escape(page),
+                links,
+                if *broken > 0 { "broken" } else { "muted" },
+                broken
+            )
+        })
+        .collect();
+    body.push_str(&site.table(&["column-page", "column-links", "column-broken"], rows));
+
+    site.layout(&site.title(), "", &body)
+}
@@ -0,0 +1,107 @@
+use crate::link::normalize_url;
+use crate::output::ScanReport;
+use log::{debug, warn};
+use regex::Regex;
+use std::collections::{HashSet, VecDeque};
+
+/// Most sitemap files read for one crawl, indexes included
+pub const MAX_SITEMAPS: usize = 100;
+
+/// Entries of a sitemap file: the pages of a `<urlset>` and the sitemaps of
+/// a `<sitemapindex>`
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Sitemap {
+    pub urls: Vec<String>,
+    pub sitemaps: Vec<String>,
+}
+
+/// Parse a sitemap or sitemap index, as defined on sitemaps.org
+pub fn parse_sitemap(xml: &str) -> Sitemap {
+    let entry = Regex::new(
+        r"(?is)<(url|sitemap)(?:\s[^>]*)?>.*?<loc(?:\s[^>]*)?>\s*(?:<!\[CDATA\[)?(.*?)(?:\]\]>)?\s*</loc>",
+    )
+    .unwrap();
+    let mut sitemap = Sitemap::default();
+    for captures in entry.captures_iter(xml) {
+        let location = unescape(&captures[2]);
+        if captures[1].eq_ignore_ascii_case("sitemap") {
+            sitemap.sitemaps.push(location);
+        } else {
+            sitemap.urls.push(location);
+        }
+    }
+    sitemap
+}
+
+/// Text of an XML element with the predefined entities replaced
+fn unescape(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+/// Page URLs listed by the sitemaps at `locations` and the indexes they lead
+/// to, in order and without duplicates. `fetch` returns the body of a file or
+/// why it could not be read; at most [`MAX_SITEMAPS`] files are read.
+pub fn collect_sitemap_urls(
+    locations: &[String],
+    mut fetch: impl FnMut(&str) -> Result<String, String>,
+) -> Vec<String> {
+    let mut queue: VecDeque<String> = locations.iter().cloned().collect();
+    let mut read = HashSet::new();
+    let mut seen = HashSet::new();
+    let mut urls = Vec::new();
+    while let Some(location) = queue.pop_front() {
+        if !read.insert(location.clone()) {
+            continue;
+        }
+        if read.len() > MAX_SITEMAPS {
+            warn!(
+                "Read {} sitemaps; skipping {} and the rest",
+                MAX_SITEMAPS, location
+            );
+            break;
+        }
+        let body = match fetch(&location) {
+            Ok(body) => body,
+            Err(e) => {
+                warn!("Could not read the sitemap {}: {}", location, e);
+                continue;
+            }
+        };
+        let sitemap = parse_sitemap(&body);
+        debug!(
+            "Sitemap {} lists {} pages and {} sitemaps",
+            location,
+            sitemap.urls.len(),
+            sitemap.sitemaps.len()
+        );
+        queue.extend(sitemap.sitemaps);
+        for url in sitemap.urls {
+            if seen.insert(url.clone()) {
+                urls.push(url);
+            }
+        }
+    }
+    urls
+}
+
+/// Pages of `sitemap_urls` that were checked but no crawled page links to,
+/// in sitemap order. The start URL is not an orphan.
+pub fn orphan_pages(sitemap_urls: &[String], report: &ScanReport, start_url: &str) -> Vec<String> {
+    let start = normalize_url(start_url);
+    let unlinked: HashSet<&str> = report
+        .links
+        .iter()
+        .chain(&report.ignored_links)
+        .filter(|link| link.sources.is_empty())
+        .map(|link| link.url.as_str())
+        .collect();
+    sitemap_urls
+        .iter()
+        .map(|url| normalize_url(url))
+        .filter(|url| *url != start && unlinked.contains(url.as_str()))
+        .collect()
+}
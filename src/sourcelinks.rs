@@ -0,0 +1,243 @@
+use crate::check::{Check, Finding, Page, Severity};
+use crate::github::GITHUB_TOKEN_ENV;
+use crate::link::USER_AGENT;
+use log::debug;
+use regex::Regex;
+use reqwest::blocking::Client;
+use std::collections::{HashMap, HashSet};
+use std::env;
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+use url::Url;
+
+/// Environment variable holding a token for the GitLab API
+pub const GITLAB_TOKEN_ENV: &str = "GITLAB_TOKEN";
+
+const GITHUB_API_URL: &str = "https://api.github.com";
+/// Time allowed for one API request
+const API_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Forge hosting a repository
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Forge {
+    GitHub,
+    GitLab,
+}
+
+/// A link to a file of a repository at a ref, such as
+/// `https://github.com/owner/repo/blob/main/src/lib.rs#L42`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SourceLink {
+    pub forge: Forge,
+    pub host: String,
+    /// `owner/repo` on GitHub, the project path on GitLab
+    pub repository: String,
+    /// Branch, tag or commit
+    pub reference: String,
+    /// Path of the file in the repository, percent-encoded as in the link
+    pub path: String,
+    /// First and last line the fragment selects
+    pub lines: Option<(usize, usize)>,
+}
+
+impl SourceLink {
+    /// Parse a GitHub blob link, or a GitLab one on any host (`/-/blob/`).
+    /// Refs containing `/` cannot be told apart from the path and are read
+    /// as their first segment.
+    pub fn parse(url: &str) -> Option<Self> {
+        let url = Url::parse(url).ok()?;
+        let host = url.host_str()?.to_ascii_lowercase();
+        let segments: Vec<&str> = url.path_segments()?.filter(|s| !s.is_empty()).collect();
+        let (forge, repository, rest) = if host == "github.com" || host == "www.github.com" {
+            match segments.as_slice() {
+                [owner, repo, "blob", rest @ ..] => {
+                    (Forge::GitHub, format!("{}/{}", owner, repo), rest)
+                }
+                _ => return None,
+            }
+        } else {
+            let dash = segments.iter().position(|segment| *segment == "-")?;
+            match &segments[dash..] {
+                ["-", "blob", rest @ ..] if dash > 1 => {
+                    (Forge::GitLab, segments[..dash].join("/"), rest)
+                }
+                _ => return None,
+            }
+        };
+        let [reference, path @ ..] = rest else {
+            return None;
+        };
+        if path.is_empty() {
+            return None;
+        }
+        Some(SourceLink {
+            forge,
+            host,
+            repository,
+            reference: reference.to_string(),
+            path: path.join("/"),
+            lines: url.fragment().and_then(parse_lines),
+        })
+    }
+
+    /// API URL returning the raw content of the file
+    pub fn api_url(&self) -> String {
+        match self.forge {
+            Forge::GitHub => format!(
+                "{}/repos/{}/contents/{}?ref={}",
+                GITHUB_API_URL, self.repository, self.path, self.reference
+            ),
+            Forge::GitLab => format!(
+                "https://{}/api/v4/projects/{}/repository/files/{}/raw?ref={}",
+                self.host,
+                self.repository.replace('/', "%2F"),
+                self.path.replace('/', "%2F"),
+                self.reference
+            ),
+        }
+    }
+}
+
+/// Lines selected by a fragment: `L42`, GitHub's `L10-L20` and `L10C5-L20C3`,
+/// or GitLab's `L10-20`
+pub fn parse_lines(fragment: &str) -> Option<(usize, usize)> {
+    let pattern = Regex::new(r"^L(\d+)(?:C\d+)?(?:-L?(\d+)(?:C\d+)?)?$").unwrap();
+    let captures = pattern.captures(fragment)?;
+    let first: usize = captures[1].parse().ok()?;
+    let last = match captures.get(2) {
+        Some(last) => last.as_str().parse().ok()?,
+        None => first,
+    };
+    Some((first, last.max(first)))
+}
+
+/// What the API says about a file
+#[derive(Debug, Clone)]
+enum Lookup {
+    Lines(usize),
+    Missing,
+    /// The file could not be looked up, e.g. because of rate limits
+    Unknown(String),
+}
+
+/// Verifies that links to files of GitHub and GitLab repositories, and the
+/// lines they select, still exist, through the APIs of the forges. Uses
+/// `GITHUB_TOKEN` and `GITLAB_TOKEN` when set, for private repositories and
+/// higher rate limits. Each file is looked up once per scan.
+#[derive(Default)]
+pub struct SourceLinkCheck {
+    client: OnceLock<Result<Client, String>>,
+    files: Mutex<HashMap<String, Lookup>>,
+}
+
+impl SourceLinkCheck {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn lookup(&self, link: &SourceLink) -> Lookup {
+        let api_url = link.api_url();
+        if let Some(lookup) = self.files.lock().unwrap().get(&api_url) {
+            return lookup.clone();
+        }
+        let lookup = self.fetch(link, &api_url);
+        self.files.lock().unwrap().insert(api_url, lookup.clone());
+        lookup
+    }
+
+    fn fetch(&self, link: &SourceLink, api_url: &str) -> Lookup {
+        let client = self.client.get_or_init(|| {
+            Client::builder()
+                .timeout(API_TIMEOUT)
+                .user_agent(USER_AGENT)
+                .build()
+                .map_err(|e| e.to_string())
+        });
+        let client = match client {
+            Ok(client) => client,
+            Err(e) => return Lookup::Unknown(e.clone()),
+        };
+        let mut request = client.get(api_url);
+        request = match link.forge {
+            Forge::GitHub => {
+                let request = request.header("Accept", "application/vnd.github.raw");
+                match env::var(GITHUB_TOKEN_ENV) {
+                    Ok(token) => request.bearer_auth(token),
+                    Err(_) => request,
+                }
+            }
+            Forge::GitLab => match env::var(GITLAB_TOKEN_ENV) {
+                Ok(token) => request.header("PRIVATE-TOKEN", token),
+                Err(_) => request,
+            },
+        };
+        debug!("Looking up {}", api_url);
+        match request.send() {
+            Ok(response) if response.status().is_success() => match response.text() {
+                Ok(content) => Lookup::Lines(content.lines().count()),
+                Err(e) => Lookup::Unknown(e.to_string()),
+            },
+            Ok(response) if response.status().as_u16() == 404 => Lookup::Missing,
+            Ok(response) => Lookup::Unknown(response.status().to_string()),
+            Err(e) => Lookup::Unknown(e.to_string()),
+        }
+    }
+}
+
+impl Check for SourceLinkCheck {
+    fn id(&self) -> &str {
+        "source-links"
+    }
+
+    fn enabled_by_default(&self) -> bool {
+        false
+    }
+
+    fn check(&self, page: &Page) -> Vec<Finding> {
+        let mut reported = HashSet::new();
+        page.links
+            .iter()
+            .filter(|link| reported.insert(link.as_str()))
+            .filter_map(|link| Some((link, SourceLink::parse(link)?)))
+            .filter_map(|(link, source)| {
+                let (severity, message) = match (self.lookup(&source), source.lines) {
+                    (Lookup::Missing, _) => (
+                        Severity::Error,
+                        format!(
+                            "{} does not exist at {} in {}",
+                            source.path, source.reference, source.repository
+                        ),
+                    ),
+                    (Lookup::Lines(count), Some((first, last))) if first == 0 || last > count => {
+                        let selection = if first == last {
+                            format!("line {}", first)
+                        } else {
+                            format!("lines {}-{}", first, last)
+                        };
+                        (
+                            Severity::Error,
+                            format!(
+                                "Links to {} of {}, which has {} lines at {}",
+                                selection, source.path, count, source.reference
+                            ),
+                        )
+                    }
+                    (Lookup::Unknown(reason), _) => (
+                        Severity::Warning,
+                        format!("Could not look {} up: {}", source.path, reason),
+                    ),
+                    (Lookup::Lines(_), _) => return None,
+                };
+                Some(Finding {
+                    rule: self.id().to_string(),
+                    severity,
+                    page: page.url.to_string(),
+                    message,
+                    target: Some(link.clone()),
+                    owner: None,
+                    other_pages: Vec::new(),
+                })
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,205 @@
+use crate::link::{LinkInfo, LinkStatus};
+use crate::manifest::unix_seconds;
+use crate::output::{OutputFormatter, OutputOptions, ScanReport};
+use rusqlite::{params, Connection, OptionalExtension, Transaction};
+use serde_json::Value;
+use std::error::Error;
+use std::time::SystemTime;
+
+/// Schema of the `sqlite` output: one `crawls` row per run, and one
+/// `results` row per link of a run pointing at its URL and status
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS crawls (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    start_url TEXT NOT NULL,
+    started_at INTEGER NOT NULL,
+    finished_at INTEGER NOT NULL,
+    version TEXT NOT NULL,
+    scanned INTEGER NOT NULL,
+    broken INTEGER NOT NULL,
+    incomplete TEXT
+);
+CREATE TABLE IF NOT EXISTS crawl_labels (
+    crawl_id INTEGER NOT NULL REFERENCES crawls(id) ON DELETE CASCADE,
+    key TEXT NOT NULL,
+    value TEXT NOT NULL,
+    PRIMARY KEY (crawl_id, key)
+);
+CREATE TABLE IF NOT EXISTS urls (
+    id INTEGER PRIMARY KEY,
+    url TEXT NOT NULL UNIQUE
+);
+CREATE TABLE IF NOT EXISTS statuses (
+    id INTEGER PRIMARY KEY,
+    name TEXT NOT NULL UNIQUE,
+    broken INTEGER NOT NULL
+);
+CREATE TABLE IF NOT EXISTS results (
+    id INTEGER PRIMARY KEY,
+    crawl_id INTEGER NOT NULL REFERENCES crawls(id) ON DELETE CASCADE,
+    url_id INTEGER NOT NULL REFERENCES urls(id),
+    kind TEXT NOT NULL,
+    status_id INTEGER NOT NULL REFERENCES statuses(id),
+    detail TEXT,
+    depth INTEGER NOT NULL,
+    response_time_ms INTEGER,
+    attempts INTEGER NOT NULL,
+    redirected_to INTEGER REFERENCES urls(id),
+    content_hash TEXT
+);
+CREATE INDEX IF NOT EXISTS results_by_url ON results (url_id, crawl_id);
+CREATE TABLE IF NOT EXISTS result_sources (
+    result_id INTEGER NOT NULL REFERENCES results(id) ON DELETE CASCADE,
+    source_id INTEGER NOT NULL REFERENCES urls(id),
+    PRIMARY KEY (result_id, source_id)
+);
+";
+
+/// Name of a status, its variant, and the detail some variants carry
+fn status_parts(status: &LinkStatus) -> (String, Option<String>) {
+    match serde_json::to_value(status) {
+        Ok(Value::String(name)) => (name, None),
+        Ok(Value::Object(variant)) => match variant.into_iter().next() {
+            Some((name, Value::String(detail))) => (name, Some(detail)),
+            Some((name, detail)) => (name, Some(detail.to_string())),
+            None => (String::new(), None),
+        },
+        _ => (String::new(), None),
+    }
+}
+
+/// Id of a row of `table` whose `column` is `value`, inserting it first
+/// when missing. `extra` fills the other columns of a new row.
+fn row_id(
+    transaction: &Transaction,
+    table: &str,
+    column: &str,
+    value: &str,
+    extra: Option<(&str, i64)>,
+) -> rusqlite::Result<i64> {
+    let query = format!("SELECT id FROM {} WHERE {} = ?1", table, column);
+    if let Some(id) = transaction
+        .query_row(&query, params![value], |row| row.get(0))
+        .optional()?
+    {
+        return Ok(id);
+    }
+    match extra {
+        Some((name, extra)) => transaction.execute(
+            &format!(
+                "INSERT INTO {} ({}, {}) VALUES (?1, ?2)",
+                table, column, name
+            ),
+            params![value, extra],
+        )?,
+        None => transaction.execute(
+            &format!("INSERT INTO {} ({}) VALUES (?1)", table, column),
+            params![value],
+        )?,
+    };
+    Ok(transaction.last_insert_rowid())
+}
+
+/// Insert a link of a crawl, with the pages it was found on
+fn insert_result(
+    transaction: &Transaction,
+    crawl_id: i64,
+    kind: &str,
+    link: &LinkInfo,
+) -> rusqlite::Result<()> {
+    let url_id = row_id(transaction, "urls", "url", &link.url, None)?;
+    let (status, detail) = status_parts(&link.status);
+    let broken = Some(("broken", i64::from(link.status.is_broken())));
+    let status_id = row_id(transaction, "statuses", "name", &status, broken)?;
+    let redirected_to = link
+        .redirected_to
+        .as_deref()
+        .map(|url| row_id(transaction, "urls", "url", url, None))
+        .transpose()?;
+    transaction.execute(
+        "INSERT INTO results (crawl_id, url_id, kind, status_id, detail, depth, response_time_ms, attempts, redirected_to, content_hash)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+        params![
+            crawl_id,
+            url_id,
+            kind,
+            status_id,
+            detail,
+            link.depth as i64,
+            link.response_time_ms.map(|time| time as i64),
+            link.attempts.max(1),
+            redirected_to,
+            link.content_hash,
+        ],
+    )?;
+    let result_id = transaction.last_insert_rowid();
+    for source in &link.sources {
+        let source_id = row_id(transaction, "urls", "url", source, None)?;
+        transaction.execute(
+            "INSERT OR IGNORE INTO result_sources (result_id, source_id) VALUES (?1, ?2)",
+            params![result_id, source_id],
+        )?;
+    }
+    Ok(())
+}
+
+/// Append a crawl and its links to the database at `path`, creating it if
+/// needed; returns the id of the new crawl
+pub fn append_crawl(
+    path: &str,
+    report: &ScanReport,
+    detailed: bool,
+) -> Result<i64, Box<dyn Error>> {
+    let mut connection = Connection::open(path)?;
+    connection.execute_batch(SCHEMA)?;
+    let transaction = connection.transaction()?;
+    let broken = report.links.iter().filter(|link| link.is_broken()).count();
+    transaction.execute(
+        "INSERT INTO crawls (start_url, started_at, finished_at, version, scanned, broken, incomplete)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+        params![
+            report.start_url,
+            report.started_at as i64,
+            unix_seconds(SystemTime::now()) as i64,
+            env!("CARGO_PKG_VERSION"),
+            report.links.len() as i64,
+            broken as i64,
+            report.incomplete,
+        ],
+    )?;
+    let crawl_id = transaction.last_insert_rowid();
+    for (key, value) in &report.labels {
+        transaction.execute(
+            "INSERT INTO crawl_labels (crawl_id, key, value) VALUES (?1, ?2, ?3)",
+            params![crawl_id, key, value],
+        )?;
+    }
+    let ignored: &[LinkInfo] = if detailed { &report.ignored_links } else { &[] };
+    let results = [
+        ("scanned", report.links.as_slice()),
+        ("external", report.external_links.as_slice()),
+        ("ignored", ignored),
+    ];
+    for (kind, links) in results {
+        for link in links {
+            insert_result(&transaction, crawl_id, kind, link)?;
+        }
+    }
+    transaction.commit()?;
+    Ok(crawl_id)
+}
+
+/// Built-in formatter appending each run to a SQLite database, for queries
+/// across runs
+pub struct SqliteFormatter;
+
+impl OutputFormatter for SqliteFormatter {
+    fn name(&self) -> &str {
+        "sqlite"
+    }
+
+    fn write(&self, report: &ScanReport, options: &OutputOptions) -> Result<(), Box<dyn Error>> {
+        append_crawl(&options.file, report, options.detailed)?;
+        Ok(())
+    }
+}
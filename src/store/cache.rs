@@ -0,0 +1,92 @@
+use super::{StoredLink, StoredRun};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+use url::Url;
+
+/// On-disk crawl cache: the latest result of every URL, keyed by URL, in a
+/// single JSON file.
+///
+/// Unlike a [`super::JsonStore`], which keeps a file per run, the cache only
+/// holds the last state of each page, so it stays the size of the sites it
+/// covers and is loaded with a single read.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct CrawlCache {
+    #[serde(skip)]
+    path: PathBuf,
+    links: BTreeMap<String, StoredLink>,
+}
+
+impl CrawlCache {
+    /// Load the cache at `path`, empty if the file does not exist yet
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, Box<dyn Error>> {
+        let path = path.as_ref();
+        let mut cache: CrawlCache = match fs::read_to_string(path) {
+            Ok(contents) => serde_json::from_str(&contents)
+                .map_err(|e| format!("invalid crawl cache {}: {}", path.display(), e))?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => CrawlCache::default(),
+            Err(e) => return Err(e.into()),
+        };
+        cache.path = path.to_path_buf();
+        Ok(cache)
+    }
+
+    /// Cached result of a URL
+    pub fn get(&self, url: &str) -> Option<&StoredLink> {
+        self.links.get(url)
+    }
+
+    /// The cached URLs on the host of `url`, as the previous run of a scan of it
+    pub fn previous_run(&self, url: &str) -> Option<StoredRun> {
+        let links: Vec<StoredLink> = self
+            .links
+            .values()
+            .filter(|link| same_host(&link.url, url))
+            .cloned()
+            .collect();
+        let started_at = links.iter().map(|link| link.checked_at).max()?;
+        Some(StoredRun {
+            id: "cache".to_string(),
+            url: url.to_string(),
+            started_at,
+            labels: Default::default(),
+            links,
+        })
+    }
+
+    /// Replace the cached URLs on the host of `run` with the URLs of the run,
+    /// so pages gone from the site leave the cache, and write it.
+    ///
+    /// Links of the run to other hosts are not cached: no scan of this site
+    /// reads them back, and no run would ever replace them.
+    pub fn update(&mut self, run: &StoredRun) -> Result<(), Box<dyn Error>> {
+        self.links.retain(|url, _| !same_host(url, &run.url));
+        for link in run
+            .links
+            .iter()
+            .filter(|link| same_host(&link.url, &run.url))
+        {
+            self.links.insert(link.url.clone(), link.clone());
+        }
+        if let Some(dir) = self.path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            fs::create_dir_all(dir)?;
+        }
+        // Written aside first so an interrupted run leaves the old cache intact
+        let partial = self.path.with_extension("partial");
+        fs::write(&partial, serde_json::to_string(self)?)?;
+        fs::rename(&partial, &self.path)?;
+        Ok(())
+    }
+}
+
+fn same_host(url: &str, other: &str) -> bool {
+    let host = |url: &str| {
+        Url::parse(url)
+            .ok()?
+            .host_str()
+            .map(str::to_ascii_lowercase)
+    };
+    host(url).is_some() && host(url) == host(other)
+}
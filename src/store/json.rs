@@ -0,0 +1,64 @@
+use super::{Labels, ScanStore, StoredRun};
+use std::error::Error;
+use std::fs;
+use std::path::PathBuf;
+
+/// Filesystem store keeping one JSON file per run under `<dir>/runs`
+pub struct JsonStore {
+    dir: PathBuf,
+}
+
+impl JsonStore {
+    /// Open (and create if needed) a store rooted at `dir`
+    pub fn open(dir: impl Into<PathBuf>) -> Result<Self, Box<dyn Error>> {
+        let dir = dir.into();
+        fs::create_dir_all(dir.join("runs"))?;
+        Ok(JsonStore { dir })
+    }
+
+    fn run_path(&self, id: &str) -> PathBuf {
+        self.dir.join("runs").join(format!("{}.json", id))
+    }
+
+    fn runs(&self) -> Result<Vec<StoredRun>, Box<dyn Error>> {
+        let mut runs = Vec::new();
+        for entry in fs::read_dir(self.dir.join("runs"))? {
+            let path = entry?.path();
+            if path.extension().and_then(|ext| ext.to_str()) != Some("json") {
+                continue;
+            }
+            runs.push(serde_json::from_str(&fs::read_to_string(&path)?)?);
+        }
+        Ok(runs)
+    }
+}
+
+impl ScanStore for JsonStore {
+    fn save_run(&self, run: &StoredRun) -> Result<(), Box<dyn Error>> {
+        fs::write(self.run_path(&run.id), serde_json::to_string_pretty(run)?)?;
+        Ok(())
+    }
+
+    fn load_run(&self, id: &str) -> Result<Option<StoredRun>, Box<dyn Error>> {
+        let path = self.run_path(id);
+        if !path.exists() {
+            return Ok(None);
+        }
+        Ok(Some(serde_json::from_str(&fs::read_to_string(path)?)?))
+    }
+
+    fn run_ids(&self, url: &str) -> Result<Vec<String>, Box<dyn Error>> {
+        self.labeled_run_ids(url, &Labels::new())
+    }
+
+    fn labeled_run_ids(&self, url: &str, filter: &Labels) -> Result<Vec<String>, Box<dyn Error>> {
+        let mut runs: Vec<(u64, String)> = self
+            .runs()?
+            .into_iter()
+            .filter(|run| run.url == url && run.has_labels(filter))
+            .map(|run| (run.started_at, run.id))
+            .collect();
+        runs.sort();
+        Ok(runs.into_iter().map(|(_, id)| id).collect())
+    }
+}
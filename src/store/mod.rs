@@ -0,0 +1,350 @@
+use crate::check::Finding;
+use crate::link::{LinkInfo, LinkStatus};
+use crate::output::ScanReport;
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::RandomState;
+use std::collections::{BTreeMap, HashMap};
+use std::error::Error;
+use std::hash::BuildHasher;
+use std::path::Path;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+mod cache;
+mod json;
+#[cfg(feature = "postgres")]
+mod postgres;
+mod sqlite;
+
+pub use cache::CrawlCache;
+pub use json::JsonStore;
+#[cfg(feature = "postgres")]
+pub use postgres::PostgresStore;
+pub use sqlite::SqliteStore;
+
+/// `key=value` labels of a scan, such as `env=prod`
+pub type Labels = BTreeMap<String, String>;
+
+/// Parse a `key=value` label. Keys are letters, digits, `_`, `-` and `.`.
+pub fn parse_label(label: &str) -> Result<(String, String), String> {
+    let (key, value) = label
+        .split_once('=')
+        .ok_or_else(|| format!("label {} is not key=value", label))?;
+    let key = key.trim();
+    if key.is_empty()
+        || !key
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.'))
+    {
+        return Err(format!("invalid label key in {}", label));
+    }
+    Ok((key.to_string(), value.trim().to_string()))
+}
+
+/// A scan run as kept in the persistent store
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StoredRun {
+    pub id: String,
+    pub url: String,
+    pub started_at: u64,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub labels: Labels,
+    pub links: Vec<StoredLink>,
+}
+
+/// Result of a single link in a stored run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StoredLink {
+    pub url: String,
+    pub status: LinkStatus,
+    /// Unix time the status was obtained from the server
+    pub checked_at: u64,
+    /// Links found on the page, so trusted pages can be crawled without fetching
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub outlinks: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content_hash: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub redirected_to: Option<String>,
+    /// Validators the page was served with, for conditional requests
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub etag: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_modified: Option<u64>,
+    /// Findings of the page checks, reported again when the page is not fetched
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub findings: Vec<Finding>,
+    /// Anchors of the page, so fragments of links to it are checked without
+    /// fetching it; `None` for URLs that were not checked as pages
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub anchors: Option<Vec<String>>,
+}
+
+impl StoredRun {
+    /// Snapshot a finished report, with its labels
+    pub fn from_report(url: &str, started_at: u64, report: &ScanReport) -> Self {
+        let links = report
+            .links
+            .iter()
+            .map(|link| StoredLink {
+                url: link.url.clone(),
+                status: link.status.clone(),
+                checked_at: link.last_checked.unwrap_or(started_at),
+                outlinks: report.outlinks.get(&link.url).cloned().unwrap_or_default(),
+                content_hash: link.content_hash.clone(),
+                redirected_to: link.redirected_to.clone(),
+                etag: link.etag.clone(),
+                last_modified: link.last_modified,
+                findings: report
+                    .page_findings
+                    .get(&link.url)
+                    .cloned()
+                    .unwrap_or_default(),
+                anchors: report.anchors.get(&link.url).map(|anchors| {
+                    let mut anchors: Vec<String> = anchors.iter().cloned().collect();
+                    anchors.sort();
+                    anchors
+                }),
+            })
+            .collect();
+
+        StoredRun {
+            id: run_id(url, started_at),
+            url: url.to_string(),
+            started_at,
+            labels: report.labels.clone(),
+            links,
+        }
+    }
+
+    /// Whether the run carries every label of `filter`
+    pub fn has_labels(&self, filter: &Labels) -> bool {
+        has_labels(&self.labels, filter)
+    }
+}
+
+/// Whether `labels` include every label of `filter`
+pub fn has_labels(labels: &Labels, filter: &Labels) -> bool {
+    filter
+        .iter()
+        .all(|(key, value)| labels.get(key) == Some(value))
+}
+
+/// Id of a new run: its start time, so ids sort chronologically, then the
+/// current nanoseconds and a random hash of the URL, so runs started within the
+/// same second never replace each other
+fn run_id(url: &str, started_at: u64) -> String {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|time| time.subsec_nanos())
+        .unwrap_or_default();
+    let hash = RandomState::new().hash_one(url) as u32;
+    format!("{}-{:09}-{:08x}", started_at, nanos, hash)
+}
+
+/// Number of runs shown in trend charts when `trend_runs` is not set
+pub const DEFAULT_TREND_RUNS: usize = 20;
+
+/// Link counts of one stored run, a point of a trend chart
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TrendPoint {
+    pub started_at: u64,
+    pub links: usize,
+    pub broken: usize,
+}
+
+impl TrendPoint {
+    pub fn of(run: &StoredRun) -> Self {
+        TrendPoint {
+            started_at: run.started_at,
+            links: run.links.len(),
+            broken: run
+                .links
+                .iter()
+                .filter(|link| link.status.is_broken())
+                .count(),
+        }
+    }
+}
+
+/// Persistent storage of scan runs.
+///
+/// The crawler only talks to this trait, so new backends can be added without
+/// touching it. Use [`open_store`] to pick a backend from a location string.
+pub trait ScanStore {
+    /// Persist a run
+    fn save_run(&self, run: &StoredRun) -> Result<(), Box<dyn Error>>;
+
+    /// Load a run by id
+    fn load_run(&self, id: &str) -> Result<Option<StoredRun>, Box<dyn Error>>;
+
+    /// Ids of all runs for a start URL, oldest first
+    fn run_ids(&self, url: &str) -> Result<Vec<String>, Box<dyn Error>>;
+
+    /// Ids of the runs for a start URL carrying every label of `filter`,
+    /// oldest first
+    fn labeled_run_ids(&self, url: &str, filter: &Labels) -> Result<Vec<String>, Box<dyn Error>> {
+        let mut ids = Vec::new();
+        for id in self.run_ids(url)? {
+            if self
+                .load_run(&id)?
+                .is_some_and(|run| run.has_labels(filter))
+            {
+                ids.push(id);
+            }
+        }
+        Ok(ids)
+    }
+
+    /// Most recent run for a start URL, if any
+    fn latest_run(&self, url: &str) -> Result<Option<StoredRun>, Box<dyn Error>> {
+        match self.run_ids(url)?.last() {
+            Some(id) => self.load_run(id),
+            None => Ok(None),
+        }
+    }
+
+    /// Link counts of the last `runs` runs for a start URL, oldest first
+    fn trend(&self, url: &str, runs: usize) -> Result<Vec<TrendPoint>, Box<dyn Error>> {
+        let ids = self.run_ids(url)?;
+        let mut points = Vec::new();
+        for id in &ids[ids.len().saturating_sub(runs)..] {
+            if let Some(run) = self.load_run(id)? {
+                points.push(TrendPoint::of(&run));
+            }
+        }
+        Ok(points)
+    }
+
+    /// Differences between two stored runs
+    fn diff_runs(&self, from: &str, to: &str) -> Result<RunDiff, Box<dyn Error>> {
+        let load = |id: &str| {
+            self.load_run(id)?
+                .ok_or_else(|| Box::<dyn Error>::from(format!("No stored run with id {}", id)))
+        };
+        Ok(diff_runs(&load(from)?, &load(to)?))
+    }
+}
+
+/// Open the store at `location`: Postgres for `postgres://` URLs (with the
+/// `postgres` feature), a SQLite database for `sqlite://` locations and
+/// `.db`/`.sqlite`/`.sqlite3` files, a directory of JSON files otherwise
+pub fn open_store(location: &str) -> Result<Box<dyn ScanStore>, Box<dyn Error>> {
+    if location.starts_with("postgres://") || location.starts_with("postgresql://") {
+        #[cfg(feature = "postgres")]
+        return Ok(Box::new(PostgresStore::connect(location)?));
+        #[cfg(not(feature = "postgres"))]
+        return Err("Postgres stores require building with the `postgres` feature".into());
+    }
+    if let Some(path) = location.strip_prefix("sqlite://") {
+        return Ok(Box::new(SqliteStore::open(path)?));
+    }
+    let is_database = Path::new(location)
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| matches!(ext, "db" | "sqlite" | "sqlite3"));
+    if is_database {
+        Ok(Box::new(SqliteStore::open(location)?))
+    } else {
+        Ok(Box::new(JsonStore::open(location)?))
+    }
+}
+
+/// Link-level differences between two runs
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RunDiff {
+    /// Links that are broken now but were not broken (or not seen) before
+    pub newly_broken: Vec<String>,
+    /// Links that were broken before and are not anymore
+    pub fixed: Vec<String>,
+    /// Links seen only in the newer run
+    pub added: Vec<String>,
+    /// Links seen only in the older run
+    pub removed: Vec<String>,
+}
+
+/// Compare two runs link by link
+pub fn diff_runs(from: &StoredRun, to: &StoredRun) -> RunDiff {
+    let before: HashMap<&str, &LinkStatus> = from
+        .links
+        .iter()
+        .map(|link| (link.url.as_str(), &link.status))
+        .collect();
+    let after: HashMap<&str, &LinkStatus> = to
+        .links
+        .iter()
+        .map(|link| (link.url.as_str(), &link.status))
+        .collect();
+
+    let mut diff = RunDiff::default();
+    for link in &to.links {
+        let previous = before.get(link.url.as_str());
+        if previous.is_none() {
+            diff.added.push(link.url.clone());
+        }
+        let was_broken = previous.is_some_and(|status| status.is_broken());
+        if link.status.is_broken() && !was_broken {
+            diff.newly_broken.push(link.url.clone());
+        } else if !link.status.is_broken() && was_broken {
+            diff.fixed.push(link.url.clone());
+        }
+    }
+    for link in &from.links {
+        if !after.contains_key(link.url.as_str()) {
+            diff.removed.push(link.url.clone());
+        }
+    }
+    diff
+}
+
+/// How long previous results are trusted, per status
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct TrustConfig {
+    pub valid: Option<String>,
+    pub not_found: Option<String>,
+    pub error: Option<String>,
+}
+
+impl TrustConfig {
+    /// Trust window for a status; failures are never trusted unless configured
+    pub fn window(
+        &self,
+        status: &LinkStatus,
+    ) -> Result<Option<Duration>, humantime::DurationError> {
+        let window = match status {
+            LinkStatus::Valid => &self.valid,
+            LinkStatus::NotFound => &self.not_found,
+            LinkStatus::Error(_) => &self.error,
+            _ => &None,
+        };
+        window.as_deref().map(humantime::parse_duration).transpose()
+    }
+}
+
+/// Links from a previous run whose status is still within its trust window
+pub fn trusted_links(
+    run: &StoredRun,
+    trust: &TrustConfig,
+    now: u64,
+) -> Result<HashMap<String, StoredLink>, humantime::DurationError> {
+    let mut trusted = HashMap::new();
+    for link in &run.links {
+        if let Some(window) = trust.window(&link.status)? {
+            if now.saturating_sub(link.checked_at) < window.as_secs() {
+                trusted.insert(link.url.clone(), link.clone());
+            }
+        }
+    }
+    Ok(trusted)
+}
+
+impl From<&StoredLink> for LinkInfo {
+    fn from(stored: &StoredLink) -> Self {
+        let mut link = LinkInfo::new(&stored.url, stored.status.clone());
+        link.last_checked = Some(stored.checked_at);
+        link.content_hash = stored.content_hash.clone();
+        link.redirected_to = stored.redirected_to.clone();
+        link.etag = stored.etag.clone();
+        link.last_modified = stored.last_modified;
+        link
+    }
+}
@@ -0,0 +1,198 @@
+use super::{Labels, ScanStore, StoredLink, StoredRun};
+use native_tls::TlsConnector;
+use postgres::Client;
+use postgres_native_tls::MakeTlsConnector;
+use std::error::Error;
+use std::sync::Mutex;
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS inspector_runs (
+    id TEXT PRIMARY KEY,
+    url TEXT NOT NULL,
+    started_at BIGINT NOT NULL
+);
+CREATE TABLE IF NOT EXISTS inspector_links (
+    run_id TEXT NOT NULL REFERENCES inspector_runs(id) ON DELETE CASCADE,
+    position INTEGER NOT NULL,
+    url TEXT NOT NULL,
+    status TEXT NOT NULL,
+    checked_at BIGINT NOT NULL,
+    outlinks TEXT NOT NULL,
+    content_hash TEXT,
+    redirected_to TEXT,
+    etag TEXT,
+    last_modified BIGINT,
+    findings TEXT,
+    anchors TEXT,
+    PRIMARY KEY (run_id, position)
+);
+ALTER TABLE inspector_links ADD COLUMN IF NOT EXISTS etag TEXT;
+ALTER TABLE inspector_links ADD COLUMN IF NOT EXISTS last_modified BIGINT;
+ALTER TABLE inspector_links ADD COLUMN IF NOT EXISTS findings TEXT;
+ALTER TABLE inspector_links ADD COLUMN IF NOT EXISTS anchors TEXT;
+CREATE INDEX IF NOT EXISTS inspector_runs_by_url ON inspector_runs (url, started_at);
+CREATE TABLE IF NOT EXISTS inspector_run_labels (
+    run_id TEXT NOT NULL REFERENCES inspector_runs(id) ON DELETE CASCADE,
+    key TEXT NOT NULL,
+    value TEXT NOT NULL,
+    PRIMARY KEY (run_id, key)
+);
+";
+
+/// Store keeping runs in a shared Postgres database.
+///
+/// Tables are prefixed with `inspector_` so the store can live in an existing
+/// database next to other data; runs of many sites share the same tables.
+pub struct PostgresStore {
+    client: Mutex<Client>,
+}
+
+impl PostgresStore {
+    /// Connect with a `postgres://` URL and create the tables if needed.
+    /// The connection is encrypted as the `sslmode` of the URL asks:
+    /// `prefer` (the default) uses TLS when the server offers it, `require`
+    /// refuses to go on without it and `disable` never uses it. Server
+    /// certificates are verified against the system's trusted roots.
+    pub fn connect(url: &str) -> Result<Self, Box<dyn Error>> {
+        let tls = MakeTlsConnector::new(TlsConnector::new()?);
+        let mut client = Client::connect(url, tls)?;
+        client.batch_execute(SCHEMA)?;
+        Ok(PostgresStore {
+            client: Mutex::new(client),
+        })
+    }
+}
+
+impl ScanStore for PostgresStore {
+    fn save_run(&self, run: &StoredRun) -> Result<(), Box<dyn Error>> {
+        let mut client = self.client.lock().unwrap();
+        let mut transaction = client.transaction()?;
+        transaction.execute("DELETE FROM inspector_links WHERE run_id = $1", &[&run.id])?;
+        transaction.execute(
+            "DELETE FROM inspector_run_labels WHERE run_id = $1",
+            &[&run.id],
+        )?;
+        transaction.execute(
+            "INSERT INTO inspector_runs (id, url, started_at) VALUES ($1, $2, $3)
+             ON CONFLICT (id) DO UPDATE SET url = EXCLUDED.url, started_at = EXCLUDED.started_at",
+            &[&run.id, &run.url, &(run.started_at as i64)],
+        )?;
+        for (key, value) in &run.labels {
+            transaction.execute(
+                "INSERT INTO inspector_run_labels (run_id, key, value) VALUES ($1, $2, $3)",
+                &[&run.id, key, value],
+            )?;
+        }
+        let insert = transaction.prepare(
+            "INSERT INTO inspector_links (run_id, position, url, status, checked_at, outlinks, content_hash, redirected_to, etag, last_modified, findings, anchors)
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12)",
+        )?;
+        for (position, link) in run.links.iter().enumerate() {
+            transaction.execute(
+                &insert,
+                &[
+                    &run.id,
+                    &(position as i32),
+                    &link.url,
+                    &serde_json::to_string(&link.status)?,
+                    &(link.checked_at as i64),
+                    &serde_json::to_string(&link.outlinks)?,
+                    &link.content_hash,
+                    &link.redirected_to,
+                    &link.etag,
+                    &link.last_modified.map(|time| time as i64),
+                    &serde_json::to_string(&link.findings)?,
+                    &link
+                        .anchors
+                        .as_ref()
+                        .map(serde_json::to_string)
+                        .transpose()?,
+                ],
+            )?;
+        }
+        transaction.commit()?;
+        Ok(())
+    }
+
+    fn load_run(&self, id: &str) -> Result<Option<StoredRun>, Box<dyn Error>> {
+        let mut client = self.client.lock().unwrap();
+        let run = client.query_opt(
+            "SELECT url, started_at FROM inspector_runs WHERE id = $1",
+            &[&id],
+        )?;
+        let run = match run {
+            Some(run) => run,
+            None => return Ok(None),
+        };
+
+        let labels: Labels = client
+            .query(
+                "SELECT key, value FROM inspector_run_labels WHERE run_id = $1",
+                &[&id],
+            )?
+            .iter()
+            .map(|row| (row.get(0), row.get(1)))
+            .collect();
+
+        let mut links = Vec::new();
+        for row in client.query(
+            "SELECT url, status, checked_at, outlinks, content_hash, redirected_to, etag, last_modified, findings, anchors
+             FROM inspector_links WHERE run_id = $1 ORDER BY position",
+            &[&id],
+        )? {
+            links.push(StoredLink {
+                url: row.get(0),
+                status: serde_json::from_str(row.get(1))?,
+                checked_at: row.get::<_, i64>(2) as u64,
+                outlinks: serde_json::from_str(row.get(3))?,
+                content_hash: row.get(4),
+                redirected_to: row.get(5),
+                etag: row.get(6),
+                last_modified: row.get::<_, Option<i64>>(7).map(|time| time as u64),
+                findings: row
+                    .get::<_, Option<&str>>(8)
+                    .map(serde_json::from_str)
+                    .transpose()?
+                    .unwrap_or_default(),
+                anchors: row
+                    .get::<_, Option<&str>>(9)
+                    .map(serde_json::from_str)
+                    .transpose()?,
+            });
+        }
+
+        Ok(Some(StoredRun {
+            id: id.to_string(),
+            url: run.get(0),
+            started_at: run.get::<_, i64>(1) as u64,
+            labels,
+            links,
+        }))
+    }
+
+    fn run_ids(&self, url: &str) -> Result<Vec<String>, Box<dyn Error>> {
+        let rows = self.client.lock().unwrap().query(
+            "SELECT id FROM inspector_runs WHERE url = $1 ORDER BY started_at, id",
+            &[&url],
+        )?;
+        Ok(rows.iter().map(|row| row.get(0)).collect())
+    }
+
+    fn labeled_run_ids(&self, url: &str, filter: &Labels) -> Result<Vec<String>, Box<dyn Error>> {
+        // Runs having as many of the wanted labels as there are
+        let keys: Vec<&String> = filter.keys().collect();
+        let values: Vec<&String> = filter.values().collect();
+        let rows = self.client.lock().unwrap().query(
+            "SELECT id FROM inspector_runs
+             WHERE url = $1 AND (
+                 SELECT COUNT(*) FROM inspector_run_labels labels
+                 JOIN UNNEST($2::TEXT[], $3::TEXT[]) AS wanted (key, value)
+                     ON labels.key = wanted.key AND labels.value = wanted.value
+                 WHERE labels.run_id = inspector_runs.id
+             ) = $4
+             ORDER BY started_at, id",
+            &[&url, &keys, &values, &(filter.len() as i64)],
+        )?;
+        Ok(rows.iter().map(|row| row.get(0)).collect())
+    }
+}
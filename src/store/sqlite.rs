@@ -0,0 +1,236 @@
+use super::{has_labels, Labels, ScanStore, StoredLink, StoredRun};
+use rusqlite::{params, Connection, OptionalExtension};
+use std::error::Error;
+use std::path::Path;
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS runs (
+    id TEXT PRIMARY KEY,
+    url TEXT NOT NULL,
+    started_at INTEGER NOT NULL
+);
+CREATE TABLE IF NOT EXISTS links (
+    run_id TEXT NOT NULL REFERENCES runs(id) ON DELETE CASCADE,
+    position INTEGER NOT NULL,
+    url TEXT NOT NULL,
+    status TEXT NOT NULL,
+    checked_at INTEGER NOT NULL,
+    outlinks TEXT NOT NULL,
+    content_hash TEXT,
+    redirected_to TEXT,
+    etag TEXT,
+    last_modified INTEGER,
+    findings TEXT,
+    anchors TEXT,
+    PRIMARY KEY (run_id, position)
+);
+CREATE INDEX IF NOT EXISTS runs_by_url ON runs (url, started_at);
+CREATE TABLE IF NOT EXISTS run_labels (
+    run_id TEXT NOT NULL REFERENCES runs(id) ON DELETE CASCADE,
+    key TEXT NOT NULL,
+    value TEXT NOT NULL,
+    PRIMARY KEY (run_id, key)
+);
+";
+
+/// Columns added to `links` after its first release, with their types
+const ADDED_LINK_COLUMNS: [(&str, &str); 4] = [
+    ("etag", "TEXT"),
+    ("last_modified", "INTEGER"),
+    ("findings", "TEXT"),
+    ("anchors", "TEXT"),
+];
+
+/// Store keeping all runs in a single SQLite database
+pub struct SqliteStore {
+    connection: Connection,
+}
+
+impl SqliteStore {
+    /// Open (and create if needed) the database at `path`
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, Box<dyn Error>> {
+        let connection = Connection::open(path)?;
+        connection.execute_batch(SCHEMA)?;
+        // Databases created before a column was added get it here
+        for (column, kind) in ADDED_LINK_COLUMNS {
+            let exists: bool = connection.query_row(
+                "SELECT COUNT(*) > 0 FROM pragma_table_info('links') WHERE name = ?1",
+                params![column],
+                |row| row.get(0),
+            )?;
+            if !exists {
+                connection.execute_batch(&format!(
+                    "ALTER TABLE links ADD COLUMN {} {};",
+                    column, kind
+                ))?;
+            }
+        }
+        Ok(SqliteStore { connection })
+    }
+}
+
+impl ScanStore for SqliteStore {
+    fn save_run(&self, run: &StoredRun) -> Result<(), Box<dyn Error>> {
+        let transaction = self.connection.unchecked_transaction()?;
+        transaction.execute("DELETE FROM links WHERE run_id = ?1", params![run.id])?;
+        transaction.execute("DELETE FROM run_labels WHERE run_id = ?1", params![run.id])?;
+        transaction.execute(
+            "INSERT OR REPLACE INTO runs (id, url, started_at) VALUES (?1, ?2, ?3)",
+            params![run.id, run.url, run.started_at as i64],
+        )?;
+        for (key, value) in &run.labels {
+            transaction.execute(
+                "INSERT INTO run_labels (run_id, key, value) VALUES (?1, ?2, ?3)",
+                params![run.id, key, value],
+            )?;
+        }
+        {
+            let mut insert = transaction.prepare(
+                "INSERT INTO links (run_id, position, url, status, checked_at, outlinks, content_hash, redirected_to, etag, last_modified, findings, anchors)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)",
+            )?;
+            for (position, link) in run.links.iter().enumerate() {
+                insert.execute(params![
+                    run.id,
+                    position as i64,
+                    link.url,
+                    serde_json::to_string(&link.status)?,
+                    link.checked_at as i64,
+                    serde_json::to_string(&link.outlinks)?,
+                    link.content_hash,
+                    link.redirected_to,
+                    link.etag,
+                    link.last_modified.map(|time| time as i64),
+                    serde_json::to_string(&link.findings)?,
+                    link.anchors
+                        .as_ref()
+                        .map(serde_json::to_string)
+                        .transpose()?,
+                ])?;
+            }
+        }
+        transaction.commit()?;
+        Ok(())
+    }
+
+    fn load_run(&self, id: &str) -> Result<Option<StoredRun>, Box<dyn Error>> {
+        let run = self
+            .connection
+            .query_row(
+                "SELECT url, started_at FROM runs WHERE id = ?1",
+                params![id],
+                |row| Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?)),
+            )
+            .optional()?;
+        let (url, started_at) = match run {
+            Some(run) => run,
+            None => return Ok(None),
+        };
+
+        let mut query = self
+            .connection
+            .prepare("SELECT key, value FROM run_labels WHERE run_id = ?1")?;
+        let labels = query
+            .query_map(params![id], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<Result<Labels, _>>()?;
+
+        let mut query = self.connection.prepare(
+            "SELECT url, status, checked_at, outlinks, content_hash, redirected_to, etag, last_modified, findings, anchors
+             FROM links WHERE run_id = ?1 ORDER BY position",
+        )?;
+        let rows = query.query_map(params![id], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, i64>(2)?,
+                row.get::<_, String>(3)?,
+                row.get::<_, Option<String>>(4)?,
+                row.get::<_, Option<String>>(5)?,
+                row.get::<_, Option<String>>(6)?,
+                row.get::<_, Option<i64>>(7)?,
+                row.get::<_, Option<String>>(8)?,
+                row.get::<_, Option<String>>(9)?,
+            ))
+        })?;
+        let mut links = Vec::new();
+        for row in rows {
+            let (
+                url,
+                status,
+                checked_at,
+                outlinks,
+                content_hash,
+                redirected_to,
+                etag,
+                last_modified,
+                findings,
+                anchors,
+            ) = row?;
+            links.push(StoredLink {
+                url,
+                status: serde_json::from_str(&status)?,
+                checked_at: checked_at as u64,
+                outlinks: serde_json::from_str(&outlinks)?,
+                content_hash,
+                redirected_to,
+                etag,
+                last_modified: last_modified.map(|time| time as u64),
+                findings: findings
+                    .map(|findings| serde_json::from_str(&findings))
+                    .transpose()?
+                    .unwrap_or_default(),
+                anchors: anchors
+                    .map(|anchors| serde_json::from_str(&anchors))
+                    .transpose()?,
+            });
+        }
+
+        Ok(Some(StoredRun {
+            id: id.to_string(),
+            url,
+            started_at: started_at as u64,
+            labels,
+            links,
+        }))
+    }
+
+    fn run_ids(&self, url: &str) -> Result<Vec<String>, Box<dyn Error>> {
+        let mut query = self
+            .connection
+            .prepare("SELECT id FROM runs WHERE url = ?1 ORDER BY started_at, id")?;
+        let ids = query
+            .query_map(params![url], |row| row.get(0))?
+            .collect::<Result<Vec<String>, _>>()?;
+        Ok(ids)
+    }
+
+    fn labeled_run_ids(&self, url: &str, filter: &Labels) -> Result<Vec<String>, Box<dyn Error>> {
+        let mut query = self.connection.prepare(
+            "SELECT runs.id, run_labels.key, run_labels.value
+             FROM runs LEFT JOIN run_labels ON run_labels.run_id = runs.id
+             WHERE runs.url = ?1 ORDER BY runs.started_at, runs.id",
+        )?;
+        let rows = query.query_map(params![url], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, Option<String>>(1)?,
+                row.get::<_, Option<String>>(2)?,
+            ))
+        })?;
+        let mut runs: Vec<(String, Labels)> = Vec::new();
+        for row in rows {
+            let (id, key, value) = row?;
+            if runs.last().map(|(last, _)| last) != Some(&id) {
+                runs.push((id, Labels::new()));
+            }
+            if let (Some(key), Some(value), Some((_, labels))) = (key, value, runs.last_mut()) {
+                labels.insert(key, value);
+            }
+        }
+        Ok(runs
+            .into_iter()
+            .filter(|(_, labels)| has_labels(labels, filter))
+            .map(|(id, _)| id)
+            .collect())
+    }
+}
@@ -0,0 +1,169 @@
+use crate::alternates::{alternate_kind, AlternateKind};
+use crate::link::{asset_sources, bare_urls};
+use html5ever::tendril::StrTendril;
+use html5ever::tokenizer::states::RawKind;
+use html5ever::tokenizer::{
+    BufferQueue, CharacterTokens, EndTag, StartTag, Tag, TagToken, Token, TokenSink,
+    TokenSinkResult, Tokenizer, TokenizerOpts,
+};
+use std::collections::HashSet;
+use url::Url;
+
+/// Links of a page, as the crawler needs them
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct PageLinks {
+    /// Targets of anchors in running text
+    pub links: Vec<String>,
+    /// Targets of anchors inside `<pre>` blocks and bare URLs in their text
+    pub code_links: Vec<String>,
+    /// `(hreflang, url)` of `<link rel="alternate" hreflang>` elements
+    pub alternates: Vec<(String, String)>,
+    /// AMP, print and feed versions of the page
+    pub representations: Vec<(AlternateKind, String)>,
+    /// Every `id` and `<a name>` defined on the page, fragments can point at
+    pub anchors: Vec<String>,
+    /// Images, scripts, stylesheets and media the page loads, as
+    /// [`extract_asset_links`](crate::link::extract_asset_links) finds them
+    pub assets: Vec<String>,
+}
+
+/// Extract the links of a page from the HTML token stream, without building a
+/// DOM. Gives the same links as [`partition_code_links`](crate::link::partition_code_links)
+/// and the alternates [`LanguageFilter`](crate::lang::LanguageFilter) records,
+/// at a fraction of the cost on large pages; checks that need the document
+/// cannot run on it.
+pub fn stream_links(html: &str, base_url: &str) -> PageLinks {
+    let Ok(base) = Url::parse(base_url) else {
+        return PageLinks::default();
+    };
+    let mut input = BufferQueue::new();
+    input.push_back(StrTendril::from_slice(html));
+    let mut tokenizer = Tokenizer::new(
+        LinkSink {
+            base,
+            pre_depth: 0,
+            pre_text: String::new(),
+            pre_urls: Vec::new(),
+            page: PageLinks::default(),
+        },
+        TokenizerOpts::default(),
+    );
+    let _ = tokenizer.feed(&mut input);
+    tokenizer.end();
+
+    let sink = tokenizer.sink;
+    let mut page = sink.page;
+    // Bare URLs follow the anchors, as in the DOM extraction
+    page.code_links.extend(sink.pre_urls);
+    for list in [&mut page.links, &mut page.code_links, &mut page.assets] {
+        let mut seen = HashSet::new();
+        list.retain(|url| seen.insert(url.clone()));
+    }
+    let mut seen = HashSet::new();
+    page.representations
+        .retain(|representation| seen.insert(representation.clone()));
+    page
+}
+
+/// Token sink collecting links, tracking whether the tokens are inside `<pre>`
+struct LinkSink {
+    base: Url,
+    /// Number of open `<pre>` elements
+    pre_depth: usize,
+    /// Text of the outermost open `<pre>` element
+    pre_text: String,
+    /// Bare URLs found in the text of `<pre>` elements
+    pre_urls: Vec<String>,
+    page: PageLinks,
+}
+
+impl LinkSink {
+    fn start_tag(&mut self, tag: &Tag) -> TokenSinkResult<()> {
+        let attr = |name: &str| {
+            tag.attrs
+                .iter()
+                .find(|attr| &*attr.name.local == name)
+                .map(|attr| &*attr.value)
+        };
+        if let Some(id) = attr("id") {
+            self.page.anchors.push(id.to_string());
+        }
+        for source in asset_sources(&tag.name, attr) {
+            if let Ok(url) = self.base.join(source.trim()) {
+                self.page.assets.push(String::from(url));
+            }
+        }
+        if &*tag.name == "link" {
+            if let Some(kind) = alternate_kind(attr) {
+                if let Some(url) = attr("href").and_then(|href| self.base.join(href.trim()).ok()) {
+                    self.page.representations.push((kind, String::from(url)));
+                }
+            }
+        }
+        match &*tag.name {
+            "a" => {
+                if let Some(name) = attr("name") {
+                    self.page.anchors.push(name.to_string());
+                }
+                if let Some(url) = attr("href").and_then(|href| self.base.join(href).ok()) {
+                    let list = if self.pre_depth > 0 {
+                        &mut self.page.code_links
+                    } else {
+                        &mut self.page.links
+                    };
+                    list.push(String::from(url));
+                }
+            }
+            "pre" if !tag.self_closing => self.pre_depth += 1,
+            "link" if attr("rel") == Some("alternate") => {
+                if let (Some(lang), Some(href)) = (attr("hreflang"), attr("href")) {
+                    if let Ok(url) = self.base.join(href) {
+                        self.page
+                            .alternates
+                            .push((lang.to_string(), url.to_string()));
+                    }
+                }
+            }
+            // Elements whose content is text, as a tree builder would switch to
+            "script" => return TokenSinkResult::RawData(RawKind::ScriptData),
+            "style" | "xmp" | "iframe" | "noembed" | "noframes" | "noscript" => {
+                return TokenSinkResult::RawData(RawKind::Rawtext)
+            }
+            "title" | "textarea" => return TokenSinkResult::RawData(RawKind::Rcdata),
+            _ => {}
+        }
+        TokenSinkResult::Continue
+    }
+
+    fn end_tag(&mut self, tag: &Tag) {
+        if &*tag.name == "pre" && self.pre_depth > 0 {
+            self.pre_depth -= 1;
+            if self.pre_depth == 0 {
+                let text = std::mem::take(&mut self.pre_text);
+                self.pre_urls.extend(bare_urls(&text));
+            }
+        }
+    }
+}
+
+impl TokenSink for LinkSink {
+    type Handle = ();
+
+    fn process_token(&mut self, token: Token, _line_number: u64) -> TokenSinkResult<()> {
+        match token {
+            TagToken(tag) if tag.kind == StartTag => return self.start_tag(&tag),
+            TagToken(tag) if tag.kind == EndTag => self.end_tag(&tag),
+            CharacterTokens(text) if self.pre_depth > 0 => self.pre_text.push_str(&text),
+            _ => {}
+        }
+        TokenSinkResult::Continue
+    }
+
+    fn end(&mut self) {
+        // An unclosed `<pre>` runs to the end of the document
+        if self.pre_depth > 0 {
+            let text = std::mem::take(&mut self.pre_text);
+            self.pre_urls.extend(bare_urls(&text));
+        }
+    }
+}
@@ -0,0 +1,336 @@
+use crate::check::Severity;
+use crate::config::ReportConfig;
+use crate::link::{LinkInfo, LinkStatus};
+use crate::locale::Messages;
+use crate::output::ScanReport;
+use std::collections::{BTreeMap, HashSet};
+use url::Url;
+
+/// Maximum number of action items printed before the rest is summarized
+const MAX_ACTIONS: usize = 5;
+
+/// A prioritized next step derived from the scan results
+#[derive(Debug, Clone, PartialEq)]
+pub struct ActionItem {
+    pub severity: Severity,
+    pub message: String,
+    pub count: usize,
+    pub score: u64,
+}
+
+/// Weight of a severity when ranking action items
+fn severity_weight(severity: Severity) -> u64 {
+    match severity {
+        Severity::Error => 100,
+        Severity::Warning => 10,
+        Severity::Info => 1,
+    }
+}
+
+/// Path of a URL for display, falling back to the value as is (e.g. `file.md:12`)
+fn display_path(url: &str) -> String {
+    Url::parse(url)
+        .ok()
+        .filter(|url| matches!(url.scheme(), "http" | "https"))
+        .map(|url| url.path().to_string())
+        .unwrap_or_else(|| url.to_string())
+}
+
+/// Kind of a broken link status, as passed to the `action-fix-broken` message
+fn broken_kind(link: &LinkInfo) -> &'static str {
+    match link.status {
+        LinkStatus::NotFound => "not-found",
+        _ => "other",
+    }
+}
+
+#[derive(Default)]
+struct Group<'a> {
+    count: usize,
+    /// Distinct pages linking to the items of the group
+    inlinks: HashSet<&'a str>,
+    min_depth: usize,
+}
+
+impl<'a> Group<'a> {
+    fn add(&mut self, sources: &'a [String], depth: usize) {
+        self.min_depth = if self.count == 0 {
+            depth
+        } else {
+            self.min_depth.min(depth)
+        };
+        self.count += 1;
+        self.inlinks.extend(sources.iter().map(String::as_str));
+    }
+
+    /// Severity first, then how many pages are affected, favoring shallow pages
+    fn score(&self, severity: Severity) -> u64 {
+        severity_weight(severity) * (self.count + self.inlinks.len()) as u64 * 10
+            / (self.min_depth as u64 + 1)
+    }
+}
+
+/// Build the prioritized list of action items for a report.
+///
+/// Broken links are grouped by each page they were found on and findings by
+/// rule and page. Groups are ranked by severity, inlink count and crawl depth.
+pub fn action_plan(report: &ScanReport, messages: &Messages) -> Vec<ActionItem> {
+    let mut broken: BTreeMap<(&'static str, String), Group> = BTreeMap::new();
+    for link in report.links.iter().filter(|link| link.is_broken()) {
+        let pages: Vec<String> = if link.sources.is_empty() {
+            vec![display_path(&link.url)]
+        } else {
+            link.sources
+                .iter()
+                .map(|source| display_path(source))
+                .collect()
+        };
+        for page in pages {
+            broken
+                .entry((broken_kind(link), page))
+                .or_default()
+                .add(&link.sources, link.depth);
+        }
+    }
+
+    let depths: BTreeMap<&str, usize> = report
+        .links
+        .iter()
+        .map(|link| (link.url.as_str(), link.depth))
+        .collect();
+    let mut findings: BTreeMap<(Severity, &str, String), Group> = BTreeMap::new();
+    for finding in &report.findings {
+        let depth = depths.get(finding.page.as_str()).copied().unwrap_or(0);
+        findings
+            .entry((
+                finding.severity,
+                finding.rule.as_str(),
+                display_path(&finding.page),
+            ))
+            .or_default()
+            .add(&[], depth);
+    }
+
+    let mut actions: Vec<ActionItem> = broken
+        .into_iter()
+        .map(|((kind, page), group)| ActionItem {
+            severity: Severity::Error,
+            message: messages.get(
+                "action-fix-broken",
+                &[
+                    ("count", &group.count),
+                    ("kind", &kind),
+                    ("page", &page),
+                    ("inlinks", &group.inlinks.len()),
+                ],
+            ),
+            count: group.count,
+            score: group.score(Severity::Error),
+        })
+        .chain(
+            findings
+                .into_iter()
+                .map(|((severity, rule, page), group)| ActionItem {
+                    severity,
+                    message: messages.get(
+                        "action-resolve-findings",
+                        &[
+                            ("count", &group.count),
+                            ("rule", &rule),
+                            ("severity", &severity),
+                            ("page", &page),
+                        ],
+                    ),
+                    count: group.count,
+                    score: group.score(severity),
+                }),
+        )
+        .collect();
+
+    actions.sort_by(|a, b| {
+        b.severity
+            .cmp(&a.severity)
+            .then(b.score.cmp(&a.score))
+            .then(a.message.cmp(&b.message))
+    });
+    actions
+}
+
+/// Render the human summary printed at the end of a run
+pub fn render_summary(report: &ScanReport, messages: &Messages) -> String {
+    let broken = report.links.iter().filter(|link| link.is_broken()).count();
+    let count = |severity| {
+        report
+            .findings
+            .iter()
+            .filter(|finding| finding.severity == severity)
+            .count()
+    };
+    let mut lines = vec![messages.get(
+        "summary-totals",
+        &[
+            ("links", &report.links.len()),
+            ("broken", &broken),
+            ("findings", &report.findings.len()),
+            ("errors", &count(Severity::Error)),
+            ("warnings", &count(Severity::Warning)),
+            ("info", &count(Severity::Info)),
+        ],
+    )];
+
+    if !report.sections.is_empty() {
+        lines.push(messages.text("summary-sections"));
+        for section in &report.sections {
+            let health = format!("{:.1}", section.health);
+            let line = messages.get(
+                "summary-section",
+                &[
+                    ("label", &section.label),
+                    ("pages", &section.pages),
+                    ("links", &section.links),
+                    ("broken", &section.broken),
+                    ("health", &health),
+                ],
+            );
+            lines.push(format!("  {}", line));
+        }
+    }
+
+    let actions = action_plan(report, messages);
+    if actions.is_empty() {
+        lines.push(messages.text("summary-nothing-to-fix"));
+        return lines.join("\n");
+    }
+
+    lines.push(messages.text("summary-next-steps"));
+    for (index, action) in actions.iter().take(MAX_ACTIONS).enumerate() {
+        lines.push(format!(
+            "  {}. [{}] {}",
+            index + 1,
+            messages.get("severity", &[("severity", &action.severity)]),
+            action.message
+        ));
+    }
+
+    let mut deferred: BTreeMap<Severity, usize> = BTreeMap::new();
+    for action in actions.iter().skip(MAX_ACTIONS) {
+        *deferred.entry(action.severity).or_default() += action.count;
+    }
+    if !deferred.is_empty() {
+        let parts: Vec<String> = deferred
+            .iter()
+            .rev()
+            .map(|(severity, count)| {
+                messages.get(
+                    "summary-deferred",
+                    &[("count", count), ("severity", severity)],
+                )
+            })
+            .collect();
+        let issues = parts.join(&messages.text("summary-deferred-separator"));
+        lines.push(format!(
+            "  {}",
+            messages.get("summary-later", &[("issues", &issues)])
+        ));
+    }
+
+    lines.join("\n")
+}
+
+/// Header lines of a branded Markdown report: logo and title, then the
+/// configured links
+fn markdown_branding(branding: &ReportConfig) -> Vec<String> {
+    let mut lines = Vec::new();
+    let logo = branding
+        .logo_url
+        .as_ref()
+        .map(|url| format!("<img src=\"{}\" alt=\"\" height=\"24\"> ", url));
+    match (&branding.title, logo) {
+        (Some(title), logo) => lines.push(format!("## {}{}", logo.unwrap_or_default(), title)),
+        (None, Some(logo)) => lines.push(logo.trim_end().to_string()),
+        (None, None) => {}
+    }
+    if let Some(links) = branding.links.as_ref().filter(|links| !links.is_empty()) {
+        let links: Vec<String> = links
+            .iter()
+            .map(|link| format!("[{}]({})", link.label, link.url))
+            .collect();
+        lines.push(links.join(" · "));
+    }
+    if !lines.is_empty() {
+        lines.push(String::new());
+    }
+    lines
+}
+
+/// Render the summary as Markdown, e.g. for a GitHub check run, headed by
+/// the configured branding
+pub fn render_markdown_summary(
+    report: &ScanReport,
+    branding: &ReportConfig,
+    messages: &Messages,
+) -> String {
+    let broken = report.links.iter().filter(|link| link.is_broken()).count();
+    let mut lines = markdown_branding(branding);
+    lines.push(messages.get(
+        "markdown-totals",
+        &[
+            ("links", &report.links.len()),
+            ("broken", &broken),
+            ("findings", &report.findings.len()),
+        ],
+    ));
+
+    let actions = action_plan(report, messages);
+    if !actions.is_empty() {
+        lines.push(String::new());
+        lines.push(format!("### {}", messages.text("markdown-next-steps")));
+        lines.push(String::new());
+        for action in actions.iter().take(MAX_ACTIONS) {
+            lines.push(format!(
+                "- **{}**: {}",
+                messages.get("severity", &[("severity", &action.severity)]),
+                action.message
+            ));
+        }
+        if actions.len() > MAX_ACTIONS {
+            let more = actions.len() - MAX_ACTIONS;
+            lines.push(format!(
+                "- {}",
+                messages.get("markdown-more", &[("count", &more)])
+            ));
+        }
+    }
+
+    let broken_links: Vec<&LinkInfo> = report
+        .links
+        .iter()
+        .filter(|link| link.is_broken())
+        .collect();
+    if !broken_links.is_empty() {
+        lines.push(String::new());
+        lines.push(format!("### {}", messages.text("markdown-broken-links")));
+        lines.push(String::new());
+        lines.push(format!(
+            "| {} | {} | {} |",
+            messages.text("column-link"),
+            messages.text("column-status"),
+            messages.text("column-found-on")
+        ));
+        lines.push("|------|--------|----------|".to_string());
+        for link in broken_links {
+            lines.push(format!(
+                "| {} | {:?} | {} |",
+                link.url,
+                link.status,
+                link.sources
+                    .iter()
+                    .map(|source| display_path(source))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ));
+        }
+    }
+
+    lines.join("\n")
+}
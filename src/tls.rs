@@ -0,0 +1,76 @@
+use crate::config::TlsConfig;
+use reqwest::{blocking, Certificate, Identity};
+use std::error::Error;
+use std::fs;
+
+/// The certificates of the `tls` settings, read once and applied to every
+/// client built for a scan
+#[derive(Clone, Default)]
+pub struct TlsSettings {
+    roots: Vec<Certificate>,
+    identity: Option<Identity>,
+    insecure: bool,
+}
+
+impl TlsSettings {
+    /// Read the certificate authorities and client certificate of `config`,
+    /// rejecting unreadable files and invalid PEM
+    pub fn load(config: &TlsConfig) -> Result<Self, Box<dyn Error>> {
+        let read =
+            |path: &str| fs::read(path).map_err(|e| format!("Could not read {}: {}", path, e));
+        let roots = match &config.ca_cert {
+            Some(path) => Certificate::from_pem_bundle(&read(path)?)
+                .map_err(|e| format!("{} is not a PEM certificate: {}", path, e))?,
+            None => Vec::new(),
+        };
+        let identity = match (&config.client_cert, &config.client_key) {
+            (Some(cert), Some(key)) => {
+                let key_pem = read(key)?;
+                if !String::from_utf8_lossy(&key_pem).contains("BEGIN PRIVATE KEY") {
+                    return Err(format!(
+                        "{} is not a PKCS#8 PEM key; convert it with `openssl pkcs8 -topk8 -nocrypt -in {} -out key.pem`",
+                        key, key
+                    )
+                    .into());
+                }
+                Some(
+                    Identity::from_pkcs8_pem(&read(cert)?, &key_pem)
+                        .map_err(|e| format!("Invalid client certificate {}: {}", cert, e))?,
+                )
+            }
+            (None, None) => None,
+            _ => return Err("tls.client_cert and tls.client_key must be set together".into()),
+        };
+        Ok(TlsSettings {
+            roots,
+            identity,
+            insecure: config.insecure.unwrap_or(false),
+        })
+    }
+
+    /// Apply the settings to a blocking client
+    pub fn apply(&self, mut builder: blocking::ClientBuilder) -> blocking::ClientBuilder {
+        for root in &self.roots {
+            builder = builder.add_root_certificate(root.clone());
+        }
+        if let Some(identity) = &self.identity {
+            builder = builder.identity(identity.clone());
+        }
+        builder
+            .danger_accept_invalid_certs(self.insecure)
+            .danger_accept_invalid_hostnames(self.insecure)
+    }
+
+    /// Same as [`TlsSettings::apply`], for the async client
+    pub fn apply_async(&self, mut builder: reqwest::ClientBuilder) -> reqwest::ClientBuilder {
+        for root in &self.roots {
+            builder = builder.add_root_certificate(root.clone());
+        }
+        if let Some(identity) = &self.identity {
+            builder = builder.identity(identity.clone());
+        }
+        builder
+            .danger_accept_invalid_certs(self.insecure)
+            .danger_accept_invalid_hostnames(self.insecure)
+    }
+}
@@ -0,0 +1,145 @@
+use crate::check::{document_anchors, Check, Finding, Page, Severity};
+use crate::config::TocConfig;
+use scraper::{ElementRef, Selector};
+use std::collections::HashSet;
+
+/// Rule id of table of contents findings
+pub const TOC_RULE: &str = "toc";
+/// Deepest heading level that must be listed when `toc.max_level` is not set
+pub const DEFAULT_TOC_MAX_LEVEL: usize = 3;
+
+/// Compares hand-written tables of contents with the headings of their
+/// page: every entry must link to a heading, and every `<h2>` down to
+/// `max_level` must have an entry. Pages without a table of contents are
+/// left alone.
+pub struct TocCheck {
+    selector: Selector,
+    max_level: usize,
+}
+
+impl TocCheck {
+    /// Parse the `toc` settings, rejecting invalid selectors
+    pub fn new(config: &TocConfig) -> Result<Self, String> {
+        let selector = Selector::parse(&config.selector)
+            .map_err(|e| format!("toc.selector is not a valid selector: {:?}", e))?;
+        Ok(TocCheck {
+            selector,
+            max_level: config.max_level.unwrap_or(DEFAULT_TOC_MAX_LEVEL),
+        })
+    }
+
+    fn finding(&self, page: &Page, message: String, target: Option<String>) -> Finding {
+        Finding {
+            rule: TOC_RULE.to_string(),
+            severity: Severity::Warning,
+            page: page.url.to_string(),
+            message,
+            target,
+            owner: None,
+            other_pages: Vec::new(),
+        }
+    }
+}
+
+/// Level of a heading element, `None` for other elements
+fn heading_level(element: &ElementRef) -> Option<usize> {
+    let name = element.value().name();
+    let level = name.strip_prefix('h')?.parse().ok()?;
+    (1..=6).contains(&level).then_some(level)
+}
+
+/// Text of a heading with its whitespace collapsed
+fn heading_text(element: &ElementRef) -> String {
+    let text = element.text().collect::<String>();
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+impl Check for TocCheck {
+    fn id(&self) -> &str {
+        TOC_RULE
+    }
+
+    fn check(&self, page: &Page) -> Vec<Finding> {
+        let tocs: Vec<ElementRef> = page.document.select(&self.selector).collect();
+        if tocs.is_empty() {
+            return Vec::new();
+        }
+        let links = Selector::parse("a[href]").unwrap();
+        let headings = Selector::parse("h1, h2, h3, h4, h5, h6").unwrap();
+        let anchors = document_anchors(page.document);
+        let in_toc = |element: &ElementRef| {
+            element
+                .ancestors()
+                .any(|ancestor| tocs.iter().any(|toc| toc.id() == ancestor.id()))
+        };
+
+        // Fragments a heading can be reached at: its id, or that of an
+        // element inside it, such as a permalink anchor
+        let mut heading_fragments: Vec<(ElementRef, Vec<String>)> = Vec::new();
+        for heading in page.document.select(&headings) {
+            let fragments = heading
+                .descendants()
+                .filter_map(ElementRef::wrap)
+                .filter_map(|element| {
+                    let element = element.value();
+                    element
+                        .attr("id")
+                        .or_else(|| element.attr("name").filter(|_| element.name() == "a"))
+                })
+                .map(String::from)
+                .collect();
+            heading_fragments.push((heading, fragments));
+        }
+        let heading_ids: HashSet<&str> = heading_fragments
+            .iter()
+            .flat_map(|(_, fragments)| fragments.iter().map(String::as_str))
+            .collect();
+
+        let mut findings = Vec::new();
+        let mut listed = HashSet::new();
+        for fragment in tocs
+            .iter()
+            .flat_map(|toc| toc.select(&links))
+            .filter_map(|link| link.value().attr("href"))
+            .filter_map(|href| href.strip_prefix('#'))
+            .filter(|fragment| !fragment.is_empty())
+        {
+            if !listed.insert(fragment) || heading_ids.contains(fragment) {
+                continue;
+            }
+            let message = if anchors.contains(fragment) {
+                format!("Table of contents entry #{} is not a heading", fragment)
+            } else {
+                format!(
+                    "Table of contents entry #{} does not exist on the page",
+                    fragment
+                )
+            };
+            findings.push(self.finding(page, message, Some(format!("#{}", fragment))));
+        }
+
+        for (heading, fragments) in &heading_fragments {
+            let Some(level) = heading_level(heading) else {
+                continue;
+            };
+            if level < 2 || level > self.max_level || in_toc(heading) {
+                continue;
+            }
+            if !fragments
+                .iter()
+                .any(|fragment| listed.contains(fragment.as_str()))
+            {
+                findings.push(self.finding(
+                    page,
+                    format!(
+                        "Heading \"{}\" (h{}) is missing from the table of contents",
+                        heading_text(heading),
+                        level
+                    ),
+                    fragments.first().map(|fragment| format!("#{}", fragment)),
+                ));
+            }
+        }
+        findings
+    }
+}
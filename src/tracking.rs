@@ -0,0 +1,87 @@
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+/// Script and beacon URLs of common analytics and telemetry services, with
+/// the name of the service
+pub const DEFAULT_TRACKING_PATTERNS: [(&str, &str); 12] = [
+    (
+        "Google Analytics",
+        r"^https?://([^/]+\.)?google-analytics\.com/",
+    ),
+    ("Google Analytics", r"^https?://analytics\.google\.com/"),
+    (
+        "Google Tag Manager",
+        r"^https?://(www\.)?googletagmanager\.com/",
+    ),
+    ("Segment", r"^https?://(cdn|api)\.segment\.(com|io)/"),
+    (
+        "PostHog",
+        r"^https?://([a-z0-9-]+\.)*(i\.posthog\.com|app\.posthog\.com)/",
+    ),
+    ("Plausible", r"^https?://plausible\.io/(js|api)/"),
+    (
+        "Mixpanel",
+        r"^https?://(cdn|api|api-js)\.(mxpnl|mixpanel)\.com/",
+    ),
+    ("Amplitude", r"^https?://(cdn|api|api2)\.amplitude\.com/"),
+    ("Hotjar", r"^https?://(static|script|vars)\.hotjar\.com/"),
+    ("Meta Pixel", r"^https?://connect\.facebook\.net/"),
+    ("Microsoft Clarity", r"^https?://(www\.)?clarity\.ms/"),
+    (
+        "Matomo",
+        r"^https?://[^?#]+/(matomo|piwik)\.(js|php)([?#]|$)",
+    ),
+];
+
+/// An analytics or telemetry endpoint loaded by pages, listed instead of
+/// being checked
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TrackingEndpoint {
+    pub url: String,
+    /// Service the endpoint belongs to, for the built-in patterns
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub service: Option<String>,
+    /// Pages loading it
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub sources: Vec<String>,
+}
+
+/// Patterns of tracking endpoints: the built-in ones and those of
+/// `tracking_urls`
+#[derive(Debug, Clone)]
+pub struct TrackingEndpoints {
+    patterns: Vec<(Option<String>, Regex)>,
+}
+
+impl Default for TrackingEndpoints {
+    fn default() -> Self {
+        TrackingEndpoints::new(&[]).expect("built-in tracking patterns compile")
+    }
+}
+
+impl TrackingEndpoints {
+    /// The built-in patterns and `extra` ones, rejecting invalid regexes
+    pub fn new(extra: &[String]) -> Result<Self, regex::Error> {
+        let built_in = DEFAULT_TRACKING_PATTERNS
+            .iter()
+            .map(|(service, pattern)| (Some(service.to_string()), *pattern));
+        let patterns = built_in
+            .chain(extra.iter().map(|pattern| (None, pattern.as_str())))
+            .map(|(service, pattern)| Ok((service, Regex::new(pattern)?)))
+            .collect::<Result<_, regex::Error>>()?;
+        Ok(TrackingEndpoints { patterns })
+    }
+
+    /// The endpoint `url` is, if it matches a pattern, with no sources yet
+    pub fn find(&self, url: &str) -> Option<TrackingEndpoint> {
+        let (service, _) = self
+            .patterns
+            .iter()
+            .find(|(_, pattern)| pattern.is_match(url))?;
+        Some(TrackingEndpoint {
+            url: url.to_string(),
+            service: service.clone(),
+            sources: Vec::new(),
+        })
+    }
+}
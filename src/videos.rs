@@ -0,0 +1,209 @@
+use crate::check::{Check, Finding, Page, Severity};
+use crate::link::USER_AGENT;
+use log::debug;
+use reqwest::blocking::Client;
+use scraper::Selector;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+use url::Url;
+
+/// Time allowed for one oEmbed request
+const OEMBED_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Site hosting a video
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum VideoHost {
+    YouTube,
+    Vimeo,
+}
+
+/// A video on YouTube or Vimeo, linked or embedded
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Video {
+    pub host: VideoHost,
+    pub id: String,
+}
+
+impl Video {
+    /// Parse a watch, short, embed or player URL of YouTube or Vimeo
+    pub fn parse(url: &str) -> Option<Self> {
+        let url = Url::parse(url).ok()?;
+        let host = url.host_str()?.to_ascii_lowercase();
+        let host = host.strip_prefix("www.").unwrap_or(&host);
+        let segments: Vec<&str> = url.path_segments()?.filter(|s| !s.is_empty()).collect();
+        let (video_host, id) = match (host, segments.as_slice()) {
+            ("youtube.com" | "m.youtube.com", ["watch"]) => (
+                VideoHost::YouTube,
+                url.query_pairs()
+                    .find(|(name, _)| name == "v")
+                    .map(|(_, id)| id.into_owned())?,
+            ),
+            ("youtube.com" | "m.youtube.com", ["shorts" | "embed" | "live", id])
+            | ("youtube-nocookie.com", ["embed", id])
+            | ("youtu.be", [id]) => (VideoHost::YouTube, id.to_string()),
+            ("vimeo.com", [id, ..]) | ("player.vimeo.com", ["video", id]) => {
+                (VideoHost::Vimeo, id.to_string())
+            }
+            _ => return None,
+        };
+        let valid = match video_host {
+            VideoHost::YouTube => {
+                !id.is_empty()
+                    && id
+                        .chars()
+                        .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+            }
+            VideoHost::Vimeo => !id.is_empty() && id.chars().all(|c| c.is_ascii_digit()),
+        };
+        valid.then_some(Video {
+            host: video_host,
+            id,
+        })
+    }
+
+    /// Canonical page of the video
+    pub fn page_url(&self) -> String {
+        match self.host {
+            VideoHost::YouTube => format!("https://www.youtube.com/watch?v={}", self.id),
+            VideoHost::Vimeo => format!("https://vimeo.com/{}", self.id),
+        }
+    }
+
+    /// oEmbed endpoint describing the video
+    pub fn oembed_url(&self) -> String {
+        let endpoint = match self.host {
+            VideoHost::YouTube => "https://www.youtube.com/oembed?format=json",
+            VideoHost::Vimeo => "https://vimeo.com/api/oembed.json",
+        };
+        let mut url = Url::parse(endpoint).expect("oEmbed endpoints are valid");
+        url.query_pairs_mut().append_pair("url", &self.page_url());
+        url.to_string()
+    }
+}
+
+/// What the oEmbed endpoint says about a video
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Availability {
+    Public,
+    /// Removed, or never existed
+    Removed,
+    /// Private, or restricted from being embedded
+    Private,
+}
+
+/// Availability of a video from the status of its oEmbed response, or `None`
+/// when the status says nothing about the video
+pub fn oembed_availability(status: u16) -> Option<Availability> {
+    match status {
+        200 => Some(Availability::Public),
+        400 | 404 => Some(Availability::Removed),
+        401 | 403 => Some(Availability::Private),
+        _ => None,
+    }
+}
+
+/// Verifies linked and embedded YouTube and Vimeo videos through the oEmbed
+/// endpoints of the hosts, whose watch pages answer `200 OK` even for removed
+/// and private videos. Each video is looked up once per scan.
+#[derive(Default)]
+pub struct VideoCheck {
+    client: OnceLock<Result<Client, String>>,
+    videos: Mutex<HashMap<String, Result<Availability, String>>>,
+}
+
+impl VideoCheck {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn lookup(&self, video: &Video) -> Result<Availability, String> {
+        let oembed_url = video.oembed_url();
+        if let Some(availability) = self.videos.lock().unwrap().get(&oembed_url) {
+            return availability.clone();
+        }
+        let availability = self.fetch(&oembed_url);
+        self.videos
+            .lock()
+            .unwrap()
+            .insert(oembed_url, availability.clone());
+        availability
+    }
+
+    fn fetch(&self, oembed_url: &str) -> Result<Availability, String> {
+        let client = self.client.get_or_init(|| {
+            Client::builder()
+                .timeout(OEMBED_TIMEOUT)
+                .user_agent(USER_AGENT)
+                .build()
+                .map_err(|e| e.to_string())
+        });
+        let client = client.as_ref().map_err(Clone::clone)?;
+        debug!("Looking up {}", oembed_url);
+        let response = client.get(oembed_url).send().map_err(|e| e.to_string())?;
+        oembed_availability(response.status().as_u16()).ok_or_else(|| response.status().to_string())
+    }
+}
+
+impl Check for VideoCheck {
+    fn id(&self) -> &str {
+        "videos"
+    }
+
+    fn enabled_by_default(&self) -> bool {
+        false
+    }
+
+    fn check(&self, page: &Page) -> Vec<Finding> {
+        let iframes = Selector::parse("iframe[src]").unwrap();
+        let base = Url::parse(page.url).ok();
+        let embedded = page
+            .document
+            .select(&iframes)
+            .filter_map(|element| element.value().attr("src"))
+            .filter_map(|src| Some(base.as_ref()?.join(src).ok()?.to_string()))
+            .map(|src| (src, true));
+        let linked = page.links.iter().map(|link| (link.clone(), false));
+
+        let mut reported = HashSet::new();
+        embedded
+            .chain(linked)
+            .filter_map(|(target, embedded)| Some((Video::parse(&target)?, target, embedded)))
+            .filter(|(video, _, _)| reported.insert(video.clone()))
+            .filter_map(|(video, target, embedded)| {
+                let (severity, message) = match self.lookup(&video) {
+                    Ok(Availability::Public) => return None,
+                    Ok(Availability::Removed) => (
+                        Severity::Error,
+                        format!("Video {} was removed or does not exist", video.page_url()),
+                    ),
+                    // Videos that cannot be embedded can still be watched from a link
+                    Ok(Availability::Private) => (
+                        if embedded {
+                            Severity::Error
+                        } else {
+                            Severity::Warning
+                        },
+                        format!(
+                            "Video {} is private or cannot be embedded",
+                            video.page_url()
+                        ),
+                    ),
+                    Err(reason) => (
+                        Severity::Warning,
+                        format!("Could not look video {} up: {}", video.page_url(), reason),
+                    ),
+                };
+                Some(Finding {
+                    rule: self.id().to_string(),
+                    severity,
+                    page: page.url.to_string(),
+                    message,
+                    target: Some(target),
+                    owner: None,
+                    other_pages: Vec::new(),
+                })
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,180 @@
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use hmac::{Hmac, Mac};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::error::Error;
+use std::io::Read;
+use thiserror::Error;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Environment variable holding the shared webhook secret
+pub const WEBHOOK_SECRET_ENV: &str = "INSPECTOR_WEBHOOK_SECRET";
+
+/// Largest webhook body read, in bytes; deploy events are far smaller
+pub const MAX_WEBHOOK_BODY: u64 = 1 << 20;
+
+/// Why a webhook request was rejected
+#[derive(Error, Debug)]
+pub enum WebhookError {
+    #[error("request is neither a GitHub nor a Netlify webhook")]
+    UnknownSource,
+    #[error("webhook signature does not match")]
+    InvalidSignature,
+    #[error("invalid webhook payload: {0}")]
+    InvalidPayload(String),
+    #[error("webhook body is larger than {} bytes", MAX_WEBHOOK_BODY)]
+    TooLarge,
+    #[error("could not read the webhook body: {0}")]
+    Unreadable(String),
+}
+
+/// Read the body of a webhook request, refusing it beyond [`MAX_WEBHOOK_BODY`]
+pub fn read_webhook_body(reader: impl Read) -> Result<Vec<u8>, WebhookError> {
+    let mut body = Vec::new();
+    reader
+        .take(MAX_WEBHOOK_BODY + 1)
+        .read_to_end(&mut body)
+        .map_err(|e| WebhookError::Unreadable(e.to_string()))?;
+    if body.len() as u64 > MAX_WEBHOOK_BODY {
+        return Err(WebhookError::TooLarge);
+    }
+    Ok(body)
+}
+
+/// Verify and parse a deploy webhook.
+///
+/// GitHub requests are recognized by `X-GitHub-Event` and signed with
+/// `X-Hub-Signature-256`; Netlify requests carry a JWT in `X-Webhook-Signature`.
+/// Returns `None` for verified events that should not trigger a scan (pings,
+/// failed deploys, other event types).
+pub fn receive_webhook(
+    secret: &str,
+    header: impl Fn(&str) -> Option<String>,
+    body: &[u8],
+) -> Result<Option<DeployEvent>, WebhookError> {
+    let invalid_payload = |e: Box<dyn Error>| WebhookError::InvalidPayload(e.to_string());
+
+    if let Some(event) = header("X-GitHub-Event") {
+        let signature = header("X-Hub-Signature-256").unwrap_or_default();
+        if !verify_github_signature(secret, body, &signature) {
+            return Err(WebhookError::InvalidSignature);
+        }
+        return match event.as_str() {
+            "deployment_status" => parse_github_deployment(body).map_err(invalid_payload),
+            _ => Ok(None),
+        };
+    }
+
+    if let Some(token) = header("X-Webhook-Signature") {
+        if !verify_netlify_signature(secret, body, &token) {
+            return Err(WebhookError::InvalidSignature);
+        }
+        return parse_netlify_deploy(body).map_err(invalid_payload);
+    }
+
+    Err(WebhookError::UnknownSource)
+}
+
+/// A successful deploy announced by a webhook
+#[derive(Debug, Clone, PartialEq)]
+pub struct DeployEvent {
+    /// URL of the freshly deployed site
+    pub url: String,
+    /// `owner/repo` the deploy was built from, when known
+    pub repository: Option<String>,
+    /// Commit the deploy was built from, when known
+    pub sha: Option<String>,
+}
+
+/// Verify a GitHub `X-Hub-Signature-256` header (`sha256=<hex hmac>`)
+pub fn verify_github_signature(secret: &str, body: &[u8], header: &str) -> bool {
+    let Some(signature) = header.strip_prefix("sha256=").and_then(decode_hex) else {
+        return false;
+    };
+    let mut mac = HmacSha256::new_from_slice(secret.as_bytes()).expect("HMAC accepts any key");
+    mac.update(body);
+    mac.verify_slice(&signature).is_ok()
+}
+
+/// Verify a Netlify `X-Webhook-Signature` header, an HS256 JWT whose `sha256`
+/// claim is the hash of the body
+pub fn verify_netlify_signature(secret: &str, body: &[u8], token: &str) -> bool {
+    let parts: Vec<&str> = token.split('.').collect();
+    let [header, payload, signature] = parts.as_slice() else {
+        return false;
+    };
+    let Ok(signature) = URL_SAFE_NO_PAD.decode(signature) else {
+        return false;
+    };
+    let mut mac = HmacSha256::new_from_slice(secret.as_bytes()).expect("HMAC accepts any key");
+    mac.update(format!("{}.{}", header, payload).as_bytes());
+    if mac.verify_slice(&signature).is_err() {
+        return false;
+    }
+
+    let claims: Option<Value> = URL_SAFE_NO_PAD
+        .decode(payload)
+        .ok()
+        .and_then(|payload| serde_json::from_slice(&payload).ok());
+    let body_hash = format!("{:x}", Sha256::digest(body));
+    claims
+        .as_ref()
+        .and_then(|claims| claims.get("sha256"))
+        .and_then(Value::as_str)
+        .is_some_and(|hash| hash == body_hash)
+}
+
+/// Parse a GitHub `deployment_status` payload; only successful deploys with an
+/// environment URL trigger a scan
+pub fn parse_github_deployment(body: &[u8]) -> Result<Option<DeployEvent>, Box<dyn Error>> {
+    let payload: Value = serde_json::from_slice(body)?;
+    let status = &payload["deployment_status"];
+    if status["state"].as_str() != Some("success") {
+        return Ok(None);
+    }
+    let url = status["environment_url"]
+        .as_str()
+        .or_else(|| status["target_url"].as_str())
+        .filter(|url| !url.is_empty());
+    Ok(url.map(|url| DeployEvent {
+        url: url.to_string(),
+        repository: payload["repository"]["full_name"]
+            .as_str()
+            .map(String::from),
+        sha: payload["deployment"]["sha"].as_str().map(String::from),
+    }))
+}
+
+/// Parse a Netlify deploy notification; only `ready` deploys trigger a scan
+pub fn parse_netlify_deploy(body: &[u8]) -> Result<Option<DeployEvent>, Box<dyn Error>> {
+    let payload: Value = serde_json::from_slice(body)?;
+    if payload["state"].as_str() != Some("ready") {
+        return Ok(None);
+    }
+    let url = ["deploy_ssl_url", "ssl_url", "url"]
+        .iter()
+        .find_map(|field| payload[*field].as_str().filter(|url| !url.is_empty()));
+    // commit_url looks like https://github.com/<owner>/<repo>/commit/<sha>
+    let repository = payload["commit_url"].as_str().and_then(|commit_url| {
+        let path = commit_url.strip_prefix("https://github.com/")?;
+        let (repository, _) = path.split_once("/commit/")?;
+        Some(repository.to_string())
+    });
+    Ok(url.map(|url| DeployEvent {
+        url: url.to_string(),
+        repository,
+        sha: payload["commit_ref"].as_str().map(String::from),
+    }))
+}
+
+fn decode_hex(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+        .collect()
+}